@@ -20,6 +20,9 @@ enum citeproc_rs_error_code {
   CITEPROC_RS_ERROR_CODE_CLUSTER_NOT_IN_FLOW = 10,
   CITEPROC_RS_ERROR_CODE_INVALID_STYLE = 11,
   CITEPROC_RS_ERROR_CODE_SET_LOGGER = 12,
+  CITEPROC_RS_ERROR_CODE_SESSION = 13,
+  CITEPROC_RS_ERROR_CODE_THREAD_POOL = 14,
+  CITEPROC_RS_ERROR_CODE_UNKNOWN_COLLATION = 15,
 };
 typedef int32_t citeproc_rs_error_code;
 
@@ -92,6 +95,22 @@ enum citeproc_rs_output_format {
 };
 typedef uint8_t citeproc_rs_output_format;
 
+/**
+ * How much [citeproc_rs_driver_collect_garbage] discards. See [citeproc::GcLevel].
+ */
+enum citeproc_rs_gc_level {
+  /**
+   * Results not used since the last change, including everything for removed references and
+   * clusters.
+   */
+  CITEPROC_RS_GC_LEVEL_OUTDATED,
+  /**
+   * Everything that can be recomputed.
+   */
+  CITEPROC_RS_GC_LEVEL_ALL_VALUES,
+};
+typedef uint8_t citeproc_rs_gc_level;
+
 /**
  * An opaque, boxed wrapper for a [citeproc::prelude::Cluster].
  */
@@ -276,6 +295,140 @@ citeproc_rs_error_code citeproc_rs_driver_format_cluster(struct citeproc_rs_driv
 citeproc_rs_error_code citeproc_rs_driver_format_bibliography(struct citeproc_rs_driver *driver,
                                                               void *user_buf);
 
+/**
+ * Writes a JSON session into a buffer, using [citeproc::Processor::export_session]. Pass it
+ * to [citeproc_rs_driver_import_session] to recreate the driver later.
+ *
+ * Cluster ids are saved as the strings they were interned from; after importing, intern them
+ * again with [citeproc_rs_driver_intern_cluster_id].
+ */
+citeproc_rs_error_code citeproc_rs_driver_export_session(struct citeproc_rs_driver *driver,
+                                                         void *user_buf);
+
+/**
+ * Creates a new Processor from a JSON session written by [citeproc_rs_driver_export_session].
+ * Free with [citeproc_rs_driver_free].
+ */
+struct citeproc_rs_driver *citeproc_rs_driver_import_session(const char *session_json,
+                                                             uintptr_t session_json_len,
+                                                             struct citeproc_rs_buffer_ops buffer_ops);
+
+/**
+ * Discards memoized results, using [citeproc::Processor::collect_garbage].
+ */
+citeproc_rs_error_code citeproc_rs_driver_collect_garbage(struct citeproc_rs_driver *driver,
+                                                          citeproc_rs_gc_level level);
+
+/**
+ * Sets how often [citeproc::Processor::compute] collects garbage by itself. `every` is a
+ * number of calls; zero means never.
+ */
+citeproc_rs_error_code citeproc_rs_driver_set_sweep_policy(struct citeproc_rs_driver *driver,
+                                                           uint32_t every,
+                                                           citeproc_rs_gc_level level);
+
+/**
+ * Writes [citeproc::Processor::memory_stats] into a buffer, as JSON.
+ */
+citeproc_rs_error_code citeproc_rs_driver_memory_stats(struct citeproc_rs_driver *driver,
+                                                       void *user_buf);
+
+/**
+ * Chooses the threads the driver renders on. `num_threads` of 1 renders on the calling
+ * thread; 0 uses a pool shared by every driver in the process, with one thread per CPU; any
+ * other number creates a pool of that size for this driver.
+ *
+ * Without the `parallel` feature, only 1 is accepted.
+ */
+citeproc_rs_error_code citeproc_rs_driver_set_thread_count(struct citeproc_rs_driver *driver,
+                                                           uintptr_t num_threads);
+
+#if defined(CITEPROC_RS_PARALLEL)
+/**
+ * A read-only view of a [Driver], as it was when [citeproc_rs_driver_snapshot] was called.
+ *
+ * Thread safe: the `citeproc_rs_snapshot_*` functions can be called on one snapshot from any
+ * number of threads at the same time.
+ *
+ * Every function that modifies the driver blocks until all of its snapshots are freed. Calling
+ * one on a thread that is holding a snapshot will never return.
+ */
+typedef struct citeproc_rs_snapshot citeproc_rs_snapshot;
+
+/**
+ * Takes a snapshot of a driver. Free with [citeproc_rs_snapshot_free].
+ */
+struct citeproc_rs_snapshot *citeproc_rs_driver_snapshot(struct citeproc_rs_driver *driver);
+
+/**
+ * Frees a [Snapshot], unblocking any functions waiting to modify its driver.
+ */
+void citeproc_rs_snapshot_free(struct citeproc_rs_snapshot *snapshot);
+
+/**
+ * Writes a formatted cluster into a buffer, like [crate::citeproc_rs_driver_format_cluster].
+ */
+citeproc_rs_error_code citeproc_rs_snapshot_format_cluster(const struct citeproc_rs_snapshot *snapshot,
+                                                           citeproc_rs_cluster_id cluster_id,
+                                                           void *user_buf);
+
+/**
+ * Writes a bibliography into a buffer, like [crate::citeproc_rs_driver_format_bibliography].
+ */
+citeproc_rs_error_code citeproc_rs_snapshot_format_bibliography(const struct citeproc_rs_snapshot *snapshot,
+                                                                void *user_buf);
+#endif
+
+/**
+ * Renders `placeholder` in place of any cluster or bibliography entry that panics, instead of
+ * poisoning the driver, and records the failure for [citeproc_rs_driver_render_errors]. A
+ * null `placeholder` goes back to panicking. See [citeproc::PanicMode].
+ *
+ * The placeholder is plain text, which is escaped for the output format.
+ *
+ * # Safety
+ *
+ * `driver` must be a valid pointer to a Driver.
+ *
+ * Either `placeholder` must refer to a byte array of length `placeholder_len`, or it must be null.
+ */
+citeproc_rs_error_code citeproc_rs_driver_set_render_error_placeholder(struct citeproc_rs_driver *driver,
+                                                                       const char *placeholder,
+                                                                       uintptr_t placeholder_len);
+
+/**
+ * Writes the render errors recorded since the last call into a buffer, as a JSON array, using
+ * [citeproc::Processor::take_render_errors]. Each is an object with a `clusterId` (a
+ * ClusterId, or null for a bibliography entry), a `refId` (or null) and a `message`.
+ */
+citeproc_rs_error_code citeproc_rs_driver_render_errors(struct citeproc_rs_driver *driver,
+                                                        void *user_buf);
+
+/**
+ * Writes the problems the processor worked around into a buffer, as a JSON array, using
+ * [citeproc::Processor::diagnostics]. Each is an object with a `code` (e.g.
+ * `"missingReference"`), a `clusterId` (a ClusterId, or null), a `refId` (or null) and a
+ * `message`.
+ */
+citeproc_rs_error_code citeproc_rs_driver_diagnostics(struct citeproc_rs_driver *driver,
+                                                      void *user_buf);
+
+/**
+ * Sorts with a built-in collation, given as a language tag like `sv-SE` or
+ * `de-u-co-phonebk`. A null `tag` goes back to the collation for the style's default locale.
+ * The built-in collations are CLDR's, and an error is returned only for a malformed tag. See
+ * [citeproc::Collation::for_tag].
+ *
+ * # Safety
+ *
+ * `driver` must be a valid pointer to a Driver.
+ *
+ * Either `tag` must refer to a byte array of length `tag_len`, or it must be null.
+ */
+citeproc_rs_error_code citeproc_rs_driver_set_collation(struct citeproc_rs_driver *driver,
+                                                        const char *tag,
+                                                        uintptr_t tag_len);
+
 /**
  * Formats a bibliography entry for a given reference.
  *
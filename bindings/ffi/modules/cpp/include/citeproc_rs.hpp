@@ -23,6 +23,9 @@ enum class ErrorCode : int32_t {
   cluster_not_in_flow = 10,
   invalid_style = 11,
   set_logger = 12,
+  session = 13,
+  thread_pool = 14,
+  unknown_collation = 15,
 };
 
 enum class LevelFilter : uintptr_t {
@@ -80,6 +83,15 @@ enum class OutputFormat : uint8_t {
   plain,
 };
 
+/// How much [citeproc_rs_driver_collect_garbage] discards. See [citeproc::GcLevel].
+enum class GcLevel : uint8_t {
+  /// Results not used since the last change, including everything for removed references and
+  /// clusters.
+  outdated,
+  /// Everything that can be recomputed.
+  all_values,
+};
+
 /// An opaque, boxed wrapper for a [citeproc::prelude::Cluster].
 struct Cluster;
 
@@ -229,6 +241,100 @@ ErrorCode citeproc_rs_driver_set_cluster_order(Driver *driver,
 /// Writes a bibliography into a buffer, using [citeproc::Processor::get_bibliography]
  ErrorCode citeproc_rs_driver_format_bibliography(Driver *driver, void *user_buf);
 
+/// Writes a JSON session into a buffer, using [citeproc::Processor::export_session]. Pass it
+/// to [citeproc_rs_driver_import_session] to recreate the driver later.
+///
+/// Cluster ids are saved as the strings they were interned from; after importing, intern them
+/// again with [citeproc_rs_driver_intern_cluster_id].
+ ErrorCode citeproc_rs_driver_export_session(Driver *driver, void *user_buf);
+
+/// Creates a new Processor from a JSON session written by [citeproc_rs_driver_export_session].
+/// Free with [citeproc_rs_driver_free].
+ Driver *citeproc_rs_driver_import_session(const char *session_json,
+                                           uintptr_t session_json_len,
+                                           BufferOps buffer_ops);
+
+/// Discards memoized results, using [citeproc::Processor::collect_garbage].
+ ErrorCode citeproc_rs_driver_collect_garbage(Driver *driver, GcLevel level);
+
+/// Sets how often [citeproc::Processor::compute] collects garbage by itself. `every` is a
+/// number of calls; zero means never.
+ ErrorCode citeproc_rs_driver_set_sweep_policy(Driver *driver, uint32_t every, GcLevel level);
+
+/// Writes [citeproc::Processor::memory_stats] into a buffer, as JSON.
+ ErrorCode citeproc_rs_driver_memory_stats(Driver *driver, void *user_buf);
+
+/// Chooses the threads the driver renders on. `num_threads` of 1 renders on the calling
+/// thread; 0 uses a pool shared by every driver in the process, with one thread per CPU; any
+/// other number creates a pool of that size for this driver.
+///
+/// Without the `parallel` feature, only 1 is accepted.
+ ErrorCode citeproc_rs_driver_set_thread_count(Driver *driver, uintptr_t num_threads);
+
+#if defined(CITEPROC_RS_PARALLEL)
+/// A read-only view of a [Driver], as it was when [citeproc_rs_driver_snapshot] was called.
+///
+/// Thread safe: the `citeproc_rs_snapshot_*` functions can be called on one snapshot from any
+/// number of threads at the same time.
+///
+/// Every function that modifies the driver blocks until all of its snapshots are freed. Calling
+/// one on a thread that is holding a snapshot will never return.
+struct Snapshot;
+
+/// Takes a snapshot of a driver. Free with [citeproc_rs_snapshot_free].
+ Snapshot *citeproc_rs_driver_snapshot(Driver *driver);
+
+/// Frees a [Snapshot], unblocking any functions waiting to modify its driver.
+ void citeproc_rs_snapshot_free(Snapshot *snapshot);
+
+/// Writes a formatted cluster into a buffer, like [crate::citeproc_rs_driver_format_cluster].
+ ErrorCode citeproc_rs_snapshot_format_cluster(const Snapshot *snapshot,
+                                              ClusterId cluster_id,
+                                              void *user_buf);
+
+/// Writes a bibliography into a buffer, like [crate::citeproc_rs_driver_format_bibliography].
+ ErrorCode citeproc_rs_snapshot_format_bibliography(const Snapshot *snapshot, void *user_buf);
+#endif
+
+/// Renders `placeholder` in place of any cluster or bibliography entry that panics, instead of
+/// poisoning the driver, and records the failure for [citeproc_rs_driver_render_errors]. A
+/// null `placeholder` goes back to panicking. See [citeproc::PanicMode].
+///
+/// The placeholder is plain text, which is escaped for the output format.
+///
+/// # Safety
+///
+/// `driver` must be a valid pointer to a Driver.
+///
+/// Either `placeholder` must refer to a byte array of length `placeholder_len`, or it must be null.
+
+ErrorCode citeproc_rs_driver_set_render_error_placeholder(Driver *driver,
+                                                          const char *placeholder,
+                                                          uintptr_t placeholder_len);
+
+/// Writes the render errors recorded since the last call into a buffer, as a JSON array, using
+/// [citeproc::Processor::take_render_errors]. Each is an object with a `clusterId` (a
+/// ClusterId, or null for a bibliography entry), a `refId` (or null) and a `message`.
+ ErrorCode citeproc_rs_driver_render_errors(Driver *driver, void *user_buf);
+
+/// Writes the problems the processor worked around into a buffer, as a JSON array, using
+/// [citeproc::Processor::diagnostics]. Each is an object with a `code` (e.g.
+/// `"missingReference"`), a `clusterId` (a ClusterId, or null), a `refId` (or null) and a
+/// `message`.
+ ErrorCode citeproc_rs_driver_diagnostics(Driver *driver, void *user_buf);
+
+/// Sorts with a built-in collation, given as a language tag like `sv-SE` or
+/// `de-u-co-phonebk`. A null `tag` goes back to the collation for the style's default locale.
+/// The built-in collations are CLDR's, and an error is returned only for a malformed tag. See
+/// [citeproc::Collation::for_tag].
+///
+/// # Safety
+///
+/// `driver` must be a valid pointer to a Driver.
+///
+/// Either `tag` must refer to a byte array of length `tag_len`, or it must be null.
+ ErrorCode citeproc_rs_driver_set_collation(Driver *driver, const char *tag, uintptr_t tag_len);
+
 /// Formats a bibliography entry for a given reference.
 ///
 /// Writes the result into user_buf using the buffer_ops interface.
@@ -23,6 +23,9 @@ typedef CF_ENUM(int32_t, CRErrorCode) {
   CRErrorCode_ClusterNotInFlow = 10,
   CRErrorCode_InvalidStyle = 11,
   CRErrorCode_SetLogger = 12,
+  CRErrorCode_Session = 13,
+  CRErrorCode_ThreadPool = 14,
+  CRErrorCode_UnknownCollation = 15,
 };
 
 typedef CF_ENUM(uintptr_t, CRLevelFilter) {
@@ -88,6 +91,24 @@ typedef CF_ENUM(uint8_t, CROutputFormat) {
   CROutputFormat_Html,
   CROutputFormat_Rtf,
   CROutputFormat_Plain,
+  CROutputFormat_Jats,
+  CROutputFormat_Ooxml,
+  CROutputFormat_Odf,
+};
+
+/**
+ * How much [citeproc_rs_driver_collect_garbage] discards. See [citeproc::GcLevel].
+ */
+typedef CF_ENUM(uint8_t, CRGcLevel) {
+  /**
+   * Results not used since the last change, including everything for removed references and
+   * clusters.
+   */
+  CRGcLevel_Outdated,
+  /**
+   * Everything that can be recomputed.
+   */
+  CRGcLevel_AllValues,
 };
 
 /**
@@ -274,6 +295,140 @@ CRErrorCode citeproc_rs_driver_format_cluster(struct CRDriver *driver,
 CRErrorCode citeproc_rs_driver_format_bibliography(struct CRDriver *driver,
                                                    void *user_buf) CF_SWIFT_NAME(citeproc_rs_driver_format_bibliography(driver:user_buf:));
 
+/**
+ * Writes a JSON session into a buffer, using [citeproc::Processor::export_session]. Pass it
+ * to [citeproc_rs_driver_import_session] to recreate the driver later.
+ *
+ * Cluster ids are saved as the strings they were interned from; after importing, intern them
+ * again with [citeproc_rs_driver_intern_cluster_id].
+ */
+CRErrorCode citeproc_rs_driver_export_session(struct CRDriver *driver,
+                                              void *user_buf) CF_SWIFT_NAME(citeproc_rs_driver_export_session(driver:user_buf:));
+
+/**
+ * Creates a new Processor from a JSON session written by [citeproc_rs_driver_export_session].
+ * Free with [citeproc_rs_driver_free].
+ */
+struct CRDriver *citeproc_rs_driver_import_session(const char *session_json,
+                                                   uintptr_t session_json_len,
+                                                   struct CRBufferOps buffer_ops) CF_SWIFT_NAME(citeproc_rs_driver_import_session(session_json:session_json_len:buffer_ops:));
+
+/**
+ * Discards memoized results, using [citeproc::Processor::collect_garbage].
+ */
+CRErrorCode citeproc_rs_driver_collect_garbage(struct CRDriver *driver,
+                                               CRGcLevel level) CF_SWIFT_NAME(citeproc_rs_driver_collect_garbage(driver:level:));
+
+/**
+ * Sets how often [citeproc::Processor::compute] collects garbage by itself. `every` is a
+ * number of calls; zero means never.
+ */
+CRErrorCode citeproc_rs_driver_set_sweep_policy(struct CRDriver *driver,
+                                                uint32_t every,
+                                                CRGcLevel level) CF_SWIFT_NAME(citeproc_rs_driver_set_sweep_policy(driver:every:level:));
+
+/**
+ * Writes [citeproc::Processor::memory_stats] into a buffer, as JSON.
+ */
+CRErrorCode citeproc_rs_driver_memory_stats(struct CRDriver *driver,
+                                            void *user_buf) CF_SWIFT_NAME(citeproc_rs_driver_memory_stats(driver:user_buf:));
+
+/**
+ * Chooses the threads the driver renders on. `num_threads` of 1 renders on the calling
+ * thread; 0 uses a pool shared by every driver in the process, with one thread per CPU; any
+ * other number creates a pool of that size for this driver.
+ *
+ * Without the `parallel` feature, only 1 is accepted.
+ */
+CRErrorCode citeproc_rs_driver_set_thread_count(struct CRDriver *driver,
+                                                uintptr_t num_threads) CF_SWIFT_NAME(citeproc_rs_driver_set_thread_count(driver:num_threads:));
+
+#if defined(CITEPROC_RS_PARALLEL)
+/**
+ * A read-only view of a [Driver], as it was when [citeproc_rs_driver_snapshot] was called.
+ *
+ * Thread safe: the `citeproc_rs_snapshot_*` functions can be called on one snapshot from any
+ * number of threads at the same time.
+ *
+ * Every function that modifies the driver blocks until all of its snapshots are freed. Calling
+ * one on a thread that is holding a snapshot will never return.
+ */
+typedef struct CRSnapshot CRSnapshot;
+
+/**
+ * Takes a snapshot of a driver. Free with [citeproc_rs_snapshot_free].
+ */
+struct CRSnapshot *citeproc_rs_driver_snapshot(struct CRDriver *driver) CF_SWIFT_NAME(citeproc_rs_driver_snapshot(driver:));
+
+/**
+ * Frees a [Snapshot], unblocking any functions waiting to modify its driver.
+ */
+void citeproc_rs_snapshot_free(struct CRSnapshot *snapshot) CF_SWIFT_NAME(citeproc_rs_snapshot_free(snapshot:));
+
+/**
+ * Writes a formatted cluster into a buffer, like [crate::citeproc_rs_driver_format_cluster].
+ */
+CRErrorCode citeproc_rs_snapshot_format_cluster(const struct CRSnapshot *snapshot,
+                                                CRClusterId cluster_id,
+                                                void *user_buf) CF_SWIFT_NAME(citeproc_rs_snapshot_format_cluster(snapshot:cluster_id:user_buf:));
+
+/**
+ * Writes a bibliography into a buffer, like [crate::citeproc_rs_driver_format_bibliography].
+ */
+CRErrorCode citeproc_rs_snapshot_format_bibliography(const struct CRSnapshot *snapshot,
+                                                     void *user_buf) CF_SWIFT_NAME(citeproc_rs_snapshot_format_bibliography(snapshot:user_buf:));
+#endif
+
+/**
+ * Renders `placeholder` in place of any cluster or bibliography entry that panics, instead of
+ * poisoning the driver, and records the failure for [citeproc_rs_driver_render_errors]. A
+ * null `placeholder` goes back to panicking. See [citeproc::PanicMode].
+ *
+ * The placeholder is plain text, which is escaped for the output format.
+ *
+ * # Safety
+ *
+ * `driver` must be a valid pointer to a Driver.
+ *
+ * Either `placeholder` must refer to a byte array of length `placeholder_len`, or it must be null.
+ */
+CRErrorCode citeproc_rs_driver_set_render_error_placeholder(struct CRDriver *driver,
+                                                            const char *placeholder,
+                                                            uintptr_t placeholder_len) CF_SWIFT_NAME(citeproc_rs_driver_set_render_error_placeholder(driver:placeholder:placeholder_len:));
+
+/**
+ * Writes the render errors recorded since the last call into a buffer, as a JSON array, using
+ * [citeproc::Processor::take_render_errors]. Each is an object with a `clusterId` (a
+ * ClusterId, or null for a bibliography entry), a `refId` (or null) and a `message`.
+ */
+CRErrorCode citeproc_rs_driver_render_errors(struct CRDriver *driver,
+                                             void *user_buf) CF_SWIFT_NAME(citeproc_rs_driver_render_errors(driver:user_buf:));
+
+/**
+ * Writes the problems the processor worked around into a buffer, as a JSON array, using
+ * [citeproc::Processor::diagnostics]. Each is an object with a `code` (e.g.
+ * `"missingReference"`), a `clusterId` (a ClusterId, or null), a `refId` (or null) and a
+ * `message`.
+ */
+CRErrorCode citeproc_rs_driver_diagnostics(struct CRDriver *driver,
+                                           void *user_buf) CF_SWIFT_NAME(citeproc_rs_driver_diagnostics(driver:user_buf:));
+
+/**
+ * Sorts with a built-in collation, given as a language tag like `sv-SE` or
+ * `de-u-co-phonebk`. A null `tag` goes back to the collation for the style's default locale.
+ * The built-in collations are CLDR's, and an error is returned only for a malformed tag. See
+ * [citeproc::Collation::for_tag].
+ *
+ * # Safety
+ *
+ * `driver` must be a valid pointer to a Driver.
+ *
+ * Either `tag` must refer to a byte array of length `tag_len`, or it must be null.
+ */
+CRErrorCode citeproc_rs_driver_set_collation(struct CRDriver *driver,
+                                             const char *tag,
+                                             uintptr_t tag_len) CF_SWIFT_NAME(citeproc_rs_driver_set_collation(driver:tag:tag_len:));
+
 /**
  * Formats a bibliography entry for a given reference.
  *
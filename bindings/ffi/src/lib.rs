@@ -20,13 +20,16 @@ mod nullable;
 
 mod clusters;
 pub use clusters::*;
+#[cfg(feature = "parallel")]
+mod snapshot;
 pub use errors::*;
+#[cfg(feature = "parallel")]
+pub use snapshot::*;
 
 use thiserror::Error;
 
 use libc::{c_char, c_void};
 use std::ffi::CStr;
-use std::sync::Arc;
 
 use crate::buffer::BufferWriter;
 
@@ -57,6 +60,12 @@ pub enum FFIError {
     InvalidStyle(#[from] csl::StyleError),
     #[error("could not set logger: {0}")]
     SetLogger(#[from] log::SetLoggerError),
+    #[error("session error: {0}")]
+    Session(#[from] citeproc::SessionError),
+    #[error("thread pool error: {0}")]
+    ThreadPool(String),
+    #[error("unknown collation: {0}")]
+    UnknownCollation(String),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -75,6 +84,9 @@ pub enum ErrorCode {
     ClusterNotInFlow = 10,
     InvalidStyle = 11,
     SetLogger = 12,
+    Session = 13,
+    ThreadPool = 14,
+    UnknownCollation = 15,
 }
 
 impl FFIError {
@@ -101,6 +113,9 @@ impl FFIError {
             Self::ClusterNotInFlow(_) => ErrorCode::ClusterNotInFlow,
             Self::InvalidStyle(_) => ErrorCode::InvalidStyle,
             Self::SetLogger(_) => ErrorCode::SetLogger,
+            Self::Session(_) => ErrorCode::Session,
+            Self::ThreadPool(_) => ErrorCode::ThreadPool,
+            Self::UnknownCollation(_) => ErrorCode::UnknownCollation,
         }
     }
 }
@@ -218,6 +233,9 @@ pub enum OutputFormat {
     Html,
     Rtf,
     Plain,
+    Jats,
+    Ooxml,
+    Odf,
 }
 
 #[repr(C)]
@@ -236,6 +254,9 @@ impl OutputFormat {
             OutputFormat::Html => rust::SupportedFormat::Html,
             OutputFormat::Rtf => rust::SupportedFormat::Rtf,
             OutputFormat::Plain => rust::SupportedFormat::Plain,
+            OutputFormat::Jats => rust::SupportedFormat::Jats,
+            OutputFormat::Ooxml => rust::SupportedFormat::Ooxml,
+            OutputFormat::Odf => rust::SupportedFormat::Odf,
         }
     }
 }
@@ -249,7 +270,8 @@ ffi_fn! {
             let rs_init = rust::InitOptions {
                 format: init.format.to_supported_format(),
                 style,
-                fetcher: Some(Arc::new(rust::PredefinedLocales::bundled_en_us())),
+                // en-US, or every locale with the bundled-locales feature
+                fetcher: None,
                 ..Default::default()
             };
             let mut proc = Processor::new(rs_init)?;
@@ -372,6 +394,249 @@ ffi_fn_nullify! {
     }
 }
 
+ffi_fn_nullify! {
+    /// Writes a JSON session into a buffer, using [citeproc::Processor::export_session]. Pass it
+    /// to [citeproc_rs_driver_import_session] to recreate the driver later.
+    ///
+    /// Cluster ids are saved as the strings they were interned from; after importing, intern them
+    /// again with [citeproc_rs_driver_intern_cluster_id].
+    @safety unsafe fn citeproc_rs_driver_export_session(#[nullify_on_panic] driver: *mut Driver, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let session = serde_json::to_string(&proc.export_session())?;
+            let mut buffer = unsafe { BufferWriter::new(driver.buffer_ops, user_buf) };
+            buffer.clear();
+            buffer.write_str(&session)?;
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn! {
+    /// Creates a new Processor from a JSON session written by [citeproc_rs_driver_export_session].
+    /// Free with [citeproc_rs_driver_free].
+    fn citeproc_rs_driver_import_session(session_json: *const c_char, session_json_len: usize, buffer_ops: buffer::BufferOps) -> *mut Driver {
+        result_to_error_code(|| {
+            let session_json = unsafe { borrow_utf8_slice(session_json, session_json_len) }?;
+            let session: rust::Session = serde_json::from_str(session_json)?;
+            // en-US, or every locale with the bundled-locales feature. The session has the rest.
+            let proc = Processor::import_session(session, None)?;
+            Ok(Box::into_raw(Box::new(Driver {
+                processor: Some(proc),
+                buffer_ops,
+                positions_scratch: Vec::new(),
+            })))
+        })
+    }
+}
+
+/// How much [citeproc_rs_driver_collect_garbage] discards. See [citeproc::GcLevel].
+#[derive(Copy, Clone, Debug)]
+#[repr(u8)]
+pub enum GcLevel {
+    /// Results not used since the last change, including everything for removed references and
+    /// clusters.
+    Outdated,
+    /// Everything that can be recomputed.
+    AllValues,
+}
+
+impl GcLevel {
+    fn to_rust(self) -> rust::GcLevel {
+        match self {
+            GcLevel::Outdated => rust::GcLevel::Outdated,
+            GcLevel::AllValues => rust::GcLevel::AllValues,
+        }
+    }
+}
+
+ffi_fn_nullify! {
+    /// Discards memoized results, using [citeproc::Processor::collect_garbage].
+    @safety unsafe fn citeproc_rs_driver_collect_garbage(#[nullify_on_panic] driver: *mut Driver, level: GcLevel) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            proc.collect_garbage(level.to_rust());
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Sets how often [citeproc::Processor::compute] collects garbage by itself. `every` is a
+    /// number of calls; zero means never.
+    @safety unsafe fn citeproc_rs_driver_set_sweep_policy(#[nullify_on_panic] driver: *mut Driver, every: u32, level: GcLevel) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            proc.set_sweep_policy(rust::SweepPolicy { every, level: level.to_rust() });
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Writes [citeproc::Processor::memory_stats] into a buffer, as JSON.
+    @safety unsafe fn citeproc_rs_driver_memory_stats(#[nullify_on_panic] driver: *mut Driver, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let stats = serde_json::to_string(&proc.memory_stats())?;
+            let mut buffer = unsafe { BufferWriter::new(driver.buffer_ops, user_buf) };
+            buffer.clear();
+            buffer.write_str(&stats)?;
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Chooses the threads the driver renders on. `num_threads` of 1 renders on the calling
+    /// thread; 0 uses a pool shared by every driver in the process, with one thread per CPU; any
+    /// other number creates a pool of that size for this driver.
+    ///
+    /// Without the `parallel` feature, only 1 is accepted.
+    @safety unsafe fn citeproc_rs_driver_set_thread_count(#[nullify_on_panic] driver: *mut Driver, num_threads: usize) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            proc.set_parallelism(parallelism_for_threads(num_threads)?);
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn parallelism_for_threads(num_threads: usize) -> Result<rust::Parallelism, FFIError> {
+    match num_threads {
+        0 => Ok(rust::Parallelism::GlobalPool),
+        1 => Ok(rust::Parallelism::Serial),
+        n => rust::Parallelism::with_threads(n).map_err(|e| FFIError::ThreadPool(e.to_string())),
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn parallelism_for_threads(num_threads: usize) -> Result<rust::Parallelism, FFIError> {
+    match num_threads {
+        1 => Ok(rust::Parallelism::Serial),
+        _ => Err(FFIError::ThreadPool(
+            "citeproc_rs was built without the `parallel` feature".into(),
+        )),
+    }
+}
+
+ffi_fn_nullify! {
+    /// Renders `placeholder` in place of any cluster or bibliography entry that panics, instead of
+    /// poisoning the driver, and records the failure for [citeproc_rs_driver_render_errors]. A
+    /// null `placeholder` goes back to panicking. See [citeproc::PanicMode].
+    ///
+    /// The placeholder is plain text, which is escaped for the output format.
+    ///
+    /// # Safety
+    ///
+    /// `driver` must be a valid pointer to a Driver.
+    ///
+    /// Either `placeholder` must refer to a byte array of length `placeholder_len`, or it must be null.
+    @safety unsafe fn citeproc_rs_driver_set_render_error_placeholder(#[nullify_on_panic] driver: *mut Driver, placeholder: *const c_char, placeholder_len: usize) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let mode = if placeholder.is_null() {
+                rust::PanicMode::Propagate
+            } else {
+                let placeholder = unsafe { borrow_utf8_slice(placeholder, placeholder_len) }?;
+                rust::PanicMode::placeholder(placeholder)
+            };
+            proc.set_panic_mode(mode);
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Writes the render errors recorded since the last call into a buffer, as a JSON array, using
+    /// [citeproc::Processor::take_render_errors]. Each is an object with a `clusterId` (a
+    /// ClusterId, or null for a bibliography entry), a `refId` (or null) and a `message`.
+    @safety unsafe fn citeproc_rs_driver_render_errors(#[nullify_on_panic] driver: *mut Driver, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let errors: Vec<rust::RenderError<ClusterId>> = proc
+                .take_render_errors()
+                .into_iter()
+                .map(|error| rust::RenderError {
+                    cluster_id: error.cluster_id.map(|id| id.0),
+                    ref_id: error.ref_id,
+                    message: error.message,
+                })
+                .collect();
+            let json = serde_json::to_string(&errors)?;
+            let mut buffer = unsafe { BufferWriter::new(driver.buffer_ops, user_buf) };
+            buffer.clear();
+            buffer.write_str(&json)?;
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Writes the problems the processor worked around into a buffer, as a JSON array, using
+    /// [citeproc::Processor::diagnostics]. Each is an object with a `code` (e.g.
+    /// `"missingReference"`), a `clusterId` (a ClusterId, or null), a `refId` (or null) and a
+    /// `message`.
+    @safety unsafe fn citeproc_rs_driver_diagnostics(#[nullify_on_panic] driver: *mut Driver, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let diagnostics: Vec<rust::Diagnostic<ClusterId>> = proc
+                .diagnostics()
+                .into_iter()
+                .map(|diagnostic| rust::Diagnostic {
+                    code: diagnostic.code,
+                    cluster_id: diagnostic.cluster_id.map(|id| id.0),
+                    ref_id: diagnostic.ref_id,
+                    message: diagnostic.message,
+                })
+                .collect();
+            let json = serde_json::to_string(&diagnostics)?;
+            let mut buffer = unsafe { BufferWriter::new(driver.buffer_ops, user_buf) };
+            buffer.clear();
+            buffer.write_str(&json)?;
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Sorts with a built-in collation, given as a language tag like `sv-SE` or
+    /// `de-u-co-phonebk`. A null `tag` goes back to the collation for the style's default locale.
+    /// The built-in collations are CLDR's, and an error is returned only for a malformed tag. See
+    /// [citeproc::Collation::for_tag].
+    ///
+    /// # Safety
+    ///
+    /// `driver` must be a valid pointer to a Driver.
+    ///
+    /// Either `tag` must refer to a byte array of length `tag_len`, or it must be null.
+    @safety unsafe fn citeproc_rs_driver_set_collation(#[nullify_on_panic] driver: *mut Driver, tag: *const c_char, tag_len: usize) -> ErrorCode {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_mut().ok_or(FFIError::Poisoned)?;
+            let collation = if tag.is_null() {
+                None
+            } else {
+                let tag = unsafe { borrow_utf8_slice(tag, tag_len) }?;
+                let collation = rust::Collation::for_tag(tag)
+                    .ok_or_else(|| FFIError::UnknownCollation(tag.into()))?;
+                Some(collation)
+            };
+            proc.set_collation(collation);
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
 ffi_fn_nullify! {
     /// Formats a bibliography entry for a given reference.
     ///
@@ -0,0 +1,96 @@
+//! Read-only snapshots of a driver, for rendering from many threads at once. Only available with
+//! the `parallel` feature.
+
+use libc::c_void;
+
+use citeproc::prelude as rust;
+
+use crate::buffer::{self, BufferWriter};
+use crate::clusters::ClusterId;
+use crate::macros::nullify_on_panic;
+use crate::util::*;
+use crate::{Driver, ErrorCode, FFIError};
+
+/// A read-only view of a [Driver], as it was when [citeproc_rs_driver_snapshot] was called.
+///
+/// Thread safe: the `citeproc_rs_snapshot_*` functions can be called on one snapshot from any
+/// number of threads at the same time.
+///
+/// Every function that modifies the driver blocks until all of its snapshots are freed. Calling
+/// one on a thread that is holding a snapshot will never return.
+pub struct Snapshot {
+    inner: rust::SharedSnapshot,
+    buffer_ops: buffer::BufferOps,
+}
+
+ffi_fn_nullify! {
+    /// Takes a snapshot of a driver. Free with [citeproc_rs_snapshot_free].
+    ///
+    /// # Safety
+    ///
+    /// Driver must be a valid pointer to a Driver.
+    @safety unsafe fn citeproc_rs_driver_snapshot(#[nullify_on_panic] driver: *mut Driver) -> *mut Snapshot {
+        result_to_error_code(|| {
+            let driver = unsafe { borrow_raw_ptr_mut(driver) } ?;
+            let proc = driver.processor.as_ref().ok_or(FFIError::Poisoned)?;
+            Ok(Box::into_raw(Box::new(Snapshot {
+                inner: proc.shared_snapshot(),
+                buffer_ops: driver.buffer_ops,
+            })))
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Frees a [Snapshot], unblocking any functions waiting to modify its driver.
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must either be from [citeproc_rs_driver_snapshot] or be null, and no other
+    /// thread may be using it.
+    fn citeproc_rs_snapshot_free(snapshot: *mut Snapshot) {
+        if !snapshot.is_null() {
+            drop(unsafe { Box::from_raw(snapshot) });
+        }
+    }
+}
+
+ffi_fn_nullify! {
+    /// Writes a formatted cluster into a buffer, like [crate::citeproc_rs_driver_format_cluster].
+    ///
+    /// # Safety
+    ///
+    /// The snapshot must be from [citeproc_rs_driver_snapshot] and not freed. `user_buf` must
+    /// match the buffer ops the driver was created with.
+    @safety unsafe fn citeproc_rs_snapshot_format_cluster(snapshot: *const Snapshot, cluster_id: ClusterId, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let snapshot = unsafe { borrow_raw_ptr(snapshot) } ?;
+            let mut buffer = unsafe { BufferWriter::new(snapshot.buffer_ops, user_buf) };
+            let id = rust::ClusterId(cluster_id);
+            let built = snapshot.inner.get_cluster(id).ok_or(FFIError::ClusterNotInFlow(id))?;
+            buffer.clear();
+            buffer.write_str(built.as_str())?;
+            Ok(ErrorCode::None)
+        })
+    }
+}
+
+ffi_fn_nullify! {
+    /// Writes a bibliography into a buffer, like [crate::citeproc_rs_driver_format_bibliography].
+    ///
+    /// # Safety
+    ///
+    /// As for [citeproc_rs_snapshot_format_cluster].
+    @safety unsafe fn citeproc_rs_snapshot_format_bibliography(snapshot: *const Snapshot, user_buf: *mut c_void) -> ErrorCode {
+        result_to_error_code(|| {
+            let snapshot = unsafe { borrow_raw_ptr(snapshot) } ?;
+            let mut buffer = unsafe { BufferWriter::new(snapshot.buffer_ops, user_buf) };
+            buffer.clear();
+            for entry in snapshot.inner.get_bibliography() {
+                buffer.write_str(entry.value.as_str())?;
+                buffer.write_str("\n")?;
+            }
+            Ok(ErrorCode::None)
+        })
+    }
+}
@@ -20,6 +20,12 @@ program
   .command('to-yml <test_case.txt>')
   .action(to_yml)
 
+program
+  .version('0.0.1')
+  .command('render')
+  .description('render a JSON job from stdin, for citeproc-rs\' `tools compat`')
+  .action(render)
+
 // program
 //   .version('0.0.1')
 //   .command('to-txt <test_case.yml>')
@@ -118,4 +124,44 @@ function to_yml(txtFile) {
   console.log(yaml.safeDump(y));
 }
 
+// Reads { style, references, clusters: [{ id, note?, cites }], localesDir } from stdin, and writes
+// { clusters: [{ id, html }], bibliography: [{ id, html }] | null } to stdout.
+function render() {
+  let job = JSON.parse(fs.readFileSync(0, 'utf8'));
+
+  let library = {};
+  for (let item of job.references) {
+    library[item.id] = item;
+  }
+
+  let sys = {
+    retrieveItem(id) {
+      return library[id];
+    },
+    retrieveLocale(lang) {
+      let file = path.join(job.localesDir, "locales-" + lang + ".xml");
+      return fs.existsSync(file) ? fs.readFileSync(file, 'utf8') : false;
+    },
+  };
 
+  let engine = new CSL.Engine(sys, job.style);
+  // citeproc-rs links URLs and DOIs by default
+  engine.opt.development_extensions.wrap_url_and_doi = true;
+
+  let citations = job.clusters.map(cluster => ({
+    citationID: cluster.id,
+    citationItems: cluster.cites,
+    properties: { noteIndex: cluster.note || 0 },
+  }));
+  let clusters = engine
+    .rebuildProcessorState(citations, 'html', [])
+    .map(([id, _noteIndex, html]) => ({ id, html }));
+
+  let bibliography = null;
+  let bib = engine.makeBibliography();
+  if (bib) {
+    bibliography = bib[1].map((html, ix) => ({ id: String(bib[0].entry_ids[ix][0]), html }));
+  }
+
+  console.log(JSON.stringify({ clusters, bibliography }));
+}
@@ -0,0 +1,270 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+//! Document-scale benchmarks: 2,000 clusters citing 5,000 references, in an author-date, a
+//! numeric and a note style.
+//!
+//! The fixtures in `benches/data` are generated by `cargo tools gen-bench-data`, and are checked
+//! in so that results stay comparable between runs.
+
+use cfg_if::cfg_if;
+cfg_if! {
+    if #[cfg(feature="jemalloc")] {
+        use jemallocator::Jemalloc;
+        #[global_allocator]
+        static A: Jemalloc = Jemalloc;
+    } else {
+        use std::alloc::System;
+        #[global_allocator]
+        static A: System = System;
+    }
+}
+
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BatchSize, Criterion};
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use citeproc::io::cite_compat_vec;
+use citeproc::prelude::*;
+use csl::Lang;
+
+static APA: &str = include_str!("./data/apa.csl");
+static NUMERIC: &str = include_str!("./data/numeric.csl");
+static AGLC: &str = include_str!("./data/australian-guide-to-legal-citation.csl");
+
+static REFERENCES: &str = include_str!("./data/references.json");
+static CLUSTERS: &str = include_str!("./data/clusters.json");
+
+#[derive(Deserialize)]
+struct ClusterInput {
+    id: String,
+    note: u32,
+    #[serde(deserialize_with = "cite_compat_vec::deserialize")]
+    cites: Vec<Cite<Markup>>,
+}
+
+struct Document {
+    references: Vec<Reference>,
+    clusters: Vec<ClusterInput>,
+}
+
+impl Document {
+    fn load() -> Self {
+        Document {
+            references: serde_json::from_str(REFERENCES).unwrap(),
+            clusters: serde_json::from_str(CLUSTERS).unwrap(),
+        }
+    }
+}
+
+struct BenchStyle {
+    name: &'static str,
+    csl: &'static str,
+    /// Whether the clusters are footnotes, or in-text
+    notes: bool,
+}
+
+static STYLES: &[BenchStyle] = &[
+    BenchStyle {
+        name: "author-date",
+        csl: APA,
+        notes: false,
+    },
+    BenchStyle {
+        name: "numeric",
+        csl: NUMERIC,
+        notes: false,
+    },
+    BenchStyle {
+        name: "note",
+        csl: AGLC,
+        notes: true,
+    },
+];
+
+/// en-US, plus the partial de-DE and fr-FR locales to switch between.
+fn fetcher() -> Arc<dyn LocaleFetcher> {
+    let mut locales = PredefinedLocales::bundled_en_us();
+    locales.0.insert(
+        Lang::from_str("de-DE").unwrap(),
+        include_str!("./data/locales-de-DE.xml").to_owned(),
+    );
+    locales.0.insert(
+        Lang::from_str("fr-FR").unwrap(),
+        include_str!("./data/locales-fr-FR.xml").to_owned(),
+    );
+    Arc::new(locales)
+}
+
+fn new_processor(style: &BenchStyle) -> Processor {
+    Processor::new(InitOptions {
+        style: style.csl,
+        fetcher: Some(fetcher()),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn position(style: &BenchStyle, id: ClusterId, note: u32) -> ClusterPosition {
+    if style.notes {
+        ClusterPosition::note(id, note)
+    } else {
+        ClusterPosition::in_text(id)
+    }
+}
+
+/// Inserts the whole document and renders every cluster and the bibliography, like a word
+/// processor plugin opening a document.
+fn render_document(proc: &mut Processor, style: &BenchStyle, doc: &Document) {
+    proc.reset_references(doc.references.clone());
+    let mut clusters = Vec::with_capacity(doc.clusters.len());
+    let mut positions = Vec::with_capacity(doc.clusters.len());
+    for input in &doc.clusters {
+        let id = proc.cluster_id(&input.id);
+        clusters.push(Cluster::new(id, input.cites.clone(), None));
+        positions.push(position(style, id, input.note));
+    }
+    proc.init_clusters(clusters);
+    proc.set_cluster_order(&positions).unwrap();
+    for pos in &positions {
+        criterion::black_box(proc.get_cluster(pos.id.unwrap()));
+    }
+    criterion::black_box(proc.get_bibliography());
+}
+
+fn rendered_processor(style: &BenchStyle, doc: &Document) -> Processor {
+    let mut proc = new_processor(style);
+    render_document(&mut proc, style, doc);
+    proc
+}
+
+fn bench_style(c: &mut Criterion, style: &BenchStyle, doc: &Document) {
+    let mut group = c.benchmark_group(format!("document({})", style.name));
+    group.sample_size(10);
+
+    group.bench_function("initial_render", |b| {
+        b.iter_batched(
+            || new_processor(style),
+            |mut proc| {
+                render_document(&mut proc, style, doc);
+                proc
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    // The edits alternate between two versions, so every iteration invalidates something, and
+    // each is followed by fetching the updates, the way an editor would.
+    let middle = &doc.clusters[doc.clusters.len() / 2];
+
+    group.bench_function("edit_cluster", |b| {
+        let mut proc = rendered_processor(style, doc);
+        let id = proc.cluster_id(&middle.id);
+        let original = middle.cites.clone();
+        let mut edited = original.clone();
+        edited.reverse();
+        edited.push(Cite::basic(doc.references[0].id.clone()));
+        let mut toggle = false;
+        b.iter(|| {
+            toggle = !toggle;
+            let cites = if toggle { &edited } else { &original };
+            proc.insert_cluster(Cluster::new(id, cites.clone(), None));
+            proc.batched_updates()
+        })
+    });
+
+    // A reference cited in the middle of the document, so both earlier and later clusters
+    // have positions and disambiguation that could depend on it.
+    let cited = middle.cites[0].ref_id.clone();
+    let mut edited_ref = doc
+        .references
+        .iter()
+        .find(|refr| refr.id == cited)
+        .cloned()
+        .unwrap();
+    let original_ref = edited_ref.clone();
+    edited_ref
+        .ordinary
+        .insert(csl::Variable::Title, "An edited title".into());
+
+    group.bench_function("edit_reference", |b| {
+        let mut proc = rendered_processor(style, doc);
+        let mut toggle = false;
+        b.iter(|| {
+            toggle = !toggle;
+            let refr = if toggle { &edited_ref } else { &original_ref };
+            proc.insert_reference(refr.clone());
+            proc.batched_updates()
+        })
+    });
+
+    group.bench_function("bibliography", |b| {
+        let mut proc = rendered_processor(style, doc);
+        let mut toggle = false;
+        b.iter(|| {
+            toggle = !toggle;
+            let refr = if toggle { &edited_ref } else { &original_ref };
+            proc.insert_reference(refr.clone());
+            proc.get_bibliography()
+        })
+    });
+
+    group.bench_function("preview_citation_cluster", |b| {
+        let mut proc = rendered_processor(style, doc);
+        let mut positions: Vec<_> = doc
+            .clusters
+            .iter()
+            .map(|input| position(style, proc.cluster_id(&input.id), input.note))
+            .collect();
+        let mid = positions.len() / 2;
+        let note = positions[mid].note;
+        positions.insert(mid, ClusterPosition { id: None, note });
+        let cites = vec![
+            Cite::basic(doc.references[1].id.clone()),
+            Cite::basic(cited.clone()),
+        ];
+        b.iter(|| {
+            proc.preview_citation_cluster(
+                PreviewCluster::new(cites.clone(), None),
+                PreviewPosition::MarkWithZero(&positions),
+                None,
+            )
+            .unwrap()
+        })
+    });
+
+    group.bench_function("switch_locale", |b| {
+        let mut proc = rendered_processor(style, doc);
+        let langs = [
+            Lang::from_str("de-DE").unwrap(),
+            Lang::from_str("fr-FR").unwrap(),
+        ];
+        let mut toggle = false;
+        b.iter(|| {
+            toggle = !toggle;
+            let lang = if toggle { &langs[0] } else { &langs[1] };
+            proc.set_default_lang_override(Some(lang.clone()));
+            let updates = proc.batched_updates();
+            (updates, proc.get_bibliography())
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_documents(c: &mut Criterion) {
+    let doc = Document::load();
+    for style in STYLES {
+        bench_style(c, style, &doc);
+    }
+}
+
+criterion_group!(documents, bench_documents);
+criterion_main!(documents);
@@ -7,6 +7,7 @@
 #![allow(dead_code)]
 
 use super::processor::Interner;
+use super::render_error::RenderError;
 use citeproc_db::ClusterId;
 use citeproc_io::output::{markup::Markup, OutputFormat};
 use citeproc_io::{Cite, ClusterMode, SmartString};
@@ -48,9 +49,9 @@ use std::sync::Arc;
 ///               mode: Some(ClusterMode::Composite { infix: None, suppress_first: 2 }), },
 /// ]);
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(bound(
-    serialize = "Id: serde::Serialize",
+    serialize = "Id: serde::Serialize, O::Input: serde::Serialize",
     deserialize = "Id: serde::Deserialize<'de>"
 ))]
 pub struct Cluster<O: OutputFormat = Markup, Id = ClusterId> {
@@ -196,6 +197,27 @@ impl ReorderingError {
     }
 }
 
+#[doc(inline)]
+pub use citeproc_proc::disamb::{DisambExplanation, DisambStep, DisambStepReport};
+
+#[doc(inline)]
+pub use citeproc_proc::{CitationLabelConfig, Trigraph};
+
+#[doc(inline)]
+pub use citeproc_proc::{Collation, Collator};
+
+/// A reference with cites that are still ambiguous after disambiguation. See
+/// [`crate::Processor::ambiguity_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", bound(serialize = "Id: serde::Serialize"))]
+pub struct AmbiguousReference<Id = ClusterId> {
+    pub ref_id: Atom,
+    /// The references its cites could be mistaken for, in the order they were found.
+    pub collides_with: Vec<Atom>,
+    /// The clusters with ambiguous cites of this reference, in document order.
+    pub clusters: Vec<Id>,
+}
+
 pub mod string_id {
     //! This is the API using string IDs only, useful for exposing citeproc-rs to non-Rust
     //! consumers.
@@ -210,6 +232,10 @@ pub mod string_id {
 
     pub type Cluster<O = Markup> = super::Cluster<O, SmartString>;
 
+    pub type AmbiguousReference = super::AmbiguousReference<SmartString>;
+    pub type RenderError = crate::render_error::RenderError<SmartString>;
+    pub type Diagnostic = crate::diagnostics::Diagnostic<SmartString>;
+
     #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct ClusterPosition {
         pub id: Option<SmartString>,
@@ -251,6 +277,9 @@ pub mod string_id {
         /// A list of clusters that were updated, paired with the formatted output for each
         pub clusters: Vec<(SmartString, Arc<O::Output>)>,
         pub bibliography: Option<BibliographyUpdate>,
+        /// Anything that rendered as a placeholder under [`PanicMode::Placeholder`](crate::PanicMode)
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub render_errors: Vec<RenderError>,
     }
 
     #[derive(Serialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -320,6 +349,9 @@ pub struct UpdateSummary<O: OutputFormat = Markup> {
     /// A list of clusters that were updated, paired with the formatted output for each
     pub clusters: Vec<(ClusterId, Arc<O::Output>)>,
     pub bibliography: Option<BibliographyUpdate>,
+    /// Anything that rendered as a placeholder under [`PanicMode::Placeholder`](crate::PanicMode)
+    /// since the errors were last taken.
+    pub render_errors: Vec<RenderError>,
 }
 
 #[derive(Serialize, Default, Debug, Clone, PartialEq, Eq)]
@@ -359,6 +391,14 @@ pub enum SupportedFormat {
     Html,
     Rtf,
     Plain,
+    Jats,
+    Ooxml,
+    Odf,
+    /// Pandoc JSON inlines. Fetch them with [`crate::Processor::get_cluster_pandoc`] and
+    /// [`crate::Processor::get_bibliography_pandoc`]. Reference fields are parsed as Pandoc
+    /// Markdown.
+    #[cfg(feature = "pandoc")]
+    Pandoc,
 }
 
 impl Default for SupportedFormat {
@@ -373,6 +413,36 @@ impl SupportedFormat {
             SupportedFormat::Html => Markup::Html(options),
             SupportedFormat::Rtf => Markup::Rtf(options),
             SupportedFormat::Plain => Markup::Plain(options),
+            SupportedFormat::Jats => Markup::Jats(options),
+            SupportedFormat::Ooxml => Markup::Ooxml(options),
+            SupportedFormat::Odf => Markup::Odf(options),
+            #[cfg(feature = "pandoc")]
+            SupportedFormat::Pandoc => Markup::Pandoc(options),
+        }
+    }
+    pub(crate) fn from_markup(markup: &Markup) -> (Self, FormatOptions) {
+        match *markup {
+            Markup::Html(options) => (SupportedFormat::Html, options),
+            Markup::Rtf(options) => (SupportedFormat::Rtf, options),
+            Markup::Plain(options) => (SupportedFormat::Plain, options),
+            Markup::Jats(options) => (SupportedFormat::Jats, options),
+            Markup::Ooxml(options) => (SupportedFormat::Ooxml, options),
+            Markup::Odf(options) => (SupportedFormat::Odf, options),
+            #[cfg(feature = "pandoc")]
+            Markup::Pandoc(options) => (SupportedFormat::Pandoc, options),
+        }
+    }
+    /// The name [`FromStr`] accepts.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SupportedFormat::Html => "html",
+            SupportedFormat::Rtf => "rtf",
+            SupportedFormat::Plain => "plain",
+            SupportedFormat::Jats => "jats",
+            SupportedFormat::Ooxml => "ooxml",
+            SupportedFormat::Odf => "odf",
+            #[cfg(feature = "pandoc")]
+            SupportedFormat::Pandoc => "pandoc",
         }
     }
 }
@@ -384,6 +454,11 @@ impl FromStr for SupportedFormat {
             "html" => Ok(SupportedFormat::Html),
             "rtf" => Ok(SupportedFormat::Rtf),
             "plain" => Ok(SupportedFormat::Plain),
+            "jats" => Ok(SupportedFormat::Jats),
+            "ooxml" => Ok(SupportedFormat::Ooxml),
+            "odf" => Ok(SupportedFormat::Odf),
+            #[cfg(feature = "pandoc")]
+            "pandoc" => Ok(SupportedFormat::Pandoc),
             _ => Err(()),
         }
     }
@@ -401,6 +476,15 @@ impl<'de> serde::de::Deserialize<'de> for SupportedFormat {
     }
 }
 
+impl serde::Serialize for SupportedFormat {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 pub enum PreviewPosition<'a> {
     /// Convenience, if your user is merely editing a cluster.
     ReplaceCluster(ClusterId),
@@ -0,0 +1,71 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+//! Problems with the input that the processor works around, rather than failing on.
+//!
+//! A cite of a missing reference still renders, as `???`. A CSL-JSON key that isn't a CSL
+//! variable is ignored, a date that can't be parsed is rendered as written or dropped, and a term
+//! missing from the locale renders as nothing. [`Processor::diagnostics`](crate::Processor::diagnostics)
+//! lists each of these, so that you can tell the user about them.
+//!
+//! The list is worked out from the processor's current inputs when you ask for it, not recorded
+//! while rendering. Rendering is memoized, so anything recorded there would only be seen the
+//! first time a cluster or entry is rendered. This limits what can be checked for terms: see
+//! [`DiagnosticCode::MissingTerm`].
+
+use citeproc_db::ClusterId;
+use csl::Atom;
+use serde::Serialize;
+
+/// What kind of problem a [`Diagnostic`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticCode {
+    /// A cite refers to a reference the processor doesn't have.
+    MissingReference,
+    /// A reference has a CSL-JSON key that is not a CSL variable.
+    UnknownVariable,
+    /// A reference has a date that could not be parsed in full.
+    InvalidDate,
+    /// There is no locale file for the style's default language.
+    MissingLocale,
+    /// The style uses a term that the locale does not define.
+    ///
+    /// Only `<text term="..."/>` elements are checked, in every branch of the citation and
+    /// bibliography layouts and the macros they call, against the default locale. Terms that
+    /// `<label>`, `<date>` and `<number>` look up for a variable, and locales chosen by a cite or
+    /// reference `language`, are not checked.
+    MissingTerm,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic<Id = ClusterId> {
+    pub code: DiagnosticCode,
+    /// The cluster with the problem, for a `MissingReference`.
+    pub cluster_id: Option<Id>,
+    /// The reference with the problem, or the missing one.
+    pub ref_id: Option<Atom>,
+    pub message: String,
+}
+
+impl<Id> Diagnostic<Id> {
+    pub(crate) fn new(code: DiagnosticCode, message: String) -> Self {
+        Diagnostic {
+            code,
+            cluster_id: None,
+            ref_id: None,
+            message,
+        }
+    }
+
+    pub(crate) fn for_reference(code: DiagnosticCode, ref_id: Atom, message: String) -> Self {
+        Diagnostic {
+            ref_id: Some(ref_id),
+            ..Diagnostic::new(code, message)
+        }
+    }
+}
@@ -8,25 +8,46 @@
 // extern crate log;
 
 pub(crate) mod api;
+pub(crate) mod diagnostics;
+pub(crate) mod memory;
+pub(crate) mod parallel;
 pub(crate) mod processor;
+pub(crate) mod render_error;
+pub(crate) mod session;
 
 #[cfg(test)]
 mod test;
 
 pub use self::api::*;
 
+pub use self::diagnostics::{Diagnostic, DiagnosticCode};
+pub use self::memory::{GcLevel, MemoryStats, QueryGroupStats, QueryStats, SweepPolicy};
+pub use self::parallel::Parallelism;
+#[cfg(feature = "rayon")]
+pub use self::parallel::SharedSnapshot;
 pub use self::processor::{InitOptions, Processor};
+pub use self::render_error::{PanicMode, RenderError};
+pub use self::session::{ReferenceProblems, Session, SessionError, SESSION_VERSION};
 
 pub mod prelude {
     pub use crate::api::*;
+    pub use crate::diagnostics::{Diagnostic, DiagnosticCode};
+    pub use crate::memory::{GcLevel, MemoryStats, QueryGroupStats, QueryStats, SweepPolicy};
+    pub use crate::parallel::Parallelism;
+    #[cfg(feature = "rayon")]
+    pub use crate::parallel::SharedSnapshot;
     pub use crate::processor::{InitOptions, Processor};
-    pub use citeproc_db::PredefinedLocales;
+    pub use crate::render_error::{PanicMode, RenderError};
+    pub use crate::session::{ReferenceProblems, Session, SessionError, SESSION_VERSION};
+    #[cfg(feature = "bundled-locales")]
+    pub use citeproc_db::BundledLocales;
     pub use citeproc_db::{
         CiteDatabase, CiteId, ClusterNumber, IntraNote, LocaleDatabase, LocaleFetchError,
         LocaleFetcher, StyleDatabase,
     };
+    pub use citeproc_db::{LayeredLocales, LocaleDirectory, PredefinedLocales};
     pub use citeproc_io::output::{markup::Markup, OutputFormat};
-    pub use citeproc_io::{Cite, Reference, SmartString};
+    pub use citeproc_io::{Cite, Reference, SmartString, Stopwords};
     pub use citeproc_proc::db::{ImplementationDetails, IrDatabase};
     pub use csl::Atom;
 
@@ -38,6 +59,9 @@ pub mod prelude {
 pub use citeproc_io as io;
 #[doc(no_inline)]
 pub use csl;
+#[cfg(feature = "rayon")]
+#[doc(no_inline)]
+pub use rayon;
 
 pub fn random_cluster_id() -> citeproc_io::SmartString {
     use rand::distributions::Alphanumeric;
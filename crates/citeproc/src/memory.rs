@@ -0,0 +1,159 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Controlling how much memoized data a [`Processor`](crate::Processor) keeps around.
+//!
+//! Every query result is memoized until it is swept. Sweeping outdated results (those not used
+//! since the last edit) reclaims what was computed for removed references and clusters. Sweeping
+//! every value bounds memory at the cost of recomputing everything on the next render.
+
+use salsa::debug::{DebugQueryTable, TableEntry};
+use salsa::SweepStrategy;
+use serde::{Deserialize, Serialize};
+use std::mem::size_of;
+
+/// How much [`Processor::collect_garbage`](crate::Processor::collect_garbage) discards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GcLevel {
+    /// Results (and their dependency information) that were not used since the last change to
+    /// the inputs. This includes everything computed for references and clusters that have since
+    /// been removed, and interned cites and names that nothing refers to any more.
+    Outdated,
+    /// Also the results that are still current, keeping only enough to know what to recompute.
+    /// Frees the most memory, but the next render starts nearly from scratch.
+    AllValues,
+}
+
+impl Default for GcLevel {
+    fn default() -> Self {
+        GcLevel::Outdated
+    }
+}
+
+impl GcLevel {
+    pub(crate) fn strategies(self) -> &'static [fn() -> SweepStrategy] {
+        fn outdated() -> SweepStrategy {
+            SweepStrategy::discard_outdated()
+        }
+        fn all_values() -> SweepStrategy {
+            // Interned queries ignore sweep_all_revisions, and only ever discard outdated keys.
+            SweepStrategy::default()
+                .discard_values()
+                .sweep_all_revisions()
+        }
+        match self {
+            GcLevel::Outdated => &[outdated],
+            GcLevel::AllValues => &[outdated, all_values],
+        }
+    }
+}
+
+/// When [`Processor::compute`](crate::Processor::compute) (and so `batched_updates`) collects
+/// garbage on its own.
+///
+/// The default sweeps outdated results after every call. Long-running sessions that render
+/// often may prefer to sweep less often, or to set `every` to zero and call
+/// [`Processor::collect_garbage`](crate::Processor::collect_garbage) when idle.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SweepPolicy {
+    /// Collect garbage after this many calls to `compute`. Zero means never.
+    pub every: u32,
+    pub level: GcLevel,
+}
+
+impl Default for SweepPolicy {
+    fn default() -> Self {
+        SweepPolicy {
+            every: 1,
+            level: GcLevel::Outdated,
+        }
+    }
+}
+
+impl SweepPolicy {
+    /// Never collect garbage automatically.
+    pub const MANUAL: SweepPolicy = SweepPolicy {
+        every: 0,
+        level: GcLevel::Outdated,
+    };
+}
+
+/// What a [`Processor`](crate::Processor) is holding on to, from
+/// [`Processor::memory_stats`](crate::Processor::memory_stats).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryStats {
+    /// Disambiguation, sorting and rendering.
+    pub ir: QueryGroupStats,
+    /// References, clusters and cites.
+    pub cite: QueryGroupStats,
+    /// Locales.
+    pub locale: QueryGroupStats,
+    /// The style.
+    pub style: QueryGroupStats,
+}
+
+impl MemoryStats {
+    pub fn memoized(&self) -> usize {
+        self.groups().map(|g| g.memoized).sum()
+    }
+    pub fn shallow_bytes(&self) -> usize {
+        self.groups().map(|g| g.shallow_bytes).sum()
+    }
+    fn groups(&self) -> impl Iterator<Item = &QueryGroupStats> {
+        std::iter::once(&self.ir)
+            .chain(std::iter::once(&self.cite))
+            .chain(std::iter::once(&self.locale))
+            .chain(std::iter::once(&self.style))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryGroupStats {
+    /// Entries that currently hold a value.
+    pub memoized: usize,
+    /// See [`QueryStats::shallow_bytes`].
+    pub shallow_bytes: usize,
+    pub queries: Vec<QueryStats>,
+}
+
+impl QueryGroupStats {
+    pub(crate) fn new(queries: Vec<QueryStats>) -> Self {
+        QueryGroupStats {
+            memoized: queries.iter().map(|q| q.memoized).sum(),
+            shallow_bytes: queries.iter().map(|q| q.shallow_bytes).sum(),
+            queries,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStats {
+    pub name: &'static str,
+    /// Keys the query has seen, including those whose values were discarded.
+    pub entries: usize,
+    /// Entries that currently hold a value. For interned queries, live interned keys.
+    pub memoized: usize,
+    /// `size_of` the key for each entry, plus `size_of` the value for each memoized entry. Nothing
+    /// the keys and values point to is counted, and most values are `Arc`s, so this is a count of
+    /// entries weighted by how big their slots are, not the memory the query is holding.
+    pub shallow_bytes: usize,
+}
+
+pub(crate) fn query_stats<T: DebugQueryTable>(name: &'static str, table: T) -> QueryStats {
+    let entries: Vec<TableEntry<T::Key, T::Value>> = table.entries();
+    let memoized = entries.iter().filter(|e| e.value.is_some()).count();
+    QueryStats {
+        name,
+        entries: entries.len(),
+        memoized,
+        shallow_bytes: entries.len() * size_of::<T::Key>() + memoized * size_of::<T::Value>(),
+    }
+}
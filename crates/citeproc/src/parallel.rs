@@ -0,0 +1,114 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Choosing which threads a [`Processor`](crate::Processor) renders on, and sharing one processor's state between
+//! many threads. Everything beyond [`Parallelism::Serial`] needs the `parallel` feature.
+
+#[cfg(feature = "rayon")]
+use crate::api::BibEntry;
+#[cfg(feature = "rayon")]
+use crate::Processor;
+#[cfg(feature = "rayon")]
+use citeproc_db::ClusterId;
+#[cfg(feature = "rayon")]
+use citeproc_io::SmartString;
+#[cfg(feature = "rayon")]
+use csl::Atom;
+#[cfg(feature = "rayon")]
+use salsa::{ParallelDatabase, Snapshot};
+#[cfg(feature = "rayon")]
+use std::sync::{Arc, Mutex};
+
+/// Where [`Processor::compute`](crate::Processor::compute) (and so `batched_updates`) and
+/// [`Processor::get_bibliography`](crate::Processor::get_bibliography) do their work.
+#[derive(Debug, Clone)]
+pub enum Parallelism {
+    /// On the calling thread.
+    ///
+    /// With the `parallel` feature, disambiguation still checks candidate references using
+    /// whichever rayon pool it is called from (the global one, from a thread of your own). For a
+    /// processor that never leaves one thread, use a [`Parallelism::Pool`] with one thread.
+    Serial,
+    /// Rayon's global thread pool. The default with the `parallel` feature.
+    #[cfg(feature = "rayon")]
+    GlobalPool,
+    /// A thread pool of your own, e.g. to share a fixed number of threads between many
+    /// processors.
+    #[cfg(feature = "rayon")]
+    Pool(Arc<rayon::ThreadPool>),
+}
+
+impl Default for Parallelism {
+    #[cfg(feature = "rayon")]
+    fn default() -> Self {
+        Parallelism::GlobalPool
+    }
+    #[cfg(not(feature = "rayon"))]
+    fn default() -> Self {
+        Parallelism::Serial
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl Parallelism {
+    /// A new pool with `num_threads` threads. Zero lets rayon choose, usually one per CPU.
+    pub fn with_threads(num_threads: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|ix| format!("citeproc-{}", ix))
+            .build()?;
+        Ok(Parallelism::Pool(Arc::new(pool)))
+    }
+}
+
+/// A read-only view of a [`Processor`], frozen at the time of
+/// [`Processor::shared_snapshot`], that any number of threads can render from at once.
+///
+/// Each call takes its own salsa snapshot, so work done by one thread (e.g. disambiguating a
+/// cite) is reused by the others, and by the processor itself once the snapshot is gone.
+///
+/// While a `SharedSnapshot` (or anything from [`SharedSnapshot::fork`]) is alive, every
+/// `&mut self` method on the processor blocks until it is dropped. Calling one on the thread
+/// holding the snapshot deadlocks.
+#[cfg(feature = "rayon")]
+pub struct SharedSnapshot {
+    base: Mutex<Snapshot<Processor>>,
+}
+
+#[cfg(feature = "rayon")]
+impl SharedSnapshot {
+    pub(crate) fn new(processor: &Processor) -> Self {
+        SharedSnapshot {
+            base: Mutex::new(processor.snapshot()),
+        }
+    }
+
+    /// A snapshot for the current thread, which dereferences to a [`Processor`]. Use it for
+    /// anything not wrapped here, and to make several calls without going through the lock.
+    pub fn fork(&self) -> Snapshot<Processor> {
+        self.base.lock().unwrap().snapshot()
+    }
+
+    /// See [`Processor::get_cluster`].
+    pub fn get_cluster(&self, cluster_id: ClusterId) -> Option<Arc<SmartString>> {
+        self.fork().get_cluster(cluster_id)
+    }
+
+    /// See [`Processor::get_cluster_str`].
+    pub fn get_cluster_str(&self, cluster_id: &str) -> Option<Arc<SmartString>> {
+        self.fork().get_cluster_str(cluster_id)
+    }
+
+    /// See [`Processor::get_bib_item`].
+    pub fn get_bib_item(&self, ref_id: Atom) -> Arc<SmartString> {
+        self.fork().get_bib_item(ref_id)
+    }
+
+    /// See [`Processor::get_bibliography`].
+    pub fn get_bibliography(&self) -> Vec<BibEntry> {
+        self.fork().get_bibliography()
+    }
+}
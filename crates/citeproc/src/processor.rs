@@ -11,27 +11,37 @@
 use crate::prelude::*;
 
 use crate::api::{
-    string_id, BibEntry, BibliographyMeta, BibliographyUpdate, ClusterPosition, IncludeUncited,
-    ReorderingError, SecondFieldAlign, UpdateSummary,
+    string_id, AmbiguousReference, BibEntry, BibliographyMeta, BibliographyUpdate, ClusterPosition,
+    IncludeUncited, ReorderingError, SecondFieldAlign, UpdateSummary,
 };
+use crate::memory::{query_stats, GcLevel, MemoryStats, QueryGroupStats, SweepPolicy};
+use crate::parallel::Parallelism;
+#[cfg(feature = "rayon")]
+use crate::parallel::SharedSnapshot;
+use crate::render_error::{catch_panic, PanicMode, RenderError};
+use crate::session::{feature_names, ReferenceProblems, Session, SessionError, SESSION_VERSION};
 use citeproc_db::{
     CiteData, CiteDatabaseStorage, HasFetcher, LocaleDatabaseStorage, StyleDatabaseStorage, Uncited,
 };
-use citeproc_io::output::markup::FormatOptions;
+use citeproc_io::output::markup::{BibParagraph, FormatOptions};
 use citeproc_proc::db::IrDatabaseStorage;
-use citeproc_proc::BibNumber;
+use citeproc_proc::{BibNumber, CitationLabelConfig, Collation};
 use indexmap::set::IndexSet;
+use indexmap::IndexMap;
+#[cfg(feature = "pandoc")]
+use pandoc_types::definition::Inline;
 
-use salsa::{Database, Durability, SweepStrategy};
+use salsa::{Database, Durability};
 #[cfg(feature = "rayon")]
 use salsa::{ParallelDatabase, Snapshot};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::sync::{Mutex, RwLock};
 
-use csl::{Lang, Style, StyleError};
+use csl::{InvalidCsl, Lang, LocaleSource, Style, StyleError};
 
 use citeproc_io::output::{markup::Markup, OutputFormat};
-use citeproc_io::{Cite, ClusterMode, Reference, SmartString};
+use citeproc_io::{Cite, ClusterMode, Reference, SmartString, StopwordTable};
 use csl::Atom;
 
 use string_interner::{backend::StringBackend, StringInterner};
@@ -73,6 +83,15 @@ pub struct Processor {
     last_clusters: Arc<Mutex<FnvHashMap<ClusterId, Arc<SmartString>>>>,
     interner: Arc<RwLock<Interner>>,
     preview_cluster_id: ClusterId,
+    sweep_policy: SweepPolicy,
+    computes_since_sweep: Arc<AtomicU32>,
+    parallelism: Parallelism,
+    panic_mode: PanicMode,
+    render_errors: Arc<Mutex<Vec<RenderError>>>,
+    /// The style as it was given, and any features enabled with it, for
+    /// [`Processor::export_session`].
+    style_text: Arc<str>,
+    csl_features: Option<csl::Features>,
 }
 
 impl Database for Processor {}
@@ -89,6 +108,13 @@ impl ParallelDatabase for Processor {
             last_clusters: self.last_clusters.clone(),
             interner: self.interner.clone(),
             preview_cluster_id: self.preview_cluster_id,
+            sweep_policy: self.sweep_policy,
+            computes_since_sweep: self.computes_since_sweep.clone(),
+            parallelism: self.parallelism.clone(),
+            panic_mode: self.panic_mode.clone(),
+            render_errors: self.render_errors.clone(),
+            style_text: self.style_text.clone(),
+            csl_features: self.csl_features.clone(),
         })
     }
 }
@@ -109,6 +135,56 @@ impl ImplementationDetails for Processor {
     }
 }
 
+fn upsert_diff(
+    into_h: &mut FnvHashMap<ClusterId, Arc<SmartString>>,
+    id: ClusterId,
+    built: Arc<SmartString>,
+) -> Option<(ClusterId, Arc<SmartString>)> {
+    let mut diff = None;
+    into_h
+        .entry(id)
+        .and_modify(|existing| {
+            if built != *existing {
+                diff = Some((id, built.clone()));
+            }
+            *existing = built.clone();
+        })
+        .or_insert_with(|| {
+            diff = Some((id, built.clone()));
+            built
+        });
+    diff
+}
+
+#[cfg(feature = "rayon")]
+fn compute_parallel(snap: Snap) -> Vec<(ClusterId, Arc<SmartString>)> {
+    use rayon::prelude::*;
+    use std::ops::DerefMut;
+
+    let clusters = snap.0.clusters_cites_sorted();
+    let cite_ids = snap.0.all_cite_ids();
+    // compute ir2s, so the first year_suffixes call doesn't trigger all ir2s on a
+    // single rayon thread. If this panics, so will the clusters that need it, and they get the
+    // render errors.
+    let _ = catch_panic(&snap.0.panic_mode, || {
+        cite_ids
+            .par_iter()
+            .for_each_with(snap.clone(), |snap, &cite_id| {
+                snap.0.ir_gen2_add_given_name(cite_id);
+            });
+        snap.0.year_suffixes();
+    });
+    clusters
+        .par_iter()
+        .map_with(snap, |snap, cluster| {
+            let built = snap.0.render_cluster(cluster.id);
+            let mut into_hashmap = snap.0.last_clusters.lock().unwrap();
+            upsert_diff(into_hashmap.deref_mut(), cluster.id, built)
+        })
+        .filter_map(|x| x)
+        .collect()
+}
+
 // need a Clone impl for map_with
 // thanks to rust-analyzer for the tip
 #[cfg(feature = "rayon")]
@@ -133,7 +209,9 @@ pub struct InitOptions<'a> {
     pub style: &'a str,
     /// You might get this from a dependent style via `StyleMeta::parse(dependent_xml_string)`
     pub locale_override: Option<Lang>,
-    /// Mechanism for fetching the locale you provide, if necessary.
+    /// Mechanism for fetching the locale you provide, if necessary. Defaults to
+    /// [BundledLocales](citeproc_db::BundledLocales) with the `bundled-locales` feature, and
+    /// otherwise to a fetcher that only has en-US.
     pub fetcher: Option<Arc<dyn LocaleFetcher>>,
 
     /// Which csl features to enable globally. Using the `<features>` declaration is highly
@@ -146,10 +224,39 @@ pub struct InitOptions<'a> {
     /// Disables sorting on the bibliography (enabled by default)
     pub bibliography_no_sort: bool,
 
+    /// How to generate `citation-label` for references that don't have one, and whether to give
+    /// colliding labels suffixes.
+    pub citation_label: CitationLabelConfig,
+
+    /// How to compare sort keys. Defaults to the CLDR collation for the style's default locale
+    /// (see [`Collation::for_lang`]). Pass [`Collation::new`] to use a collator of your own.
+    pub collation: Option<Collation>,
+
+    /// When to collect garbage after rendering. Defaults to sweeping outdated results every time.
+    pub sweep_policy: SweepPolicy,
+
+    /// Which threads to render on. Defaults to rayon's global pool with the `parallel` feature,
+    /// and the calling thread without it.
+    pub parallelism: Parallelism,
+
+    /// Whether to render a placeholder instead of panicking when a cluster or bibliography entry
+    /// fails. See [`PanicMode`].
+    pub panic_mode: PanicMode,
+
     #[doc(hidden)]
     pub use_default_default: private::CannotConstruct,
 }
 
+#[cfg(feature = "bundled-locales")]
+fn default_fetcher() -> Arc<dyn LocaleFetcher> {
+    Arc::new(citeproc_db::BundledLocales)
+}
+
+#[cfg(not(feature = "bundled-locales"))]
+fn default_fetcher() -> Arc<dyn LocaleFetcher> {
+    Arc::new(citeproc_db::PredefinedLocales::bundled_en_us())
+}
+
 mod private {
     #[derive(Clone, Default)]
     #[non_exhaustive]
@@ -170,6 +277,13 @@ impl Processor {
             // This uses DefaultBackend, which is
             interner: Arc::new(RwLock::new(interner)),
             preview_cluster_id,
+            sweep_policy: SweepPolicy::default(),
+            computes_since_sweep: Arc::new(AtomicU32::new(0)),
+            parallelism: Parallelism::default(),
+            panic_mode: PanicMode::default(),
+            render_errors: Arc::new(Mutex::new(Vec::new())),
+            style_text: Arc::from(""),
+            csl_features: None,
         };
         citeproc_db::safe_default(&mut db);
         citeproc_proc::safe_default(&mut db);
@@ -190,24 +304,35 @@ impl Processor {
             csl_features,
             test_mode,
             bibliography_no_sort,
+            citation_label,
+            collation,
+            sweep_policy,
+            parallelism,
+            panic_mode,
             use_default_default: _,
         } = options;
 
-        let fetcher =
-            fetcher.unwrap_or_else(|| Arc::new(citeproc_db::PredefinedLocales::bundled_en_us()));
+        let fetcher = fetcher.unwrap_or_else(default_fetcher);
         let mut db = Processor::safe_default(fetcher);
-        let style = Style::parse_with_opts(
+        let parsed = Style::parse_with_opts(
             &style,
             csl::ParseOptions {
                 allow_no_info: test_mode,
-                features: csl_features,
+                features: csl_features.clone(),
                 ..Default::default()
             },
         )?;
-        db.set_style_with_durability(Arc::new(style), Durability::HIGH);
+        db.set_style_with_durability(Arc::new(parsed), Durability::HIGH);
+        db.style_text = Arc::from(style);
+        db.csl_features = csl_features;
         db.set_output_format(format, format_options);
         db.set_default_lang_override_with_durability(locale_override, Durability::HIGH);
         db.set_bibliography_no_sort_with_durability(bibliography_no_sort, Durability::HIGH);
+        db.set_citation_label_config_with_durability(Arc::new(citation_label), Durability::HIGH);
+        db.set_collation_override_with_durability(collation, Durability::HIGH);
+        db.sweep_policy = sweep_policy;
+        db.parallelism = parallelism;
+        db.panic_mode = panic_mode;
         Ok(db)
     }
 
@@ -223,91 +348,330 @@ impl Processor {
         self.set_formatter_with_durability(formatter, Durability::HIGH);
     }
 
+    /// Configures the title-case stopwords for a language. By default, title case is only applied
+    /// to English text; setting stopwords for another language enables it for that language, and
+    /// setting them for English replaces the built-in list. Pass `None` to remove a configuration.
+    ///
+    /// [`Stopwords::builtin`] has lists for some languages. Stopwords set for `de` also apply to
+    /// `de-AT`, etc.
+    pub fn set_title_case_stopwords(&mut self, lang: Lang, stopwords: Option<Stopwords>) {
+        let mut table = (*self.title_case_stopwords()).clone();
+        match stopwords {
+            Some(stopwords) => table.insert(lang, stopwords),
+            None => {
+                if !table.remove(&lang) {
+                    return;
+                }
+            }
+        }
+        self.set_title_case_stopwords_with_durability(Arc::new(table), Durability::HIGH);
+    }
+
+    /// Changes how `citation-label` is generated. See [`InitOptions::citation_label`].
+    pub fn set_citation_label_config(&mut self, config: CitationLabelConfig) {
+        if *self.citation_label_config() == config {
+            return;
+        }
+        self.set_citation_label_config_with_durability(Arc::new(config), Durability::HIGH);
+    }
+
+    /// Changes how sort keys are compared. `None` goes back to the built-in collation for the
+    /// style's default locale. See [`InitOptions::collation`].
+    pub fn set_collation(&mut self, collation: Option<Collation>) {
+        if self.collation_override() == collation {
+            return;
+        }
+        self.set_collation_override_with_durability(collation, Durability::HIGH);
+    }
+
     /// Sets the CSL style to be used. Will require nearly everything to be recomputed, so call sparingly.
     pub fn set_style_text(&mut self, style_text: &str) -> Result<(), StyleError> {
         let style = Style::parse(style_text)?;
         self.set_style_with_durability(Arc::new(style), Durability::HIGH);
+        self.style_text = Arc::from(style_text);
+        self.csl_features = None;
         Ok(())
     }
 
+    /// Runs [`csl::lint::lint_style`] over some style text, checking terms against the current
+    /// default locale and enabling the current style's features. Does not change the style in use.
+    pub fn lint_style(&self, style_text: &str) -> Result<Vec<InvalidCsl>, StyleError> {
+        let options = csl::ParseOptions {
+            features: Some(self.style().features.clone()),
+            ..Default::default()
+        };
+        csl::lint::lint_style(style_text, options, Some(&self.default_locale()))
+    }
+
     #[cfg(feature = "rayon")]
     fn snap(&self) -> Snap {
         Snap(self.snapshot())
     }
 
+    /// Runs `op` with a snapshot on the configured thread pool, or returns None if the processor
+    /// is configured to work serially.
+    #[cfg(feature = "rayon")]
+    fn in_pool<R: Send>(&self, op: impl FnOnce(Snap) -> R + Send) -> Option<R> {
+        match &self.parallelism {
+            Parallelism::Serial => None,
+            Parallelism::GlobalPool => Some(op(self.snap())),
+            Parallelism::Pool(pool) => {
+                let snap = self.snap();
+                Some(pool.install(move || op(snap)))
+            }
+        }
+    }
+
+    /// Changes which threads the processor renders on.
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.parallelism
+    }
+
+    /// A handle for rendering from many threads at once, without a `&mut` anywhere. It sees the
+    /// processor as it is now; see [`SharedSnapshot`] for how it interacts with later edits.
+    #[cfg(feature = "rayon")]
+    pub fn shared_snapshot(&self) -> SharedSnapshot {
+        SharedSnapshot::new(self)
+    }
+
     // TODO: This might not play extremely well with Salsa's garbage collector,
     // which will have a new revision number for each built_cluster call.
     // Probably better to have this as a real query.
     pub fn compute(&self) -> Vec<(ClusterId, Arc<SmartString>)> {
-        fn upsert_diff(
-            into_h: &mut FnvHashMap<ClusterId, Arc<SmartString>>,
-            id: ClusterId,
-            built: Arc<SmartString>,
-        ) -> Option<(ClusterId, Arc<SmartString>)> {
-            let mut diff = None;
-            into_h
-                .entry(id)
-                .and_modify(|existing| {
-                    if built != *existing {
-                        diff = Some((id, built.clone()));
-                    }
-                    *existing = built.clone();
-                })
-                .or_insert_with(|| {
-                    diff = Some((id, built.clone()));
-                    built
-                });
-            diff
-        }
-
-        let clusters = self.clusters_cites_sorted();
-
         #[cfg(feature = "rayon")]
-        let result = {
-            use rayon::prelude::*;
-            use std::ops::DerefMut;
-
-            let cite_ids = self.all_cite_ids();
-            // compute ir2s, so the first year_suffixes call doesn't trigger all ir2s on a
-            // single rayon thread
-            cite_ids
-                .par_iter()
-                .for_each_with(self.snap(), |snap, &cite_id| {
-                    snap.0.ir_gen2_add_given_name(cite_id);
-                });
-            self.year_suffixes();
-            clusters
-                .par_iter()
-                .map_with(self.snap(), |snap, cluster| {
-                    let built = snap.0.built_cluster(cluster.id);
-                    let mut into_hashmap = snap.0.last_clusters.lock().unwrap();
-                    upsert_diff(into_hashmap.deref_mut(), cluster.id, built)
-                })
-                .filter_map(|x| x)
-                .collect()
-        };
+        let parallel = self.in_pool(compute_parallel);
         #[cfg(not(feature = "rayon"))]
-        let result = {
+        let parallel = None;
+
+        let result = parallel.unwrap_or_else(|| {
+            let clusters = self.clusters_cites_sorted();
             let mut into_hashmap = self.last_clusters.lock().unwrap();
             clusters
                 .iter()
                 .filter_map(|cluster| {
-                    let built = self.built_cluster(cluster.id);
+                    let built = self.render_cluster(cluster.id);
                     upsert_diff(&mut into_hashmap, cluster.id, built)
                 })
                 .collect()
-        };
+        });
 
-        // Run salsa GC.
-        self.sweep_all(SweepStrategy::discard_outdated());
+        self.sweep_after_compute();
         result
     }
 
+    fn sweep_after_compute(&self) {
+        let SweepPolicy { every, level } = self.sweep_policy;
+        if every == 0 {
+            return;
+        }
+        let computes = self.computes_since_sweep.fetch_add(1, Ordering::Relaxed) + 1;
+        if computes >= every {
+            self.collect_garbage(level);
+        }
+    }
+
+    /// Changes when [`Processor::compute`] collects garbage by itself.
+    pub fn set_sweep_policy(&mut self, policy: SweepPolicy) {
+        self.sweep_policy = policy;
+    }
+
+    pub fn sweep_policy(&self) -> SweepPolicy {
+        self.sweep_policy
+    }
+
+    /// Changes what happens when a cluster or bibliography entry panics while rendering.
+    pub fn set_panic_mode(&mut self, mode: PanicMode) {
+        self.panic_mode = mode;
+    }
+
+    pub fn panic_mode(&self) -> &PanicMode {
+        &self.panic_mode
+    }
+
+    /// Removes and returns the render errors recorded since they were last taken, whether here or
+    /// by `batched_updates`.
+    pub fn take_render_errors(&self) -> Vec<RenderError> {
+        std::mem::take(&mut *self.render_errors.lock().unwrap())
+    }
+
+    pub fn take_render_errors_str(&self) -> Vec<string_id::RenderError> {
+        let errors = self.take_render_errors();
+        let interner = self.interner.read().unwrap();
+        errors
+            .into_iter()
+            .map(|error| string_id::RenderError {
+                cluster_id: error
+                    .cluster_id
+                    .and_then(|id| interner.resolve(id))
+                    .map(SmartString::from),
+                ref_id: error.ref_id,
+                message: error.message,
+            })
+            .collect()
+    }
+
+    fn report_render_error(&self, error: RenderError) {
+        log::warn!("rendered a placeholder: {:?}", error);
+        self.render_errors.lock().unwrap().push(error);
+    }
+
+    /// The placeholder text, written by `fmt` like any other text, so it is escaped for HTML and
+    /// RTF and is a valid Pandoc inline.
+    fn placeholder(&self, fmt: &Markup) -> Arc<MarkupOutput> {
+        Arc::new(fmt.output(self.placeholder_build(fmt), false))
+    }
+
+    fn placeholder_build(&self, fmt: &Markup) -> <Markup as OutputFormat>::Build {
+        match &self.panic_mode {
+            PanicMode::Placeholder(text) => fmt.plain(text),
+            PanicMode::Propagate => unreachable!("caught a panic without PanicMode::Placeholder"),
+        }
+    }
+
+    /// `built_cluster`, or the placeholder if it panics.
+    fn render_cluster(&self, cluster_id: ClusterId) -> Arc<MarkupOutput> {
+        match catch_panic(&self.panic_mode, || self.built_cluster(cluster_id)) {
+            Ok(built) => built,
+            Err(message) => {
+                self.report_cluster_error(Some(cluster_id), cluster_id, message);
+                self.placeholder(&self.get_formatter())
+            }
+        }
+    }
+
+    /// Records a cluster that failed, blaming the first of its cites that also fails when
+    /// rendered on its own.
+    fn report_cluster_error(
+        &self,
+        reported_id: Option<ClusterId>,
+        cluster_id: ClusterId,
+        message: String,
+    ) {
+        let ref_id = self
+            .cluster_cites(cluster_id)
+            .iter()
+            .find(|&&cite_id| {
+                catch_panic(&self.panic_mode, || self.ir_fully_disambiguated(cite_id)).is_err()
+            })
+            .map(|cite_id| cite_id.lookup(self).ref_id.clone());
+        self.report_render_error(RenderError {
+            cluster_id: reported_id,
+            ref_id,
+            message,
+        });
+    }
+
+    /// Discards memoized results. After removing references or clusters, this is what frees the
+    /// memory used to render them.
+    pub fn collect_garbage(&self, level: GcLevel) {
+        // Cites in clusters that aren't currently in the document are still reachable, but
+        // haven't been looked at lately. Look at them, so their interned data survives the sweep.
+        for &cluster_id in self.all_cluster_ids().iter() {
+            for cite_id in self.cluster_cites(cluster_id).iter() {
+                cite_id.lookup(self);
+            }
+        }
+        for strategy in level.strategies() {
+            self.sweep_all(strategy());
+        }
+        self.computes_since_sweep.store(0, Ordering::Relaxed);
+    }
+
+    /// Counts the entries and memoized results of each query, grouped by query group.
+    pub fn memory_stats(&self) -> MemoryStats {
+        use citeproc_db as cdb;
+        use citeproc_proc::db as pdb;
+        macro_rules! group {
+            ($($query:ident from $module:ident),* $(,)?) => {
+                QueryGroupStats::new(vec![
+                    $(query_stats(stringify!($query), $module::$query.in_db(self)),)*
+                ])
+            };
+        }
+        MemoryStats {
+            ir: group![
+                RefDfaQuery from pdb,
+                EdgeStreamMatchesRefQuery from pdb,
+                IrGen0Query from pdb,
+                IrGen2AddGivenNameQuery from pdb,
+                IrGen2MatchingRefsQuery from pdb,
+                YearSuffixesQuery from pdb,
+                YearSuffixForQuery from pdb,
+                IrFullyDisambiguatedQuery from pdb,
+                DisambExplanationQuery from pdb,
+                BuiltClusterQuery from pdb,
+                IntextQuery from pdb,
+                BibItemGen0Query from pdb,
+                BibItemQuery from pdb,
+                GetBibliographyMapQuery from pdb,
+                BibMaxOffsetQuery from pdb,
+                BranchRunsQuery from pdb,
+                AllPersonNamesQuery from pdb,
+                DisambiguatedPersonNamesQuery from pdb,
+                DisambNameQuery from pdb,
+                ClustersCitesSortedQuery from pdb,
+                ClusterDataSortedQuery from pdb,
+                ClusterCitesSortedQuery from pdb,
+                CitePositionsQuery from pdb,
+                CitePositionQuery from pdb,
+                SortedRefsQuery from pdb,
+                BibliographyNoSortQuery from pdb,
+                TitleCaseStopwordsQuery from pdb,
+                CitationLabelConfigQuery from pdb,
+                CitationLabelBaseQuery from pdb,
+                CitationLabelSuffixesQuery from pdb,
+                CitationLabelQuery from pdb,
+                CollationOverrideQuery from pdb,
+                CollationQuery from pdb,
+                BibNumberQuery from pdb,
+            ],
+            cite: group![
+                ReferenceInputQuery from cdb,
+                ReferenceQuery from cdb,
+                AllKeysQuery from cdb,
+                AllUncitedQuery from cdb,
+                AllClusterIdsQuery from cdb,
+                ClustersOrderedQuery from cdb,
+                ClusterNoteNumberQuery from cdb,
+                ClusterModeQuery from cdb,
+                ClusterCitesQuery from cdb,
+                CiteQuery from cdb,
+                GhostCiteQuery from cdb,
+                CitedKeysQuery from cdb,
+                DisambParticipantsQuery from cdb,
+                AllCiteIdsQuery from cdb,
+                ClustersSortedQuery from cdb,
+            ],
+            locale: group![
+                LocaleInputXmlQuery from cdb,
+                LocaleInputLangsQuery from cdb,
+                DefaultLangOverrideQuery from cdb,
+                InlineLocaleQuery from cdb,
+                ParsedLocaleQuery from cdb,
+                MergedLocaleQuery from cdb,
+                DefaultLocaleQuery from cdb,
+                RawDateParserQuery from cdb,
+            ],
+            style: group![
+                StyleQuery from cdb,
+                FormatterQuery from cdb,
+                NameInfoCitationQuery from cdb,
+                NameInfoBibliographyQuery from cdb,
+                NameConfigurationsQuery from cdb,
+            ],
+        }
+    }
+
     pub fn batched_updates(&self) -> UpdateSummary {
         let delta = self.compute();
         UpdateSummary {
             clusters: delta,
             bibliography: self.save_and_diff_bibliography(),
+            render_errors: self.take_render_errors(),
         }
     }
 
@@ -320,9 +684,11 @@ impl Processor {
                 delta_str.push((SmartString::from(resolved), neu));
             }
         }
+        drop(interner);
         string_id::UpdateSummary {
             clusters: delta_str,
             bibliography: self.save_and_diff_bibliography(),
+            render_errors: self.take_render_errors_str(),
         }
     }
 
@@ -567,7 +933,7 @@ impl Processor {
     /// Returns None if the cluster has not been assigned a position in the document.
     pub fn get_cluster(&self, cluster_id: ClusterId) -> Option<Arc<MarkupOutput>> {
         if self.cluster_note_number(cluster_id).is_some() {
-            Some(self.built_cluster(cluster_id))
+            Some(self.render_cluster(cluster_id))
         } else {
             None
         }
@@ -584,15 +950,120 @@ impl Processor {
     }
 
     pub fn get_bib_item(&self, ref_id: Atom) -> Arc<MarkupOutput> {
-        self.bib_item(ref_id)
+        self.render_bib_item(ref_id)
+    }
+
+    /// `bib_item`, or the placeholder if it panics.
+    fn render_bib_item(&self, ref_id: Atom) -> Arc<MarkupOutput> {
+        match catch_panic(&self.panic_mode, || self.bib_item(ref_id.clone())) {
+            Ok(built) => built,
+            Err(message) => {
+                self.report_render_error(RenderError {
+                    cluster_id: None,
+                    ref_id: Some(ref_id),
+                    message,
+                });
+                self.placeholder(&self.get_formatter())
+            }
+        }
+    }
+
+    /// `sorted_refs` and `get_bibliography_map`. If the map panics, renders each entry on its own
+    /// instead, with placeholders for the ones that fail.
+    fn render_bibliography(
+        &self,
+    ) -> (
+        Arc<(Vec<Atom>, FnvHashMap<Atom, BibNumber>)>,
+        Arc<FnvHashMap<Atom, Arc<MarkupOutput>>>,
+    ) {
+        let message = match catch_panic(&self.panic_mode, || self.get_bibliography_map()) {
+            Ok(map) => return (self.sorted_refs(), map),
+            Err(message) => message,
+        };
+        let errors_before = self.render_errors.lock().unwrap().len();
+        let fmt = self.get_formatter();
+        let (sorted_refs, entries) =
+            self.bibliography_by_entry(&fmt, |build, piq| fmt.output(build, piq));
+        let map: FnvHashMap<_, _> = entries
+            .into_iter()
+            .filter(|(_, string)| !string.is_empty())
+            .map(|(ref_id, string)| (ref_id, Arc::new(string)))
+            .collect();
+        if self.render_errors.lock().unwrap().len() == errors_before {
+            // Every entry was fine, so it was the bibliography as a whole.
+            self.report_render_error(RenderError {
+                cluster_id: None,
+                ref_id: None,
+                message,
+            });
+        }
+        (sorted_refs, Arc::new(map))
+    }
+
+    /// Like `bibliography_before_output`, including subsequent-author-substitute, but writes each
+    /// entry with `output` and catches panics one entry at a time. An entry that panics is
+    /// reported and replaced with the placeholder.
+    fn bibliography_by_entry<T>(
+        &self,
+        fmt: &Markup,
+        output: impl Fn(MarkupBuild, bool) -> T,
+    ) -> (
+        Arc<(Vec<Atom>, FnvHashMap<Atom, BibNumber>)>,
+        Vec<(Atom, T)>,
+    ) {
+        let sorted_refs = match catch_panic(&self.panic_mode, || self.sorted_refs()) {
+            Ok(sorted_refs) => sorted_refs,
+            Err(message) => {
+                self.report_render_error(RenderError {
+                    cluster_id: None,
+                    ref_id: None,
+                    message,
+                });
+                return Default::default();
+            }
+        };
+        let mut substitution = citeproc_proc::BibSubstitution::default();
+        let entries = sorted_refs
+            .0
+            .iter()
+            .filter_map(|ref_id| {
+                catch_panic(&self.panic_mode, || {
+                    substitution
+                        .next_entry(self, ref_id.clone(), fmt)
+                        .map(|build| output(build, citeproc_proc::get_piq(self)))
+                })
+                .unwrap_or_else(|message| {
+                    self.report_render_error(RenderError {
+                        cluster_id: None,
+                        ref_id: Some(ref_id.clone()),
+                        message,
+                    });
+                    Some(output(self.placeholder_build(fmt), false))
+                })
+                .map(|entry| (ref_id.clone(), entry))
+            })
+            .collect();
+        (sorted_refs, entries)
     }
 
     pub fn get_bibliography_meta(&self) -> Option<BibliographyMeta> {
         let style = self.get_style();
         style.bibliography.as_ref().map(|bib| {
+            // Zero if rendering the bibliography panics. That is reported by get_bibliography.
+            let max_offset = if bib.second_field_align.is_some() {
+                catch_panic(&self.panic_mode, || self.bib_max_offset()).unwrap_or(0)
+            } else {
+                0
+            };
+            let paragraph = BibParagraph {
+                hanging_indent: bib.hanging_indent,
+                line_spacing: bib.line_spacing,
+                entry_spacing: bib.entry_spacing,
+                second_field_align: bib.second_field_align,
+                max_offset,
+            };
             BibliographyMeta {
-                // TODO
-                max_offset: 0,
+                max_offset,
                 entry_spacing: bib.entry_spacing,
                 line_spacing: bib.line_spacing,
                 hanging_indent: bib.hanging_indent,
@@ -601,17 +1072,37 @@ impl Processor {
                     csl::style::SecondFieldAlign::Flush => SecondFieldAlign::Flush,
                     csl::style::SecondFieldAlign::Margin => SecondFieldAlign::Margin,
                 }),
-                format_meta: self.get_formatter().meta(),
+                format_meta: self.get_formatter().meta_with_paragraph(paragraph),
             }
         })
     }
 
+    /// Renders every bibliography entry on the thread pool, leaving only the sequential
+    /// subsequent-author-substitute pass in `get_bibliography_map`.
+    fn prepare_bibliography(&self) {
+        // Anything that panics here panics again in render_bibliography, which reports it.
+        #[cfg(feature = "rayon")]
+        let _ = catch_panic(&self.panic_mode, || {
+            self.in_pool(|snap| {
+                use rayon::prelude::*;
+                let sorted_refs = snap.0.sorted_refs();
+                sorted_refs
+                    .0
+                    .par_iter()
+                    .for_each_with(snap, |snap, ref_id| {
+                        snap.0.bib_item_gen0(ref_id.clone());
+                    });
+            })
+        });
+    }
+
     fn save_and_diff_bibliography(&self) -> Option<BibliographyUpdate> {
         if self.get_style().bibliography.is_none() {
             return None;
         }
+        self.prepare_bibliography();
         let mut last_bibliography = self.last_bibliography.lock().unwrap();
-        let new = self.get_bibliography_map();
+        let (sorted_refs, new) = self.render_bibliography();
         let old = std::mem::replace(&mut *last_bibliography, SavedBib::new());
         let mut update = BibliographyUpdate::new();
         for (k, v) in new.iter() {
@@ -621,7 +1112,6 @@ impl Processor {
             }
         }
         last_bibliography.bib_entries = new;
-        let sorted_refs = self.sorted_refs();
         if sorted_refs.0 != old.sorted_refs.0 {
             update.entry_ids = Some(sorted_refs.0.clone());
         }
@@ -661,8 +1151,9 @@ impl Processor {
     }
 
     pub fn get_bibliography(&self) -> Vec<BibEntry> {
-        let bib_map = self.get_bibliography_map();
-        self.sorted_refs()
+        self.prepare_bibliography();
+        let (sorted_refs, bib_map) = self.render_bibliography();
+        sorted_refs
             .0
             .iter()
             .filter_map(|k| bib_map.get(k).map(|v| (k, v)))
@@ -679,6 +1170,49 @@ impl Processor {
             .collect()
     }
 
+    /// Like [`Processor::get_cluster`], but as native Pandoc inlines. Returns None if the output
+    /// format is not [`SupportedFormat::Pandoc`].
+    #[cfg(feature = "pandoc")]
+    pub fn get_cluster_pandoc(&self, cluster_id: ClusterId) -> Option<Vec<Inline>> {
+        if !self.is_pandoc() || self.cluster_note_number(cluster_id).is_none() {
+            return None;
+        }
+        let fmt = self.get_formatter();
+        let inlines = catch_panic(&self.panic_mode, || {
+            let build = citeproc_proc::built_cluster_before_output(self, cluster_id, &fmt);
+            fmt.output_pandoc(build, citeproc_proc::get_piq(self))
+        })
+        .unwrap_or_else(|message| {
+            self.report_cluster_error(Some(cluster_id), cluster_id, message);
+            fmt.output_pandoc(self.placeholder_build(&fmt), false)
+        });
+        Some(inlines)
+    }
+
+    /// Like [`Processor::get_bibliography`], but as native Pandoc inlines. Returns None if the
+    /// output format is not [`SupportedFormat::Pandoc`].
+    #[cfg(feature = "pandoc")]
+    pub fn get_bibliography_pandoc(&self) -> Option<Vec<(Atom, Vec<Inline>)>> {
+        if !self.is_pandoc() {
+            return None;
+        }
+        self.prepare_bibliography();
+        let fmt = self.get_formatter();
+        let (_, entries) =
+            self.bibliography_by_entry(&fmt, |build, piq| fmt.output_pandoc(build, piq));
+        Some(
+            entries
+                .into_iter()
+                .filter(|(_, inlines)| !inlines.is_empty())
+                .collect(),
+        )
+    }
+
+    #[cfg(feature = "pandoc")]
+    fn is_pandoc(&self) -> bool {
+        matches!(self.formatter, Markup::Pandoc(_))
+    }
+
     pub fn get_reference(&self, ref_id: Atom) -> Option<Arc<Reference>> {
         self.reference(ref_id)
     }
@@ -710,6 +1244,358 @@ impl Processor {
     }
 }
 
+impl Processor {
+    /// Captures everything needed to recreate this processor: the style, output format, locale
+    /// override and stored locales, references, clusters, cluster order and uncited references.
+    /// Restore it with [`Processor::import_session`].
+    pub fn export_session(&self) -> Session {
+        let (format, format_options) = SupportedFormat::from_markup(&self.formatter);
+        let mut locales: Vec<(Lang, String)> = self
+            .locale_input_langs()
+            .iter()
+            .map(|lang| (lang.clone(), (*self.locale_input_xml(lang.clone())).clone()))
+            .collect();
+        locales.sort();
+        let mut title_case_stopwords: Vec<(Lang, Vec<String>)> = self
+            .title_case_stopwords()
+            .iter()
+            .map(|(lang, stopwords)| (lang.clone(), stopwords.words().map(String::from).collect()))
+            .collect();
+        title_case_stopwords.sort();
+        let references: Vec<Reference> = self
+            .all_keys()
+            .iter()
+            .filter_map(|key| self.reference(key.clone()))
+            .map(|refr| (*refr).clone())
+            .collect();
+        let mut reference_problems: Vec<ReferenceProblems> = references
+            .iter()
+            .filter_map(ReferenceProblems::from_reference)
+            .collect();
+        reference_problems.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut cluster_ids: Vec<(SmartString, ClusterId)> = self
+            .all_cluster_ids()
+            .iter()
+            .filter(|&&id| id != self.preview_cluster_id)
+            .filter_map(|&id| Some((self.lookup_cluster_id(id)?, id)))
+            .collect();
+        cluster_ids.sort_by(|a, b| a.0.cmp(&b.0));
+        let clusters = cluster_ids
+            .into_iter()
+            .map(|(string_id, id)| {
+                let cites = self
+                    .cluster_cites(id)
+                    .iter()
+                    .map(|cite_id| (*cite_id.lookup(self)).clone())
+                    .collect();
+                string_id::Cluster::new(string_id, cites, self.cluster_mode(id))
+            })
+            .collect();
+        let cluster_order = self
+            .clusters_ordered()
+            .iter()
+            .filter(|&&id| id != self.preview_cluster_id)
+            .filter_map(|&id| {
+                let note = match self.cluster_note_number(id)? {
+                    ClusterNumber::Note(intra) => Some(intra.note_number()),
+                    ClusterNumber::InText(_) => None,
+                    ClusterNumber::OutsideFlow => return None,
+                };
+                Some(string_id::ClusterPosition {
+                    id: Some(self.lookup_cluster_id(id)?),
+                    note,
+                })
+            })
+            .collect();
+        let uncited = match &*self.all_uncited() {
+            Uncited::All => IncludeUncited::All,
+            Uncited::Enumerated(set) if set.is_empty() => IncludeUncited::None,
+            Uncited::Enumerated(set) => {
+                IncludeUncited::Specific(set.iter().map(|atom| atom.to_string()).collect())
+            }
+        };
+        Session {
+            version: SESSION_VERSION,
+            style: self.style_text.to_string(),
+            csl_features: self
+                .csl_features
+                .as_ref()
+                .map(feature_names)
+                .unwrap_or_default(),
+            format,
+            format_options,
+            locale_override: self.default_lang_override(),
+            locales,
+            bibliography_no_sort: self.bibliography_no_sort(),
+            citation_label: (*self.citation_label_config()).clone(),
+            collation: self
+                .collation_override()
+                .and_then(|c| c.tag().map(String::from)),
+            title_case_stopwords,
+            references,
+            reference_problems,
+            clusters,
+            cluster_order,
+            uncited,
+        }
+    }
+
+    /// Recreates a processor from a [`Session`] returned by [`Processor::export_session`]. Its
+    /// output is identical to the original's. As with [`InitOptions::fetcher`], the fetcher
+    /// defaults to the bundled locales.
+    pub fn import_session(
+        session: Session,
+        fetcher: Option<Arc<dyn LocaleFetcher>>,
+    ) -> Result<Self, SessionError> {
+        let Session {
+            version,
+            style,
+            csl_features,
+            format,
+            format_options,
+            locale_override,
+            locales,
+            bibliography_no_sort,
+            citation_label,
+            collation,
+            title_case_stopwords,
+            mut references,
+            reference_problems,
+            clusters,
+            cluster_order,
+            uncited,
+        } = session;
+        if version != SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion(version));
+        }
+        let csl_features = if csl_features.is_empty() {
+            None
+        } else {
+            let features = csl::version::read_features(csl_features.iter().map(|f| f.as_str()))
+                .map_err(|f| SessionError::UnknownFeature(f.into()))?;
+            Some(features)
+        };
+        let fetcher = fetcher.unwrap_or_else(default_fetcher);
+        let mut db = Processor::safe_default(fetcher);
+        // The style was accepted once already, and may have been a test-suite style with no
+        // <info>.
+        let parsed = Style::parse_with_opts(
+            &style,
+            csl::ParseOptions {
+                allow_no_info: true,
+                features: csl_features.clone(),
+                ..Default::default()
+            },
+        )?;
+        db.set_style_with_durability(Arc::new(parsed), Durability::HIGH);
+        db.style_text = Arc::from(style);
+        db.csl_features = csl_features;
+        db.set_output_format(format, format_options);
+        db.set_default_lang_override_with_durability(locale_override, Durability::HIGH);
+        db.set_bibliography_no_sort_with_durability(bibliography_no_sort, Durability::HIGH);
+        db.set_citation_label_config_with_durability(Arc::new(citation_label), Durability::HIGH);
+        let collation = match collation {
+            Some(tag) => Some(Collation::for_tag(&tag).ok_or(SessionError::UnknownCollation(tag))?),
+            None => None,
+        };
+        db.set_collation_override_with_durability(collation, Durability::HIGH);
+        let mut stopwords = StopwordTable::default();
+        for (lang, words) in title_case_stopwords {
+            stopwords.insert(lang, Stopwords::new(words));
+        }
+        db.set_title_case_stopwords_with_durability(Arc::new(stopwords), Durability::HIGH);
+        db.store_locales(locales);
+        let mut problems: FnvHashMap<String, ReferenceProblems> = reference_problems
+            .into_iter()
+            .map(|p| (p.id.clone(), p))
+            .collect();
+        for refr in &mut references {
+            if let Some(p) = problems.remove(&*refr.id) {
+                p.apply_to(refr);
+            }
+        }
+        db.reset_references(references);
+        db.include_uncited(uncited);
+        db.init_clusters_str(clusters);
+        db.set_cluster_order_str(&cluster_order)?;
+        Ok(db)
+    }
+}
+
+impl Processor {
+    /// Explains what disambiguation did to each cite in a cluster, in the order the cites are
+    /// rendered. Returns None if the cluster has not been assigned a position in the document.
+    pub fn explain_disambiguation(
+        &self,
+        cluster_id: ClusterId,
+    ) -> Option<Vec<Arc<DisambExplanation>>> {
+        let cites = self.cluster_cites_sorted(cluster_id)?;
+        self.cluster_note_number(cluster_id)?;
+        Some(
+            cites
+                .iter()
+                .map(|&cite_id| self.disamb_explanation(cite_id))
+                .collect(),
+        )
+    }
+
+    pub fn explain_disambiguation_str(
+        &self,
+        cluster_id: &str,
+    ) -> Option<Vec<Arc<DisambExplanation>>> {
+        let id = self.cluster_id(cluster_id);
+        self.explain_disambiguation(id)
+    }
+
+    /// Lists the references that have at least one cite in the document that disambiguation could
+    /// not make unique, in the order they are first cited.
+    pub fn ambiguity_report(&self) -> Vec<AmbiguousReference> {
+        let mut by_ref: IndexMap<Atom, AmbiguousReference> = IndexMap::new();
+        for cluster in self.clusters_cites_sorted().iter() {
+            for &cite_id in cluster.cites.iter() {
+                let explanation = self.disamb_explanation(cite_id);
+                if !explanation.ambiguous {
+                    continue;
+                }
+                let entry = by_ref.entry(explanation.ref_id.clone()).or_insert_with(|| {
+                    AmbiguousReference {
+                        ref_id: explanation.ref_id.clone(),
+                        collides_with: Vec::new(),
+                        clusters: Vec::new(),
+                    }
+                });
+                for other in explanation.remaining_collisions() {
+                    if !entry.collides_with.contains(other) {
+                        entry.collides_with.push(other.clone());
+                    }
+                }
+                if entry.clusters.last() != Some(&cluster.id) {
+                    entry.clusters.push(cluster.id);
+                }
+            }
+        }
+        by_ref.into_iter().map(|(_, v)| v).collect()
+    }
+
+    pub fn ambiguity_report_str(&self) -> Vec<string_id::AmbiguousReference> {
+        self.ambiguity_report()
+            .into_iter()
+            .map(|amb| string_id::AmbiguousReference {
+                ref_id: amb.ref_id,
+                collides_with: amb.collides_with,
+                clusters: amb
+                    .clusters
+                    .into_iter()
+                    .filter_map(|id| self.lookup_cluster_id(id))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Lists the problems with the current style, locales, references and clusters that the
+    /// processor works around when rendering: locale gaps first, then each reference in the
+    /// order they were inserted, then each missing reference in document order.
+    ///
+    /// These are worked out afresh from the current state rather than recorded while rendering,
+    /// so call this after `compute()` or `batched_updates()` to get the problems with what was
+    /// rendered. See [`DiagnosticCode::MissingTerm`] for which terms are checked.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let lang = self.default_lang();
+        let found = lang
+            .iter_fetchable_langs()
+            .find(|l| self.parsed_locale(LocaleSource::File(l.clone())).is_some());
+        let missing = match found {
+            Some(ref found) if *found == lang => None,
+            Some(found) => Some(format!("no locale for {}, using {} instead", lang, found)),
+            None => Some(format!("no locale for {}, or any fallback", lang)),
+        };
+        if let Some(message) = missing {
+            diagnostics.push(Diagnostic::new(DiagnosticCode::MissingLocale, message));
+        }
+        let locale = self.default_locale();
+        for (sel, plural) in citeproc_proc::style_text_terms(&self.style()) {
+            if locale.get_text_term(sel, plural).is_none() {
+                let form = if plural { "plural" } else { "singular" };
+                let message = format!(
+                    "term `{}` ({}) is not defined for {}",
+                    sel.name(),
+                    form,
+                    lang
+                );
+                diagnostics.push(Diagnostic::new(DiagnosticCode::MissingTerm, message));
+            }
+        }
+
+        for key in self.all_keys().iter() {
+            let refr = match self.reference(key.clone()) {
+                Some(refr) => refr,
+                None => continue,
+            };
+            for var in &refr.unknown_variables {
+                let message = format!("`{}` is not a CSL variable", var);
+                diagnostics.push(Diagnostic::for_reference(
+                    DiagnosticCode::UnknownVariable,
+                    key.clone(),
+                    message,
+                ));
+            }
+            let mut invalid: Vec<_> = refr
+                .invalid_dates
+                .iter()
+                .map(|(var, problem)| (var.as_ref(), format!("{}: {}", var.as_ref(), problem)))
+                .chain(refr.raw_dates.iter().map(|var| {
+                    let message = format!(
+                        "{}: could not parse the raw date, so it is rendered as written",
+                        var.as_ref()
+                    );
+                    (var.as_ref(), message)
+                }))
+                .collect();
+            invalid.sort();
+            for (_, message) in invalid {
+                diagnostics.push(Diagnostic::for_reference(
+                    DiagnosticCode::InvalidDate,
+                    key.clone(),
+                    message,
+                ));
+            }
+        }
+
+        for cluster in self.clusters_cites_sorted().iter() {
+            for &cite_id in cluster.cites.iter() {
+                let ref_id = cite_id.lookup(self).ref_id.clone();
+                if self.reference(ref_id.clone()).is_none() {
+                    diagnostics.push(Diagnostic {
+                        cluster_id: Some(cluster.id),
+                        ref_id: Some(ref_id.clone()),
+                        ..Diagnostic::new(
+                            DiagnosticCode::MissingReference,
+                            format!("reference `{}` not found", ref_id),
+                        )
+                    });
+                }
+            }
+        }
+        diagnostics
+    }
+
+    pub fn diagnostics_str(&self) -> Vec<string_id::Diagnostic> {
+        self.diagnostics()
+            .into_iter()
+            .map(|diagnostic| string_id::Diagnostic {
+                code: diagnostic.code,
+                cluster_id: diagnostic
+                    .cluster_id
+                    .and_then(|id| self.lookup_cluster_id(id)),
+                ref_id: diagnostic.ref_id,
+                message: diagnostic.message,
+            })
+            .collect()
+    }
+}
+
 /// Stores all the relevant #[salsa::input] entries from CiteDatabase.
 /// They are all Arcs, so this is cheap.
 #[derive(Debug)]
@@ -826,7 +1712,14 @@ impl Processor {
         let formatter = format
             .map(|fmt| fmt.make_markup(self.format_options))
             .unwrap_or_else(|| self.get_formatter());
-        let markup = citeproc_proc::db::built_cluster_preview(self, id, &formatter);
+        let markup = catch_panic(&self.panic_mode, || {
+            citeproc_proc::db::built_cluster_preview(self, id, &formatter)
+        })
+        .unwrap_or_else(|message| {
+            let reported_id = Some(id).filter(|&id| id != self.preview_cluster_id);
+            self.report_cluster_error(reported_id, id, message);
+            self.placeholder(&formatter)
+        });
         let cluster_cites_sorted = self.cluster_cites_sorted(id);
         let nn = self.cluster_note_number(id);
         log::debug!("cluster_cites_sorted: {:?}", cluster_cites_sorted);
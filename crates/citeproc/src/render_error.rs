@@ -0,0 +1,82 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+//! Rendering the rest of a document when one cluster or bibliography entry panics.
+//!
+//! By default, a panic while rendering unwinds out of the [`Processor`](crate::Processor) method
+//! that triggered it. With [`PanicMode::Placeholder`], the processor catches it for each cluster
+//! or bibliography entry, renders the placeholder text in its place, and records a
+//! [`RenderError`], which you get back in [`UpdateSummary`](crate::UpdateSummary) or from
+//! [`Processor::take_render_errors`](crate::Processor::take_render_errors).
+//!
+//! Panics can only be caught where they unwind. With `panic = "abort"`, and on
+//! `wasm32-unknown-unknown`, where every panic aborts, this mode changes nothing. The panic hook
+//! still runs for caught panics, so they are printed to stderr as usual.
+
+use citeproc_db::ClusterId;
+use citeproc_io::SmartString;
+use csl::Atom;
+use serde::Serialize;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+/// What to do when rendering a cluster or bibliography entry panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PanicMode {
+    /// Let the panic unwind out of the processor. The default.
+    Propagate,
+    /// Render this text instead, and record a [`RenderError`]. It is written like any other
+    /// text in the output format, so it is escaped for HTML and RTF.
+    Placeholder(SmartString),
+}
+
+impl Default for PanicMode {
+    fn default() -> Self {
+        PanicMode::Propagate
+    }
+}
+
+impl PanicMode {
+    pub fn placeholder(text: impl Into<SmartString>) -> Self {
+        PanicMode::Placeholder(text.into())
+    }
+}
+
+/// A cluster or bibliography entry that panicked under [`PanicMode::Placeholder`].
+///
+/// The same failure is reported again each time the processor renders it, e.g. on every call to
+/// `batched_updates` until the cluster or reference is edited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderError<Id = ClusterId> {
+    /// The cluster that failed, or None for a bibliography entry or a preview.
+    pub cluster_id: Option<Id>,
+    /// The reference the failure was traced to. For a cluster, this is the first cite that also
+    /// fails when rendered on its own, and None if none of them do.
+    pub ref_id: Option<Atom>,
+    /// The panic message.
+    pub message: String,
+}
+
+/// Runs `f`, catching a panic if the mode says to.
+pub(crate) fn catch_panic<R>(mode: &PanicMode, f: impl FnOnce() -> R) -> Result<R, String> {
+    match mode {
+        PanicMode::Propagate => Ok(f()),
+        PanicMode::Placeholder(_) => {
+            panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(&*payload))
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panic with a non-string payload".to_owned()
+    }
+}
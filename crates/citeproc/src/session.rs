@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Saving a [`Processor`](crate::Processor)'s inputs, so a document can be reopened without
+//! re-sending everything.
+
+use crate::api::{string_id, IncludeUncited, SupportedFormat};
+use citeproc_io::output::markup::FormatOptions;
+use citeproc_io::Reference;
+use citeproc_proc::CitationLabelConfig;
+use csl::{Features, Lang, StyleError};
+use serde::{Deserialize, Serialize};
+
+/// The [`Session::version`] written by this version of citeproc-rs.
+pub const SESSION_VERSION: u32 = 1;
+
+/// Everything that was fed into a [`Processor`](crate::Processor), from
+/// [`Processor::export_session`](crate::Processor::export_session). Serialize it with serde (e.g.
+/// to JSON), and restore it later with
+/// [`Processor::import_session`](crate::Processor::import_session).
+///
+/// Cluster ids are stored as the strings they were interned from, so after a restore, get the
+/// numeric ids again with [`Processor::cluster_id`](crate::Processor::cluster_id). Clusters whose
+/// ids were never interned are left out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// Always [`SESSION_VERSION`] when exported.
+    pub version: u32,
+    /// The style's CSL XML, exactly as it was given.
+    pub style: String,
+    /// Features enabled with [`InitOptions::csl_features`](crate::InitOptions::csl_features),
+    /// in kebab-case. Features the style declares itself are in its XML.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub csl_features: Vec<String>,
+    pub format: SupportedFormat,
+    #[serde(default)]
+    pub format_options: FormatOptions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale_override: Option<Lang>,
+    /// Locale XML supplied through [`Processor::store_locales`](crate::Processor::store_locales),
+    /// sorted by language.
+    #[serde(default)]
+    pub locales: Vec<(Lang, String)>,
+    #[serde(default)]
+    pub bibliography_no_sort: bool,
+    #[serde(default)]
+    pub citation_label: CitationLabelConfig,
+    /// The tag of a built-in collation set with
+    /// [`Processor::set_collation`](crate::Processor::set_collation). Collators of your own are
+    /// not saved, and have to be set again after a restore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+    /// Stopwords set with
+    /// [`Processor::set_title_case_stopwords`](crate::Processor::set_title_case_stopwords),
+    /// sorted by language.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub title_case_stopwords: Vec<(Lang, Vec<String>)>,
+    #[serde(default)]
+    pub references: Vec<Reference>,
+    /// What could not be read from each reference's CSL-JSON, which `references` leaves out.
+    /// Only references with problems are listed, sorted by id.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reference_problems: Vec<ReferenceProblems>,
+    /// All clusters, including any not currently placed in the document, sorted by id.
+    #[serde(default)]
+    pub clusters: Vec<string_id::Cluster>,
+    #[serde(default)]
+    pub cluster_order: Vec<string_id::ClusterPosition>,
+    #[serde(default)]
+    pub uncited: IncludeUncited,
+}
+
+/// The parts of a [`Reference`] that are only there to produce
+/// [`Processor::diagnostics`](crate::Processor::diagnostics).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceProblems {
+    pub id: String,
+    /// See [`Reference::unknown_variables`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown_variables: Vec<String>,
+    /// See [`Reference::invalid_dates`]. Pairs of a date variable and the problem, sorted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invalid_dates: Vec<(String, String)>,
+}
+
+impl ReferenceProblems {
+    pub(crate) fn from_reference(refr: &Reference) -> Option<Self> {
+        if refr.unknown_variables.is_empty() && refr.invalid_dates.is_empty() {
+            return None;
+        }
+        let mut invalid_dates: Vec<(String, String)> = refr
+            .invalid_dates
+            .iter()
+            .map(|(var, problem)| (var.as_ref().to_owned(), problem.clone()))
+            .collect();
+        invalid_dates.sort();
+        Some(ReferenceProblems {
+            id: refr.id.to_string(),
+            unknown_variables: refr
+                .unknown_variables
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            invalid_dates,
+        })
+    }
+
+    /// Date variables that are no longer known are dropped.
+    pub(crate) fn apply_to(self, refr: &mut Reference) {
+        refr.unknown_variables = self.unknown_variables.into_iter().map(Into::into).collect();
+        refr.invalid_dates = self
+            .invalid_dates
+            .into_iter()
+            .filter_map(|(var, problem)| Some((var.parse().ok()?, problem)))
+            .collect();
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("unsupported session version {0} (expected {})", SESSION_VERSION)]
+    UnsupportedVersion(u32),
+    #[error("invalid style in session: {0}")]
+    Style(#[from] StyleError),
+    #[error("invalid cluster order in session: {0}")]
+    ClusterOrder(#[from] string_id::ReorderingError),
+    #[error("unknown collation in session: {0}")]
+    UnknownCollation(String),
+    #[error("unknown CSL feature in session: {0}")]
+    UnknownFeature(String),
+}
+
+pub(crate) fn feature_names(features: &Features) -> Vec<String> {
+    let mut names = Vec::new();
+    features.walk_feature_fields(|name, enabled| {
+        if enabled {
+            names.push(name.replace('_', "-"));
+        }
+    });
+    names
+}
@@ -416,4 +416,1065 @@ mod terms {
             Some("USA"),
         )
     }
+
+    #[test]
+    fn layered_locales() {
+        let user = predefined_xml(&[(en_au(), r#"<term name="and">Australia</term>"#)]);
+        let bundle = predefined_xml(&[
+            (Lang::en_us(), r#"<term name="and">USA</term>"#),
+            (en_au(), r#"<term name="and">Bundled</term>"#),
+        ]);
+        let layered = LayeredLocales(vec![Arc::new(user), Arc::new(bundle)]);
+        let db = Processor::safe_default(Arc::new(layered));
+        let and = TextTermSelector::Simple(term_and(TermFormExtended::Long));
+        assert_eq!(
+            db.merged_locale(en_au()).get_text_term(and, false),
+            Some("Australia")
+        );
+        assert_eq!(
+            db.merged_locale(Lang::en_us()).get_text_term(and, false),
+            Some("USA")
+        );
+    }
+
+    #[cfg(feature = "bundled-locales")]
+    #[test]
+    fn bundled_locales() {
+        assert!(BundledLocales::langs().any(|lang| lang == Lang::en_us()));
+        // Not just en-US
+        let fr_fr: Lang = "fr-FR".parse().unwrap();
+        assert!(BundledLocales::langs().any(|lang| lang == fr_fr));
+        let locale = BundledLocales.fetch_locale(&Lang::en_us()).unwrap();
+        let and = TextTermSelector::Simple(term_and(TermFormExtended::Long));
+        assert_eq!(locale.get_text_term(and, false), Some("and"));
+    }
+}
+
+mod bibliography_meta {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout></layout></citation>
+        <bibliography hanging-indent="true" line-spacing="2">
+            <layout><text variable="title" /></layout>
+        </bibliography>
+    </style>"#;
+
+    fn paragraph_properties(db: &Processor) -> Option<String> {
+        let meta = db.get_bibliography_meta().unwrap();
+        let json = serde_json::to_value(&meta).unwrap();
+        json["formatMeta"]["paragraphProperties"]
+            .as_str()
+            .map(String::from)
+    }
+
+    #[test]
+    fn ooxml_paragraph_properties() {
+        let mut db = test_db(Some(STYLE));
+        assert_eq!(paragraph_properties(&db), None);
+        db.set_output_format(SupportedFormat::Ooxml, Default::default());
+        assert_eq!(
+            paragraph_properties(&db).as_deref(),
+            Some(concat!(
+                r#"<w:pPr><w:spacing w:after="240" w:line="480" w:lineRule="auto"/>"#,
+                r#"<w:ind w:left="720" w:hanging="720"/></w:pPr>"#,
+            ))
+        );
+    }
+
+    #[test]
+    fn odf_paragraph_properties() {
+        let mut db = test_db(Some(STYLE));
+        db.set_output_format(SupportedFormat::Odf, Default::default());
+        let props = paragraph_properties(&db).unwrap();
+        assert!(props.contains(r#"fo:margin-left="36pt" fo:text-indent="-36pt""#));
+        assert!(props.contains(r#"fo:line-height="200%""#));
+    }
+
+    #[test]
+    fn max_offset() {
+        let style = r#"<style version="1.0" class="in-text">
+            <citation><layout></layout></citation>
+            <bibliography second-field-align="flush">
+                <layout>
+                    <text variable="citation-number" prefix="[" suffix="]" />
+                    <text variable="title" />
+                </layout>
+            </bibliography>
+        </style>"#;
+        let mut db = test_db(Some(style));
+        let refs = (1..=10)
+            .map(|i| {
+                let mut refr = Reference::empty(Atom::from(format!("r{}", i)), CslType::Book);
+                refr.ordinary.insert(Variable::Title, "Title".into());
+                refr
+            })
+            .collect();
+        db.reset_references(refs);
+        db.include_uncited(IncludeUncited::All);
+        // "[10]"
+        assert_eq!(db.get_bibliography_meta().unwrap().max_offset, 4);
+        db.set_output_format(SupportedFormat::Ooxml, Default::default());
+        let props = paragraph_properties(&db).unwrap();
+        assert!(props.contains(r#"<w:tab w:val="left" w:pos="480"/>"#));
+    }
+}
+
+mod text_case {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout><text variable="title" text-case="title" /></layout></citation>
+    </style>"#;
+
+    fn render_title(db: &mut Processor, language: &str, title: &str) -> Option<String> {
+        let mut refr = Reference::empty(Atom::from("ref"), CslType::Book);
+        refr.language = Some(language.parse().unwrap());
+        refr.ordinary.insert(Variable::Title, title.into());
+        db.insert_reference(refr);
+        insert_ascending_notes(db, &["ref"]);
+        let id = cid(db, 1);
+        db.get_cluster(id).map(|s| s.to_string())
+    }
+
+    #[test]
+    fn title_case_only_english_by_default() {
+        let mut db = test_db(Some(STYLE));
+        assert_eq!(
+            render_title(&mut db, "fr-FR", "histoire de la france").as_deref(),
+            Some("histoire de la france")
+        );
+    }
+
+    #[test]
+    fn configured_stopwords() {
+        let mut db = test_db(Some(STYLE));
+        let fr = Lang::Iso(IsoLang::French, None);
+        db.set_title_case_stopwords(fr, Stopwords::builtin(crate::io::CaseLanguage::French));
+        assert_eq!(
+            render_title(&mut db, "fr-FR", "histoire de la france").as_deref(),
+            Some("Histoire de la France")
+        );
+    }
+}
+
+mod session {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="note">
+        <citation>
+            <layout delimiter="; ">
+                <choose>
+                    <if position="ibid"><text value="ibid" /></if>
+                    <else>
+                        <group delimiter=", ">
+                            <names variable="author"><name form="short" /></names>
+                            <text variable="title" />
+                            <date variable="issued"><date-part name="year" /></date>
+                            <text variable="locator" />
+                        </group>
+                    </else>
+                </choose>
+            </layout>
+        </citation>
+        <bibliography>
+            <sort><key variable="title" /></sort>
+            <layout><text variable="title" /></layout>
+        </bibliography>
+    </style>"#;
+
+    fn mk_db() -> Processor {
+        let mut db = test_db(Some(STYLE));
+        let refs: Vec<Reference> = serde_json::from_str(
+            r#"[
+                { "id": "one", "type": "book", "title": "Book one",
+                  "author": [{ "family": "Smith", "given": "Jo" }],
+                  "issued": { "date-parts": [[2001, 4]] }, "volume": 3 },
+                { "id": "two", "type": "article-journal", "title": "Article two",
+                  "author": [{ "literal": "The Collective" }],
+                  "issued": { "date-parts": [[1999], [2000]], "circa": true } },
+                { "id": "three", "type": "book", "title": "Another book",
+                  "issued": { "literal": "forthcoming" } }
+            ]"#,
+        )
+        .unwrap();
+        db.reset_references(refs);
+        let clusters: Vec<string_id::Cluster> = serde_json::from_str(
+            r#"[
+                { "id": "a", "cites": [{ "id": "one", "locator": "5", "label": "page" }] },
+                { "id": "b", "cites": [{ "id": "one" }, { "id": "two", "prefix": "see " }] },
+                { "id": "c", "cites": [{ "id": "two" }], "mode": "AuthorOnly" },
+                { "id": "unplaced", "cites": [{ "id": "one" }] }
+            ]"#,
+        )
+        .unwrap();
+        db.init_clusters_str(clusters);
+        db.set_cluster_order_str(&[
+            string_id::ClusterPosition::note("a".into(), 1),
+            string_id::ClusterPosition::note("b".into(), 1),
+            string_id::ClusterPosition::in_text("c".into()),
+        ])
+        .unwrap();
+        db.include_uncited(IncludeUncited::Specific(vec!["three".into()]));
+        db
+    }
+
+    #[test]
+    fn restore_gives_identical_output() {
+        let db = mk_db();
+        let json = serde_json::to_string(&db.export_session()).unwrap();
+        let session: Session = serde_json::from_str(&json).unwrap();
+        let restored = Processor::import_session(session, None).unwrap();
+        assert_eq!(restored.all_clusters_str(), db.all_clusters_str());
+        assert_eq!(restored.get_bibliography(), db.get_bibliography());
+        // And the round trip is stable.
+        assert_eq!(
+            serde_json::to_string(&restored.export_session()).unwrap(),
+            json
+        );
+    }
+
+    #[test]
+    fn keeps_style_text() {
+        let session = mk_db().export_session();
+        assert_eq!(session.style, STYLE);
+        assert!(session.csl_features.is_empty());
+    }
+
+    #[test]
+    fn csl_features_round_trip() {
+        let features = csl::version::read_features(std::iter::once("conditions")).unwrap();
+        let db = Processor::new(InitOptions {
+            style: STYLE,
+            test_mode: true,
+            csl_features: Some(features),
+            ..Default::default()
+        })
+        .unwrap();
+        let session = db.export_session();
+        assert_eq!(session.csl_features, vec!["conditions".to_string()]);
+        let restored = Processor::import_session(session.clone(), None).unwrap();
+        assert_eq!(restored.export_session(), session);
+    }
+
+    #[test]
+    fn title_case_stopwords_round_trip() {
+        let style = r#"<style version="1.0" class="in-text">
+            <citation><layout><text variable="title" text-case="title" /></layout></citation>
+        </style>"#;
+        let mut db = test_db(Some(style));
+        let mut refr = Reference::empty(Atom::from("ref"), CslType::Book);
+        refr.language = Some("fr-FR".parse().unwrap());
+        refr.ordinary
+            .insert(Variable::Title, "histoire de la france".into());
+        db.insert_reference(refr);
+        insert_ascending_notes(&mut db, &["ref"]);
+        db.set_title_case_stopwords(
+            Lang::Iso(IsoLang::French, None),
+            Some(Stopwords::new(vec!["de", "la"])),
+        );
+        let session = db.export_session();
+        assert_eq!(session.title_case_stopwords.len(), 1);
+        let json = serde_json::to_string(&session).unwrap();
+        let mut restored =
+            Processor::import_session(serde_json::from_str(&json).unwrap(), None).unwrap();
+        assert_eq!(restored.all_clusters_str(), db.all_clusters_str());
+        let id = cid(&mut restored, 1);
+        assert_eq!(
+            restored.get_cluster(id).map(|s| s.to_string()).as_deref(),
+            Some("Histoire de la France")
+        );
+        assert_eq!(restored.export_session(), session);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut session = mk_db().export_session();
+        session.version = SESSION_VERSION + 1;
+        assert!(matches!(
+            Processor::import_session(session, None),
+            Err(SessionError::UnsupportedVersion(_))
+        ));
+    }
+}
+
+mod disamb_explanation {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation disambiguate-add-givenname="true" givenname-disambiguation-rule="by-cite">
+            <layout delimiter="; ">
+                <group delimiter=" ">
+                    <names variable="author"><name form="short" initialize-with=". " /></names>
+                    <date variable="issued"><date-part name="year" /></date>
+                </group>
+            </layout>
+        </citation>
+    </style>"#;
+
+    fn mk_db() -> Processor {
+        let mut db = test_db(Some(STYLE));
+        let refs: Vec<Reference> = serde_json::from_str(
+            r#"[
+                { "id": "john", "type": "book", "author": [{ "family": "Smith", "given": "John" }],
+                  "issued": { "date-parts": [[2001]] } },
+                { "id": "jane", "type": "book", "author": [{ "family": "Smith", "given": "Jane" }],
+                  "issued": { "date-parts": [[2001]] } },
+                { "id": "twin", "type": "book", "author": [{ "family": "Jones", "given": "Al" }],
+                  "issued": { "date-parts": [[1999]] } },
+                { "id": "twin-copy", "type": "book", "author": [{ "family": "Jones", "given": "Al" }],
+                  "issued": { "date-parts": [[1999]] } }
+            ]"#,
+        )
+        .unwrap();
+        db.reset_references(refs);
+        insert_ascending_notes(&mut db, &["john", "twin", "jane", "twin-copy"]);
+        db
+    }
+
+    #[test]
+    fn given_name_step() {
+        let mut db = mk_db();
+        let id = cid(&mut db, 1);
+        let explained = db.explain_disambiguation(id).unwrap();
+        assert_eq!(explained.len(), 1);
+        let john = &explained[0];
+        assert!(!john.ambiguous);
+        let steps: Vec<_> = john.steps.iter().map(|s| &s.step).collect();
+        assert_eq!(steps, vec![&DisambStep::Initial, &DisambStep::AddGivenName]);
+        assert_eq!(john.steps[0].output.as_str(), "Smith 2001");
+        assert_eq!(john.steps[0].collisions, vec![Atom::from("jane")]);
+        assert_eq!(john.steps[1].output.as_str(), "J. Smith 2001");
+        assert!(john.steps[1].collisions.is_empty());
+    }
+
+    #[test]
+    fn ambiguity_report() {
+        let mut db = mk_db();
+        let twin_cluster = cid(&mut db, 2);
+        let copy_cluster = cid(&mut db, 4);
+        let explained = db.explain_disambiguation(twin_cluster).unwrap();
+        assert!(explained[0].ambiguous);
+        assert_eq!(
+            explained[0].remaining_collisions(),
+            &[Atom::from("twin-copy")]
+        );
+        assert_eq!(
+            db.ambiguity_report(),
+            vec![
+                AmbiguousReference {
+                    ref_id: Atom::from("twin"),
+                    collides_with: vec![Atom::from("twin-copy")],
+                    clusters: vec![twin_cluster],
+                },
+                AmbiguousReference {
+                    ref_id: Atom::from("twin-copy"),
+                    collides_with: vec![Atom::from("twin")],
+                    clusters: vec![copy_cluster],
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn last_step_is_rendered() {
+        let style = r#"<style version="1.0" class="in-text">
+            <citation disambiguate-add-names="true" disambiguate-add-givenname="true"
+                      disambiguate-add-year-suffix="true" givenname-disambiguation-rule="by-cite"
+                      et-al-min="2" et-al-use-first="1">
+                <layout delimiter="; ">
+                    <group delimiter=" ">
+                        <names variable="author">
+                            <name form="short" initialize-with=". " delimiter=", " />
+                        </names>
+                        <date variable="issued"><date-part name="year" /></date>
+                    </group>
+                </layout>
+            </citation>
+        </style>"#;
+        let mut db = test_db(Some(style));
+        let refs: Vec<Reference> = serde_json::from_str(
+            r#"[
+                { "id": "brown", "type": "book", "issued": { "date-parts": [[2001]] },
+                  "author": [{ "family": "Smith", "given": "John" }, { "family": "Brown" }] },
+                { "id": "green", "type": "book", "issued": { "date-parts": [[2001]] },
+                  "author": [{ "family": "Smith", "given": "John" }, { "family": "Green" }] },
+                { "id": "jane", "type": "book", "issued": { "date-parts": [[2005]] },
+                  "author": [{ "family": "Doe", "given": "Jane" }] },
+                { "id": "mary", "type": "book", "issued": { "date-parts": [[2005]] },
+                  "author": [{ "family": "Doe", "given": "Mary" }] },
+                { "id": "first", "type": "book", "issued": { "date-parts": [[2010]] },
+                  "author": [{ "family": "Roe", "given": "Al" }] },
+                { "id": "second", "type": "book", "issued": { "date-parts": [[2010]] },
+                  "author": [{ "family": "Roe", "given": "Al" }] }
+            ]"#,
+        )
+        .unwrap();
+        db.reset_references(refs);
+        let ids = ["brown", "green", "jane", "mary", "first", "second"];
+        insert_ascending_notes(&mut db, &ids);
+        let mut last_steps = Vec::new();
+        for n in 1..=ids.len() as u32 {
+            let id = cid(&mut db, n);
+            let explained = db.explain_disambiguation(id).unwrap();
+            let last = explained[0].steps.last().unwrap();
+            assert_cluster!(db.get_cluster(id), Some(last.output.as_str()));
+            last_steps.push(last.step.clone());
+        }
+        assert_eq!(
+            last_steps,
+            vec![
+                DisambStep::AddNames,
+                DisambStep::AddNames,
+                DisambStep::AddGivenName,
+                DisambStep::AddGivenName,
+                DisambStep::AddYearSuffix { year_suffix: 1 },
+                DisambStep::AddYearSuffix { year_suffix: 2 },
+            ]
+        );
+    }
+}
+
+mod citation_label {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation>
+            <layout><text variable="citation-label" prefix="[" suffix="]" /></layout>
+        </citation>
+        <bibliography>
+            <sort><key variable="citation-label" /></sort>
+            <layout><text variable="citation-label" /></layout>
+        </bibliography>
+    </style>"#;
+
+    fn mk_db() -> Processor {
+        let mut db = test_db(Some(STYLE));
+        let refs: Vec<Reference> = serde_json::from_str(
+            r#"[
+                { "id": "smith-one", "type": "book", "author": [{ "family": "Smith" }],
+                  "issued": { "date-parts": [[2001]] } },
+                { "id": "adams", "type": "book", "author": [{ "family": "Adams" }],
+                  "issued": { "date-parts": [[1999]] } },
+                { "id": "smith-two", "type": "book", "author": [{ "family": "Smith" }],
+                  "issued": { "date-parts": [[2001]] } },
+                { "id": "edited", "type": "book", "editor": [{ "family": "Young" }],
+                  "issued": { "date-parts": [[2005]] } }
+            ]"#,
+        )
+        .unwrap();
+        db.reset_references(refs);
+        insert_ascending_notes(&mut db, &["smith-one", "adams", "smith-two", "edited"]);
+        db
+    }
+
+    fn bib_labels(db: &Processor) -> Vec<String> {
+        db.get_bibliography()
+            .into_iter()
+            .map(|entry| entry.value.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn collisions_unsuffixed_by_default() {
+        let mut db = mk_db();
+        let two = cid(&mut db, 3);
+        let edited = cid(&mut db, 4);
+        assert_cluster!(db.get_cluster(two), Some("[Smit01]"));
+        assert_cluster!(db.get_cluster(edited), Some("[Youn05]"));
+        assert_eq!(
+            bib_labels(&db),
+            vec!["Adam99", "Smit01", "Smit01", "Youn05"]
+        );
+    }
+
+    #[test]
+    fn collision_suffixes() {
+        let mut db = mk_db();
+        db.set_citation_label_config(CitationLabelConfig {
+            collision_suffixes: true,
+            ..Default::default()
+        });
+        let one = cid(&mut db, 1);
+        let two = cid(&mut db, 3);
+        assert_cluster!(db.get_cluster(one), Some("[Smit01a]"));
+        assert_cluster!(db.get_cluster(two), Some("[Smit01b]"));
+        assert_eq!(
+            bib_labels(&db),
+            vec!["Adam99", "Smit01a", "Smit01b", "Youn05"]
+        );
+    }
+
+    #[test]
+    fn template() {
+        let mut db = mk_db();
+        db.set_citation_label_config(CitationLabelConfig {
+            template: Trigraph::parse("Aa0").unwrap(),
+            ..Default::default()
+        });
+        let adams = cid(&mut db, 2);
+        assert_cluster!(db.get_cluster(adams), Some("[Ad9]"));
+    }
+}
+
+mod collation {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text" default-locale="sv-SE">
+        <macro name="title"><text variable="title" /></macro>
+        <citation><layout><text variable="title" /></layout></citation>
+        <bibliography>
+            <sort><key macro="title" /></sort>
+            <layout><text variable="title" /></layout>
+        </bibliography>
+    </style>"#;
+
+    fn mk_db(style: &str) -> Processor {
+        let mut db = test_db(Some(style));
+        let refs: Vec<Reference> = serde_json::from_str(
+            r#"[
+                { "id": "a", "type": "book", "title": "Ångström",
+                  "author": [{ "family": "Åkesson" }] },
+                { "id": "z", "type": "book", "title": "Zeta",
+                  "author": [{ "family": "Zetterberg" }] },
+                { "id": "o", "type": "book", "title": "Orm",
+                  "author": [{ "family": "Olsson" }] }
+            ]"#,
+        )
+        .unwrap();
+        db.reset_references(refs);
+        insert_ascending_notes(&mut db, &["a", "z", "o"]);
+        db
+    }
+
+    fn bib_ids(db: &Processor) -> Vec<String> {
+        db.get_bibliography()
+            .into_iter()
+            .map(|entry| entry.id.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn default_locale_macro_key() {
+        let db = mk_db(STYLE);
+        assert_eq!(db.collation().tag(), Some("sv-SE"));
+        assert_eq!(bib_ids(&db), vec!["o", "z", "a"]);
+    }
+
+    #[test]
+    fn default_locale_name_key() {
+        let style = STYLE.replace(r#"<key macro="title" />"#, r#"<key variable="author" />"#);
+        let db = mk_db(&style);
+        assert_eq!(bib_ids(&db), vec!["o", "z", "a"]);
+    }
+
+    #[test]
+    fn overridden() {
+        let mut db = mk_db(STYLE);
+        db.set_collation(Collation::for_tag("en-US"));
+        assert_eq!(bib_ids(&db), vec!["a", "o", "z"]);
+        db.set_collation(None);
+        assert_eq!(bib_ids(&db), vec!["o", "z", "a"]);
+    }
+
+    #[test]
+    fn saved_in_session() {
+        let mut db = mk_db(STYLE);
+        db.set_collation(Collation::for_tag("de-u-co-phonebk"));
+        let session = db.export_session();
+        assert_eq!(session.collation.as_deref(), Some("de-u-co-phonebk"));
+        let restored = Processor::import_session(session, None).unwrap();
+        assert_eq!(restored.collation_override(), db.collation_override());
+    }
+}
+
+mod memory {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout><text variable="title" /></layout></citation>
+    </style>"#;
+
+    fn mk_db() -> Processor {
+        let mut db = Processor::new(InitOptions {
+            style: STYLE,
+            format: SupportedFormat::Plain,
+            test_mode: true,
+            sweep_policy: SweepPolicy::MANUAL,
+            ..Default::default()
+        })
+        .unwrap();
+        insert_basic_refs(&mut db, &["one", "two", "three"]);
+        insert_ascending_notes(&mut db, &["one", "two", "three"]);
+        db
+    }
+
+    #[test]
+    fn outdated_results_for_removed_clusters() {
+        let mut db = mk_db();
+        db.compute();
+        let before = db.memory_stats();
+        assert!(before.ir.memoized > 0);
+
+        let three = cid(&mut db, 3);
+        db.remove_cluster(three);
+        db.remove_reference(Atom::from("three"));
+        db.compute();
+        db.collect_garbage(GcLevel::Outdated);
+        let after = db.memory_stats();
+        assert!(after.ir.memoized < before.ir.memoized);
+        assert!(after.cite.memoized < before.cite.memoized);
+
+        let two = cid(&mut db, 2);
+        assert_cluster!(db.get_cluster(two), Some("Book two"));
+    }
+
+    #[test]
+    fn clusters_outside_the_document_survive() {
+        let mut db = mk_db();
+        let one = cid(&mut db, 1);
+        let two = cid(&mut db, 2);
+        db.compute();
+        db.set_cluster_order(&[ClusterPosition::note(one, 1)])
+            .unwrap();
+        db.compute();
+        db.collect_garbage(GcLevel::Outdated);
+        db.set_cluster_order(&[ClusterPosition::note(one, 1), ClusterPosition::note(two, 2)])
+            .unwrap();
+        assert_cluster!(db.get_cluster(two), Some("Book two"));
+    }
+
+    #[test]
+    fn all_values() {
+        let mut db = mk_db();
+        let one = cid(&mut db, 1);
+        db.compute();
+        let before = db.memory_stats();
+        db.collect_garbage(GcLevel::AllValues);
+        let after = db.memory_stats();
+        assert!(after.ir.memoized < before.ir.memoized);
+        assert_cluster!(db.get_cluster(one), Some("Book one"));
+    }
+
+    #[test]
+    fn every_query_is_counted() {
+        use salsa::plumbing::DatabaseOps;
+        let db = mk_db();
+        let mut storages = 0;
+        db.for_each_query(&mut |_| storages += 1);
+        let stats = db.memory_stats();
+        let listed = [&stats.ir, &stats.cite, &stats.locale, &stats.style]
+            .iter()
+            .map(|group| group.queries.len())
+            .sum::<usize>();
+        // `cite` and `disamb_name` are interned, and each has a lookup query sharing its table.
+        assert_eq!(listed + 2, storages);
+    }
+
+    #[test]
+    fn policy() {
+        let mut db = mk_db();
+        db.compute();
+        let three = cid(&mut db, 3);
+        db.remove_cluster(three);
+        db.compute();
+        let unswept = db.memory_stats().ir.memoized;
+        db.set_sweep_policy(SweepPolicy {
+            every: 2,
+            level: GcLevel::Outdated,
+        });
+        db.compute();
+        assert_eq!(db.memory_stats().ir.memoized, unswept);
+        db.compute();
+        assert!(db.memory_stats().ir.memoized < unswept);
+    }
+}
+
+mod render_errors {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout><text variable="title" /></layout></citation>
+        <bibliography><layout><text variable="title" /></layout></bibliography>
+    </style>"#;
+
+    /// Panics the first time anything is rendered, which is when the locale is needed.
+    struct PanickingFetcher;
+
+    impl LocaleFetcher for PanickingFetcher {
+        fn fetch_string(&self, _lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
+            panic!("no locales here")
+        }
+    }
+
+    fn mk_db(panic_mode: PanicMode) -> Processor {
+        let mut db = Processor::new(InitOptions {
+            style: STYLE,
+            format: SupportedFormat::Plain,
+            test_mode: true,
+            fetcher: Some(Arc::new(PanickingFetcher)),
+            parallelism: Parallelism::Serial,
+            panic_mode,
+            ..Default::default()
+        })
+        .unwrap();
+        insert_basic_refs(&mut db, &["one", "two"]);
+        insert_ascending_notes(&mut db, &["one", "two"]);
+        db
+    }
+
+    fn error(cluster_id: Option<ClusterId>, ref_id: &str) -> RenderError {
+        RenderError {
+            cluster_id,
+            ref_id: Some(Atom::from(ref_id)),
+            message: "no locales here".into(),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "no locales here")]
+    fn propagates_by_default() {
+        let mut db = mk_db(PanicMode::default());
+        let one = cid(&mut db, 1);
+        db.get_cluster(one);
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut db = mk_db(PanicMode::placeholder("[error]"));
+        let one = cid(&mut db, 1);
+        let two = cid(&mut db, 2);
+        let summary = db.batched_updates();
+        let placeholder = Arc::new(SmartString::from("[error]"));
+        assert_eq!(
+            summary.clusters,
+            vec![(one, placeholder.clone()), (two, placeholder.clone())]
+        );
+        let bib = summary.bibliography.unwrap();
+        assert_eq!(bib.updated_entries.len(), 2);
+        assert!(bib.updated_entries.values().all(|v| *v == placeholder));
+        assert_eq!(
+            summary.render_errors,
+            vec![
+                error(Some(one), "one"),
+                error(Some(two), "two"),
+                error(None, "one"),
+                error(None, "two"),
+            ]
+        );
+        assert_eq!(db.take_render_errors(), vec![]);
+
+        assert_cluster!(db.get_cluster(one), Some("[error]"));
+        assert_eq!(db.take_render_errors(), vec![error(Some(one), "one")]);
+    }
+
+    #[test]
+    #[cfg(feature = "pandoc")]
+    fn pandoc_placeholders() {
+        use pandoc_types::definition::Inline;
+        let mut db = mk_db(PanicMode::placeholder("[render error]"));
+        db.set_output_format(SupportedFormat::Pandoc, Default::default());
+        let one = cid(&mut db, 1);
+        let placeholder = vec![
+            Inline::Str("[render".into()),
+            Inline::Space,
+            Inline::Str("error]".into()),
+        ];
+        assert_eq!(db.get_cluster_pandoc(one), Some(placeholder.clone()));
+        assert_eq!(
+            db.get_bibliography_pandoc(),
+            Some(vec![
+                (Atom::from("one"), placeholder.clone()),
+                (Atom::from("two"), placeholder),
+            ])
+        );
+    }
+}
+
+mod diagnostics {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="note" default-locale="tlh">
+        <citation><layout>
+            <text term="in" />
+            <text variable="title" />
+        </layout></citation>
+    </style>"#;
+
+    const EN_US: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <locale xmlns="http://purl.org/net/xbiblio/csl" version="1.0" xml:lang="en-US">
+        <terms></terms></locale>"#;
+
+    fn fetcher() -> Arc<dyn LocaleFetcher> {
+        let mut locales = HashMap::new();
+        locales.insert(Lang::en_us(), EN_US.to_string());
+        Arc::new(PredefinedLocales(locales))
+    }
+
+    fn mk_db() -> Processor {
+        let mut db = Processor::new(InitOptions {
+            style: STYLE,
+            format: SupportedFormat::Plain,
+            test_mode: true,
+            fetcher: Some(fetcher()),
+            ..Default::default()
+        })
+        .unwrap();
+        let refr: Reference = serde_json::from_str(
+            r#"{ "id": "one", "type": "book", "tittle": "A typo",
+                 "issued": { "date-parts": "1998" } }"#,
+        )
+        .unwrap();
+        db.insert_reference(refr);
+        insert_ascending_notes(&mut db, &["one", "missing"]);
+        db
+    }
+
+    #[test]
+    fn codes() {
+        let mut db = mk_db();
+        db.compute();
+        let two = cid(&mut db, 2);
+        let found: Vec<_> = db
+            .diagnostics()
+            .into_iter()
+            .map(|d| (d.code, d.cluster_id, d.ref_id))
+            .collect();
+        let one = Some(Atom::from("one"));
+        assert_eq!(
+            found,
+            vec![
+                (DiagnosticCode::MissingLocale, None, None),
+                (DiagnosticCode::MissingTerm, None, None),
+                (DiagnosticCode::UnknownVariable, None, one.clone()),
+                (DiagnosticCode::InvalidDate, None, one),
+                (
+                    DiagnosticCode::MissingReference,
+                    Some(two),
+                    Some(Atom::from("missing"))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn session_round_trip() {
+        let db = mk_db();
+        let session = db.export_session();
+        assert_eq!(session.reference_problems.len(), 1);
+        let json = serde_json::to_string(&session).unwrap();
+        let restored =
+            Processor::import_session(serde_json::from_str(&json).unwrap(), Some(fetcher()))
+                .unwrap();
+        assert_eq!(restored.diagnostics_str(), db.diagnostics_str());
+        assert_eq!(restored.export_session(), session);
+    }
+
+    #[test]
+    fn string_ids() {
+        let db = mk_db();
+        let missing = db.diagnostics_str().pop().unwrap();
+        assert_eq!(
+            missing,
+            string_id::Diagnostic {
+                code: DiagnosticCode::MissingReference,
+                cluster_id: Some("2".into()),
+                ref_id: Some(Atom::from("missing")),
+                message: "reference `missing` not found".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn term_scope() {
+        let style = r#"<style version="1.0" class="in-text">
+            <macro name="container">
+                <choose>
+                    <if type="chapter"><text term="in" /></if>
+                    <else><text term="anonymous" plural="true" /></else>
+                </choose>
+            </macro>
+            <citation><layout>
+                <text macro="container" />
+                <label variable="page" />
+                <text variable="page" />
+            </layout></citation>
+            <bibliography><layout><text macro="container" /></layout></bibliography>
+        </style>"#;
+        let mut locales = HashMap::new();
+        locales.insert(Lang::en_us(), EN_US.to_string());
+        let db = Processor::new(InitOptions {
+            style,
+            format: SupportedFormat::Plain,
+            test_mode: true,
+            fetcher: Some(Arc::new(PredefinedLocales(locales))),
+            ..Default::default()
+        })
+        .unwrap();
+        // Both branches are checked, once each; the page label is not.
+        let messages: Vec<_> = db.diagnostics().into_iter().map(|d| d.message).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "term `in` (singular) is not defined for en-US".to_owned(),
+                "term `anonymous` (plural) is not defined for en-US".to_owned(),
+            ]
+        );
+    }
+}
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    use super::*;
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout><text variable="title" /></layout></citation>
+        <bibliography><layout><text variable="title" /></layout></bibliography>
+    </style>"#;
+
+    fn mk_db(parallelism: Parallelism) -> Processor {
+        let mut db = Processor::new(InitOptions {
+            style: STYLE,
+            format: SupportedFormat::Plain,
+            test_mode: true,
+            parallelism,
+            ..Default::default()
+        })
+        .unwrap();
+        insert_basic_refs(&mut db, &["one", "two", "three"]);
+        insert_ascending_notes(&mut db, &["one", "two", "three"]);
+        db
+    }
+
+    fn sorted(
+        mut updates: Vec<(ClusterId, Arc<SmartString>)>,
+    ) -> Vec<(ClusterId, Arc<SmartString>)> {
+        updates.sort_by_key(|(id, _)| id.0);
+        updates
+    }
+
+    #[test]
+    fn same_output_on_any_pool() {
+        let serial = mk_db(Parallelism::Serial);
+        let expected = sorted(serial.compute());
+        assert_eq!(expected.len(), 3);
+        for parallelism in vec![
+            Parallelism::GlobalPool,
+            Parallelism::with_threads(2).unwrap(),
+        ] {
+            let db = mk_db(parallelism);
+            assert_eq!(sorted(db.compute()), expected);
+            assert_eq!(db.get_bibliography(), serial.get_bibliography());
+        }
+    }
+
+    #[test]
+    fn shared_snapshot_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedSnapshot>();
+
+        let mut db = mk_db(Parallelism::with_threads(2).unwrap());
+        let ids: Vec<ClusterId> = (1..=3).map(|n| cid(&mut db, n)).collect();
+        let expected: Vec<_> = ids.iter().map(|&id| db.get_cluster(id)).collect();
+        let shared = Arc::new(db.shared_snapshot());
+        let threads: Vec<_> = ids
+            .iter()
+            .map(|&id| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    assert_eq!(shared.get_bibliography().len(), 3);
+                    shared.get_cluster(id)
+                })
+            })
+            .collect();
+        let rendered: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert_eq!(rendered, expected);
+        drop(shared);
+        db.remove_cluster(ids[0]);
+        assert_eq!(db.get_cluster(ids[0]), None);
+    }
+}
+
+#[cfg(feature = "pandoc")]
+mod pandoc {
+    use super::*;
+    use pandoc_types::definition::{Inline, MathType};
+
+    const STYLE: &str = r#"<style version="1.0" class="in-text">
+        <citation><layout><text variable="title" /></layout></citation>
+    </style>"#;
+
+    #[test]
+    fn markdown_title_to_inlines() {
+        let mut db = test_db(Some(STYLE));
+        let mut refr = Reference::empty(Atom::from("ref"), CslType::Book);
+        refr.ordinary
+            .insert(Variable::Title, "On *Species* and $x^2$".into());
+        db.insert_reference(refr);
+        insert_ascending_notes(&mut db, &["ref"]);
+        let id = cid(&mut db, 1);
+        assert_eq!(db.get_cluster_pandoc(id), None);
+        db.set_output_format(SupportedFormat::Pandoc, Default::default());
+        let s = |x: &str| Inline::Str(x.into());
+        assert_eq!(
+            db.get_cluster_pandoc(id),
+            Some(vec![
+                s("On"),
+                Inline::Space,
+                Inline::Emph(vec![s("Species")]),
+                Inline::Space,
+                s("and"),
+                Inline::Space,
+                Inline::Math(MathType::InlineMath, "x^2".into()),
+            ])
+        );
+        // The same inlines as the JSON that get_cluster returns.
+        let json: Vec<Inline> = serde_json::from_str(&db.get_cluster(id).unwrap()).unwrap();
+        assert_eq!(db.get_cluster_pandoc(id), Some(json));
+    }
+
+    #[test]
+    fn bibliography_like_get_bibliography() {
+        let mut db = test_db(Some(
+            r#"<style version="1.0" class="in-text">
+                <citation><layout><text variable="title" /></layout></citation>
+                <bibliography subsequent-author-substitute="———">
+                    <layout><group delimiter=" ">
+                        <names variable="author" />
+                        <text variable="title" />
+                    </group></layout>
+                </bibliography>
+            </style>"#,
+        ));
+        db.set_output_format(SupportedFormat::Pandoc, Default::default());
+        let refs: Vec<Reference> = serde_json::from_value(serde_json::json!([
+            { "id": "one", "type": "book", "title": "One",
+              "author": [{ "family": "Smith", "given": "John" }] },
+            { "id": "two", "type": "book", "title": "Two",
+              "author": [{ "family": "Smith", "given": "John" }] },
+            { "id": "empty", "type": "book" },
+        ]))
+        .unwrap();
+        db.reset_references(refs);
+        insert_ascending_notes(&mut db, &["one", "two", "empty"]);
+        let s = |x: &str| Inline::Str(x.into());
+        assert_eq!(
+            db.get_bibliography_pandoc(),
+            Some(vec![
+                (
+                    Atom::from("one"),
+                    vec![
+                        s("John"),
+                        Inline::Space,
+                        s("Smith"),
+                        Inline::Space,
+                        s("One")
+                    ]
+                ),
+                (Atom::from("two"), vec![s("———"), Inline::Space, s("Two")]),
+            ])
+        );
+        let ids: Vec<_> = db.get_bibliography().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![Atom::from("one"), Atom::from("two")]);
+    }
 }
@@ -24,31 +24,18 @@ cfg_if! {
 }
 
 mod test_format;
-use test_format::{humans::parse_human_test, yaml::parse_yaml_test};
+use test_format::{
+    humans::{parse_human_csl, parse_human_test},
+    yaml::parse_yaml_test,
+    FixtureCase, PrettyString,
+};
 
 use lazy_static::lazy_static;
 use pretty_assertions::assert_eq;
 use std::collections::HashSet;
-use std::fmt;
 use std::fs::read_to_string;
 use std::path::Path;
 
-/// See https://github.com/colin-kiegel/rust-pretty-assertions/issues/24
-///
-/// Wrapper around string slice that makes debug output `{:?}` to print string same way as `{}`.
-/// Used in different `assert*!` macros in combination with `pretty_assertions` crate to make
-/// test failures to show nice diffs.
-#[derive(PartialEq, Eq)]
-#[doc(hidden)]
-pub struct PrettyString<'a>(pub &'a str);
-
-/// Make diff to display string as multi-line string
-impl<'a> fmt::Debug for PrettyString<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(self.0)
-    }
-}
-
 fn ignore_file(file: &str) -> HashSet<String> {
     let mut m = HashSet::new();
     for name in file.lines() {
@@ -120,9 +107,26 @@ fn csl_test_suite(path: &Path) {
 fn humans(path: &Path) {
     setup();
     let input = read_to_string(path).unwrap();
-    let mut test_case = parse_yaml_test(&input).unwrap();
-    if let Some(res) = test_case.execute() {
-        assert_eq!(PrettyString(&res), PrettyString(&test_case.result));
+    for FixtureCase { mut case, .. } in parse_yaml_test(&input).unwrap() {
+        if let Some(res) = case.execute() {
+            assert_eq!(PrettyString(&res), PrettyString(&case.result));
+        }
+    }
+}
+
+/// The JATS fixtures are also checked against the JATS 1.2 declarations in `tests/data/jats`.
+#[datatest::files("tests/data/humans", {
+    path in r"^jats_(.*)\.yml",
+})]
+fn jats_schema(path: &Path) {
+    setup();
+    let input = read_to_string(path).unwrap();
+    for FixtureCase { mut case, .. } in parse_yaml_test(&input).unwrap() {
+        if let Some(res) = case.execute() {
+            if let Err(e) = test_format::jats::validate(&res, case.mode) {
+                panic!("invalid JATS: {}\n{}", e, res);
+            }
+        }
     }
 }
 
@@ -149,3 +153,65 @@ fn fixtures_local(path: &Path) {
         }
     }
 }
+
+/// Writing a style back out with `Style::to_xml` must not change what it parses to.
+fn assert_style_round_trip(csl: &str, features: Option<csl::Features>) {
+    let options = csl::ParseOptions {
+        allow_no_info: true,
+        features,
+        ..Default::default()
+    };
+    // Some fixtures test how invalid styles are rejected; there is nothing to write.
+    let style = match csl::Style::parse_with_opts(csl, options.clone()) {
+        Ok(style) => style,
+        Err(_) => return,
+    };
+    let written = style.to_xml();
+    match csl::Style::parse_with_opts(&written, options) {
+        Ok(reparsed) => assert_eq!(style, reparsed, "\n{}", written),
+        Err(e) => panic!("written style did not parse: {:?}\n{}", e, written),
+    }
+}
+
+// ignore.txt lists fixtures the processor fails, not styles that can't be written, so the
+// round-trip tests run every fixture. They only read the CSL section, which works for fixtures
+// whose mode or other sections the test harness can't parse.
+#[datatest::files("tests/data/test-suite/processor-tests/humans", {
+    path in r"^(.*)\.txt",
+})]
+fn style_round_trip_suite(path: &Path) {
+    let input = read_to_string(path).unwrap();
+    let csl = parse_human_csl(&input).expect("test case without a CSL section");
+    assert_style_round_trip(&csl, None);
+}
+
+#[datatest::files("tests/data/humans", {
+    path in r"^(.*)\.yml",
+})]
+fn style_round_trip_humans(path: &Path) {
+    let input = read_to_string(path).unwrap();
+    for FixtureCase { case, .. } in parse_yaml_test(&input).unwrap() {
+        assert_style_round_trip(&case.csl, case.init.csl_features);
+    }
+}
+
+#[datatest::files("tests/data/fixtures-local", {
+    path in r"^(.*)\.txt",
+})]
+fn style_round_trip_local(path: &Path) {
+    let input = read_to_string(path).unwrap();
+    let csl = parse_human_csl(&input).expect("test case without a CSL section");
+    let features = csl::Features {
+        custom_intext: true,
+        ..Default::default()
+    };
+    assert_style_round_trip(&csl, Some(features));
+}
+
+#[datatest::files("tests/data/styles", {
+    path in r"^(.*)\.csl",
+})]
+fn style_round_trip_styles(path: &Path) {
+    let csl = read_to_string(path).unwrap();
+    assert_style_round_trip(&csl, None);
+}
@@ -0,0 +1,254 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Checks JATS output against the JATS 1.2 declarations in
+//! `tests/data/jats/citation-subset.dtd`.
+//!
+//! Every content model in that file is either mixed content, `(#PCDATA | a | b)*`, or a group of
+//! child elements, so an element is checked by the names its model mentions: its children must be
+//! among them, and it may only contain character data if the model has `#PCDATA`. The order and
+//! number of children in a group (`ref-list`, `ref`) are not checked. Attributes must be declared
+//! in the element's `<!ATTLIST>`, `#REQUIRED` ones must be present, and enumerated ones must have
+//! one of their listed values.
+
+use super::Mode;
+use anyhow::{anyhow, bail, Error};
+use std::collections::HashMap;
+use std::fs::read_to_string;
+
+const XLINK: &str = "http://www.w3.org/1999/xlink";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+
+#[derive(Debug, Default)]
+struct Element {
+    text: bool,
+    children: Vec<String>,
+    attributes: Vec<Attribute>,
+}
+
+#[derive(Debug)]
+struct Attribute {
+    name: String,
+    /// For enumerated types.
+    values: Option<Vec<String>>,
+    required: bool,
+}
+
+fn load_dtd() -> Result<HashMap<String, Element>, Error> {
+    let dtd = read_to_string("./tests/data/jats/citation-subset.dtd")?;
+    let mut entities = HashMap::new();
+    let mut elements: HashMap<String, Element> = HashMap::new();
+    for decl in declarations(&dtd)? {
+        let decl = expand_entities(decl, &entities)?;
+        let tokens = tokenize(&decl)?;
+        match tokens.as_slice() {
+            [keyword, percent, name, value] if keyword == "ENTITY" && percent == "%" => {
+                entities.insert(name.clone(), unquote(value)?.to_owned());
+            }
+            [keyword, name, model] if keyword == "ELEMENT" => {
+                let element = elements.entry(name.clone()).or_default();
+                if model == "EMPTY" {
+                    continue;
+                }
+                if !model.starts_with('(') {
+                    bail!("unsupported content model for <{}>: {}", name, model);
+                }
+                for word in model.split(|c: char| "(),|*+?".contains(c) || c.is_whitespace()) {
+                    match word {
+                        "" => {}
+                        "#PCDATA" => element.text = true,
+                        child => element.children.push(child.to_owned()),
+                    }
+                }
+            }
+            [keyword, name, defs @ ..] if keyword == "ATTLIST" => {
+                let element = elements.entry(name.clone()).or_default();
+                let mut defs = defs.iter();
+                while let Some(att_name) = defs.next() {
+                    let (ty, default) = match (defs.next(), defs.next()) {
+                        (Some(ty), Some(default)) => (ty, default),
+                        _ => bail!("incomplete attribute {} on <{}>", att_name, name),
+                    };
+                    if default == "#FIXED" {
+                        defs.next();
+                    }
+                    let values = if ty.starts_with('(') {
+                        Some(
+                            ty.trim_matches(|c| c == '(' || c == ')')
+                                .split('|')
+                                .map(|v| v.trim().to_owned())
+                                .collect(),
+                        )
+                    } else {
+                        None
+                    };
+                    element.attributes.push(Attribute {
+                        name: att_name.clone(),
+                        values,
+                        required: default == "#REQUIRED",
+                    });
+                }
+            }
+            _ => bail!("unsupported declaration <!{}>", decl),
+        }
+    }
+    Ok(elements)
+}
+
+/// The text of each `<!...>` declaration, without comments.
+fn declarations(dtd: &str) -> Result<Vec<&str>, Error> {
+    let mut decls = Vec::new();
+    let mut rest = dtd;
+    while let Some(start) = rest.find("<!") {
+        rest = &rest[start..];
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|end| end + 3)
+        } else {
+            let end = rest.find('>');
+            decls.extend(end.map(|end| &rest[2..end]));
+            end.map(|end| end + 1)
+        };
+        rest = &rest[end.ok_or_else(|| anyhow!("unterminated declaration"))?..];
+    }
+    Ok(decls)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || "-_:.".contains(c)
+}
+
+/// Replaces `%name;` references with the entity's value.
+fn expand_entities(decl: &str, entities: &HashMap<String, String>) -> Result<String, Error> {
+    let mut expanded = String::new();
+    let mut rest = decl;
+    while let Some(ix) = rest.find('%') {
+        expanded.push_str(&rest[..ix]);
+        rest = &rest[ix + 1..];
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if len > 0 && rest[len..].starts_with(';') {
+            let value = entities
+                .get(&rest[..len])
+                .ok_or_else(|| anyhow!("undeclared parameter entity %{};", &rest[..len]))?;
+            expanded.push_str(value);
+            rest = &rest[len + 1..];
+        } else {
+            expanded.push('%');
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Splits a declaration into words, quoted literals and parenthesised groups, each with any
+/// trailing `?`, `*` or `+`.
+fn tokenize(decl: &str) -> Result<Vec<String>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = decl.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = match c {
+            '"' | '\'' => rest[1..].find(c).map(|end| end + 2),
+            '(' => {
+                let mut depth = 0;
+                rest.find(|c| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|end| end + 1)
+            }
+            _ => Some(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+        }
+        .ok_or_else(|| anyhow!("unterminated {} in <!{}>", c, decl))?;
+        let len = rest[len..]
+            .find(|c| !"?*+".contains(c))
+            .map_or(rest.len(), |end| len + end);
+        tokens.push(rest[..len].to_owned());
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+fn unquote(literal: &str) -> Result<&str, Error> {
+    literal
+        .strip_prefix('"')
+        .and_then(|l| l.strip_suffix('"'))
+        .or_else(|| {
+            literal
+                .strip_prefix('\'')
+                .and_then(|l| l.strip_suffix('\''))
+        })
+        .ok_or_else(|| anyhow!("expected a quoted literal, found {}", literal))
+}
+
+/// A citation or preview is validated as the contents of a `<mixed-citation>`; a bibliography is
+/// expected to be a `<ref-list>` as produced by the test harness.
+pub fn validate(output: &str, mode: Mode) -> Result<(), Error> {
+    let dtd = load_dtd()?;
+    let doc = match mode {
+        Mode::Citation | Mode::Preview => format!(
+            r#"<mixed-citation xmlns:xlink="{}">{}</mixed-citation>"#,
+            XLINK, output
+        ),
+        Mode::Bibliography => output.replacen(
+            "<ref-list>",
+            &format!(r#"<ref-list xmlns:xlink="{}">"#, XLINK),
+            1,
+        ),
+    };
+    let doc = roxmltree::Document::parse(&doc)?;
+    validate_node(&dtd, doc.root_element())
+}
+
+fn validate_node(dtd: &HashMap<String, Element>, node: roxmltree::Node) -> Result<(), Error> {
+    let name = node.tag_name().name();
+    let element = dtd
+        .get(name)
+        .ok_or_else(|| anyhow!("<{}> is not declared", name))?;
+    for attr in node.attributes() {
+        let attr_name = match attr.namespace() {
+            Some(XLINK) => format!("xlink:{}", attr.name()),
+            Some(XML) => format!("xml:{}", attr.name()),
+            _ => attr.name().to_owned(),
+        };
+        let decl = element
+            .attributes
+            .iter()
+            .find(|decl| decl.name == attr_name)
+            .ok_or_else(|| anyhow!("attribute {} not allowed on <{}>", attr_name, name))?;
+        if let Some(values) = &decl.values {
+            if !values.iter().any(|v| v == attr.value()) {
+                bail!("{}={:?} not allowed on <{}>", attr_name, attr.value(), name);
+            }
+        }
+    }
+    for decl in element.attributes.iter().filter(|decl| decl.required) {
+        let present = node.attributes().iter().any(|attr| {
+            let local = decl.name.rsplit(':').next().unwrap_or(&decl.name);
+            attr.name() == local
+        });
+        if !present {
+            bail!("<{}> is missing required attribute {}", name, decl.name);
+        }
+    }
+    for child in node.children() {
+        if child.is_element() {
+            let child_name = child.tag_name().name();
+            if !element.children.iter().any(|c| c == child_name) {
+                bail!("<{}> not allowed inside <{}>", child_name, name);
+            }
+            validate_node(dtd, child)?;
+        } else if child.is_text()
+            && !element.text
+            && child.text().map_or(false, |t| !t.trim().is_empty())
+        {
+            bail!("character data not allowed inside <{}>", name);
+        }
+    }
+    Ok(())
+}
@@ -34,6 +34,16 @@ pub fn file_diagnostics<'a>(err: &StyleError, filename: &'a str, document: &'a s
     }
 }
 
+pub fn lint_diagnostics<'a>(lints: &[InvalidCsl], filename: &'a str, document: &'a str) {
+    let mut code_map = CodeMap::new();
+    code_map.add_filemap(filename.to_owned().into(), document.to_string());
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    for d in lints.iter().filter_map(to_diagnostic) {
+        emit(&mut writer.lock(), &code_map, &d).unwrap();
+        eprintln!();
+    }
+}
+
 pub(crate) fn diagnostics(err: &StyleError, file_map: &FileMap) -> Vec<Result<Diagnostic, String>> {
     match *err {
         StyleError::Invalid(ref invs) => invs
@@ -21,7 +21,7 @@ use citeproc::input::Reference;
 use clap::{App, Arg, SubCommand};
 use directories::ProjectDirs;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -29,8 +29,10 @@ mod error;
 mod pandoc;
 use pandoc_types::definition::{Inline, MetaValue, Pandoc as PandocDocument};
 
-use citeproc::{LocaleFetchError, LocaleFetcher, Processor};
-use csl::{Lang, Locale};
+use citeproc::prelude::LocaleDirectory;
+use citeproc::{DisambStep, InitOptions, LocaleFetcher, Processor, SupportedFormat};
+use citeproc_test::runner::{run_path, Outcome, Report, RunOptions};
+use csl::{Lang, Locale, StyleClass};
 
 fn main() {
     // heuristically determine if we're running as an external pandoc filter
@@ -59,6 +61,63 @@ fn main() {
             "Force Pandoc JSON filter mode. Operates on stdin > stdout.\
              \nNormally, you can just use `pandoc -F citeproc-rs`.",
         ))
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Checks a CSL style for errors, and for mistakes that aren't errors")
+                .arg(
+                    Arg::with_name("style")
+                        .value_name("FILE")
+                        .help("A CSL style")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain-disamb")
+                .about("Shows how each cite was disambiguated, and which remain ambiguous")
+                .arg(
+                    Arg::with_name("style")
+                        .value_name("FILE")
+                        .help("A CSL style")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("library")
+                        .value_name("FILE.json")
+                        .help("A CSL-JSON file")
+                        .required(true)
+                        .index(2),
+                )
+                .arg(
+                    Arg::with_name("keys")
+                        .value_name("CITEKEY")
+                        .help("Cite these, each in its own cluster (default: the whole library)")
+                        .multiple(true)
+                        .index(3),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Print the explanations as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("Runs YAML and TOML test fixtures, and shows how the output differs")
+                .arg(
+                    Arg::with_name("dir")
+                        .value_name("DIR")
+                        .help("A directory of fixtures, or a single fixture")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("update")
+                        .long("update")
+                        .help("Rewrite failing fixtures to expect the actual output"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("disamb-index")
                 .about("Prints the inverted disambiguation index for the reference library"),
@@ -130,18 +189,12 @@ fn main() {
         let locales_dir = matches
             .value_of("locales-dir")
             .map(PathBuf::from)
-            .unwrap_or_else(|| {
-                let pd = ProjectDirs::from("net", "cormacrelf", "citeproc-rs")
-                    .expect("No home directory found.");
-                let mut locales_dir = pd.cache_dir().to_owned();
-                locales_dir.push("locales");
-                locales_dir
-            });
+            .unwrap_or_else(default_locales_dir);
         if matches.subcommand_matches("parse-locale").is_some() {
             let locales_dir = locales_dir.clone();
             dbg!(locales_dir);
         }
-        Arc::new(Filesystem::new(locales_dir))
+        locale_fetcher(locales_dir)
     };
 
     if let Some(matches) = matches.subcommand_matches("parse-locale") {
@@ -158,7 +211,7 @@ fn main() {
         } else {
             Lang::en_us()
         };
-        fn fetch_cli(fetcher: &Filesystem, lang: &Lang) -> Option<Locale> {
+        fn fetch_cli(fetcher: &dyn LocaleFetcher, lang: &Lang) -> Option<Locale> {
             let string = match fetcher.fetch_string(lang) {
                 Ok(opt) => opt?,
                 Err(e) => panic!("failed to read locale file, exiting\n{:?}", e),
@@ -172,11 +225,84 @@ fn main() {
                 }
             }
         }
-        let locale = fetch_cli(&filesystem_fetcher, &lang);
+        let locale = fetch_cli(&*filesystem_fetcher, &lang);
         dbg!(locale);
         return;
     }
 
+    if let Some(matches) = matches.subcommand_matches("lint") {
+        let csl_path = matches.value_of("style").unwrap();
+        let text = fs::read_to_string(&csl_path).expect("No CSL file found at that path");
+        let options = InitOptions {
+            style: &text,
+            fetcher: Some(filesystem_fetcher),
+            ..Default::default()
+        };
+        // A style that fails to parse can still be linted, just against en-US.
+        let linted = match Processor::new(options) {
+            Ok(db) => db.lint_style(&text),
+            Err(_) => csl::lint::lint_style(&text, Default::default(), None),
+        };
+        match linted {
+            Ok(lints) => {
+                self::error::lint_diagnostics(&lints, &csl_path, &text);
+                if lints.iter().any(|l| l.severity == csl::Severity::Error) {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                self::error::file_diagnostics(&e, &csl_path, &text);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("explain-disamb") {
+        let csl_path = matches.value_of("style").unwrap();
+        let text = fs::read_to_string(&csl_path).expect("No CSL file found at that path");
+        let options = InitOptions {
+            style: &text,
+            fetcher: Some(filesystem_fetcher),
+            format: SupportedFormat::Plain,
+            ..Default::default()
+        };
+        match Processor::new(options) {
+            Ok(db) => explain_disamb(
+                db,
+                expect_refs(matches.value_of("library").unwrap()),
+                matches.values_of("keys"),
+                matches.is_present("json"),
+            ),
+            Err(e) => {
+                self::error::file_diagnostics(&e, &csl_path, &text);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("test") {
+        let options = RunOptions {
+            update: matches.is_present("update"),
+            fetcher: Some(filesystem_fetcher),
+        };
+        // Panics are reported with the fixture that caused them
+        std::panic::set_hook(Box::new(|_| {}));
+        match run_path(Path::new(matches.value_of("dir").unwrap()), &options) {
+            Ok(reports) => {
+                if print_test_reports(&reports) {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     // if let Some(_) = matches.subcommand_matches("disamb-index") {
     //     let mut db = Processor::new(filesystem_fetcher);
     //     db.insert_references(refs);
@@ -198,7 +324,13 @@ fn main() {
 
         let text = fs::read_to_string(&csl_path).expect("No CSL file found at that path");
 
-        match Processor::new(&text, filesystem_fetcher) {
+        let options = InitOptions {
+            style: &text,
+            fetcher: Some(filesystem_fetcher),
+            format: SupportedFormat::Pandoc,
+            ..Default::default()
+        };
+        match Processor::new(options) {
             Ok(mut db) => {
                 let refs = if let Some(library_path) = matches.value_of("library") {
                     expect_refs(library_path)
@@ -206,14 +338,17 @@ fn main() {
                     serde_json::from_str(&lib_text).expect("sample lib_text not parseable")
                 };
 
-                db.init_clusters(vec![citeproc::input::Cluster {
-                    id: 0,
-                    note_number: 1,
-                    cites: vec![citeproc::input::Cite::basic(key)],
-                }]);
-                db.inssert_references(refs);
+                db.reset_references(refs);
+                let id = db.cluster_id("cli");
+                db.insert_cluster(citeproc::Cluster::new(
+                    id,
+                    vec![citeproc::io::Cite::basic(key)],
+                    None,
+                ));
+                db.set_cluster_order(&[citeproc::ClusterPosition::note(id, 1)])
+                    .expect("a single cluster is always in order");
 
-                let inlines = db.get_cluster(0).to_vec();
+                let inlines = db.get_cluster_pandoc(id).unwrap_or_default();
 
                 use pandoc_types::definition::{Block, Meta, Pandoc};
                 let doc = Pandoc(Meta::null(), vec![Block::Para(inlines)]);
@@ -227,6 +362,111 @@ fn main() {
     }
 }
 
+fn explain_disamb(mut db: Processor, refs: Vec<Reference>, keys: Option<clap::Values>, json: bool) {
+    let keys: Vec<citeproc::Atom> = match keys {
+        Some(keys) => keys.map(citeproc::Atom::from).collect(),
+        None => refs.iter().map(|r| r.id.clone()).collect(),
+    };
+    db.reset_references(refs);
+    let is_note_style = db.get_style().class == StyleClass::Note;
+    let mut positions = Vec::with_capacity(keys.len());
+    for (ix, key) in keys.iter().enumerate() {
+        let id = db.cluster_id(key.as_ref());
+        db.insert_cluster(citeproc::Cluster::new(
+            id,
+            vec![citeproc::io::Cite::basic(key.clone())],
+            None,
+        ));
+        positions.push(if is_note_style {
+            citeproc::ClusterPosition::note(id, ix as u32 + 1)
+        } else {
+            citeproc::ClusterPosition::in_text(id)
+        });
+    }
+    db.set_cluster_order(&positions)
+        .expect("one cluster per key is always in order");
+
+    let explanations: Vec<_> = keys
+        .iter()
+        .flat_map(|key| {
+            db.explain_disambiguation_str(key.as_ref())
+                .unwrap_or_default()
+        })
+        .collect();
+    let report = db.ambiguity_report_str();
+
+    if json {
+        let out = serde_json::json!({ "cites": explanations, "ambiguous": report });
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+        return;
+    }
+    for explanation in &explanations {
+        println!("{}", explanation.ref_id);
+        if !explanation.reference_found {
+            println!("    (reference not found)");
+        }
+        for report in &explanation.steps {
+            let step = match report.step {
+                DisambStep::Initial => "initial".to_owned(),
+                DisambStep::AddNames => "add names".to_owned(),
+                DisambStep::AddGivenName => "add given name".to_owned(),
+                DisambStep::AddYearSuffix { year_suffix } => {
+                    format!("add year suffix {}", year_suffix)
+                }
+                DisambStep::Conditionals => "disambiguate=\"true\"".to_owned(),
+            };
+            print!("    {:<24} {}", step, report.output);
+            if !report.collisions.is_empty() {
+                let collisions: Vec<&str> = report.collisions.iter().map(|c| c.as_ref()).collect();
+                print!("    (matches {})", collisions.join(", "));
+            }
+            println!();
+        }
+    }
+    if !report.is_empty() {
+        println!();
+        println!("still ambiguous:");
+        for amb in &report {
+            let collisions: Vec<&str> = amb.collides_with.iter().map(|c| c.as_ref()).collect();
+            println!("    {} matches {}", amb.ref_id, collisions.join(", "));
+        }
+    }
+}
+
+/// Returns true if any of the fixtures failed.
+fn print_test_reports(reports: &[Report]) -> bool {
+    let (mut passed, mut failed, mut updated) = (0, 0, 0);
+    for report in reports {
+        let name = match report.format {
+            Some(format) => format!("{} ({})", report.path.display(), format.as_str()),
+            None => report.path.display().to_string(),
+        };
+        match &report.outcome {
+            Outcome::Pass => {
+                passed += 1;
+                println!("ok      {}", name);
+            }
+            Outcome::Updated => {
+                updated += 1;
+                println!("updated {}", name);
+            }
+            Outcome::Fail { expected, actual } => {
+                failed += 1;
+                println!("FAILED  {}", name);
+                println!("{}", citeproc_test::diff(expected, actual));
+            }
+            Outcome::Error(e) => {
+                failed += 1;
+                println!("ERROR   {}", name);
+                println!("    {}", e);
+            }
+        }
+    }
+    println!();
+    println!("{} passed, {} failed, {} updated", passed, failed, updated);
+    failed > 0
+}
+
 fn pandoc_meta_str<'a>(doc: &'a PandocDocument, key: &str) -> Option<&'a str> {
     doc.0.lookup(key).and_then(|value| match value {
         // for metadata passed through the command line
@@ -261,14 +501,24 @@ fn do_pandoc() {
     let csl_path = pandoc_meta_str(&doc, "csl").expect("No csl path provided through metadata");
     let text = fs::read_to_string(&csl_path).expect("No CSL file found at that path");
 
-    match Processor::new(&text, Arc::new(Filesystem::default())) {
+    let options = InitOptions {
+        style: &text,
+        fetcher: Some(locale_fetcher(default_locales_dir())),
+        format: SupportedFormat::Pandoc,
+        ..Default::default()
+    };
+    match Processor::new(options) {
         Ok(mut db) => {
             if let Some(library_path) = pandoc_meta_str(&doc, "bibliography") {
                 db.reset_references(expect_refs(library_path));
             }
-            db.init_clusters(pandoc::get_clusters(&mut doc));
-            db.compute();
-            pandoc::write_clusters(&mut doc, &db);
+            let is_note_style = db.get_style().class == StyleClass::Note;
+            let (clusters, positions) = pandoc::get_clusters(&mut doc, is_note_style);
+            db.init_clusters_str(clusters);
+            db.set_cluster_order_str(&positions)
+                .expect("pandoc numbers its notes in order");
+            pandoc::write_clusters(&mut doc, &db, is_note_style);
+            pandoc::append_bibliography(&mut doc, &db);
             serde_json::to_writer(output, &doc).expect("could not write pandoc json");
         }
         Err(e) => {
@@ -277,47 +527,27 @@ fn do_pandoc() {
     }
 }
 
-pub struct Filesystem {
-    root: PathBuf,
-}
-
-impl Default for Filesystem {
-    fn default() -> Self {
-        let locales_dir = None
-            // TODO: read metadata
-            .unwrap_or_else(|| {
-                let pd = ProjectDirs::from("net", "cormacrelf", "citeproc-rs")
-                    .expect("No home directory found.");
-                let mut locales_dir = pd.cache_dir().to_owned();
-                locales_dir.push("locales");
-                locales_dir
-            });
-        Filesystem::new(locales_dir)
-    }
+fn default_locales_dir() -> PathBuf {
+    // TODO: read metadata
+    let pd =
+        ProjectDirs::from("net", "cormacrelf", "citeproc-rs").expect("No home directory found.");
+    let mut locales_dir = pd.cache_dir().to_owned();
+    locales_dir.push("locales");
+    locales_dir
 }
 
-impl Filesystem {
-    pub fn new(repo_dir: impl Into<PathBuf>) -> Self {
-        Filesystem {
-            root: repo_dir.into(),
-        }
+/// Locales from the directory, falling back to the ones compiled in with `--features
+/// bundled-locales`.
+fn locale_fetcher(locales_dir: PathBuf) -> Arc<dyn LocaleFetcher> {
+    let directory: Arc<dyn LocaleFetcher> = Arc::new(LocaleDirectory::new(locales_dir));
+    #[cfg(feature = "bundled-locales")]
+    {
+        use citeproc::prelude::{BundledLocales, LayeredLocales};
+        Arc::new(LayeredLocales(vec![directory, Arc::new(BundledLocales)]))
     }
-}
-
-use std::io;
-
-impl LocaleFetcher for Filesystem {
-    fn fetch_string(&self, lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
-        let mut path = self.root.clone();
-        path.push(&format!("locales-{}.xml", lang));
-        let read = fs::read_to_string(path);
-        match read {
-            Ok(string) => Ok(Some(string)),
-            Err(e) => match e.kind() {
-                io::ErrorKind::NotFound => Ok(None),
-                _ => Err(LocaleFetchError::Io(e)),
-            },
-        }
+    #[cfg(not(feature = "bundled-locales"))]
+    {
+        directory
     }
 }
 
@@ -4,137 +4,397 @@
 //
 // Copyright © 2019 Corporation for Digital Scholarship
 
-use pandoc_types::definition::CitationMode;
-pub fn suppression_from_pandoc_mode(mode: CitationMode) -> Option<Suppression> {
-    match mode {
-        CitationMode::AuthorInText => Some(Suppression::InText),
-        CitationMode::SuppressAuthor => Some(Suppression::Rest),
-        CitationMode::NormalCitation => None,
-    }
-}
-
 use pandoc_types::{
-    definition::{Block, Citation, Inline, Pandoc as PandocDocument},
+    definition::{
+        Attr, Block, Citation, CitationMode, Inline, MathType, Pandoc as PandocDocument, QuoteType,
+    },
     walk::MutVisitor,
 };
 
-use citeproc::input::{Cite, CiteId, Cluster, ClusterId, Suppression};
-use citeproc::output::Pandoc;
+use citeproc::io::{Cite, CiteMode, ClusterMode, Locator, Locators, NumberLike, SmartString};
+use citeproc::string_id::{Cluster, ClusterPosition};
 use citeproc::Processor;
-use csl::StyleClass;
+use csl::LocatorType;
+
+/// Pandoc's cite ids, in document order.
+fn cluster_id(index: u32) -> SmartString {
+    format!("pandoc-{}", index).into()
+}
 
 struct GetClusters {
-    next_cluster_id: ClusterId,
-    next_cite_id: CiteId,
-    clusters: Vec<Cluster<Pandoc>>,
+    is_note_style: bool,
+    clusters: Vec<Cluster>,
+    positions: Vec<ClusterPosition>,
 }
 
-pub fn get_clusters(pandoc: &mut PandocDocument) -> Vec<Cluster<Pandoc>> {
-    // pandoc-citeproc starts at 1
+/// Reads every `Inline::Cite` into a cluster, with Markdown prefixes and suffixes. Run
+/// [`Processor::set_cluster_order_str`] with the positions.
+pub fn get_clusters(
+    pandoc: &mut PandocDocument,
+    is_note_style: bool,
+) -> (Vec<Cluster>, Vec<ClusterPosition>) {
     let mut gc = GetClusters {
-        next_cluster_id: 1,
-        next_cite_id: 1,
+        is_note_style,
         clusters: vec![],
+        positions: vec![],
     };
     gc.walk_pandoc(pandoc);
-    gc.clusters
+    (gc.clusters, gc.positions)
 }
 
 impl MutVisitor for GetClusters {
-    fn walk_inline(&mut self, inline: &mut Inline) {
-        match *inline {
-            Inline::Note(_) => {
-                // just trying to mirror the cite hashes of pandoc-citeproc
-                self.next_cite_id += 1;
+    fn visit_inline(&mut self, inline: &mut Inline) {
+        if let Inline::Cite(ref p_cites, _) = *inline {
+            let id = cluster_id(self.clusters.len() as u32);
+            let mode = match p_cites.first().map(|p| &p.citation_mode) {
+                Some(CitationMode::AuthorInText) => Some(ClusterMode::Composite {
+                    infix: None,
+                    suppress_first: 1,
+                }),
+                _ => None,
+            };
+            let cites = p_cites.iter().map(cite_from_pandoc).collect();
+            let note_number = p_cites.first().map_or(0, |p| p.citation_note_num);
+            self.positions
+                .push(if self.is_note_style && note_number > 0 {
+                    ClusterPosition::note(id.clone(), note_number as u32)
+                } else {
+                    ClusterPosition::in_text(id.clone())
+                });
+            self.clusters.push(Cluster { id, cites, mode });
+        } else {
+            self.walk_inline(inline);
+        }
+    }
+}
+
+fn cite_from_pandoc(p: &Citation) -> Cite<citeproc::io::output::markup::Markup> {
+    let markdown = |inlines: &[Inline]| -> Option<SmartString> {
+        let mut md = String::new();
+        write_markdown(&mut md, inlines);
+        let md = md.trim();
+        if md.is_empty() {
+            None
+        } else {
+            Some(md.into())
+        }
+    };
+    let mut cite = Cite::basic(p.citation_id.as_str());
+    cite.prefix = markdown(&p.citation_prefix);
+    if let Some(suffix) = markdown(&p.citation_suffix) {
+        let (locator, rest) = parse_locator(&suffix);
+        cite.locators = locator.map(Locators::Single);
+        let rest = rest.trim_end();
+        if !rest.is_empty() {
+            cite.suffix = Some(rest.into());
+        }
+    }
+    if p.citation_mode == CitationMode::SuppressAuthor {
+        cite.mode = Some(CiteMode::SuppressAuthor);
+    }
+    cite
+}
+
+/// The labels recognised at the start of a suffix. Like Pandoc with an English locale, but
+/// without the other languages' terms.
+const LOCATOR_LABELS: &[(&str, LocatorType)] = &[
+    ("book", LocatorType::Book),
+    ("books", LocatorType::Book),
+    ("bk.", LocatorType::Book),
+    ("bks.", LocatorType::Book),
+    ("chapter", LocatorType::Chapter),
+    ("chapters", LocatorType::Chapter),
+    ("chap.", LocatorType::Chapter),
+    ("chaps.", LocatorType::Chapter),
+    ("column", LocatorType::Column),
+    ("columns", LocatorType::Column),
+    ("col.", LocatorType::Column),
+    ("cols.", LocatorType::Column),
+    ("figure", LocatorType::Figure),
+    ("figures", LocatorType::Figure),
+    ("fig.", LocatorType::Figure),
+    ("figs.", LocatorType::Figure),
+    ("folio", LocatorType::Folio),
+    ("folios", LocatorType::Folio),
+    ("fol.", LocatorType::Folio),
+    ("fols.", LocatorType::Folio),
+    ("number", LocatorType::Issue),
+    ("numbers", LocatorType::Issue),
+    ("no.", LocatorType::Issue),
+    ("nos.", LocatorType::Issue),
+    ("line", LocatorType::Line),
+    ("lines", LocatorType::Line),
+    ("l.", LocatorType::Line),
+    ("ll.", LocatorType::Line),
+    ("note", LocatorType::Note),
+    ("notes", LocatorType::Note),
+    ("n.", LocatorType::Note),
+    ("nn.", LocatorType::Note),
+    ("opus", LocatorType::Opus),
+    ("opera", LocatorType::Opus),
+    ("op.", LocatorType::Opus),
+    ("opp.", LocatorType::Opus),
+    ("page", LocatorType::Page),
+    ("pages", LocatorType::Page),
+    ("p.", LocatorType::Page),
+    ("pp.", LocatorType::Page),
+    ("paragraph", LocatorType::Paragraph),
+    ("paragraphs", LocatorType::Paragraph),
+    ("para.", LocatorType::Paragraph),
+    ("paras.", LocatorType::Paragraph),
+    ("¶", LocatorType::Paragraph),
+    ("¶¶", LocatorType::Paragraph),
+    ("part", LocatorType::Part),
+    ("parts", LocatorType::Part),
+    ("pt.", LocatorType::Part),
+    ("pts.", LocatorType::Part),
+    ("section", LocatorType::Section),
+    ("sections", LocatorType::Section),
+    ("sec.", LocatorType::Section),
+    ("secs.", LocatorType::Section),
+    ("§", LocatorType::Section),
+    ("§§", LocatorType::Section),
+    ("sub verbo", LocatorType::SubVerbo),
+    ("sub verbis", LocatorType::SubVerbo),
+    ("s.v.", LocatorType::SubVerbo),
+    ("s.vv.", LocatorType::SubVerbo),
+    ("verse", LocatorType::Verse),
+    ("verses", LocatorType::Verse),
+    ("v.", LocatorType::Verse),
+    ("vv.", LocatorType::Verse),
+    ("volume", LocatorType::Volume),
+    ("volumes", LocatorType::Volume),
+    ("vol.", LocatorType::Volume),
+    ("vols.", LocatorType::Volume),
+];
+
+/// Splits a locator off the start of a suffix, as Pandoc does: an optional comma, an optional
+/// label, and a value like `33`, `33-35`, `iv`, `3:16`, `33, 35` or `{any text}`. Unless it is in
+/// braces, the value has to start with a digit or be a roman numeral. Without a label, it has to
+/// start with a digit, and is a page.
+fn parse_locator(suffix: &str) -> (Option<Locator>, &str) {
+    let start = suffix.trim_start();
+    let start = start.strip_prefix(',').unwrap_or(start).trim_start();
+    let (loc_type, value) = match locator_label(start) {
+        Some((loc_type, len)) => (loc_type, start[len..].trim_start()),
+        None if start.starts_with(|c: char| c.is_ascii_digit()) => (LocatorType::Page, start),
+        None => return (None, suffix),
+    };
+    let (locator, rest) = if let Some(braced) = value.strip_prefix('{') {
+        match braced.find('}') {
+            Some(end) => (&braced[..end], &braced[end + 1..]),
+            None => return (None, suffix),
+        }
+    } else {
+        let (locator, rest) = value.split_at(locator_len(value));
+        let is_roman = |c: char| "ivxlcdmIVXLCDM".contains(c);
+        if !locator.starts_with(|c: char| c.is_ascii_digit()) && !locator.chars().all(is_roman) {
+            return (None, suffix);
+        }
+        (locator, rest)
+    };
+    if locator.is_empty() {
+        return (None, suffix);
+    }
+    let locator = Locator {
+        locator: NumberLike::Str(locator.into()),
+        loc_type,
+    };
+    (Some(locator), rest)
+}
+
+/// The longest label `s` starts with, if it isn't followed by more letters.
+fn locator_label(s: &str) -> Option<(LocatorType, usize)> {
+    LOCATOR_LABELS
+        .iter()
+        .filter(|(label, _)| {
+            s.starts_with(label) && !s[label.len()..].starts_with(char::is_alphabetic)
+        })
+        .max_by_key(|(label, _)| label.len())
+        .map(|(label, loc_type)| (*loc_type, label.len()))
+}
+
+fn locator_len(s: &str) -> usize {
+    let mut len = 0;
+    while let Some(c) = s[len..].chars().next() {
+        if c.is_alphanumeric() || "-–:".contains(c) {
+            len += c.len_utf8();
+        } else if c == ',' {
+            // Only a comma between numbers, as in `33, 35`, is part of the locator.
+            let next = s[len + 1..].trim_start();
+            if !next.starts_with(|c: char| c.is_ascii_digit()) {
+                break;
             }
-            Inline::Cite(ref p_cites, ref _literal) => {
-                let mut note_number = 0;
-                let cites = p_cites
-                    .iter()
-                    .map(|p| {
-                        note_number = p.citation_note_num;
-                        let id = self.next_cite_id;
-                        self.next_cite_id += 1;
-                        Cite {
-                            id,
-                            ref_id: p.citation_id.clone().into(),
-                            suppression: suppression_from_pandoc_mode(p.citation_mode.clone()),
-                            prefix: p.citation_prefix.clone(),
-                            suffix: p.citation_suffix.clone(),
-                            // XXX: parse these out of the suffix, and drop the rest in "suffix"
-                            locators: vec![],
-                            locator_extra: None,
-                            locator_date: None,
-                        }
-                    })
-                    .collect();
-                let cluster = Cluster {
-                    // id == note_number for Pandoc, but not necessarily with user-supplied
-                    // ids.
-                    id: self.next_cluster_id,
-                    note_number: self.next_cluster_id as u32,
-                    cites,
-                };
-                self.clusters.push(cluster);
-                self.next_cluster_id += 1;
+            len = s.len() - next.len();
+        } else {
+            break;
+        }
+    }
+    len
+}
+
+/// Turns Pandoc's already-parsed prefixes and suffixes back into the Markdown that
+/// `SupportedFormat::Pandoc` ingests.
+fn write_markdown(md: &mut String, inlines: &[Inline]) {
+    for inline in inlines {
+        match inline {
+            Inline::Str(s) => {
+                for c in s.chars() {
+                    if "*_^~$[]\\".contains(c) {
+                        md.push('\\');
+                    }
+                    md.push(c);
+                }
+            }
+            Inline::Space | Inline::SoftBreak | Inline::LineBreak => md.push(' '),
+            Inline::Emph(children) => surround(md, "*", children),
+            Inline::Strong(children) => surround(md, "**", children),
+            Inline::Superscript(children) => surround(md, "^", children),
+            Inline::Subscript(children) => surround(md, "~", children),
+            Inline::Math(MathType::InlineMath, tex) => surround_raw(md, "$", tex),
+            Inline::Quoted(QuoteType::DoubleQuote, children) => surround(md, "\"", children),
+            Inline::Quoted(QuoteType::SingleQuote, children) => surround(md, "'", children),
+            Inline::SmallCaps(children) => {
+                md.push('[');
+                write_markdown(md, children);
+                md.push_str("]{.smallcaps}");
             }
-            _ => {}
+            Inline::Span(Attr(_, classes, _), children)
+                if classes.iter().any(|c| c == "nocase") =>
+            {
+                md.push('[');
+                write_markdown(md, children);
+                md.push_str("]{.nocase}");
+            }
+            Inline::Code(_, s) | Inline::Math(_, s) => md.push_str(s),
+            Inline::Strikeout(children)
+            | Inline::Span(_, children)
+            | Inline::Link(_, children, _)
+            | Inline::Image(_, children, _)
+            | Inline::Cite(_, children) => write_markdown(md, children),
+            Inline::RawInline(..) | Inline::Note(_) => {}
         }
     }
 }
 
+fn surround(md: &mut String, delim: &str, children: &[Inline]) {
+    md.push_str(delim);
+    write_markdown(md, children);
+    md.push_str(delim);
+}
+
+fn surround_raw(md: &mut String, delim: &str, raw: &str) {
+    md.push_str(delim);
+    md.push_str(raw);
+    md.push_str(delim);
+}
+
 struct WriteClusters<'a> {
-    next_cluster_id: ClusterId,
-    next_cite_id: CiteId,
+    next_cluster: u32,
+    is_note_style: bool,
+    in_note: bool,
     db: &'a Processor,
 }
 
-/// Only works if you run it on a PandocDocument that hasn't been modified since you ingested the
-/// clusters into the database. The Inline::Cite-s & Inline::Note-s have to be in the same order.
-/// If you're adding a bibliography, do it after a get_clusters/write_clusters pair.
-pub fn write_clusters(pandoc: &mut PandocDocument, db: &Processor) {
+/// Only works if you run it on a PandocDocument that hasn't been modified since you ran
+/// [`get_clusters`] on it. The `Inline::Cite`s have to be in the same order.
+pub fn write_clusters(pandoc: &mut PandocDocument, db: &Processor, is_note_style: bool) {
     let mut wc = WriteClusters {
-        next_cluster_id: 1,
-        next_cite_id: 1,
+        next_cluster: 0,
+        is_note_style,
+        in_note: false,
         db,
     };
     wc.walk_pandoc(pandoc);
 }
 
 impl<'a> MutVisitor for WriteClusters<'a> {
-    fn walk_inline(&mut self, inline: &mut Inline) {
+    fn visit_inline(&mut self, inline: &mut Inline) {
         match *inline {
             Inline::Note(_) => {
-                // just trying to mirror the cite hashes of pandoc-citeproc
-                self.next_cite_id += 1;
+                let was_in_note = std::mem::replace(&mut self.in_note, true);
+                self.walk_inline(inline);
+                self.in_note = was_in_note;
             }
-            Inline::Cite(ref mut p_cites, ref mut literal) => {
-                let cites = p_cites
-                    .iter()
-                    .map(|p| {
-                        let id = self.next_cite_id;
-                        self.next_cite_id += 1;
-                        let rust_cite = self.db.get_cite(id);
-                        Citation {
-                            citation_hash: id as i32,
-                            citation_prefix: rust_cite.prefix.clone(),
-                            citation_suffix: rust_cite.suffix.clone(),
-                            ..p.clone()
-                        }
-                    })
-                    .collect();
-                *p_cites = cites;
-                let built = (*self.db.get_cluster(self.next_cluster_id)).clone();
-                if self.db.get_style().class == StyleClass::Note {
+            Inline::Cite(_, ref mut literal) => {
+                let id = self.db.cluster_id(cluster_id(self.next_cluster));
+                self.next_cluster += 1;
+                let built = self.db.get_cluster_pandoc(id).unwrap_or_default();
+                if self.is_note_style && !self.in_note {
                     *literal = vec![Inline::Note(vec![Block::Para(built)])];
                 } else {
                     *literal = built;
                 }
-                self.next_cluster_id += 1;
             }
-            _ => {}
+            _ => self.walk_inline(inline),
         }
     }
 }
 
-// TODO: parse locators!
+/// Appends the bibliography in the same shape as Pandoc's own citeproc, a `#refs` div of
+/// `.csl-entry` divs.
+pub fn append_bibliography(pandoc: &mut PandocDocument, db: &Processor) {
+    let entries = match db.get_bibliography_pandoc() {
+        Some(entries) if !entries.is_empty() => entries,
+        _ => return,
+    };
+    let divs = entries
+        .into_iter()
+        .map(|(id, inlines)| {
+            Block::Div(
+                Attr(format!("ref-{}", id), vec!["csl-entry".into()], vec![]),
+                vec![Block::Para(inlines)],
+            )
+        })
+        .collect();
+    pandoc.1.push(Block::Div(
+        Attr("refs".into(), vec!["references".into()], vec![]),
+        divs,
+    ));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(suffix: &str) -> (Option<(LocatorType, String)>, &str) {
+        let (locator, rest) = parse_locator(suffix);
+        let locator = locator.map(|l| (l.type_of(), l.value().clone().into_string()));
+        (locator, rest)
+    }
+
+    #[test]
+    fn locators() {
+        let page = |v: &str| Some((LocatorType::Page, v.to_owned()));
+        assert_eq!(parse(", p. 33"), (page("33"), ""));
+        assert_eq!(
+            parse("pp. 33-35, 40 and elsewhere"),
+            (page("33-35, 40"), " and elsewhere")
+        );
+        assert_eq!(parse("33, see also"), (page("33"), ", see also"));
+        assert_eq!(
+            parse("chap. {II, part 3}"),
+            (Some((LocatorType::Chapter, "II, part 3".into())), "")
+        );
+        assert_eq!(
+            parse("§§ 4:12"),
+            (Some((LocatorType::Section, "4:12".into())), "")
+        );
+        assert_eq!(
+            parse("vol. iv"),
+            (Some((LocatorType::Volume, "iv".into())), "")
+        );
+        assert_eq!(
+            parse("volumes 2"),
+            (Some((LocatorType::Volume, "2".into())), "")
+        );
+    }
+
+    #[test]
+    fn not_locators() {
+        assert_eq!(parse("and others"), (None, "and others"));
+        assert_eq!(parse("pages of history"), (None, "pages of history"));
+        assert_eq!(parse("p."), (None, "p."));
+        assert_eq!(parse("chap. {unclosed"), (None, "chap. {unclosed"));
+    }
+}
@@ -44,6 +44,9 @@ macro_rules! append_err {
 
 mod from_node;
 use from_node::*;
+mod to_xml;
+
+pub mod lint;
 
 #[cfg(test)]
 macro_rules! assert_snapshot_parse {
@@ -357,6 +360,11 @@ impl FromNode for Citation {
             cite_group_delimiter: attribute_option(node, "cite-group-delimiter", info)?,
             year_suffix_delimiter: attribute_option(node, "year-suffix-delimiter", info)?,
             after_collapse_delimiter: attribute_option(node, "after-collapse-delimiter", info)?,
+            parallel_delimiter_override: attribute_option(
+                node,
+                "parallel-delimiter-override",
+                info,
+            )?,
             collapse: attribute_option(node, "collapse", info)?,
             sort,
         })
@@ -688,6 +696,8 @@ impl FromNode for Group {
             display: attribute_option(node, "display", info)?,
             // TODO: CSL-M only
             is_parallel: bool::attribute_default_val(node, "is-parallel", info, false)?,
+            parallel_first: attribute_array_var(node, "parallel-first", NeedVarType::Any, info)?,
+            parallel_last: attribute_array_var(node, "parallel-last", NeedVarType::Any, info)?,
         })
     }
 }
@@ -866,6 +876,7 @@ impl FromNode for IfThen {
                 .into())
             }
         })?;
+        unsupported_conditions(node, &conditions)?;
         let elements = node
             .children()
             .filter(|n| n.is_element() && !n.has_tag_name("conditions"))
@@ -875,6 +886,48 @@ impl FromNode for IfThen {
     }
 }
 
+/// Conditions that parse, but that the processor cannot evaluate yet when disambiguating. Better to
+/// reject the style than to panic halfway through rendering a document.
+fn unsupported_conditions(node: &Node, conditions: &Conditions) -> Result<(), InvalidCsl> {
+    use strum::EnumProperty;
+    let Conditions(match_type, sets) = conditions;
+    let nand = "match=\"nand\" from the conditions feature is not supported yet";
+    if *match_type == Match::Nand {
+        return Err(InvalidCsl::new(node, nand));
+    }
+    for set in sets {
+        if set.match_type == Match::Nand {
+            return Err(InvalidCsl::new(node, nand));
+        }
+        for cond in &set.conds {
+            match cond {
+                Cond::Variable(AnyVariable::Ordinary(Variable::Hereinafter)) => {
+                    return Err(InvalidCsl::new(
+                        node,
+                        "testing variable=\"hereinafter\" is not supported yet",
+                    ));
+                }
+                Cond::Locator(lt) if !lt.is_standard() => {
+                    let source = match lt.get_str("feature") {
+                        Some(feature) => format!("the {} feature", feature.replace('_', "-")),
+                        None => "CSL-M".to_owned(),
+                    };
+                    return Err(InvalidCsl::new(
+                        node,
+                        format!(
+                            "testing locator=\"{}\" from {} is not supported yet",
+                            lt.as_ref(),
+                            source
+                        ),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
 fn choose_el(node: &Node, info: &ParseInfo) -> Result<Element, CslError> {
     let mut if_block: Option<IfThen> = None;
     let mut elseifs = vec![];
@@ -0,0 +1,528 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Warnings about styles that parse, but probably don't do what their author meant.
+//!
+//! [`lint_style`] returns the parser's own errors, followed by these checks, each of which is a
+//! [`Severity::Warning`]:
+//!
+//! - macros that are never called from a layout or sort key (even indirectly)
+//! - macros that call themselves
+//! - `<else-if>` branches that can never match, because an earlier branch always matches first
+//! - variables tested in conditions but never rendered anywhere
+//! - terms used in `<text term="...">` that the default locale doesn't define
+//! - attributes and elements left over from CSL 0.8
+
+use crate::error::*;
+use crate::from_node::{FromNode, ParseInfo, ParseOptions};
+use crate::locale::{Lang, Locale, LocaleSource, EN_US};
+use crate::style::{CondSet, Conditions, Match, Style};
+use crate::terms::TextTermSelector;
+use roxmltree::{Document, Node};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+/// Lints a style. `locale` should be the style's merged default locale, if you have it, or the
+/// term check falls back to the bundled en-US and the style's own `<locale>` overrides.
+///
+/// Only fails if the XML itself is malformed, or if it's a dependent style. Parse errors are
+/// returned alongside the warnings.
+pub fn lint_style(
+    xml: &str,
+    options: ParseOptions,
+    locale: Option<&Locale>,
+) -> Result<Vec<InvalidCsl>, StyleError> {
+    let doc = Document::parse(xml)?;
+    let mut diagnostics = Vec::new();
+    let style = match Style::parse_with_opts(xml, options.clone()) {
+        Ok(style) => Some(style),
+        Err(StyleError::Invalid(CslError(errors))) => {
+            diagnostics.extend(errors);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    let root = doc.root_element();
+    let info = ParseInfo {
+        features: match &style {
+            Some(style) => style.features.clone(),
+            None => options.features.clone().unwrap_or_default(),
+        },
+        options,
+        macros: None,
+    };
+
+    let mut lints = Vec::new();
+    macros(&root, &mut lints);
+    unreachable_branches(&root, &info, &mut lints);
+    untested_variables(&root, &mut lints);
+    let fallback;
+    let locale = match locale {
+        Some(locale) => locale,
+        None => {
+            fallback = bundled_locale(style.as_ref());
+            &fallback
+        }
+    };
+    missing_terms(&root, &info, locale, &mut lints);
+    deprecated(&root, &mut lints);
+    lints.sort_by_key(|lint| lint.range.start);
+
+    diagnostics.extend(lints);
+    Ok(diagnostics)
+}
+
+fn warning(range: Range<usize>, message: String, hint: &str) -> InvalidCsl {
+    InvalidCsl {
+        severity: Severity::Warning,
+        range,
+        message,
+        hint: hint.to_owned(),
+    }
+}
+
+fn attr_range(node: &Node, attr: &str) -> Range<usize> {
+    node.attribute_node(attr)
+        .map_or_else(|| node.range(), |a| a.range())
+}
+
+/// What the processor would use with no fetched locales: en-US, with the style's overrides on top.
+fn bundled_locale(style: Option<&Style>) -> Locale {
+    let lang = style
+        .and_then(|s| s.default_locale.clone())
+        .unwrap_or_else(Lang::en_us);
+    let en_us = Locale::parse(EN_US).expect("bundled en-US locale is valid");
+    let mut sources: Vec<Locale> = lang
+        .iter()
+        .filter_map(|source| match source {
+            LocaleSource::Inline(l) => style?.locale_overrides.get(&l).cloned(),
+            LocaleSource::File(l) if l == Lang::en_us() => Some(en_us.clone()),
+            LocaleSource::File(_) => None,
+        })
+        .collect();
+    let mut merged = sources.pop().unwrap_or_default();
+    while let Some(next) = sources.pop() {
+        merged.merge(&next);
+    }
+    merged
+}
+
+/// `<text macro="...">` and `<key macro="...">` calls under `node`.
+fn macro_calls<'a, 'input>(
+    node: &Node<'a, 'input>,
+) -> impl Iterator<Item = (Node<'a, 'input>, &'a str)> {
+    node.descendants()
+        .filter(|n| n.has_tag_name("text") || n.has_tag_name("key"))
+        .filter_map(|n| n.attribute("macro").map(|name| (n, name)))
+}
+
+fn macros(root: &Node, lints: &mut Vec<InvalidCsl>) {
+    let defined: BTreeMap<&str, Node> = root
+        .children()
+        .filter(|n| n.has_tag_name("macro"))
+        .filter_map(|n| n.attribute("name").map(|name| (name, n)))
+        .collect();
+    let calls: BTreeMap<&str, BTreeSet<&str>> = defined
+        .iter()
+        .map(|(&name, node)| (name, macro_calls(node).map(|(_, callee)| callee).collect()))
+        .collect();
+
+    // Anything reachable from citation, bibliography or intext is used.
+    let mut used = BTreeSet::new();
+    let mut stack: Vec<&str> = root
+        .children()
+        .filter(|n| !n.has_tag_name("macro"))
+        .flat_map(|n| macro_calls(&n).map(|(_, name)| name).collect::<Vec<_>>())
+        .collect();
+    while let Some(name) = stack.pop() {
+        if used.insert(name) {
+            stack.extend(calls.get(name).into_iter().flatten());
+        }
+    }
+    for (name, node) in &defined {
+        if !used.contains(name) {
+            lints.push(warning(
+                attr_range(node, "name"),
+                format!("macro `{}` is never used", name),
+                "",
+            ));
+        }
+    }
+
+    for (&name, node) in &defined {
+        if let Some(cycle) = find_cycle(name, &calls) {
+            lints.push(warning(
+                attr_range(node, "name"),
+                format!("macro `{}` calls itself: {}", name, cycle.join(" -> ")),
+                "Hint: the processor cannot render a recursive macro",
+            ));
+        }
+    }
+}
+
+/// A path of macro calls from `start` back to itself, if there is one.
+fn find_cycle<'a>(
+    start: &'a str,
+    calls: &BTreeMap<&'a str, BTreeSet<&'a str>>,
+) -> Option<Vec<&'a str>> {
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![vec![start]];
+    while let Some(path) = stack.pop() {
+        let last = *path.last().unwrap();
+        for &callee in calls.get(last).into_iter().flatten() {
+            let mut next = path.clone();
+            next.push(callee);
+            if callee == start {
+                return Some(next);
+            }
+            if seen.insert(callee) {
+                stack.push(next);
+            }
+        }
+    }
+    None
+}
+
+/// The conditions written on an `<if>` or `<else-if>`, either inline or as a CSL-M block.
+fn branch_conditions(node: &Node, info: &ParseInfo) -> Option<Conditions> {
+    if let Ok(set) = CondSet::from_node_custom(node, info) {
+        return Some(Conditions(Match::All, vec![set]));
+    }
+    let block = node.children().find(|n| n.has_tag_name("conditions"))?;
+    Conditions::from_node(&block, info).ok()
+}
+
+/// Whether `earlier` always matches when `later` would, so `later` never gets a chance.
+fn shadows(earlier: &Conditions, later: &Conditions) -> bool {
+    if earlier == later {
+        return true;
+    }
+    let (e, l) = match (earlier, later) {
+        (Conditions(Match::All, e), Conditions(Match::All, l)) if e.len() == 1 && l.len() == 1 => {
+            (&e[0], &l[0])
+        }
+        _ => return false,
+    };
+    // A single condition is both an `any` and an `all`, unless it is negated.
+    let single = |set: &CondSet| {
+        set.conds.len() == 1 && set.match_type != Match::None && set.match_type != Match::Nand
+    };
+    let is_any = |set: &CondSet| set.match_type == Match::Any || single(set);
+    let is_all = |set: &CondSet| set.match_type == Match::All || single(set);
+    if is_all(l) && is_any(e) && !l.conds.is_disjoint(&e.conds) {
+        return true;
+    }
+    if is_all(l) && is_all(e) && e.conds.is_subset(&l.conds) {
+        return true;
+    }
+    is_any(l) && is_any(e) && l.conds.is_subset(&e.conds)
+}
+
+fn unreachable_branches(root: &Node, info: &ParseInfo, lints: &mut Vec<InvalidCsl>) {
+    for choose in root.descendants().filter(|n| n.has_tag_name("choose")) {
+        let mut earlier = Vec::new();
+        for branch in choose
+            .children()
+            .filter(|n| n.has_tag_name("if") || n.has_tag_name("else-if"))
+        {
+            let conditions = match branch_conditions(&branch, info) {
+                Some(c) => c,
+                None => continue,
+            };
+            if earlier.iter().any(|e| shadows(e, &conditions)) {
+                lints.push(warning(
+                    branch.range(),
+                    "<else-if> can never match".into(),
+                    "Hint: an earlier branch always matches first",
+                ));
+            }
+            earlier.push(conditions);
+        }
+    }
+}
+
+const CONDITION_VAR_ATTRS: &[&str] = &[
+    "variable",
+    "is-numeric",
+    "is-uncertain-date",
+    "has-year-only",
+    "has-month-or-season",
+    "has-day",
+    "is-plural",
+];
+
+fn untested_variables(root: &Node, lints: &mut Vec<InvalidCsl>) {
+    let mut rendered = BTreeSet::new();
+    for node in root.descendants().filter(|n| {
+        ["text", "number", "label", "names", "date", "key"]
+            .iter()
+            .any(|tag| n.has_tag_name(*tag))
+    }) {
+        for var in node.attribute("variable").unwrap_or("").split_whitespace() {
+            rendered.insert(var.to_owned());
+            // <text variable="title" form="short"/> falls back to title-short
+            if node.attribute("form") == Some("short") {
+                rendered.insert(format!("{}-short", var));
+            }
+        }
+    }
+    let conditions = root.descendants().filter(|n| {
+        n.has_tag_name("if") || n.has_tag_name("else-if") || n.has_tag_name("condition")
+    });
+    for node in conditions {
+        for &attr in CONDITION_VAR_ATTRS {
+            for var in node.attribute(attr).unwrap_or("").split_whitespace() {
+                if !rendered.contains(var) {
+                    lints.push(warning(
+                        attr_range(&node, attr),
+                        format!("variable `{}` is tested, but never rendered", var),
+                        "",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn missing_terms(root: &Node, info: &ParseInfo, locale: &Locale, lints: &mut Vec<InvalidCsl>) {
+    let texts = root
+        .descendants()
+        .filter(|n| n.has_tag_name("text") && n.has_attribute("term"));
+    for node in texts {
+        // Bad term names are already parse errors.
+        let selector = match TextTermSelector::from_node(&node, info) {
+            Ok(sel) => sel,
+            Err(_) => continue,
+        };
+        let plural = node.attribute("plural") == Some("true");
+        if locale.get_text_term(selector, plural).is_none() {
+            lints.push(warning(
+                attr_range(&node, "term"),
+                format!(
+                    "term `{}` is not defined in the default locale",
+                    node.attribute("term").unwrap_or("")
+                ),
+                "Hint: define it in a <locale> in the style",
+            ));
+        }
+    }
+}
+
+fn deprecated(root: &Node, lints: &mut Vec<InvalidCsl>) {
+    if root.has_attribute(("http://www.w3.org/XML/1998/namespace", "lang")) {
+        lints.push(warning(
+            root.range(),
+            "`xml:lang` on <style> is from CSL 0.8".into(),
+            "Hint: use `default-locale`",
+        ));
+    }
+    for node in root.descendants().filter(Node::is_element) {
+        if node.has_attribute("include-period") {
+            lints.push(warning(
+                attr_range(&node, "include-period"),
+                "`include-period` is from CSL 0.8".into(),
+                "Hint: use `strip-periods`, which has the opposite meaning",
+            ));
+        }
+        if node.has_tag_name("option") {
+            lints.push(warning(
+                node.range(),
+                "<option> elements are from CSL 0.8".into(),
+                "Hint: write options as attributes on <citation> or <bibliography>",
+            ));
+        }
+        if node.has_tag_name("category") && node.has_attribute("term") {
+            lints.push(warning(
+                attr_range(&node, "term"),
+                "<category term=\"...\"> is from CSL 0.8".into(),
+                "Hint: use `citation-format` or `field`",
+            ));
+        }
+        if node.has_tag_name("link") {
+            let (old, new) = match node.attribute("rel") {
+                Some("source") => ("source", "template"),
+                Some("homepage") => ("homepage", "documentation"),
+                _ => continue,
+            };
+            lints.push(warning(
+                attr_range(&node, "rel"),
+                format!("<link rel=\"{}\"> is from CSL 0.8", old),
+                &format!("Hint: use rel=\"{}\"", new),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lint(xml: &str) -> Vec<(Severity, String)> {
+        let options = ParseOptions {
+            allow_no_info: true,
+            ..Default::default()
+        };
+        lint_style(xml, options, None)
+            .unwrap()
+            .into_iter()
+            .map(|i| (i.severity, i.message))
+            .collect()
+    }
+
+    fn warn(s: &str) -> (Severity, String) {
+        (Severity::Warning, s.into())
+    }
+
+    #[test]
+    fn clean() {
+        assert_eq!(
+            lint(
+                r#"<style class="note" version="1.0">
+                    <macro name="title"><text variable="title"/></macro>
+                    <citation><layout>
+                        <choose>
+                            <if variable="title"><text macro="title"/></if>
+                            <else><text term="anonymous"/></else>
+                        </choose>
+                    </layout></citation>
+                </style>"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn macros() {
+        assert_eq!(
+            lint(
+                r#"<style class="note" version="1.0">
+                    <macro name="a"><text macro="b"/></macro>
+                    <macro name="b"><text macro="a"/></macro>
+                    <macro name="orphan"><text macro="only-from-orphan"/></macro>
+                    <macro name="only-from-orphan"><text value="x"/></macro>
+                    <citation><layout><text macro="a"/></layout></citation>
+                </style>"#
+            ),
+            vec![
+                warn("macro `a` calls itself: a -> b -> a"),
+                warn("macro `b` calls itself: b -> a -> b"),
+                warn("macro `orphan` is never used"),
+                warn("macro `only-from-orphan` is never used"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        assert_eq!(
+            lint(
+                r#"<style class="note" version="1.0">
+                    <citation><layout><choose>
+                        <if type="book chapter" match="any"><text variable="title"/></if>
+                        <else-if type="chapter book" match="any"><text variable="title"/></else-if>
+                        <else-if type="book" variable="title" match="all"><text variable="title"/></else-if>
+                        <else-if type="book report" match="any"><text variable="title"/></else-if>
+                    </choose></layout></citation>
+                </style>"#
+            ),
+            vec![
+                warn("<else-if> can never match"),
+                warn("<else-if> can never match"),
+            ]
+        );
+    }
+
+    #[test]
+    fn negated_single_condition() {
+        let choose = |first: &str, second: &str| {
+            lint(&format!(
+                r#"<style class="note" version="1.0">
+                    <citation><layout><choose>
+                        <if {}><text variable="title"/></if>
+                        <else-if {}><text variable="title"/></else-if>
+                    </choose></layout></citation>
+                </style>"#,
+                first, second
+            ))
+        };
+        assert_eq!(
+            choose(r#"type="book" match="none""#, r#"type="book""#),
+            vec![]
+        );
+        assert_eq!(
+            choose(r#"type="book""#, r#"type="book" match="none""#),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn untested_variables_and_terms() {
+        let locale = Locale::parse(
+            r#"<locale xml:lang="en-US"><terms><term name="anonymous">anon.</term></terms></locale>"#,
+        )
+        .unwrap();
+        let options = ParseOptions {
+            allow_no_info: true,
+            ..Default::default()
+        };
+        let lints = lint_style(
+            r#"<style class="note" version="1.0">
+                <citation><layout><choose>
+                    <if variable="DOI title-short" is-numeric="edition">
+                        <text variable="title" form="short"/>
+                        <text term="anonymous"/>
+                        <text term="from"/>
+                    </if>
+                </choose></layout></citation>
+            </style>"#,
+            options,
+            Some(&locale),
+        )
+        .unwrap();
+        assert_eq!(
+            lints.into_iter().map(|i| i.message).collect::<Vec<_>>(),
+            vec![
+                "variable `DOI` is tested, but never rendered",
+                "variable `edition` is tested, but never rendered",
+                "term `from` is not defined in the default locale",
+            ]
+        );
+    }
+
+    #[test]
+    fn csl_08() {
+        assert_eq!(
+            lint(
+                r#"<style class="note" version="1.0" xml:lang="en">
+                    <citation><layout>
+                        <date variable="issued"><date-part name="month" include-period="true"/></date>
+                    </layout></citation>
+                </style>"#
+            ),
+            vec![
+                warn("`xml:lang` on <style> is from CSL 0.8"),
+                warn("`include-period` is from CSL 0.8"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_errors_come_first() {
+        let lints = lint(
+            r#"<style class="note" version="1.0">
+                <macro name="unused"><text value="x"/></macro>
+                <citation><sort><key macro="undefined"/></sort><layout/></citation>
+            </style>"#,
+        );
+        assert_eq!(
+            lints,
+            vec![
+                (Severity::Error, "macro `undefined` not defined".into()),
+                warn("macro `unused` is never used"),
+            ]
+        );
+    }
+}
@@ -102,6 +102,12 @@ pub struct Group {
     pub display: Option<DisplayMode>,
     /// CSL-M only
     pub is_parallel: bool,
+    /// CSL-M only. If the cite has any of these variables, the group is only rendered in the first
+    /// cite of a run of parallel citations.
+    pub parallel_first: Vec<AnyVariable>,
+    /// CSL-M only. If the cite has any of these variables, the group is only rendered in the last
+    /// cite of a run of parallel citations.
+    pub parallel_last: Vec<AnyVariable>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -964,6 +970,8 @@ pub struct Citation {
     pub cite_group_delimiter: Option<SmartString>,
     pub year_suffix_delimiter: Option<SmartString>,
     pub after_collapse_delimiter: Option<SmartString>,
+    /// Used between the cites of a parallel citation, instead of the layout delimiter.
+    pub parallel_delimiter_override: Option<SmartString>,
     pub collapse: Option<Collapse>,
 }
 
@@ -982,6 +990,7 @@ impl Default for Citation {
             cite_group_delimiter: None,
             year_suffix_delimiter: None,
             after_collapse_delimiter: None,
+            parallel_delimiter_override: None,
             collapse: None,
         }
     }
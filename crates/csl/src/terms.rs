@@ -47,6 +47,22 @@ tts_eq!(MonthTerm, |x| Self::Gendered(GenderedTermSelector::Month(t, _)) => x ==
 tts_eq!(SeasonTerm, |x| Self::Gendered(GenderedTermSelector::Season(t, _)) => x == t);
 tts_eq!(RoleTerm, |x| Self::Role(RoleTermSelector(t, _)) => x == t);
 
+impl TextTermSelector {
+    /// The name of the term, as written in `<text term="...">`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Simple(SimpleTermSelector::Misc(t, _)) => t.as_ref(),
+            Self::Simple(SimpleTermSelector::Category(t, _)) => t.as_ref(),
+            Self::Simple(SimpleTermSelector::Quote(t)) => t.as_ref(),
+            Self::Gendered(GenderedTermSelector::Number(t, _)) => t.as_ref(),
+            Self::Gendered(GenderedTermSelector::Locator(t, _)) => t.as_ref(),
+            Self::Gendered(GenderedTermSelector::Month(t, _)) => t.as_ref(),
+            Self::Gendered(GenderedTermSelector::Season(t, _)) => t.as_ref(),
+            Self::Role(RoleTermSelector(t, _)) => t.as_ref(),
+        }
+    }
+}
+
 pub enum AnyTermName {
     Number(NumberVariable),
     Month(MonthTerm),
@@ -465,7 +481,7 @@ impl Default for OrdinalMatch {
 
 /// [Spec](https://docs.citationstyles.org/en/stable/specification.html#locators)
 #[derive(AsRefStr, EnumProperty, EnumString, Debug, Copy, Clone, PartialEq, Eq, Hash)]
-#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[strum(serialize_all = "kebab_case")]
 #[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 #[non_exhaustive]
@@ -513,6 +529,23 @@ pub enum LocatorType {
     Supplement,
 }
 
+impl LocatorType {
+    /// The locator types in CSL 1.0.1, as opposed to those behind a feature flag or CSL-M.
+    pub fn is_standard(self) -> bool {
+        !matches!(
+            self,
+            LocatorType::Article
+                | LocatorType::Subparagraph
+                | LocatorType::Rule
+                | LocatorType::Subsection
+                | LocatorType::Schedule
+                | LocatorType::Title
+                | LocatorType::Unpublished
+                | LocatorType::Supplement
+        )
+    }
+}
+
 impl Default for LocatorType {
     fn default() -> Self {
         LocatorType::Page
@@ -170,3 +170,66 @@ fn wrong_tag_name() {
     "#
     );
 }
+
+/// These all used to parse, and then panic while disambiguating.
+#[test]
+fn unsupported_conditions() {
+    let options = ParseOptions {
+        features: Some(Features {
+            conditions: true,
+            legal_locators: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let parse = |choose: &str| {
+        let xml = format!(
+            r#"<style class="note"><citation><layout><choose>{}</choose></layout></citation></style>"#,
+            choose
+        );
+        Style::parse_for_test(&xml, Some(options.clone()))
+    };
+    let error = |choose: &str| match parse(choose) {
+        Err(StyleError::Invalid(CslError(errors))) => errors[0].message.clone(),
+        other => panic!("expected a style error, got {:?}", other),
+    };
+    let nand = r#"match="nand" from the conditions feature is not supported yet"#;
+    assert_eq!(
+        error(r#"<if match="nand" type="book"><text value="x"/></if>"#),
+        nand
+    );
+    assert_eq!(
+        error(
+            r#"<if><conditions match="nand">
+                <condition type="book"/>
+                <condition type="chapter"/>
+            </conditions><text value="x"/></if>"#
+        ),
+        nand
+    );
+    assert_eq!(
+        error(r#"<if variable="hereinafter"><text value="x"/></if>"#),
+        r#"testing variable="hereinafter" is not supported yet"#
+    );
+    assert_eq!(
+        error(r#"<if locator="page article"><text value="x"/></if>"#),
+        r#"testing locator="article" from the legal-locators feature is not supported yet"#
+    );
+    for match_type in &["all", "any", "none"] {
+        let conditions = format!(
+            r#"<if><conditions match="{}">
+                <condition type="book"/>
+                <condition position="ibid" locator="page"/>
+            </conditions><text value="x"/></if>"#,
+            match_type
+        );
+        assert!(parse(&conditions).is_ok());
+    }
+    assert!(parse(r#"<if locator="page" match="none"><text value="x"/></if>"#).is_ok());
+    assert!(parse(
+        r#"<if><conditions match="all">
+            <condition type="book" variable="title"/>
+        </conditions><text value="x"/></if>"#
+    )
+    .is_ok());
+}
@@ -0,0 +1,1303 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! The inverse of `from_node`: writes a parsed [`Style`] back out as CSL XML.
+//!
+//! Parsing the output gives back an equal `Style`. Attributes are only written when they differ
+//! from what the parser would fill in on its own, so a style that leaned on defaults stays
+//! short. Macros, locale overrides and terms live in hash maps, so they come out sorted rather
+//! than in their original order.
+
+use crate::locale::{Lang, Locale, LocaleDate, LocaleOptionsNode};
+use crate::style::info::*;
+use crate::style::*;
+use crate::terms::*;
+use crate::variables::*;
+use crate::version::Features;
+use semver::VersionReq;
+use std::collections::BTreeMap;
+
+const CSL_NAMESPACE: &str = "http://purl.org/net/xbiblio/csl";
+
+impl Style {
+    /// Writes the style as a CSL XML document.
+    pub fn to_xml(&self) -> String {
+        document(self)
+    }
+}
+
+impl Locale {
+    /// Writes the locale as a standalone CSL locale document.
+    pub fn to_xml(&self) -> String {
+        let mut el = self.to_element();
+        el.attrs.insert(0, ("xmlns", CSL_NAMESPACE.into()));
+        el.attrs.insert(1, ("version", self.version.clone()));
+        document(el)
+    }
+}
+
+fn document(root: impl ToElement) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    root.to_element().write(&mut out, 0);
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XmlElement {
+    tag: &'static str,
+    attrs: Vec<(&'static str, String)>,
+    /// Only written if there are no children.
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    fn new(tag: &'static str) -> Self {
+        XmlElement {
+            tag,
+            attrs: Vec::new(),
+            text: String::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn with_text(tag: &'static str, text: &str) -> Self {
+        let mut el = XmlElement::new(tag);
+        el.text.push_str(text);
+        el
+    }
+
+    fn attr(&mut self, name: &'static str, value: impl AsRef<str>) {
+        self.attrs.push((name, value.as_ref().to_owned()));
+    }
+
+    fn attr_opt<S: AsRef<str>>(&mut self, name: &'static str, value: Option<S>) {
+        if let Some(value) = value {
+            self.attr(name, value);
+        }
+    }
+
+    /// Only writes the attribute if it isn't the parser's default.
+    fn attr_bool(&mut self, name: &'static str, value: bool, default: bool) {
+        if value != default {
+            self.attr(name, bool_str(value));
+        }
+    }
+
+    /// Space-separated, like `variable="author editor"`. Skipped if empty.
+    fn attr_list<S: AsRef<str>>(
+        &mut self,
+        name: &'static str,
+        values: impl IntoIterator<Item = S>,
+    ) {
+        let joined = values
+            .into_iter()
+            .map(|s| s.as_ref().to_owned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !joined.is_empty() {
+            self.attrs.push((name, joined));
+        }
+    }
+
+    fn child(&mut self, child: XmlElement) {
+        self.children.push(child);
+    }
+
+    fn children<'a, T: ToElement + 'a>(&mut self, children: impl IntoIterator<Item = &'a T>) {
+        self.children
+            .extend(children.into_iter().map(ToElement::to_element));
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        indent(out, depth);
+        out.push('<');
+        out.push_str(self.tag);
+        for (name, value) in &self.attrs {
+            out.push(' ');
+            out.push_str(name);
+            out.push_str("=\"");
+            escape(out, value, true);
+            out.push('"');
+        }
+        if !self.children.is_empty() {
+            out.push_str(">\n");
+            for child in &self.children {
+                child.write(out, depth + 1);
+            }
+            indent(out, depth);
+            close(out, self.tag);
+        } else if !self.text.is_empty() {
+            out.push('>');
+            escape(out, &self.text, false);
+            close(out, self.tag);
+        } else {
+            out.push_str("/>");
+        }
+        out.push('\n');
+    }
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn close(out: &mut String, tag: &str) {
+    out.push_str("</");
+    out.push_str(tag);
+    out.push('>');
+}
+
+/// Whitespace other than a plain space has to be escaped in attributes, or the XML parser
+/// normalises it away. That matters for delimiters.
+fn escape(out: &mut String, s: &str, attribute: bool) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#13;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '\n' if attribute => out.push_str("&#10;"),
+            '\t' if attribute => out.push_str("&#9;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn bool_str(b: bool) -> &'static str {
+    if b {
+        "true"
+    } else {
+        "false"
+    }
+}
+
+/// The inverse of `FromNode`.
+trait ToElement {
+    fn to_element(&self) -> XmlElement;
+}
+
+/// For the attribute groups that `AttrChecker` reads off the node they sit on.
+trait WriteAttrs {
+    fn write_attrs(&self, el: &mut XmlElement);
+}
+
+impl<T: ToElement> ToElement for &T {
+    fn to_element(&self) -> XmlElement {
+        (*self).to_element()
+    }
+}
+
+impl ToElement for XmlElement {
+    fn to_element(&self) -> XmlElement {
+        self.clone()
+    }
+}
+
+impl<T: WriteAttrs> WriteAttrs for Option<T> {
+    fn write_attrs(&self, el: &mut XmlElement) {
+        if let Some(t) = self {
+            t.write_attrs(el);
+        }
+    }
+}
+
+impl WriteAttrs for Formatting {
+    fn write_attrs(&self, el: &mut XmlElement) {
+        el.attr_opt("font-style", self.font_style);
+        el.attr_opt("font-variant", self.font_variant);
+        el.attr_opt("font-weight", self.font_weight);
+        el.attr_opt("text-decoration", self.text_decoration);
+        el.attr_opt("vertical-align", self.vertical_alignment);
+    }
+}
+
+impl WriteAttrs for Affixes {
+    fn write_attrs(&self, el: &mut XmlElement) {
+        // A lone prefix="" still parses as Some(Affixes).
+        if !self.prefix.is_empty() || self.suffix.is_empty() {
+            el.attr("prefix", &self.prefix);
+        }
+        if !self.suffix.is_empty() {
+            el.attr("suffix", &self.suffix);
+        }
+    }
+}
+
+impl WriteAttrs for TextCase {
+    fn write_attrs(&self, el: &mut XmlElement) {
+        if *self != TextCase::None {
+            el.attr("text-case", self);
+        }
+    }
+}
+
+fn lang_attr(el: &mut XmlElement, lang: Option<&Lang>) {
+    el.attr_opt("xml:lang", lang.map(ToString::to_string));
+}
+
+fn any_var(var: &AnyVariable) -> &str {
+    match var {
+        AnyVariable::Ordinary(v) => v.as_ref(),
+        AnyVariable::Name(v) => v.as_ref(),
+        AnyVariable::Date(v) => v.as_ref(),
+        AnyVariable::Number(v) => v.as_ref(),
+    }
+}
+
+fn standard_var(var: &StandardVariable) -> &str {
+    match var {
+        StandardVariable::Ordinary(v) => v.as_ref(),
+        StandardVariable::Number(v) => v.as_ref(),
+    }
+}
+
+impl ToElement for Style {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("style");
+        el.attr("xmlns", CSL_NAMESPACE);
+        el.attr("class", self.class);
+        // Styles say version="1.0"; anything else is written the way semver prints it.
+        let version = match VersionReq::parse("1.0") {
+            Ok(req) if req == self.version_req.0 => "1.0".to_owned(),
+            _ => self.version_req.0.to_string(),
+        };
+        el.attr("version", version);
+        el.attr_opt(
+            "default-locale",
+            self.default_locale.as_ref().map(ToString::to_string),
+        );
+        el.attr_opt("page-range-format", self.page_range_format);
+        if self.demote_non_dropping_particle != DemoteNonDroppingParticle::default() {
+            el.attr(
+                "demote-non-dropping-particle",
+                self.demote_non_dropping_particle,
+            );
+        }
+        el.attr_bool("initialize-with-hyphen", self.initialize_with_hyphen, true);
+        el.attr_opt("names-delimiter", self.names_delimiter.as_ref());
+        write_inherited_name(&self.name_inheritance, &mut el);
+
+        // Only test styles get away without an <info>, and they parse to the default one.
+        if self.info != Info::default() {
+            el.child(self.info.to_element());
+        }
+        if let Some(features) = features_element(&self.features) {
+            el.child(features);
+        }
+        let mut locales: Vec<_> = self.locale_overrides.values().collect();
+        locales.sort_by_key(|locale| locale.lang.as_ref().map(ToString::to_string));
+        el.children(locales);
+        let mut macros: Vec<_> = self.macros.iter().collect();
+        macros.sort_by_key(|(name, _)| *name);
+        for (name, elements) in macros {
+            let mut mac = XmlElement::new("macro");
+            mac.attr("name", name);
+            mac.children(elements);
+            el.child(mac);
+        }
+        el.child(self.citation.to_element());
+        el.children(&self.bibliography);
+        el.children(&self.intext);
+        el
+    }
+}
+
+fn features_element(features: &Features) -> Option<XmlElement> {
+    let mut el = XmlElement::new("features");
+    let mut feature = |name: &str| {
+        let mut f = XmlElement::new("feature");
+        f.attr("name", name.replace('_', "-"));
+        el.child(f);
+    };
+    features.walk_feature_fields(|name, enabled| {
+        if enabled {
+            feature(name);
+        }
+    });
+    for (name, _since) in &features.declared_lang_features {
+        feature(name);
+    }
+    if el.children.is_empty() {
+        None
+    } else {
+        Some(el)
+    }
+}
+
+fn localized(tag: &'static str, string: &LocalizedString) -> XmlElement {
+    let mut el = XmlElement::with_text(tag, &string.value);
+    lang_attr(&mut el, string.lang.as_ref());
+    el
+}
+
+impl ToElement for Info {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("info");
+        el.child(localized("title", &self.title));
+        if let Some(title_short) = &self.title_short {
+            el.child(localized("title-short", title_short));
+        }
+        el.child(XmlElement::with_text("id", &self.id.to_string()));
+        if let Some(parent) = &self.parent {
+            let mut link = XmlElement::new("link");
+            link.attr("href", parent.href.to_string());
+            link.attr("rel", "independent-parent");
+            lang_attr(&mut link, parent.lang.as_ref());
+            el.child(link);
+        }
+        for Link { href, rel, lang } in &self.links {
+            let mut link = XmlElement::new("link");
+            link.attr("href", href.to_string());
+            link.attr("rel", rel);
+            lang_attr(&mut link, lang.as_ref());
+            el.child(link);
+        }
+        if let Some(citation_format) = self.citation_format {
+            let mut category = XmlElement::new("category");
+            category.attr("citation-format", citation_format);
+            el.child(category);
+        }
+        for field in &self.categories {
+            let mut category = XmlElement::new("category");
+            category.attr("field", field);
+            el.child(category);
+        }
+        for &(tag, issn) in &[
+            ("issn", &self.issn),
+            ("eissn", &self.eissn),
+            ("issnl", &self.issnl),
+        ] {
+            if let Some(issn) = issn {
+                el.child(XmlElement::with_text(tag, issn));
+            }
+        }
+        if let Some(summary) = &self.summary {
+            el.child(localized("summary", summary));
+        }
+        let updated = self
+            .updated
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+        el.child(XmlElement::with_text("updated", &updated));
+        if let Some(rights) = &self.rights {
+            let mut r = XmlElement::with_text("rights", &rights.value);
+            r.attr_opt("license", rights.license.as_ref().map(ToString::to_string));
+            lang_attr(&mut r, rights.lang.as_ref());
+            el.child(r);
+        }
+        el
+    }
+}
+
+impl ToElement for Locale {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("locale");
+        lang_attr(&mut el, self.lang.as_ref());
+        if self.options_node != LocaleOptionsNode::default() {
+            let options = &self.options_node;
+            let mut o = XmlElement::new("style-options");
+            o.attr_opt(
+                "limit-day-ordinals-to-day-1",
+                options.limit_day_ordinals_to_day_1.map(bool_str),
+            );
+            o.attr_opt(
+                "punctuation-in-quote",
+                options.punctuation_in_quote.map(bool_str),
+            );
+            el.child(o);
+        }
+        let mut dates: Vec<_> = self.dates.values().collect();
+        dates.sort_by(|a, b| a.form.as_ref().cmp(b.form.as_ref()));
+        el.children(dates);
+        let terms = locale_terms(self);
+        if !terms.is_empty() {
+            let mut t = XmlElement::new("terms");
+            t.children = terms;
+            el.child(t);
+        }
+        el
+    }
+}
+
+impl ToElement for LocaleDate {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("date");
+        el.attr("form", self.form);
+        el.attr_opt("delimiter", self.delimiter.as_ref());
+        self.text_case.write_attrs(&mut el);
+        self.formatting.write_attrs(&mut el);
+        el.children(&self.date_parts);
+        el
+    }
+}
+
+fn term_element(content: &TermPlurality) -> XmlElement {
+    match content {
+        TermPlurality::Invariant(s) => XmlElement::with_text("term", s),
+        TermPlurality::Pluralized { single, multiple } => {
+            let mut el = XmlElement::new("term");
+            el.child(XmlElement::with_text("single", single));
+            el.child(XmlElement::with_text("multiple", multiple));
+            el
+        }
+    }
+}
+
+fn term_form(el: &mut XmlElement, form: TermForm) {
+    if form != TermForm::default() {
+        el.attr("form", form);
+    }
+}
+
+fn term_form_extended(el: &mut XmlElement, form: TermFormExtended) {
+    if form != TermFormExtended::default() {
+        el.attr("form", form);
+    }
+}
+
+fn gender_str(gender: Gender) -> &'static str {
+    match gender {
+        Gender::Masculine => "masculine",
+        Gender::Feminine => "feminine",
+        Gender::Neuter => "neuter",
+    }
+}
+
+fn ordinal_match_str(m: OrdinalMatch) -> &'static str {
+    match m {
+        OrdinalMatch::LastDigit => "last-digit",
+        OrdinalMatch::LastTwoDigits => "last-two-digits",
+        OrdinalMatch::WholeNumber => "whole-number",
+    }
+}
+
+fn ordinal_name(term: OrdinalTerm) -> String {
+    match term {
+        OrdinalTerm::Ordinal => "ordinal".into(),
+        OrdinalTerm::Mod100(n, _) => format!("ordinal-{:02}", n),
+        long => format!("long-ordinal-{:02}", long.to_number()),
+    }
+}
+
+/// Term selectors are written back as the name/form/gender attributes they were parsed from.
+fn locale_terms(locale: &Locale) -> Vec<XmlElement> {
+    let mut terms = Vec::new();
+    for (sel, content) in &locale.simple_terms {
+        let mut el = term_element(content);
+        match *sel {
+            SimpleTermSelector::Misc(t, form) => {
+                el.attr("name", t);
+                term_form_extended(&mut el, form);
+            }
+            SimpleTermSelector::Category(t, form) => {
+                el.attr("name", t);
+                term_form(&mut el, form);
+            }
+            SimpleTermSelector::Quote(t) => el.attr("name", t),
+        }
+        terms.push(el);
+    }
+    for (sel, GenderedTerm(content, gender)) in &locale.gendered_terms {
+        let mut el = term_element(content);
+        let form = match *sel {
+            GenderedTermSelector::Number(v, form) => {
+                el.attr("name", v);
+                form
+            }
+            GenderedTermSelector::Locator(t, form) => {
+                el.attr("name", t);
+                form
+            }
+            GenderedTermSelector::Month(t, form) => {
+                el.attr("name", t);
+                form
+            }
+            GenderedTermSelector::Season(t, form) => {
+                el.attr("name", t);
+                form
+            }
+        };
+        term_form(&mut el, form);
+        if *gender != Gender::default() {
+            el.attr("gender", gender_str(*gender));
+        }
+        terms.push(el);
+    }
+    for (OrdinalTermSelector(term, gender_form), content) in &locale.ordinal_terms {
+        let mut el = XmlElement::with_text("term", content);
+        el.attr("name", ordinal_name(*term));
+        if *gender_form != Gender::default() {
+            el.attr("gender-form", gender_str(*gender_form));
+        }
+        if let OrdinalTerm::Mod100(n, m) = *term {
+            if m != OrdinalMatch::default_for(n) {
+                el.attr("match", ordinal_match_str(m));
+            }
+        }
+        terms.push(el);
+    }
+    for (RoleTermSelector(t, form), content) in &locale.role_terms {
+        let mut el = term_element(content);
+        el.attr("name", t);
+        term_form_extended(&mut el, *form);
+        terms.push(el);
+    }
+    terms.sort_by(|a, b| a.attrs.cmp(&b.attrs));
+    terms
+}
+
+/// On cs:style, cs:citation and cs:bibliography, where `form` and `delimiter` are spelled
+/// `name-form` and `name-delimiter`.
+fn write_inherited_name(name: &Name, el: &mut XmlElement) {
+    write_name_attrs(name, el, "name-delimiter", "name-form");
+}
+
+fn write_name_attrs(
+    name: &Name,
+    el: &mut XmlElement,
+    delim_attr: &'static str,
+    form_attr: &'static str,
+) {
+    el.attr_opt("and", name.and);
+    el.attr_opt(delim_attr, name.delimiter.as_ref());
+    el.attr_opt("delimiter-precedes-et-al", name.delimiter_precedes_et_al);
+    el.attr_opt("delimiter-precedes-last", name.delimiter_precedes_last);
+    el.attr_opt("et-al-min", name.et_al_min.map(|n| n.to_string()));
+    el.attr_opt(
+        "et-al-use-first",
+        name.et_al_use_first.map(|n| n.to_string()),
+    );
+    el.attr_opt("et-al-use-last", name.et_al_use_last.map(bool_str));
+    el.attr_opt(
+        "et-al-subsequent-min",
+        name.et_al_subsequent_min.map(|n| n.to_string()),
+    );
+    el.attr_opt(
+        "et-al-subsequent-use-first",
+        name.et_al_subsequent_use_first.map(|n| n.to_string()),
+    );
+    el.attr_opt(form_attr, name.form);
+    el.attr_opt("initialize", name.initialize.map(bool_str));
+    el.attr_opt("initialize-with", name.initialize_with.as_ref());
+    el.attr_opt("name-as-sort-order", name.name_as_sort_order);
+    el.attr_opt("sort-separator", name.sort_separator.as_ref());
+    name.formatting.write_attrs(el);
+    name.affixes.write_attrs(el);
+}
+
+impl ToElement for Citation {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("citation");
+        el.attr_bool("disambiguate-add-names", self.disambiguate_add_names, false);
+        el.attr_bool(
+            "disambiguate-add-givenname",
+            self.disambiguate_add_givenname,
+            false,
+        );
+        if self.givenname_disambiguation_rule != GivenNameDisambiguationRule::default() {
+            el.attr(
+                "givenname-disambiguation-rule",
+                self.givenname_disambiguation_rule,
+            );
+        }
+        el.attr_bool(
+            "disambiguate-add-year-suffix",
+            self.disambiguate_add_year_suffix,
+            false,
+        );
+        if self.near_note_distance != 5 {
+            el.attr("near-note-distance", self.near_note_distance.to_string());
+        }
+        el.attr_opt("collapse", self.collapse);
+        el.attr_opt("cite-group-delimiter", self.cite_group_delimiter.as_ref());
+        el.attr_opt("year-suffix-delimiter", self.year_suffix_delimiter.as_ref());
+        el.attr_opt(
+            "after-collapse-delimiter",
+            self.after_collapse_delimiter.as_ref(),
+        );
+        el.attr_opt(
+            "parallel-delimiter-override",
+            self.parallel_delimiter_override.as_ref(),
+        );
+        el.attr_opt("names-delimiter", self.names_delimiter.as_ref());
+        write_inherited_name(&self.name_inheritance, &mut el);
+        el.children(&self.sort);
+        el.child(self.layout.to_element());
+        el
+    }
+}
+
+impl ToElement for Bibliography {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("bibliography");
+        el.attr_bool("hanging-indent", self.hanging_indent, false);
+        el.attr_opt("second-field-align", self.second_field_align);
+        if self.line_spacing != 1 {
+            el.attr("line-spacing", self.line_spacing.to_string());
+        }
+        if self.entry_spacing != 1 {
+            el.attr("entry-spacing", self.entry_spacing.to_string());
+        }
+        el.attr_opt(
+            "subsequent-author-substitute",
+            self.subsequent_author_substitute.as_ref(),
+        );
+        if self.subsequent_author_substitute_rule != SubsequentAuthorSubstituteRule::default() {
+            el.attr(
+                "subsequent-author-substitute-rule",
+                self.subsequent_author_substitute_rule,
+            );
+        }
+        el.attr_opt("names-delimiter", self.names_delimiter.as_ref());
+        write_inherited_name(&self.name_inheritance, &mut el);
+        el.children(&self.sort);
+        el.child(self.layout.to_element());
+        el
+    }
+}
+
+impl ToElement for InText {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("intext");
+        el.attr_opt("and", self.and);
+        el.attr_opt("cite-group-delimiter", self.cite_group_delimiter.as_ref());
+        el.attr_opt(
+            "after-collapse-delimiter",
+            self.after_collapse_delimiter.as_ref(),
+        );
+        el.child(self.layout.to_element());
+        el
+    }
+}
+
+impl ToElement for Sort {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("sort");
+        el.children(&self.keys);
+        el
+    }
+}
+
+impl ToElement for SortKey {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("key");
+        match &self.sort_source {
+            SortSource::Macro(name) => el.attr("macro", name),
+            SortSource::Variable(var) => el.attr("variable", any_var(var)),
+        }
+        el.attr_opt("sort", self.direction);
+        el.attr_opt("names-min", self.names_min.map(|n| n.to_string()));
+        el.attr_opt(
+            "names-use-first",
+            self.names_use_first.map(|n| n.to_string()),
+        );
+        el.attr_opt("names-use-last", self.names_use_last.map(bool_str));
+        el
+    }
+}
+
+impl ToElement for Layout {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("layout");
+        el.attr_list("locale", self.locale.iter().map(ToString::to_string));
+        el.attr_opt("delimiter", self.delimiter.as_ref());
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el.children(&self.elements);
+        el
+    }
+}
+
+impl ToElement for Element {
+    fn to_element(&self) -> XmlElement {
+        match self {
+            Element::Text(text) => text.to_element(),
+            Element::Label(label) => label.to_element(),
+            Element::Number(number) => number.to_element(),
+            Element::Group(group) => group.to_element(),
+            Element::Choose(choose) => choose.to_element(),
+            Element::Names(names) => names.to_element(),
+            Element::Date(date) => date.to_element(),
+        }
+    }
+}
+
+fn text_term(el: &mut XmlElement, selector: &TextTermSelector) {
+    match *selector {
+        TextTermSelector::Simple(SimpleTermSelector::Misc(t, form)) => {
+            el.attr("term", t);
+            term_form_extended(el, form);
+        }
+        TextTermSelector::Simple(SimpleTermSelector::Category(t, form)) => {
+            el.attr("term", t);
+            term_form(el, form);
+        }
+        TextTermSelector::Simple(SimpleTermSelector::Quote(t)) => el.attr("term", t),
+        TextTermSelector::Gendered(GenderedTermSelector::Number(v, form)) => {
+            el.attr("term", v);
+            term_form(el, form);
+        }
+        TextTermSelector::Gendered(GenderedTermSelector::Locator(t, form)) => {
+            el.attr("term", t);
+            term_form(el, form);
+        }
+        TextTermSelector::Gendered(GenderedTermSelector::Month(t, form)) => {
+            el.attr("term", t);
+            term_form(el, form);
+        }
+        TextTermSelector::Gendered(GenderedTermSelector::Season(t, form)) => {
+            el.attr("term", t);
+            term_form(el, form);
+        }
+        TextTermSelector::Role(RoleTermSelector(t, form)) => {
+            el.attr("term", t);
+            term_form_extended(el, form);
+        }
+    }
+}
+
+impl ToElement for TextElement {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("text");
+        match &self.source {
+            TextSource::Macro(name) => el.attr("macro", name),
+            TextSource::Value(value) => el.attr("value", value),
+            TextSource::Variable(var, form) => {
+                el.attr("variable", standard_var(var));
+                if *form != VariableForm::default() {
+                    el.attr("form", form);
+                }
+            }
+            TextSource::Term(selector, plural) => {
+                text_term(&mut el, selector);
+                el.attr_bool("plural", *plural, false);
+            }
+        }
+        el.attr_bool("quotes", self.quotes, false);
+        el.attr_bool("strip-periods", self.strip_periods, false);
+        self.text_case.write_attrs(&mut el);
+        el.attr_opt("display", self.display);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for LabelElement {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("label");
+        el.attr("variable", self.variable);
+        term_form(&mut el, self.form);
+        if self.plural != Plural::default() {
+            el.attr("plural", self.plural);
+        }
+        el.attr_bool("strip-periods", self.strip_periods, false);
+        self.text_case.write_attrs(&mut el);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for NumberElement {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("number");
+        el.attr("variable", self.variable);
+        if self.form != NumericForm::default() {
+            el.attr("form", self.form);
+        }
+        self.text_case.write_attrs(&mut el);
+        el.attr_opt("display", self.display);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for Group {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("group");
+        el.attr_opt("delimiter", self.delimiter.as_ref());
+        el.attr_opt("display", self.display);
+        el.attr_bool("is-parallel", self.is_parallel, false);
+        el.attr_list("parallel-first", self.parallel_first.iter().map(any_var));
+        el.attr_list("parallel-last", self.parallel_last.iter().map(any_var));
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el.children(&self.elements);
+        el
+    }
+}
+
+impl ToElement for Choose {
+    fn to_element(&self) -> XmlElement {
+        let Choose(if_, else_ifs, Else(else_)) = self;
+        let mut el = XmlElement::new("choose");
+        el.child(if_then("if", if_));
+        for else_if in else_ifs {
+            el.child(if_then("else-if", else_if));
+        }
+        if !else_.is_empty() {
+            let mut e = XmlElement::new("else");
+            e.children(else_);
+            el.child(e);
+        }
+        el
+    }
+}
+
+/// A single `match="all"` condition set is how CSL 1.0.1 conditions are parsed, so it goes back
+/// on the `<if>` itself. Anything else needs a CSL-M `<conditions>` block.
+fn if_then(
+    tag: &'static str,
+    IfThen(Conditions(match_type, sets), elements): &IfThen,
+) -> XmlElement {
+    let mut el = XmlElement::new(tag);
+    match (match_type, sets.as_slice()) {
+        (Match::All, [single]) => write_cond_set(single, &mut el),
+        _ => {
+            let mut conditions = XmlElement::new("conditions");
+            conditions.attr("match", match_type);
+            for set in sets {
+                let mut condition = XmlElement::new("condition");
+                write_cond_set(set, &mut condition);
+                conditions.child(condition);
+            }
+            el.child(conditions);
+        }
+    }
+    el.children(elements);
+    el
+}
+
+fn owned(s: impl AsRef<str>) -> String {
+    s.as_ref().to_owned()
+}
+
+fn write_cond_set(set: &CondSet, el: &mut XmlElement) {
+    if set.match_type != Match::default() {
+        el.attr("match", &set.match_type);
+    }
+    let mut attrs: BTreeMap<&'static str, Vec<String>> = BTreeMap::new();
+    for cond in &set.conds {
+        let (attr, value) = match cond {
+            Cond::IsNumeric(v) => ("is-numeric", any_var(v).to_owned()),
+            Cond::Variable(v) => ("variable", any_var(v).to_owned()),
+            Cond::Position(p) => ("position", owned(p)),
+            // The term is "sub verbo", but the space would split the list.
+            Cond::Locator(LocatorType::SubVerbo) => ("locator", "sub-verbo".to_owned()),
+            Cond::Locator(l) => ("locator", owned(l)),
+            Cond::Disambiguate(b) => ("disambiguate", bool_str(*b).to_owned()),
+            Cond::Type(t) => ("type", owned(t)),
+            Cond::IsUncertainDate(v) => ("is-uncertain-date", owned(v)),
+            Cond::HasYearOnly(v) => ("has-year-only", owned(v)),
+            Cond::HasMonthOrSeason(v) => ("has-month-or-season", owned(v)),
+            Cond::HasDay(v) => ("has-day", owned(v)),
+            Cond::Context(c) => ("context", owned(c)),
+            Cond::IsPlural(v) => ("is-plural", owned(v)),
+            Cond::Jurisdiction(j) => ("jurisdiction", j.to_string()),
+            Cond::SubJurisdiction(n) => ("subjurisdictions", n.to_string()),
+        };
+        attrs.entry(attr).or_default().push(value);
+    }
+    for (attr, mut values) in attrs {
+        values.sort();
+        el.attr_list(attr, values);
+    }
+}
+
+impl ToElement for Names {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("names");
+        el.attr_list("variable", self.variables.iter().map(AsRef::<str>::as_ref));
+        el.attr_opt("delimiter", self.delimiter.as_ref());
+        el.attr_opt("display", self.display);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        // Whether the label comes before or after the <name> is significant.
+        let label = self.label.as_ref();
+        el.children(label.filter(|label| !label.after_name));
+        el.children(&self.name);
+        el.children(label.filter(|label| label.after_name));
+        el.children(&self.et_al);
+        el.children(&self.with);
+        el.children(&self.institution);
+        el.children(&self.substitute);
+        el
+    }
+}
+
+impl ToElement for Name {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("name");
+        write_name_attrs(self, &mut el, "delimiter", "form");
+        el.children(&self.name_part_given);
+        el.children(&self.name_part_family);
+        el
+    }
+}
+
+impl ToElement for NamePart {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("name-part");
+        el.attr("name", self.name);
+        self.text_case.write_attrs(&mut el);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for NameLabelInput {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("label");
+        el.attr_opt("form", self.form);
+        el.attr_opt("plural", self.plural);
+        el.attr_opt("strip-periods", self.strip_periods.map(bool_str));
+        el.attr_opt("text-case", self.text_case);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for NameEtAl {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("et-al");
+        if !self.term.is_empty() {
+            el.attr("term", &self.term);
+        }
+        self.formatting.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for NameWith {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("with");
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for Institution {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("institution");
+        el.attr_opt("and", self.and);
+        el.attr_opt("delimiter", self.delimiter.as_ref());
+        match self.use_first {
+            Some(InstitutionUseFirst::Normal(n)) => el.attr("use-first", n.to_string()),
+            Some(InstitutionUseFirst::Substitute(n)) => {
+                el.attr("substitute-use-first", n.to_string())
+            }
+            None => {}
+        }
+        el.attr_opt("use-last", self.use_last.map(|n| n.to_string()));
+        el.attr_bool("reverse-order", self.reverse_order, false);
+        if self.parts_selector != InstitutionParts::default() {
+            el.attr("institution-parts", self.parts_selector);
+        }
+        el.children(&self.institution_parts);
+        el
+    }
+}
+
+impl ToElement for InstitutionPart {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("institution-part");
+        match self.name {
+            InstitutionPartName::Long(if_short) => {
+                el.attr("name", "long");
+                el.attr_bool("if-short", if_short, false);
+            }
+            InstitutionPartName::Short => el.attr("name", "short"),
+        }
+        el.attr_bool("strip-periods", self.strip_periods, false);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+impl ToElement for Substitute {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("substitute");
+        el.children(&self.0);
+        el
+    }
+}
+
+impl ToElement for BodyDate {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("date");
+        match self {
+            BodyDate::Indep(date) => {
+                el.attr("variable", date.variable);
+                el.attr_opt("delimiter", date.delimiter.as_ref());
+                date.text_case.write_attrs(&mut el);
+                el.attr_opt("display", date.display);
+                date.formatting.write_attrs(&mut el);
+                date.affixes.write_attrs(&mut el);
+                el.children(&date.date_parts);
+            }
+            // The presence of form="..." is what makes it a localized date.
+            BodyDate::Local(date) => {
+                el.attr("variable", date.variable);
+                el.attr("form", date.form);
+                if date.parts_selector != DateParts::default() {
+                    el.attr("date-parts", date.parts_selector);
+                }
+                date.text_case.write_attrs(&mut el);
+                el.attr_opt("display", date.display);
+                date.formatting.write_attrs(&mut el);
+                date.affixes.write_attrs(&mut el);
+                el.children(&date.date_parts);
+            }
+        }
+        el
+    }
+}
+
+impl ToElement for DatePart {
+    fn to_element(&self) -> XmlElement {
+        let mut el = XmlElement::new("date-part");
+        match self.form {
+            DatePartForm::Day(form) => {
+                el.attr("name", "day");
+                if form != DayForm::default() {
+                    el.attr("form", form);
+                }
+            }
+            DatePartForm::Month(form, strip_periods) => {
+                el.attr("name", "month");
+                if form != MonthForm::default() {
+                    el.attr("form", form);
+                }
+                el.attr_bool("strip-periods", strip_periods, false);
+            }
+            DatePartForm::Year(form) => {
+                el.attr("name", "year");
+                if form != YearForm::default() {
+                    el.attr("form", form);
+                }
+            }
+        }
+        el.attr_opt("range-delimiter", self.range_delimiter.as_ref());
+        el.attr_opt("text-case", self.text_case);
+        self.formatting.write_attrs(&mut el);
+        self.affixes.write_attrs(&mut el);
+        el
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Features, ParseOptions, Style};
+
+    fn round_trip(xml: &str, options: Option<ParseOptions>) {
+        let style = Style::parse_for_test(xml, options.clone()).unwrap();
+        let written = style.to_xml();
+        let reparsed = Style::parse_for_test(&written, options)
+            .unwrap_or_else(|e| panic!("{:?}\n\n{}", e, written));
+        assert_eq!(style, reparsed, "\n{}", written);
+    }
+
+    #[test]
+    fn minimal() {
+        let style = Style::parse_for_test(
+            r#"<style class="in-text" version="1.0">
+                <citation><layout delimiter="; "><text variable="title" font-style="italic" /></layout></citation>
+            </style>"#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            style.to_xml(),
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<style xmlns="http://purl.org/net/xbiblio/csl" class="in-text" version="1.0">
+  <citation>
+    <layout delimiter="; ">
+      <text variable="title" font-style="italic"/>
+    </layout>
+  </citation>
+</style>
+"#
+        );
+    }
+
+    #[test]
+    fn kitchen_sink() {
+        round_trip(
+            r#"<style xmlns="http://purl.org/net/xbiblio/csl" class="note" version="1.0"
+                   default-locale="en-GB" page-range-format="chicago"
+                   demote-non-dropping-particle="never" initialize-with-hyphen="false"
+                   et-al-min="4" et-al-use-first="1" name-form="short" name-delimiter=", ">
+              <info>
+                <title>Kitchen Sink</title>
+                <title-short>KS</title-short>
+                <id>http://www.zotero.org/styles/kitchen-sink</id>
+                <link href="http://www.zotero.org/styles/kitchen-sink" rel="self"/>
+                <link href="http://example.com/docs" rel="documentation" xml:lang="en"/>
+                <category citation-format="note"/>
+                <category field="law"/>
+                <category field="history"/>
+                <issn>1234-5678</issn>
+                <summary xml:lang="en">Everything &amp; more</summary>
+                <updated>2019-03-01T00:00:00+00:00</updated>
+                <rights license="http://creativecommons.org/licenses/by-sa/3.0/">CC BY-SA</rights>
+              </info>
+              <locale xml:lang="en">
+                <style-options punctuation-in-quote="true"/>
+                <date form="numeric" delimiter="/">
+                  <date-part name="day" form="numeric-leading-zeros"/>
+                  <date-part name="month" form="numeric-leading-zeros"/>
+                  <date-part name="year"/>
+                </date>
+                <terms>
+                  <term name="editor" form="short">
+                    <single>ed.</single>
+                    <multiple>eds.</multiple>
+                  </term>
+                  <term name="page" form="short">p.</term>
+                  <term name="issue" gender="feminine">issue</term>
+                  <term name="ordinal-01" gender-form="feminine">re</term>
+                  <term name="ordinal-11" match="whole-number">th</term>
+                  <term name="long-ordinal-02">second</term>
+                  <term name="and">&amp;</term>
+                  <term name="no date" form="short"></term>
+                  <term name="month-01" form="short">Jan.</term>
+                  <term name="open-quote">“</term>
+                </terms>
+              </locale>
+              <locale>
+                <terms><term name="et-al">et al.</term></terms>
+              </locale>
+              <macro name="author">
+                <names variable="author editor" delimiter="; ">
+                  <label form="short" prefix=" (" suffix=")" text-case="capitalize-first"/>
+                  <name and="symbol" delimiter-precedes-last="always" initialize-with=". "
+                        name-as-sort-order="first" sort-separator=" " et-al-use-last="true">
+                    <name-part name="family" font-variant="small-caps"/>
+                    <name-part name="given" text-case="uppercase"/>
+                  </name>
+                  <et-al term="and others" font-style="italic"/>
+                  <substitute>
+                    <names variable="translator">
+                      <name/>
+                      <label prefix="" plural="always"/>
+                    </names>
+                    <text macro="title"/>
+                  </substitute>
+                </names>
+              </macro>
+              <macro name="title">
+                <choose>
+                  <if type="book chapter" variable="title" match="any">
+                    <text variable="title" form="short" quotes="true" strip-periods="true"/>
+                  </if>
+                  <else-if position="ibid-with-locator" locator="sub-verbo page">
+                    <text term="ibid" text-case="capitalize-first"/>
+                    <label variable="locator" form="short" plural="never"/>
+                  </else-if>
+                  <else-if is-numeric="edition" match="none">
+                    <number variable="edition" form="ordinal" suffix=" "/>
+                    <text term="edition" form="short" plural="true"/>
+                  </else-if>
+                  <else>
+                    <text value="Tab&#9;&quot;quote&quot; &lt;&amp;&gt;"/>
+                  </else>
+                </choose>
+              </macro>
+              <citation disambiguate-add-names="true" disambiguate-add-givenname="true"
+                        givenname-disambiguation-rule="primary-name" near-note-distance="3"
+                        collapse="year-suffix" cite-group-delimiter=", " year-suffix-delimiter=","
+                        after-collapse-delimiter="; " et-al-subsequent-min="2"
+                        et-al-subsequent-use-first="1">
+                <sort>
+                  <key macro="author" names-min="3" names-use-first="1" names-use-last="true"/>
+                  <key variable="issued" sort="descending"/>
+                </sort>
+                <layout prefix="(" suffix=")" delimiter="; ">
+                  <group delimiter=", " font-weight="bold" display="block">
+                    <text macro="author"/>
+                    <date variable="issued" form="numeric" date-parts="year-month"/>
+                    <date variable="accessed" delimiter="-" prefix="[" suffix="]">
+                      <date-part name="year" form="short"/>
+                      <date-part name="month" form="short" strip-periods="true" range-delimiter="/"/>
+                      <date-part name="day" form="ordinal" vertical-align="sup"/>
+                    </date>
+                  </group>
+                </layout>
+              </citation>
+              <bibliography hanging-indent="true" second-field-align="flush" line-spacing="2"
+                            entry-spacing="0" subsequent-author-substitute="———"
+                            subsequent-author-substitute-rule="partial-each" names-delimiter=". ">
+                <layout suffix=".">
+                  <names variable="author">
+                    <name form="long" delimiter=" / "/>
+                    <label form="verb" prefix=", "/>
+                  </names>
+                  <text variable="URL" text-decoration="underline"/>
+                </layout>
+              </bibliography>
+            </style>"#,
+            None,
+        );
+    }
+
+    #[test]
+    fn csl_m() {
+        let features = Features {
+            conditions: true,
+            condition_date_parts: true,
+            custom_intext: true,
+            ..Default::default()
+        };
+        round_trip(
+            r#"<style class="in-text" version="1.0">
+              <features>
+                <feature name="conditions"/>
+                <feature name="condition-date-parts"/>
+              </features>
+              <citation parallel-delimiter-override=" ">
+                <layout>
+                  <choose>
+                    <if>
+                      <conditions match="any">
+                        <condition has-day="issued" context="citation"/>
+                        <condition variable="title" type="book" match="all"/>
+                      </conditions>
+                      <group is-parallel="true" parallel-first="title" parallel-last="page">
+                        <text variable="title"/>
+                      </group>
+                    </if>
+                  </choose>
+                  <names variable="author">
+                    <name/>
+                    <with prefix=" " suffix=" "/>
+                    <institution and="text" substitute-use-first="1" use-last="1"
+                                 reverse-order="true" institution-parts="short-long">
+                      <institution-part name="long" if-short="true" strip-periods="true"/>
+                      <institution-part name="short" font-style="italic"/>
+                    </institution>
+                  </names>
+                </layout>
+              </citation>
+              <intext and="text">
+                <layout><text variable="title"/></layout>
+              </intext>
+            </style>"#,
+            Some(ParseOptions {
+                features: Some(features),
+                ..Default::default()
+            }),
+        );
+    }
+}
@@ -201,7 +201,6 @@ macro_rules! declare_features {
 // placeholders
 declare_features!(
     // Processor features
-    (placeholder, parallel_citations, "1.0.1", None, None),
     // includes legal_case form=short abbreviations, for now
     (placeholder, abbreviations, "1.0.1", None, None),
     (placeholder, condition_page, "1.0.1", None, None),
@@ -224,8 +223,6 @@ declare_features!(
     (placeholder, text_case_normal, "1.0.1", None, None),
     (placeholder, year_range_format, "1.0.1", None, None),
     (placeholder, jurisdictions, "1.0.1", None, None),
-    // E.g. page and page-first become numeric variables
-    (placeholder, more_numerics, "1.0.1", None, None),
     (placeholder, var_license, "1.0.1", None, None),
     (placeholder, var_document_name, "1.0.1", None, None),
     (placeholder, var_part_number, "1.0.1", None, None),
@@ -241,7 +238,14 @@ declare_features!(
     /// - <https://github.com/zotero/zotero/issues/1580>
     /// - <https://citeproc-js.readthedocs.io/en/latest/running.html#special-citation-forms>
     (active, custom_intext, "1.1", None, None),
-    /// includes cs:conditions, match="nand"
+    /// `cs:text` renders every number variable (page-first, volume, edition, ...) as a number when
+    /// it is numeric, like `cs:text variable="page"` and `variable="locator"` already do.
+    (active, more_numerics, "1.0.1", None, None),
+    /// Consecutive cites of the same case in different reporters are treated as one parallel
+    /// citation. Includes `cs:group parallel-first="..." parallel-last="..."` and
+    /// `cs:citation parallel-delimiter-override="..."`.
+    (active, parallel_citations, "1.0.1", None, None),
+    /// includes cs:conditions, match="nand". match="nand" is not supported yet.
     (active, conditions, "1.0.1", None, None),
     /// includes condition matchers `has-day="issued [date vars...]"`/`has-year-only="issued"`/`has-month-or-season="issued"`
     (active, condition_date_parts, "1.0.1", None, None),
@@ -262,7 +266,8 @@ declare_features!(
     ///
     /// <https://discourse.citationstyles.org/t/more-flexible-editortranslator-behavior/1498/7>
     (active, var_editortranslator, "1.0.1", None, None),
-    /// article, subparagraph, rule, subsection, schedule, title as locator types
+    /// article, subparagraph, rule, subsection, schedule, title as locator types. A style cannot
+    /// test for them with `<if locator="...">` yet.
     (active, legal_locators, "1.0.1", None, None),
     /// `<text term="unpublished">`
     (active, term_unpublished, "1.0.1", None, None),
@@ -0,0 +1,82 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+fn main() {
+    #[cfg(feature = "bundled-locales")]
+    bundle::write_bundled_locales();
+}
+
+#[cfg(feature = "bundled-locales")]
+mod bundle {
+    use std::env;
+    use std::fmt::Write as _;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn locales_dir() -> PathBuf {
+        println!("cargo:rerun-if-env-changed=CITEPROC_LOCALES_DIR");
+        if let Some(dir) = env::var_os("CITEPROC_LOCALES_DIR") {
+            return PathBuf::from(dir);
+        }
+        // Same place `cargo pull-locales` clones into
+        let pd = directories::ProjectDirs::from("net", "cormacrelf", "citeproc-rs")
+            .expect("No home directory found.");
+        let mut locales_dir = pd.cache_dir().to_owned();
+        locales_dir.push("locales");
+        locales_dir
+    }
+
+    /// (`de-AT`, path to `locales-de-AT.xml`), sorted by tag. Panics if `dir` is not a checkout of
+    /// the locales repository, so that every build with the feature bundles the same locales.
+    fn find_locales(dir: &Path) -> Vec<(String, PathBuf)> {
+        let mut found: Vec<(String, PathBuf)> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let name = path.file_name()?.to_str()?;
+                let tag = name.strip_prefix("locales-")?.strip_suffix(".xml")?;
+                Some((tag.to_owned(), path.clone()))
+            })
+            .collect();
+        if !found.iter().any(|(tag, _)| tag == "en-US") {
+            panic!(
+                "the bundled-locales feature needs the CSL locales repository, but there is no \
+                 locales-en-US.xml in {}. Run `cargo pull-locales` or set CITEPROC_LOCALES_DIR.",
+                dir.display()
+            );
+        }
+        println!("cargo:rerun-if-changed={}", dir.display());
+        found.sort();
+        found
+    }
+
+    #[cfg(feature = "bundled-locales-compressed")]
+    fn bytes_path(out_dir: &Path, tag: &str, xml: &Path) -> PathBuf {
+        let raw = fs::read(xml).expect("could not read locale file");
+        let deflated = miniz_oxide::deflate::compress_to_vec(&raw, 9);
+        let path = out_dir.join(format!("locales-{}.xml.deflate", tag));
+        fs::write(&path, deflated).expect("could not write compressed locale");
+        path
+    }
+
+    #[cfg(not(feature = "bundled-locales-compressed"))]
+    fn bytes_path(_out_dir: &Path, _tag: &str, xml: &Path) -> PathBuf {
+        xml.to_owned()
+    }
+
+    pub fn write_bundled_locales() {
+        let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+        let mut code = String::from("pub(crate) static LOCALES: &[(&str, &[u8])] = &[\n");
+        for (tag, xml) in find_locales(&locales_dir()) {
+            let path = bytes_path(&out_dir, &tag, &xml);
+            writeln!(code, "    ({:?}, include_bytes!({:?})),", tag, path).unwrap();
+        }
+        code.push_str("];\n");
+        fs::write(out_dir.join("bundled_locales.rs"), code)
+            .expect("could not write bundled_locales.rs");
+    }
+}
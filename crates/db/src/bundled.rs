@@ -0,0 +1,53 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+use crate::xml::{LocaleFetchError, LocaleFetcher};
+use csl::Lang;
+use std::str::FromStr;
+
+// Generated by build.rs: `LOCALES`, a list of (`de-AT`, locale bytes), sorted by tag.
+include!(concat!(env!("OUT_DIR"), "/bundled_locales.rs"));
+
+/// Every locale from the CSL locales repository, compiled into the binary with the
+/// `bundled-locales` feature. Needs no filesystem or network access.
+///
+/// To let users override them with their own copies, put this last in a
+/// [LayeredLocales](crate::LayeredLocales).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BundledLocales;
+
+impl BundledLocales {
+    /// The languages that were compiled in.
+    pub fn langs() -> impl Iterator<Item = Lang> {
+        LOCALES
+            .iter()
+            .filter_map(|(tag, _)| Lang::from_str(tag).ok())
+    }
+}
+
+impl LocaleFetcher for BundledLocales {
+    fn fetch_string(&self, lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
+        let tag = lang.to_string();
+        match LOCALES.binary_search_by(|(t, _)| (*t).cmp(tag.as_str())) {
+            Ok(ix) => decode(LOCALES[ix].1).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+#[cfg(feature = "bundled-locales-compressed")]
+fn decode(bytes: &[u8]) -> Result<String, LocaleFetchError> {
+    let inflated = miniz_oxide::inflate::decompress_to_vec(bytes)
+        .map_err(|e| format!("could not inflate bundled locale: {:?}", e))?;
+    String::from_utf8(inflated).map_err(|e| e.to_string().into())
+}
+
+#[cfg(not(feature = "bundled-locales-compressed"))]
+fn decode(bytes: &[u8]) -> Result<String, LocaleFetchError> {
+    std::str::from_utf8(bytes)
+        .map(String::from)
+        .map_err(|e| e.to_string().into())
+}
@@ -10,7 +10,7 @@ use super::xml::{LocaleDatabase, StyleDatabase};
 use std::sync::Arc;
 
 use citeproc_io::output::markup::Markup;
-use citeproc_io::{Cite, ClusterMode, Reference};
+use citeproc_io::{Cite, ClusterMode, DateOrRange, Reference};
 use csl::Atom;
 
 use fnv::FnvHashSet;
@@ -119,12 +119,40 @@ pub struct ClusterData {
 
 fn reference(db: &dyn CiteDatabase, key: Atom) -> Option<Arc<Reference>> {
     if db.all_keys().contains(&key) {
-        Some(db.reference_input(key))
+        let refr = db.reference_input(key);
+        if refr.raw_dates.is_empty() {
+            Some(refr)
+        } else {
+            Some(parse_raw_dates(db, &refr))
+        }
     } else {
         None
     }
 }
 
+/// Gives `raw` dates that were not in English a second chance, with the locales' month names.
+fn parse_raw_dates(db: &dyn CiteDatabase, refr: &Reference) -> Arc<Reference> {
+    let parser = db.raw_date_parser();
+    let mut refr = refr.clone();
+    let Reference {
+        date, raw_dates, ..
+    } = &mut refr;
+    raw_dates.retain(|var| {
+        let parsed = match date.get(var) {
+            Some(DateOrRange::Literal { literal, .. }) => parser.parse(literal),
+            _ => return false,
+        };
+        match parsed {
+            Some(parsed) => {
+                date.insert(*var, parsed);
+                false
+            }
+            None => true,
+        }
+    });
+    Arc::new(refr)
+}
+
 /// Type to represent which references should appear in a bibiliography even if they are not cited
 /// in the document. The default is that references only appear if they are cited.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -1,10 +1,14 @@
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "bundled-locales")]
+mod bundled;
 mod cite;
 mod cluster;
 mod xml;
 
+#[cfg(feature = "bundled-locales")]
+pub use bundled::BundledLocales;
 pub use cite::*;
 use citeproc_io::output::markup::Markup;
 pub use cluster::*;
@@ -5,9 +5,11 @@
 // Copyright © 2019 Corporation for Digital Scholarship
 
 use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use citeproc_io::output::markup::Markup;
+use citeproc_io::RawDateParser;
 use csl::{
     locale::{Lang, Locale, LocaleSource, EN_US},
     style::{Name, Style, TextElement, TextSource},
@@ -175,25 +177,28 @@ pub trait LocaleDatabase: StyleDatabase + HasFetcher {
     #[salsa::input]
     fn default_lang_override(&self) -> Option<Lang>;
 
-    /// Backed by the LocaleFetcher implementation
+    /// Backed by the LocaleFetcher implementation. Not memoized, so fetched XML is only kept as
+    /// long as it takes `parsed_locale` to parse it.
     #[salsa::transparent]
     fn locale_xml(&self, key: Lang) -> Option<Arc<String>>;
 
     /// Derived from a `Style`
-    #[salsa::transparent]
     fn inline_locale(&self, key: Option<Lang>) -> Option<Arc<Locale>>;
 
     /// A locale object, which may be `Default::default()`
     fn parsed_locale(&self, key: LocaleSource) -> Option<Arc<Locale>>;
 
     /// Derives the full lang inheritance chain, and merges them into one
-    #[salsa::transparent]
     fn merged_locale(&self, key: Lang) -> Arc<Locale>;
 
     fn default_locale(&self) -> Arc<Locale>;
 
     #[salsa::transparent]
     fn default_lang(&self) -> Lang;
+
+    /// Month and season names from English, the default locale and every locale given to the
+    /// processor, for parsing `raw` dates
+    fn raw_date_parser(&self) -> Arc<RawDateParser>;
 }
 
 fn default_lang(db: &dyn LocaleDatabase) -> Lang {
@@ -209,6 +214,18 @@ fn default_locale(db: &dyn LocaleDatabase) -> Arc<Locale> {
     db.merged_locale(db.default_lang())
 }
 
+fn raw_date_parser(db: &dyn LocaleDatabase) -> Arc<RawDateParser> {
+    let mut parser = RawDateParser::english();
+    parser.add_locale(&db.default_locale());
+    let mut langs: Vec<Lang> = db.locale_input_langs().iter().cloned().collect();
+    // so that a name shared by two languages always means the same thing
+    langs.sort_by_key(|lang| lang.to_string());
+    for lang in langs {
+        parser.add_locale(&db.merged_locale(lang));
+    }
+    Arc::new(parser)
+}
+
 fn locale_xml(db: &dyn LocaleDatabase, key: Lang) -> Option<Arc<String>> {
     let stored = db.locale_input_langs();
     if stored.contains(&key) {
@@ -253,23 +270,24 @@ fn merged_locale(db: &dyn LocaleDatabase, key: Lang) -> Arc<Locale> {
         .iter()
         .filter_map(|src| db.parsed_locale(src))
         .collect::<Vec<_>>();
-    Arc::new(
-        locales
-            .into_iter()
-            .rev()
-            .fold(None, |mut acc, l| match acc {
-                None => Some((*l).clone()),
-                Some(ref mut base) => {
-                    debug!("merging locales: {:?} <- {:?}", base.lang, l.lang);
-                    base.merge(&l);
-                    acc
-                }
-            })
-            .unwrap_or_else(|| {
-                warn!("Using default, empty locale");
-                Locale::default()
-            }),
-    )
+    let mut merged = locales
+        .into_iter()
+        .rev()
+        .fold(None, |mut acc, l| match acc {
+            None => Some((*l).clone()),
+            Some(ref mut base) => {
+                debug!("merging locales: {:?} <- {:?}", base.lang, l.lang);
+                base.merge(&l);
+                acc
+            }
+        })
+        .unwrap_or_else(|| {
+            warn!("Using default, empty locale");
+            Locale::default()
+        });
+    // The last one merged may be a fallback, or an inline `<locale>` without an xml:lang.
+    merged.lang = Some(key);
+    Arc::new(merged)
 }
 
 use std::panic::RefUnwindSafe;
@@ -332,3 +350,48 @@ impl LocaleFetcher for PredefinedLocales {
         Ok(self.0.get(lang).cloned())
     }
 }
+
+/// Reads `locales-xx-XX.xml` files from a directory, laid out like the [CSL locales
+/// repository](https://github.com/citation-style-language/locales).
+pub struct LocaleDirectory(pub PathBuf);
+
+impl LocaleDirectory {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        LocaleDirectory(dir.into())
+    }
+}
+
+impl LocaleFetcher for LocaleDirectory {
+    fn fetch_string(&self, lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
+        let path = self.0.join(format!("locales-{}.xml", lang));
+        match std::fs::read_to_string(path) {
+            Ok(string) => Ok(Some(string)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(LocaleFetchError::Io(e)),
+        }
+    }
+}
+
+/// Asks each fetcher in turn, and returns the first locale found. Put user directories first and
+/// [BundledLocales](crate::BundledLocales) last, so users can override the bundled copies.
+///
+/// A fetcher that fails is skipped, but if nothing else has the locale, the first error is
+/// returned.
+pub struct LayeredLocales(pub Vec<Arc<dyn LocaleFetcher>>);
+
+impl LocaleFetcher for LayeredLocales {
+    fn fetch_string(&self, lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
+        let mut first_error = None;
+        for layer in &self.0 {
+            match layer.fetch_string(lang) {
+                Ok(Some(string)) => return Ok(Some(string)),
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("error fetching locale {}: {:?}", lang, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        first_error.map_or(Ok(None), Err)
+    }
+}
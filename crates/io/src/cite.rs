@@ -41,16 +41,19 @@ use serde::de::{Deserialize, Deserializer};
 ///     basic_mode("smith", CiteMode::AuthorOnly),
 /// ])
 /// ```
-#[derive(Clone, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "camelCase", bound(deserialize = ""))]
+#[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(
+    rename_all = "camelCase",
+    bound(deserialize = "", serialize = "O::Input: serde::Serialize")
+)]
 pub struct Cite<O: OutputFormat> {
     #[serde(rename = "id", deserialize_with = "get_ref_id")]
     pub ref_id: Atom,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub prefix: Option<O::Input>,
 
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub suffix: Option<O::Input>,
 
     /// Multiple locator functionality needs CSL support, so it is disabled via using
@@ -144,7 +147,7 @@ pub mod cite_compat_vec {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 pub struct Locator {
     pub locator: NumberLike,
     #[serde(default, rename = "label")]
@@ -172,7 +175,7 @@ where
 /// Accepts either
 /// `{ "locator": "54", "label": "page" }` or
 /// `{ "locators": [["chapter", "19"], ["page", "581"]] }`.
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(untagged)]
 pub enum Locators {
     Single(Locator),
@@ -227,7 +230,7 @@ impl Locators {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Deserialize, Serialize)]
 #[serde(tag = "mode")]
 pub enum CiteMode {
     AuthorOnly,
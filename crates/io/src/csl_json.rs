@@ -11,8 +11,10 @@
 mod cow_str;
 
 use crate::names::Name;
+use crate::SmartString;
+use serde::de::IgnoredAny;
 use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
-use serde::de::{Error, IgnoredAny};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use std::borrow::Cow;
 use std::fmt;
 use std::str::FromStr;
@@ -28,9 +30,9 @@ use csl::Features;
 use csl::GetAttribute;
 use csl::Lang;
 
-use super::date::{Date, DateOrRange};
+use super::date::{Date, DateOrRange, RawDateParser};
 use super::reference::Reference;
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use std::marker::PhantomData;
 
 struct LanguageVisitor;
@@ -54,7 +56,30 @@ impl<'de> Visitor<'de> for LanguageVisitor {
     }
 }
 
-pub struct MaybeDate(Option<DateOrRange>);
+/// The `bool` is whether the date is a `raw` string that is only a literal for now; see
+/// [`Reference::raw_dates`]. The string is what could not be read, if anything; see
+/// [`Reference::invalid_dates`].
+pub struct MaybeDate(Option<DateOrRange>, bool, Option<String>);
+
+/// EDTF, then English words, then anything that starts like an ISO date. Everything else stays a
+/// literal until the processor has a go with its locales' month names.
+fn parse_raw(raw: &str) -> MaybeDate {
+    static ENGLISH: once_cell::sync::OnceCell<RawDateParser> = once_cell::sync::OnceCell::new();
+    let parsed = DateOrRange::from_edtf(raw)
+        .or_else(|| ENGLISH.get_or_init(RawDateParser::english).parse(raw))
+        .or_else(|| DateOrRange::from_str(raw).ok());
+    match parsed {
+        Some(parsed) => MaybeDate(Some(parsed), false, None),
+        None => MaybeDate(
+            Some(DateOrRange::Literal {
+                literal: raw.into(),
+                circa: false,
+            }),
+            true,
+            None,
+        ),
+    }
+}
 
 pub struct WrapLang(Option<Lang>);
 
@@ -100,7 +125,7 @@ enum Field<'a> {
     Any(Cow<'a, str>),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Hash)]
 #[serde(untagged)]
 pub enum NumberLike {
     Str(String),
@@ -211,6 +236,9 @@ impl<'de> Deserialize<'de> for Reference {
                 let mut number = FnvHashMap::default();
                 let mut name = FnvHashMap::default();
                 let mut date = FnvHashMap::default();
+                let mut raw_dates = FnvHashSet::default();
+                let mut unknown_variables = Vec::new();
+                let mut invalid_dates = FnvHashMap::default();
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Id => {
@@ -228,6 +256,7 @@ impl<'de> Deserialize<'de> for Reference {
                                 Err(_unknown) => {
                                     // Unknown variable. Let it slide.
                                     log::warn!("reference had unknown variable `{}`", var_name);
+                                    unknown_variables.push(SmartString::from(&*var_name));
                                     let _: IgnoredAny = map.next_value()?;
                                 }
                                 Ok(AnyVariable::Ordinary(v)) => {
@@ -241,8 +270,19 @@ impl<'de> Deserialize<'de> for Reference {
                                     name.insert(v, names);
                                 }
                                 Ok(AnyVariable::Date(v)) => {
-                                    if let MaybeDate(Some(d)) = map.next_value()? {
+                                    let MaybeDate(parsed, unparsed_raw, problem) =
+                                        map.next_value()?;
+                                    match problem {
+                                        Some(problem) => invalid_dates.insert(v, problem),
+                                        None => invalid_dates.remove(&v),
+                                    };
+                                    if let Some(d) = parsed {
                                         date.insert(v, d);
+                                        if unparsed_raw {
+                                            raw_dates.insert(v);
+                                        } else {
+                                            raw_dates.remove(&v);
+                                        }
                                     }
                                 }
                             }
@@ -259,6 +299,9 @@ impl<'de> Deserialize<'de> for Reference {
                     number,
                     name,
                     date,
+                    raw_dates,
+                    unknown_variables,
+                    invalid_dates,
                 })
             }
         }
@@ -478,28 +521,14 @@ impl<'de> Deserialize<'de> for MaybeDate {
             where
                 E: de::Error,
             {
-                FromStr::from_str(value)
-                    .or_else(|_| {
-                        Ok(DateOrRange::Literal {
-                            literal: value.into(),
-                            circa: false,
-                        })
-                    })
-                    .map(|x| MaybeDate(Some(x)))
+                Ok(parse_raw(value))
             }
 
             fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
             where
                 E: de::Error,
             {
-                FromStr::from_str(&value)
-                    .or_else(|_| {
-                        Ok(DateOrRange::Literal {
-                            literal: value.into(),
-                            circa: false,
-                        })
-                    })
-                    .map(|x| MaybeDate(Some(x)))
+                Ok(parse_raw(&value))
             }
 
             fn visit_map<V>(self, mut map: V) -> Result<Self::Value, V::Error>
@@ -507,43 +536,53 @@ impl<'de> Deserialize<'de> for MaybeDate {
                 V: MapAccess<'de>,
             {
                 let mut found = None;
+                // edtf wins over the other keys, wherever it is
+                let mut found_edtf = None;
                 let mut found_season: Option<NumberLike> = None;
                 let mut found_circa: Option<bool> = None;
+                let mut unparsed_raw = false;
+                let mut problem = None;
                 while let Some(key) = map.next_key()? {
                     match key {
                         DateType::Raw => {
                             let v: Cow<'de, str> = map.next_value()?;
                             if found.is_none() {
-                                found = Some(DateOrRange::from_str(&v).unwrap_or_else(|_| {
-                                    DateOrRange::Literal {
-                                        literal: v.as_ref().into(),
-                                        circa: false,
-                                    }
-                                }))
+                                let MaybeDate(parsed, unparsed, _) = parse_raw(&v);
+                                found = parsed;
+                                unparsed_raw = unparsed;
                             }
                         }
                         DateType::Literal => {
                             found = Some(DateOrRange::Literal {
                                 literal: map.next_value()?,
                                 circa: false,
-                            })
+                            });
+                            unparsed_raw = false;
                         }
                         DateType::DateParts => {
                             let dp: DateParts = match map.next_value() {
                                 Ok(dp) => dp,
                                 Err(e) => {
                                     log::warn!("failed to parse date-parts: {:?}", e);
+                                    problem = Some(format!("invalid date-parts: {}", e));
                                     continue;
                                 }
                             };
                             if dp.0.is_some() {
                                 found = dp.0;
+                                unparsed_raw = false;
                             }
                         }
                         DateType::Edtf => {
-                            log::warn!("unimplemented: edtf date support");
-                            let _: IgnoredAny = map.next_value()?;
-                            continue;
+                            let v: Cow<'de, str> = map.next_value()?;
+                            found_edtf = Some(DateOrRange::from_edtf(&v).unwrap_or_else(|| {
+                                log::warn!("invalid edtf date: {}", v);
+                                problem = Some(format!("invalid edtf date: {}", v));
+                                DateOrRange::Literal {
+                                    literal: v.as_ref().into(),
+                                    circa: false,
+                                }
+                            }));
                         }
                         DateType::Season => found_season = Some(map.next_value()?),
                         DateType::Circa => {
@@ -551,6 +590,7 @@ impl<'de> Deserialize<'de> for MaybeDate {
                                 found_circa = Some(circa.to_bool())
                             } else {
                                 log::warn!("invalid value for circa");
+                                problem = Some("invalid value for circa".into());
                             }
                         }
                         DateType::Year => {
@@ -560,58 +600,63 @@ impl<'de> Deserialize<'de> for MaybeDate {
                                     month: 0,
                                     day: 0,
                                     circa: false,
+                                    qualifiers: Default::default(),
                                 };
                                 found = Some(DateOrRange::Single(date));
+                                unparsed_raw = false;
                             }
                         }
                         DateType::Unknown(k) => {
                             log::warn!("unknown date variable key: {}", k);
+                            problem = Some(format!("unknown date key `{}`", k));
                             let _: IgnoredAny = map.next_value()?;
                         }
                     }
                 }
-                Ok(found
-                    .ok_or(())
-                    // .ok_or_else(|| de::Error::missing_field("raw|literal|etc"))
-                    .and_then(|mut found| {
-                        if let Some(season) = found_season {
-                            if let DateOrRange::Single(ref mut date) = found {
-                                if !date.has_day() && !date.has_month() {
-                                    let season = season
-                                        .to_number()
-                                        .map_err(|e| {
-                                            V::Error::custom(format!(
-                                                "season {:?} was not an integer: {}",
-                                                season, e
-                                            ))
-                                        })
-                                        .and_then(|unsigned| {
-                                            if unsigned < 1 || unsigned > 4 {
-                                                Err(V::Error::custom(format!(
-                                                    "season {} was not in range [1, 4]",
-                                                    unsigned
-                                                )))
-                                            } else {
-                                                Ok(unsigned as u32)
-                                            }
-                                        });
-                                    if let Ok(mut season) = season {
-                                        if season > 20 {
-                                            // handle 21, 22, 23, 24
-                                            season -= 20;
-                                        }
-                                        date.month = season + 12;
+                if found_edtf.is_some() {
+                    found = found_edtf;
+                    unparsed_raw = false;
+                }
+                let mut found = match found {
+                    Some(found) => found,
+                    None => {
+                        let problem =
+                            problem.unwrap_or_else(|| "no date-parts, literal or raw".into());
+                        return Ok(MaybeDate(None, false, Some(problem)));
+                    }
+                };
+                if let Some(season) = found_season {
+                    if let DateOrRange::Single(ref mut date) = found {
+                        if !date.has_day() && !date.has_month() {
+                            let season = season
+                                .to_number()
+                                .map_err(|e| {
+                                    format!("season {:?} was not an integer: {}", season, e)
+                                })
+                                .and_then(|unsigned| {
+                                    if unsigned < 1 || unsigned > 4 {
+                                        Err(format!("season {} was not in range [1, 4]", unsigned))
+                                    } else {
+                                        Ok(unsigned as u32)
                                     }
+                                });
+                            match season {
+                                Ok(mut season) => {
+                                    if season > 20 {
+                                        // handle 21, 22, 23, 24
+                                        season -= 20;
+                                    }
+                                    date.month = season + 12;
                                 }
+                                Err(e) => problem = Some(e),
                             }
                         }
-                        if let Some(circa) = found_circa {
-                            found.set_circa(circa)
-                        }
-                        Ok(MaybeDate(Some(found)))
-                    })
-                    .ok()
-                    .unwrap_or(MaybeDate(None)))
+                    }
+                }
+                if let Some(circa) = found_circa {
+                    found.set_circa(circa)
+                }
+                Ok(MaybeDate(Some(found), unparsed_raw, problem))
             }
         }
 
@@ -619,3 +664,95 @@ impl<'de> Deserialize<'de> for MaybeDate {
         deserializer.deserialize_struct("DateOrRange", DATE_TYPES, DateVisitor)
     }
 }
+
+// Serialization writes plain CSL-JSON, with dates as date-parts, so it reads back in as the same
+// Reference.
+
+impl Serialize for Reference {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn sorted<'a, K: AsRef<str>, V>(
+            map: &'a FnvHashMap<K, V>,
+        ) -> impl Iterator<Item = (&'a str, &'a V)> {
+            let mut entries: Vec<_> = map.iter().map(|(k, v)| (k.as_ref(), v)).collect();
+            entries.sort_by_key(|(k, _)| *k);
+            entries.into_iter()
+        }
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("id", self.id.as_ref())?;
+        map.serialize_entry("type", self.csl_type.as_ref())?;
+        if let Some(language) = &self.language {
+            map.serialize_entry("language", &language.to_string())?;
+        }
+        for (k, v) in sorted(&self.ordinary) {
+            map.serialize_entry(k, v)?;
+        }
+        for (k, v) in sorted(&self.number) {
+            map.serialize_entry(k, v)?;
+        }
+        for (k, v) in sorted(&self.name) {
+            map.serialize_entry(k, v)?;
+        }
+        for (k, v) in sorted(&self.date) {
+            match v {
+                DateOrRange::Literal { literal, .. } if self.raw_dates.contains(k) => {
+                    map.serialize_entry(k, &SerRawDate(literal))?
+                }
+                _ => map.serialize_entry(k, v)?,
+            }
+        }
+        map.end()
+    }
+}
+
+struct SerRawDate<'a>(&'a str);
+
+impl Serialize for SerRawDate<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("raw", self.0)?;
+        map.end()
+    }
+}
+
+struct SerDateParts<'a>(&'a [&'a Date]);
+
+impl Serialize for SerDateParts<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for date in self.0 {
+            let parts = [date.year, date.month as i32, date.day as i32];
+            let len = if date.day != 0 {
+                3
+            } else if date.month != 0 {
+                2
+            } else {
+                1
+            };
+            seq.serialize_element(&parts[..len])?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for DateOrRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match self {
+            DateOrRange::Single(d) => map.serialize_entry("date-parts", &SerDateParts(&[d]))?,
+            DateOrRange::Range(d1, d2) => {
+                map.serialize_entry("date-parts", &SerDateParts(&[d1, d2]))?
+            }
+            DateOrRange::Literal { literal, .. } => map.serialize_entry("literal", literal)?,
+        }
+        // date-parts can't hold these, so let anything that reads edtf have the whole thing
+        if self.has_qualifiers() {
+            if let Some(edtf) = self.to_edtf() {
+                map.serialize_entry("edtf", &edtf)?;
+            }
+        }
+        if self.is_uncertain_date() {
+            map.serialize_entry("circa", &true)?;
+        }
+        map.end()
+    }
+}
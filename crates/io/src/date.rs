@@ -7,6 +7,10 @@
 use crate::String;
 use std::cmp::Ordering;
 
+mod edtf;
+mod raw;
+pub use self::raw::RawDateParser;
+
 /// TODO: parse 2018-3-17 as if it were '03'
 
 // This is a fairly primitive date type, possible CSL-extensions could get more fine-grained, and
@@ -25,6 +29,42 @@ pub struct Date {
     pub day: u32,
     /// aka is_uncertain_date
     pub circa: bool,
+    /// Finer-grained uncertainty from EDTF input. Setting any of these also sets `circa`.
+    pub qualifiers: DateQualifiers,
+}
+
+/// The EDTF qualifiers (`?`, `~`, `%`) and unspecified digits (`X`) of a [`Date`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct DateQualifiers {
+    /// `?`
+    pub uncertain: DateComponents,
+    /// `~`
+    pub approximate: DateComponents,
+    /// Which digits of `YYYYMMDD` were written as `X`, with the first digit of the year in the
+    /// highest bit. An unspecified month or day is also zero in the [`Date`] itself.
+    pub unspecified: u8,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct DateComponents {
+    pub year: bool,
+    pub month: bool,
+    pub day: bool,
+}
+
+impl DateComponents {
+    pub fn any(&self) -> bool {
+        self.year || self.month || self.day
+    }
+}
+
+impl DateQualifiers {
+    pub fn is_uncertain(&self) -> bool {
+        self.uncertain.any() || self.approximate.any()
+    }
+    pub fn is_empty(&self) -> bool {
+        *self == DateQualifiers::default()
+    }
 }
 
 impl PartialOrd for Date {
@@ -40,6 +80,8 @@ impl Ord for Date {
             cmp = cmp.then(self.month.cmp(&other.month))
         }
         cmp.then(self.day.cmp(&other.day))
+            // Only breaks ties, but keeps the order consistent with Eq.
+            .then(self.qualifiers.cmp(&other.qualifiers))
     }
 }
 
@@ -66,6 +108,11 @@ fn test_date_ord() {
     assert!(Date::new(2000, 5, 0) < Date::new(2000, 5, 1));
 
     assert!(Date::new(2000, 0, 0) < Date::new(2001, 0, 0));
+
+    // Agrees with Eq when only the qualifiers differ
+    let mut uncertain = Date::new(2000, 5, 1);
+    uncertain.qualifiers.uncertain.year = true;
+    assert_ne!(Date::new(2000, 5, 1).cmp(&uncertain), Ordering::Equal);
 }
 
 // TODO: implement PartialOrd?
@@ -88,6 +135,7 @@ impl Date {
             month: m,
             day: d,
             circa: false,
+            qualifiers: DateQualifiers::default(),
         }
     }
     pub fn from_parts(parts: &[i32]) -> Option<Self> {
@@ -98,6 +146,7 @@ impl Date {
             month: if m >= 1 && m <= 16 { m as u32 } else { 0 },
             day: if d >= 1 && d <= 31 { d as u32 } else { 0 },
             circa: false,
+            qualifiers: DateQualifiers::default(),
         })
     }
 
@@ -159,6 +208,24 @@ impl DateOrRange {
             _ => None,
         }
     }
+    /// Parses an [EDTF](https://www.loc.gov/standards/datetime/) date, interval or set, up to
+    /// level 2. Open and unknown interval ends become a year-zero [`Date`], like an open range in
+    /// `date-parts`. Years with unspecified digits, seasons grouped into quarters and the like,
+    /// and sets all become ranges covering every date they could mean.
+    pub fn from_edtf(s: &str) -> Option<Self> {
+        edtf::parse(s)
+    }
+    /// Writes the date back out as EDTF, if it can be.
+    pub fn to_edtf(&self) -> Option<String> {
+        edtf::write(self)
+    }
+    pub fn has_qualifiers(&self) -> bool {
+        match self {
+            DateOrRange::Single(d) => !d.qualifiers.is_empty(),
+            DateOrRange::Range(d1, d2) => !d1.qualifiers.is_empty() || !d2.qualifiers.is_empty(),
+            DateOrRange::Literal { .. } => false,
+        }
+    }
     pub fn from_parts(parts: &[&[i32]]) -> Option<Self> {
         if parts.is_empty() {
             None
@@ -188,7 +255,9 @@ impl From<(Date, Date)> for DateOrRange {
 impl FromStr for DateOrRange {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if let Ok((_left_overs, parsed)) = range(s.as_bytes()) {
+        if let Some(parsed) = DateOrRange::from_edtf(s) {
+            Ok(parsed)
+        } else if let Ok((_left_overs, parsed)) = range(s.as_bytes()) {
             Ok(parsed)
         } else {
             Err(())
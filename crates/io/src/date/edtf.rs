@@ -0,0 +1,664 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! [EDTF](https://www.loc.gov/standards/datetime/) (ISO 8601-2) dates, levels 0 to 2.
+//!
+//! CSL has no way to render most of what EDTF can express, so each date, interval or set becomes
+//! the range of dates it could mean, and the qualifiers are kept on the [`Date`]s for
+//! `is-uncertain-date` and for writing the date back out.
+
+use super::{Date, DateComponents, DateOrRange, DateQualifiers};
+use crate::String;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1, take_while_m_n},
+    character::complete::{char, digit1, one_of},
+    combinator::{all_consuming, map, map_opt, opt},
+    multi::separated_list1,
+    sequence::{delimited, preceded, separated_pair, tuple},
+    IResult,
+};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// The earliest and latest dates something could mean.
+type Span = (Date, Date);
+
+pub(super) fn parse(s: &str) -> Option<DateOrRange> {
+    let (_, parsed) = all_consuming(alt((set, interval, single)))(s.trim()).ok()?;
+    Some(parsed)
+}
+
+/// Year zero with no month or day is an open range end, as in `date-parts`.
+fn is_open(d: &Date) -> bool {
+    d.year == 0 && d.month == 0 && d.day == 0 && d.qualifiers.unspecified == 0
+}
+
+fn range(start: Option<Date>, end: Option<Date>) -> DateOrRange {
+    let start = start.unwrap_or_else(|| Date::new(0, 0, 0));
+    let end = end.unwrap_or_else(|| Date::new(0, 0, 0));
+    if start == end {
+        DateOrRange::Single(start)
+    } else {
+        DateOrRange::Range(start, end)
+    }
+}
+
+fn single(inp: &str) -> IResult<&str, DateOrRange> {
+    map(date, |(lo, hi)| range(Some(lo), Some(hi)))(inp)
+}
+
+/// `a/b`, where either end (not both) can be `..` or missing.
+fn interval(inp: &str) -> IResult<&str, DateOrRange> {
+    fn end(inp: &str) -> IResult<&str, Option<Span>> {
+        map(
+            opt(alt((map(tag(".."), |_| None), map(date, Some)))),
+            Option::flatten,
+        )(inp)
+    }
+    map_opt(separated_pair(end, char('/'), end), |pair| match pair {
+        (None, None) => None,
+        (start, end) => Some(range(start.map(|s| s.0), end.map(|e| e.1))),
+    })(inp)
+}
+
+/// `[a,b,c..d]` (one of) or `{a,b,c..d}` (all of). Both become a range from the earliest to the
+/// latest; only "one of" is uncertain.
+fn set(inp: &str) -> IResult<&str, DateOrRange> {
+    fn item(inp: &str) -> IResult<&str, (Option<Span>, Option<Span>)> {
+        alt((
+            map(preceded(tag(".."), date), |d| (None, Some(d))),
+            map(tuple((date, tag(".."), opt(date))), |(a, _, b)| {
+                (Some(a), b)
+            }),
+            map(date, |d| (Some(d), Some(d))),
+        ))(inp)
+    }
+    fn items(inp: &str) -> IResult<&str, Vec<(Option<Span>, Option<Span>)>> {
+        separated_list1(char(','), item)(inp)
+    }
+    let one = map(delimited(char('['), items, char(']')), |i| (true, i));
+    let all = map(delimited(char('{'), items, char('}')), |i| (false, i));
+    map(alt((one, all)), |(uncertain, items)| {
+        let mut start = items[0].0.map(|s| s.0);
+        let mut end = items[0].1.map(|e| e.1);
+        for (s, e) in &items[1..] {
+            start = start.and_then(|a| Some(a.min(s.as_ref()?.0)));
+            end = end.and_then(|a| Some(a.max(e.as_ref()?.1)));
+        }
+        let mut parsed = range(start, end);
+        if uncertain {
+            parsed.set_circa(true);
+        }
+        parsed
+    })(inp)
+}
+
+struct Year {
+    lo: i32,
+    hi: i32,
+    unspecified: u8,
+}
+
+fn year(inp: &str) -> IResult<&str, Year> {
+    alt((long_year, short_year))(inp)
+}
+
+fn significant(inp: &str) -> IResult<&str, &str> {
+    preceded(char('S'), digit1)(inp)
+}
+
+/// `YYYY` or `-YYYY`, where any digit can be `X`.
+fn short_year(inp: &str) -> IResult<&str, Year> {
+    let digits = take_while_m_n(4, 4, |c: char| c.is_ascii_digit() || c == 'X');
+    map_opt(
+        tuple((opt(char('-')), digits, opt(significant))),
+        |(neg, digits, sig)| {
+            let mut lo = 0;
+            let mut hi = 0;
+            for c in digits.chars() {
+                lo *= 10;
+                hi *= 10;
+                match c.to_digit(10) {
+                    Some(d) => {
+                        lo += d as i64;
+                        hi += d as i64;
+                    }
+                    None => hi += 9,
+                }
+            }
+            let unspecified = unspecified_mask(digits);
+            if unspecified != 0 && sig.is_some() {
+                return None;
+            }
+            finish_year(neg.is_some(), lo, hi, sig, unspecified)
+        },
+    )(inp)
+}
+
+/// `Y` followed by any number of digits, with an optional exponent.
+fn long_year(inp: &str) -> IResult<&str, Year> {
+    let exponent = preceded(char('E'), digit1);
+    map_opt(
+        tuple((
+            char('Y'),
+            opt(char('-')),
+            digit1,
+            opt(exponent),
+            opt(significant),
+        )),
+        |(_, neg, digits, exp, sig)| {
+            let mut value = digits.parse::<i64>().ok()?;
+            if let Some(exp) = exp {
+                value = value.checked_mul(10i64.checked_pow(exp.parse().ok()?)?)?;
+            }
+            finish_year(neg.is_some(), value, value, sig, 0)
+        },
+    )(inp)
+}
+
+/// Applies significant digits (`1950S2` is some year from 1900 to 1999) and the sign.
+fn finish_year(
+    neg: bool,
+    mut lo: i64,
+    mut hi: i64,
+    sig: Option<&str>,
+    unspecified: u8,
+) -> Option<Year> {
+    if let Some(sig) = sig {
+        let sig: u32 = sig.parse().ok()?;
+        let len = lo.to_string().len() as u32;
+        if sig == 0 {
+            return None;
+        }
+        if sig < len {
+            let precision = 10i64.pow(len - sig);
+            lo = lo / precision * precision;
+            hi = lo + precision - 1;
+        }
+    }
+    if neg {
+        let (l, h) = (-hi, -lo);
+        lo = l;
+        hi = h;
+    }
+    Some(Year {
+        lo: i32::try_from(lo).ok()?,
+        hi: i32::try_from(hi).ok()?,
+        unspecified,
+    })
+}
+
+fn two_digits(inp: &str) -> IResult<&str, &str> {
+    take_while_m_n(2, 2, |c: char| c.is_ascii_digit() || c == 'X')(inp)
+}
+
+/// One bit per digit, first digit highest.
+fn unspecified_mask(digits: &str) -> u8 {
+    digits
+        .chars()
+        .fold(0, |mask, c| mask << 1 | (c == 'X') as u8)
+}
+
+struct Month {
+    lo: u32,
+    hi: u32,
+    unspecified: u8,
+    /// Only a calendar month can be followed by a day.
+    takes_day: bool,
+}
+
+/// `MM`, a season (21-32) or a quarter, quadrimester or semester (33-41).
+fn month(inp: &str) -> IResult<&str, Month> {
+    map_opt(two_digits, |digits| {
+        let unspecified = unspecified_mask(digits);
+        let (lo, hi) = if unspecified != 0 {
+            (0, 0)
+        } else {
+            match digits.parse::<u32>().ok()? {
+                m @ 1..=12 => (m, m),
+                // spring, summer, autumn, winter; then the same for the northern and southern
+                // hemispheres, which CSL does not distinguish
+                s @ 21..=24 => (s - 8, s - 8),
+                s @ 25..=28 => (s - 12, s - 12),
+                s @ 29..=32 => (s - 16, s - 16),
+                q @ 33..=36 => ((q - 33) * 3 + 1, (q - 33) * 3 + 3),
+                q @ 37..=39 => ((q - 37) * 4 + 1, (q - 37) * 4 + 4),
+                s @ 40..=41 => ((s - 40) * 6 + 1, (s - 40) * 6 + 6),
+                _ => return None,
+            }
+        };
+        Some(Month {
+            lo,
+            hi,
+            unspecified,
+            takes_day: lo == hi && hi <= 12,
+        })
+    })(inp)
+}
+
+fn day(inp: &str) -> IResult<&str, (u32, u8)> {
+    map_opt(two_digits, |digits| {
+        let unspecified = unspecified_mask(digits);
+        if unspecified != 0 {
+            return Some((0, unspecified));
+        }
+        match digits.parse().ok()? {
+            d @ 1..=31 => Some((d, 0)),
+            _ => None,
+        }
+    })(inp)
+}
+
+/// Ignored; CSL has nothing to do with times.
+fn time(inp: &str) -> IResult<&str, &str> {
+    preceded(
+        char('T'),
+        take_while1(|c: char| c.is_ascii_digit() || ":Z+-".contains(c)),
+    )(inp)
+}
+
+fn qualifier(inp: &str) -> IResult<&str, char> {
+    one_of("?~%")(inp)
+}
+
+fn qualify(quals: &mut DateQualifiers, q: Option<char>, year: bool, month: bool, day: bool) {
+    let add = |to: &mut DateComponents| {
+        to.year |= year;
+        to.month |= month;
+        to.day |= day;
+    };
+    match q {
+        Some('?') => add(&mut quals.uncertain),
+        Some('~') => add(&mut quals.approximate),
+        Some('%') => {
+            add(&mut quals.uncertain);
+            add(&mut quals.approximate);
+        }
+        _ => {}
+    }
+}
+
+/// A qualifier to the left of a component applies to that component only; one to the right
+/// applies to it and every component before it.
+fn date(inp: &str) -> IResult<&str, Span> {
+    let (rem, (y_left, y, y_right)) = tuple((opt(qualifier), year, opt(qualifier)))(inp)?;
+    let and_day = preceded(char('-'), tuple((opt(qualifier), day, opt(qualifier))));
+    let (rem, md) = opt(preceded(
+        char('-'),
+        tuple((opt(qualifier), month, opt(qualifier), opt(and_day))),
+    ))(rem)?;
+    let has_day = matches!(md, Some((_, _, _, Some(_))));
+    let (rem, _) = if has_day {
+        opt(time)(rem)?
+    } else {
+        (rem, None)
+    };
+
+    let mut quals = DateQualifiers::default();
+    qualify(&mut quals, y_left, true, false, false);
+    qualify(&mut quals, y_right, true, false, false);
+    let mut unspecified = y.unspecified << 4;
+    let (mut m_lo, mut m_hi, mut d) = (0, 0, 0);
+    if let Some((m_left, m, m_right, and_day)) = md {
+        qualify(&mut quals, m_left, false, true, false);
+        qualify(&mut quals, m_right, true, true, false);
+        unspecified |= m.unspecified << 2;
+        m_lo = m.lo;
+        m_hi = m.hi;
+        if let Some((d_left, (day, d_unspecified), d_right)) = and_day {
+            if !m.takes_day {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    inp,
+                    nom::error::ErrorKind::Verify,
+                )));
+            }
+            qualify(&mut quals, d_left, false, false, true);
+            qualify(&mut quals, d_right, true, true, true);
+            unspecified |= d_unspecified;
+            // a day in an unspecified month can't be rendered
+            if m.lo != 0 {
+                d = day;
+            }
+        }
+    }
+    quals.unspecified = unspecified;
+    let mk = |year, month| Date {
+        year,
+        month,
+        day: d,
+        circa: quals.is_uncertain(),
+        qualifiers: quals,
+    };
+    Ok((rem, (mk(y.lo, m_lo), mk(y.hi, m_hi))))
+}
+
+pub(super) fn write(dor: &DateOrRange) -> Option<String> {
+    let mut out = String::new();
+    match dor {
+        DateOrRange::Single(d) => write_date(d, &mut out),
+        DateOrRange::Range(d1, d2) => {
+            let mut end = String::new();
+            write_end(d1, &mut out);
+            write_end(d2, &mut end);
+            // 201X parses as 2010 to 2019, and should come back out as 201X
+            if out != end || is_open(d1) {
+                out.push('/');
+                out.push_str(&end);
+            }
+        }
+        DateOrRange::Literal { .. } => return None,
+    }
+    Some(out)
+}
+
+fn write_end(d: &Date, out: &mut String) {
+    if is_open(d) {
+        out.push_str("..");
+    } else {
+        write_date(d, out);
+    }
+}
+
+fn write_qualifier(
+    quals: &DateQualifiers,
+    component: fn(&DateComponents) -> bool,
+    out: &mut String,
+) {
+    match (component(&quals.uncertain), component(&quals.approximate)) {
+        (true, true) => out.push('%'),
+        (true, false) => out.push('?'),
+        (false, true) => out.push('~'),
+        (false, false) => {}
+    }
+}
+
+fn write_digits(n: u32, width: u32, unspecified: u8, out: &mut String) {
+    let digits = format!("{:01$}", n, width as usize);
+    for (i, c) in digits.chars().enumerate() {
+        if unspecified & (1 << (width - 1 - i as u32)) != 0 {
+            out.push('X');
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+fn write_date(d: &Date, out: &mut String) {
+    let quals = &d.qualifiers;
+    let unspecified = quals.unspecified;
+    write_qualifier(quals, |c| c.year, out);
+    if d.year > 9999 || d.year < -9999 {
+        write!(out, "Y{}", d.year).unwrap();
+    } else {
+        if d.year < 0 {
+            out.push('-');
+        }
+        write_digits(d.year.abs() as u32, 4, unspecified >> 4, out);
+    }
+    if d.month != 0 || unspecified & 0b1100 != 0 {
+        out.push('-');
+        write_qualifier(quals, |c| c.month, out);
+        // seasons are 13-16 here and 21-24 in EDTF
+        let month = if d.month > 12 { d.month + 8 } else { d.month };
+        write_digits(month, 2, (unspecified >> 2) & 0b11, out);
+        if d.day != 0 || unspecified & 0b11 != 0 {
+            out.push('-');
+            write_qualifier(quals, |c| c.day, out);
+            write_digits(d.day, 2, unspecified & 0b11, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn edtf(s: &str) -> DateOrRange {
+        parse(s).unwrap_or_else(|| panic!("could not parse EDTF {:?}", s))
+    }
+    fn check(s: &str, expected: impl Into<DateOrRange>) {
+        assert_eq!(edtf(s), expected.into(), "{:?}", s);
+    }
+    fn date(y: i32, m: u32, d: u32) -> Date {
+        Date::new(y, m, d)
+    }
+    fn open() -> Date {
+        Date::new(0, 0, 0)
+    }
+    fn with(mut d: Date, f: impl FnOnce(&mut DateQualifiers)) -> Date {
+        f(&mut d.qualifiers);
+        d.circa = d.qualifiers.is_uncertain();
+        d
+    }
+    const Y: DateComponents = DateComponents {
+        year: true,
+        month: false,
+        day: false,
+    };
+    const YM: DateComponents = DateComponents {
+        year: true,
+        month: true,
+        day: false,
+    };
+    const YMD: DateComponents = DateComponents {
+        year: true,
+        month: true,
+        day: true,
+    };
+    const M: DateComponents = DateComponents {
+        year: false,
+        month: true,
+        day: false,
+    };
+    const D: DateComponents = DateComponents {
+        year: false,
+        month: false,
+        day: true,
+    };
+
+    #[test]
+    fn level_0() {
+        check("1985-04-12", date(1985, 4, 12));
+        check("1985-04", date(1985, 4, 0));
+        check("1985", date(1985, 0, 0));
+        check("-1985", date(-1985, 0, 0));
+        check("1985-04-12T23:20:30", date(1985, 4, 12));
+        check("1985-04-12T23:20:30Z", date(1985, 4, 12));
+        check("1985-04-12T23:20:30-04:00", date(1985, 4, 12));
+        check("1964/2008", (date(1964, 0, 0), date(2008, 0, 0)));
+        check("2004-06/2006-08", (date(2004, 6, 0), date(2006, 8, 0)));
+        check(
+            "2004-02-01/2005-02-08",
+            (date(2004, 2, 1), date(2005, 2, 8)),
+        );
+        check("2004-02-01/2005", (date(2004, 2, 1), date(2005, 0, 0)));
+        check("2005/2006-02", (date(2005, 0, 0), date(2006, 2, 0)));
+    }
+
+    #[test]
+    fn level_1() {
+        check("Y170000002", date(170000002, 0, 0));
+        check("Y-170000002", date(-170000002, 0, 0));
+        check("2001-21", date(2001, 13, 0));
+        check("2001-24", date(2001, 16, 0));
+        check("1984?", with(date(1984, 0, 0), |q| q.uncertain = Y));
+        check("2004-06~", with(date(2004, 6, 0), |q| q.approximate = YM));
+        check(
+            "2004-06-11%",
+            with(date(2004, 6, 11), |q| {
+                q.uncertain = YMD;
+                q.approximate = YMD;
+            }),
+        );
+        check(
+            "201X",
+            (
+                with(date(2010, 0, 0), |q| q.unspecified = 0b0001_0000),
+                with(date(2019, 0, 0), |q| q.unspecified = 0b0001_0000),
+            ),
+        );
+        check(
+            "20XX",
+            (
+                with(date(2000, 0, 0), |q| q.unspecified = 0b0011_0000),
+                with(date(2099, 0, 0), |q| q.unspecified = 0b0011_0000),
+            ),
+        );
+        check(
+            "2004-XX",
+            with(date(2004, 0, 0), |q| q.unspecified = 0b0000_1100),
+        );
+        check(
+            "1985-04-XX",
+            with(date(1985, 4, 0), |q| q.unspecified = 0b0000_0011),
+        );
+        check(
+            "1985-XX-XX",
+            with(date(1985, 0, 0), |q| q.unspecified = 0b0000_1111),
+        );
+        check("1985-04-12/..", (date(1985, 4, 12), open()));
+        check("1985-04/..", (date(1985, 4, 0), open()));
+        check("../1985-04-12", (open(), date(1985, 4, 12)));
+        check("1985-04-12/", (date(1985, 4, 12), open()));
+        check("/1985-04-12", (open(), date(1985, 4, 12)));
+        assert!(edtf("1984?").is_uncertain_date());
+        assert!(edtf("1984~/2004-06").is_uncertain_date());
+        assert!(!edtf("1984/2004-06").is_uncertain_date());
+    }
+
+    #[test]
+    fn level_2() {
+        check("Y-17E7", date(-170000000, 0, 0));
+        check("1950S2", (date(1900, 0, 0), date(1999, 0, 0)));
+        check(
+            "Y171010000S3",
+            (date(171000000, 0, 0), date(171999999, 0, 0)),
+        );
+        check("Y3388E2S3", (date(338000, 0, 0), date(338999, 0, 0)));
+        // second quarter
+        check("2001-34", (date(2001, 4, 0), date(2001, 6, 0)));
+        // second semester
+        check("2001-41", (date(2001, 7, 0), date(2001, 12, 0)));
+        assert_eq!(
+            edtf("[1667,1668,1670..1672]"),
+            DateOrRange::from((date(1667, 0, 0), date(1672, 0, 0))).with_circa(true)
+        );
+        assert_eq!(
+            edtf("[..1760-12-03]"),
+            DateOrRange::from((open(), date(1760, 12, 3))).with_circa(true)
+        );
+        assert_eq!(
+            edtf("[1760-01,1760-02,1760-12..]"),
+            DateOrRange::from((date(1760, 1, 0), open())).with_circa(true)
+        );
+        check(
+            "{1667,1668,1670..1672}",
+            (date(1667, 0, 0), date(1672, 0, 0)),
+        );
+        check("{1960,1961-12}", (date(1960, 0, 0), date(1961, 12, 0)));
+        check("{..1984}", (open(), date(1984, 0, 0)));
+        check("2004?-06-11", with(date(2004, 6, 11), |q| q.uncertain = Y));
+        check(
+            "2004-06~-11",
+            with(date(2004, 6, 11), |q| q.approximate = YM),
+        );
+        check("2004-?06-11", with(date(2004, 6, 11), |q| q.uncertain = M));
+        check(
+            "?2004-06-~11",
+            with(date(2004, 6, 11), |q| {
+                q.uncertain = Y;
+                q.approximate = D;
+            }),
+        );
+        check(
+            "2004-%06-11",
+            with(date(2004, 6, 11), |q| {
+                q.uncertain = M;
+                q.approximate = M;
+            }),
+        );
+        check(
+            "156X-12-25",
+            (
+                with(date(1560, 12, 25), |q| q.unspecified = 0b0001_0000),
+                with(date(1569, 12, 25), |q| q.unspecified = 0b0001_0000),
+            ),
+        );
+        check(
+            "XXXX-12-XX",
+            (
+                with(date(0, 12, 0), |q| q.unspecified = 0b1111_0011),
+                with(date(9999, 12, 0), |q| q.unspecified = 0b1111_0011),
+            ),
+        );
+        check(
+            "1984-1X",
+            with(date(1984, 0, 0), |q| q.unspecified = 0b0000_0100),
+        );
+        check(
+            "2004-06-~01/2004-06-~20",
+            (
+                with(date(2004, 6, 1), |q| q.approximate = D),
+                with(date(2004, 6, 20), |q| q.approximate = D),
+            ),
+        );
+        check(
+            "2004-06-XX/2004-07-03",
+            (
+                with(date(2004, 6, 0), |q| q.unspecified = 0b0000_0011),
+                date(2004, 7, 3),
+            ),
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        for s in &[
+            "",
+            "85",
+            "1985-13",
+            "1985-00",
+            "1985-04-32",
+            "2004-21-05",
+            "1985-04-12junk",
+            "1985T10:00",
+            "../..",
+            "/",
+            "Y",
+            "Y99999999999",
+            "[]",
+            "[1984",
+            "19XXS2",
+        ] {
+            assert_eq!(parse(s), None, "parsed {:?}", s);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for s in &[
+            "1985-04-12",
+            "-0044-03-15",
+            "Y170000002",
+            "2001-21",
+            "?1984",
+            "~2004-~06",
+            "%2004-%06-%11",
+            "201X",
+            "19XX-XX",
+            "1985-04-XX",
+            "1985-04-12/..",
+            "../1985",
+            "?2004-06-~11",
+            "2004-06-~01/2004-06-~20",
+        ] {
+            let parsed = edtf(s);
+            assert_eq!(parsed.to_edtf().as_deref(), Some(*s), "{:?}", parsed);
+            assert_eq!(edtf(&parsed.to_edtf().unwrap()), parsed);
+        }
+    }
+}
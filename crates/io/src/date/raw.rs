@@ -0,0 +1,422 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Dates written out in words, like CSL-JSON `raw` dates from Zotero: "March 3, 1998",
+//! "3 mars 1998", "Spring 2001", "c. 1990–1995".
+
+use super::{Date, DateOrRange};
+use crate::String;
+use csl::{
+    GenderedTermSelector, Locale, MiscTerm, MonthForm, SimpleTermSelector, TermFormExtended,
+};
+
+const ENGLISH_MONTHS: &[(&str, u32)] = &[
+    ("january", 1),
+    ("jan", 1),
+    ("february", 2),
+    ("feb", 2),
+    ("march", 3),
+    ("mar", 3),
+    ("april", 4),
+    ("apr", 4),
+    ("may", 5),
+    ("june", 6),
+    ("jun", 6),
+    ("july", 7),
+    ("jul", 7),
+    ("august", 8),
+    ("aug", 8),
+    ("september", 9),
+    ("sep", 9),
+    ("sept", 9),
+    ("october", 10),
+    ("oct", 10),
+    ("november", 11),
+    ("nov", 11),
+    ("december", 12),
+    ("dec", 12),
+    ("spring", 13),
+    ("summer", 14),
+    ("autumn", 15),
+    ("fall", 15),
+    ("winter", 16),
+];
+
+const ENGLISH_CIRCA: &[&str] = &["c", "ca", "circa"];
+
+/// Parses dates written in words, with month and season names from any number of locales.
+///
+/// Handles day-month-year and month-day-year orders, ranges separated by hyphens, dashes or
+/// slashes (with whatever the ends have in common written only once, as in "3–5 March 1998"),
+/// two-digit range ends ("1998–99") and a leading circa term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawDateParser {
+    /// Lowercase, without full stops, and the month (1-12) or season (13-16) each stands for.
+    names: Vec<(String, u32)>,
+    circa: Vec<String>,
+}
+
+impl Default for RawDateParser {
+    fn default() -> Self {
+        RawDateParser::english()
+    }
+}
+
+impl RawDateParser {
+    /// English month and season names, with the usual abbreviations.
+    pub fn english() -> Self {
+        RawDateParser {
+            names: ENGLISH_MONTHS
+                .iter()
+                .map(|&(name, month)| (name.into(), month))
+                .collect(),
+            circa: ENGLISH_CIRCA.iter().map(|&c| c.into()).collect(),
+        }
+    }
+
+    /// Adds the long and short month and season terms, and the circa term, from a locale. Names
+    /// already known keep their meaning.
+    pub fn add_locale(&mut self, locale: &Locale) {
+        for month in 1..=16 {
+            for &form in &[MonthForm::Long, MonthForm::Short] {
+                let term = GenderedTermSelector::from_month_u32(month, form)
+                    .and_then(|sel| locale.get_gendered_term(sel));
+                if let Some(term) = term {
+                    let name = normalise(term.0.singular());
+                    if !name.is_empty() && self.month(&name).is_none() {
+                        self.names.push((name, month));
+                    }
+                }
+            }
+        }
+        for &form in &[TermFormExtended::Long, TermFormExtended::Short] {
+            let sel = SimpleTermSelector::Misc(MiscTerm::Circa, form);
+            if let Some(term) = locale.get_simple_term(sel) {
+                let circa = normalise(term.singular());
+                if !circa.is_empty() && !self.circa.contains(&circa) {
+                    self.circa.push(circa);
+                }
+            }
+        }
+    }
+
+    fn month(&self, word: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .find(|(name, _)| name.as_str() == word)
+            .map(|&(_, month)| month)
+    }
+
+    pub fn parse(&self, s: &str) -> Option<DateOrRange> {
+        let tokens = tokenize(s)?;
+        let mut tokens = &tokens[..];
+        let mut circa = false;
+        if let Some(Token::Word(w)) = tokens.first() {
+            if self.circa.contains(w) {
+                circa = true;
+                tokens = &tokens[1..];
+            }
+        }
+        let mut halves = tokens.split(|t| *t == Token::Dash);
+        let start = self.partial(halves.next()?)?;
+        let end = match halves.next() {
+            Some(end) => Some(self.partial(end)?),
+            None => None,
+        };
+        if halves.next().is_some() {
+            return None;
+        }
+        let parsed = match end {
+            None => DateOrRange::Single(start.finish()?),
+            Some(end) => {
+                let (start, end) = fill_in(start, end)?;
+                let (start, end) = (start.finish()?, end.finish()?);
+                if start == end {
+                    DateOrRange::Single(start)
+                } else {
+                    DateOrRange::Range(start, end)
+                }
+            }
+        };
+        Some(parsed.with_circa(circa))
+    }
+
+    fn partial(&self, tokens: &[Token]) -> Option<Partial> {
+        let mut partial = Partial::default();
+        let mut numbers = Vec::new();
+        for token in tokens {
+            match token {
+                Token::Word(w) if partial.month.is_none() => partial.month = Some(self.month(w)?),
+                Token::Number(n, digits) => numbers.push((*n, *digits)),
+                _ => return None,
+            }
+        }
+        let is_year = |&(n, digits): &(u32, usize)| digits >= 3 || n > 31;
+        match numbers[..] {
+            [] => {}
+            [only] if is_year(&only) => partial.year = Some(only.0 as i32),
+            [only] if partial.month.is_some() => partial.day = Some(only.0),
+            [only] => partial.bare = Some(only.0),
+            [day, year] if is_year(&year) && !is_year(&day) => {
+                partial.day = Some(day.0);
+                partial.year = Some(year.0 as i32);
+            }
+            [year, day] if is_year(&year) && !is_year(&day) => {
+                partial.day = Some(day.0);
+                partial.year = Some(year.0 as i32);
+            }
+            _ => return None,
+        }
+        Some(partial)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(u32, usize),
+    Word(String),
+    Dash,
+}
+
+fn normalise(word: &str) -> String {
+    word.chars()
+        .filter(|&c| c != '.')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ',' || c == '.' {
+            chars.next();
+        } else if matches!(c, '-' | '‐' | '‑' | '–' | '—' | '/') {
+            chars.next();
+            tokens.push(Token::Dash);
+        } else if c.is_ascii_digit() {
+            let mut n: u32 = 0;
+            let mut digits = 0;
+            while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                n = n.checked_mul(10)?.checked_add(d)?;
+                digits += 1;
+                chars.next();
+            }
+            // ordinal suffixes, like 3rd or 1er
+            while chars.peek().map_or(false, |c| c.is_alphabetic()) {
+                chars.next();
+            }
+            tokens.push(Token::Number(n, digits));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                for lower in c.to_lowercase() {
+                    word.push(lower);
+                }
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Partial {
+    year: Option<i32>,
+    month: Option<u32>,
+    day: Option<u32>,
+    /// A small number on its own, which is a day or a two-digit year depending on the other end
+    /// of the range.
+    bare: Option<u32>,
+}
+
+impl Partial {
+    fn finish(self) -> Option<Date> {
+        let year = self.year.filter(|&y| y != 0)?;
+        let month = self.month.unwrap_or(0);
+        let day = match self.day {
+            Some(day) if (1..=12).contains(&month) && (1..=31).contains(&day) => day,
+            Some(_) => return None,
+            None => 0,
+        };
+        if self.bare.is_some() {
+            return None;
+        }
+        Some(Date::new(year, month, day))
+    }
+}
+
+/// Shares what is only written once between the ends of a range.
+fn fill_in(mut start: Partial, mut end: Partial) -> Option<(Partial, Partial)> {
+    if let Some(bare) = start.bare.take() {
+        if end.day.is_none() {
+            return None;
+        }
+        start.day = Some(bare);
+    }
+    if let Some(bare) = end.bare.take() {
+        match (start.year, start.day, start.month) {
+            (_, Some(_), _) => end.day = Some(bare),
+            (Some(year), None, None) if bare < 100 => {
+                let century = year - year.rem_euclid(100);
+                let mut short = century + bare as i32;
+                if short < year {
+                    short += 100;
+                }
+                end.year = Some(short);
+            }
+            _ => return None,
+        }
+    }
+    if start.year.is_none() {
+        start.year = end.year;
+    }
+    if end.year.is_none() {
+        end.year = start.year;
+    }
+    if start.day.is_some() && start.month.is_none() {
+        start.month = end.month;
+    }
+    if end.day.is_some() && end.month.is_none() {
+        end.month = start.month;
+    }
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn english(s: &str) -> Option<DateOrRange> {
+        RawDateParser::english().parse(s)
+    }
+
+    fn range(a: Date, b: Date) -> Option<DateOrRange> {
+        Some(DateOrRange::Range(a, b))
+    }
+
+    #[test]
+    fn single() {
+        let march_3 = Some(DateOrRange::new(1998, 3, 3));
+        assert_eq!(english("March 3, 1998"), march_3);
+        assert_eq!(english("3 March 1998"), march_3);
+        assert_eq!(english("3rd March, 1998"), march_3);
+        assert_eq!(english("Mar. 3 1998"), march_3);
+        assert_eq!(english("1998 March 3"), march_3);
+        assert_eq!(english("march 1998"), Some(DateOrRange::new(1998, 3, 0)));
+        assert_eq!(english("Sept. 2001"), Some(DateOrRange::new(2001, 9, 0)));
+        assert_eq!(english("Spring 2001"), Some(DateOrRange::new(2001, 13, 0)));
+        assert_eq!(english("Fall 2001"), Some(DateOrRange::new(2001, 15, 0)));
+        assert_eq!(english("1998"), Some(DateOrRange::new(1998, 0, 0)));
+    }
+
+    #[test]
+    fn circa() {
+        assert_eq!(
+            english("c. 1900"),
+            Some(DateOrRange::new(1900, 0, 0).with_circa(true))
+        );
+        assert_eq!(
+            english("circa March 1900"),
+            Some(DateOrRange::new(1900, 3, 0).with_circa(true))
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let y = |y| Date::new(y, 0, 0);
+        assert_eq!(english("1990–1995"), range(y(1990), y(1995)));
+        assert_eq!(english("1990 - 1995"), range(y(1990), y(1995)));
+        assert_eq!(english("1990/1995"), range(y(1990), y(1995)));
+        assert_eq!(english("1998–99"), range(y(1998), y(1999)));
+        assert_eq!(
+            english("c. 1990–1995"),
+            Some(DateOrRange::Range(y(1990), y(1995)).with_circa(true))
+        );
+        assert_eq!(
+            english("March 3–5, 1998"),
+            range(Date::new(1998, 3, 3), Date::new(1998, 3, 5))
+        );
+        assert_eq!(
+            english("3–5 March 1998"),
+            range(Date::new(1998, 3, 3), Date::new(1998, 3, 5))
+        );
+        assert_eq!(
+            english("March–April 1998"),
+            range(Date::new(1998, 3, 0), Date::new(1998, 4, 0))
+        );
+        assert_eq!(
+            english("3 March – 5 April 1998"),
+            range(Date::new(1998, 3, 3), Date::new(1998, 4, 5))
+        );
+        assert_eq!(
+            english("December 1998 – January 1999"),
+            range(Date::new(1998, 12, 0), Date::new(1999, 1, 0))
+        );
+    }
+
+    #[test]
+    fn not_dates() {
+        for s in &[
+            "",
+            "March",
+            "3 March",
+            "12",
+            "March 3 5 1998",
+            "Spring 3, 2001",
+            "1990–1995–2000",
+            "in press",
+            "forthcoming 2021",
+            "[1998]",
+        ] {
+            assert_eq!(english(s), None, "parsed {:?}", s);
+        }
+    }
+
+    #[test]
+    fn locale_terms() {
+        let fr = Locale::parse(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <locale xmlns="http://purl.org/net/xbiblio/csl" version="1.0" xml:lang="fr-FR">
+            <terms>
+                <term name="month-03">mars</term>
+                <term name="month-07">juillet</term>
+                <term name="month-07" form="short">juil.</term>
+                <term name="season-01">printemps</term>
+                <term name="circa" form="short">env.</term>
+            </terms>
+            </locale>"#,
+        )
+        .unwrap();
+        let mut parser = RawDateParser::english();
+        assert_eq!(parser.parse("3 mars 1998"), None);
+        parser.add_locale(&fr);
+        assert_eq!(
+            parser.parse("3 mars 1998"),
+            Some(DateOrRange::new(1998, 3, 3))
+        );
+        assert_eq!(
+            parser.parse("1er juil. 1998"),
+            Some(DateOrRange::new(1998, 7, 1))
+        );
+        assert_eq!(
+            parser.parse("printemps 2001"),
+            Some(DateOrRange::new(2001, 13, 0))
+        );
+        assert_eq!(
+            parser.parse("env. 1900"),
+            Some(DateOrRange::new(1900, 0, 0).with_circa(true))
+        );
+        // English still works
+        assert_eq!(
+            parser.parse("March 3, 1998"),
+            Some(DateOrRange::new(1998, 3, 3))
+        );
+    }
+}
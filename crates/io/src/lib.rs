@@ -66,7 +66,8 @@ pub struct IngestOptions {
     pub text_case: TextCase,
     pub quotes: LocalizedQuotes,
     pub strip_periods: bool,
-    pub is_english: bool,
+    /// Language-specific rules for `text_case`. Title case is only applied to English by default.
+    pub case_rules: CaseRules,
 
     /// For `flipflop_LeadingMarkupWithApostrophe.txt`
     ///
@@ -92,3 +93,4 @@ impl IngestOptions {
 
 pub mod lazy;
 mod text_case;
+pub use self::text_case::{CaseLanguage, CaseRules, StopwordTable, Stopwords};
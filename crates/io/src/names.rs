@@ -19,6 +19,8 @@ struct PersonNameInput {
     pub static_particles: bool,
     #[serde(default, deserialize_with = "RelaxedBool::deserialize_bool")]
     pub comma_suffix: bool,
+    pub family_reading: Option<String>,
+    pub given_reading: Option<String>,
 }
 
 // kebab-case here is the same as Strum's "kebab_case",
@@ -36,6 +38,13 @@ pub struct PersonName {
     pub static_particles: bool,
     #[serde(default)]
     pub comma_suffix: bool,
+    /// How the family name is read, e.g. in kana (furigana) or pinyin. Used instead of the
+    /// family name when sorting, so that names in Han characters sort by pronunciation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family_reading: Option<String>,
+    /// How the given name is read. See `family_reading`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given_reading: Option<String>,
     #[serde(default, skip_serializing)]
     pub is_latin_cyrillic: bool,
 }
@@ -53,8 +62,9 @@ enum NameInput {
     // TODO: represent an institution in CSL-M?
 }
 
+// Serialized untagged, so it reads back in as a NameInput.
 #[derive(Debug, Eq, PartialEq, Hash, Deserialize, Serialize, Clone)]
-#[serde(from = "NameInput")]
+#[serde(from = "NameInput", untagged)]
 pub enum Name {
     // Put literal first, because PersonName's properties are all Options and derived
     // Deserialize impls run in order.
@@ -187,6 +197,8 @@ impl From<PersonNameInput> for PersonName {
             suffix,
             static_particles,
             comma_suffix,
+            family_reading,
+            given_reading,
         } = input;
 
         let mut pn = PersonName {
@@ -197,6 +209,8 @@ impl From<PersonNameInput> for PersonName {
             suffix,
             static_particles,
             comma_suffix,
+            family_reading,
+            given_reading,
             is_latin_cyrillic,
         };
 
@@ -208,6 +222,8 @@ impl From<PersonNameInput> for PersonName {
             suffix,
             static_particles,
             comma_suffix,
+            family_reading: _,
+            given_reading: _,
             is_latin_cyrillic: _,
         } = &mut pn;
 
@@ -499,3 +515,21 @@ fn test_is_latin() {
     };
     assert!(pn_is_latin_cyrillic(&pn));
 }
+
+#[test]
+fn test_readings() {
+    let pn: PersonName = serde_json::from_str(
+        r#"{ "family": "村上", "given": "春樹", "family-reading": "むらかみ", "given-reading": "はるき" }"#,
+    )
+    .unwrap();
+    assert_eq!(pn.family_reading.as_deref(), Some("むらかみ"));
+    assert_eq!(pn.given_reading.as_deref(), Some("はるき"));
+    assert!(!pn.is_latin_cyrillic);
+    let json = serde_json::to_value(&pn).unwrap();
+    assert_eq!(json["family-reading"], "むらかみ");
+    let pn: PersonName = serde_json::from_str(r#"{ "family": "Doe" }"#).unwrap();
+    assert!(serde_json::to_value(&pn)
+        .unwrap()
+        .get("given-reading")
+        .is_none());
+}
@@ -6,6 +6,7 @@
 
 use crate::NumberLike;
 use crate::String;
+use csl::{Gender, Locale, OrdinalTerm, OrdinalTermSelector};
 use std::borrow::Cow;
 
 pub mod roman;
@@ -40,6 +41,8 @@ pub enum NumericToken {
     Num(u32),
     Roman(u32, /* uppercase */ bool),
     Affixed(String, u32, String),
+    /// A number with one of the locale's ordinal suffixes, like `2nd` or `1re`.
+    Ordinal(u32, String),
     Str(String),
     Comma,
     Hyphen,
@@ -55,6 +58,7 @@ impl NumericToken {
         match *self {
             Num(u) => Some(u),
             Roman(u, _) => Some(u),
+            Ordinal(u, _) => Some(u),
             _ => None,
         }
     }
@@ -79,6 +83,14 @@ impl NumericToken {
 /// "2nd-4th"      => Tokens([Affixed("2nd"), Hyphen, Affixed("4th")])
 /// ```
 ///
+/// With a locale ([`NumericValue::from_locale`]), affixes that are the locale's ordinal suffixes
+/// make ordinals instead:
+///
+/// ```text
+/// "2nd-4th"      => Tokens([Ordinal(2, "nd"), Hyphen, Ordinal(4, "th")])
+/// "1re"          => Tokens([Ordinal(1, "re")])
+/// ```
+///
 /// We don't parse:
 ///
 /// ```text
@@ -155,7 +167,7 @@ impl<'a> NumericValue<'a> {
             if remainder.is_empty() {
                 if parsed
                     .iter()
-                    .any(|x| matches!(x, Num(_) | Roman(..) | Affixed(..)))
+                    .any(|x| matches!(x, Num(_) | Roman(..) | Affixed(..) | Ordinal(..)))
                 {
                     NumericValue::Tokens(input.into(), parsed, true)
                 } else {
@@ -184,6 +196,42 @@ impl<'a> NumericValue<'a> {
             NumberLike::Num(n) => NumericValue::num(*n),
         }
     }
+    /// Uses the locale's `and` term, and reads its ordinals, so that `2nd-4th` can be rendered as
+    /// roman numerals or long ordinals.
+    pub fn from_locale(locale: &'a Locale) -> impl Fn(&'a NumberLike) -> NumericValue<'a> + 'a {
+        let and_term = locale.and_term(None).unwrap_or("and");
+        move |like| NumericValue::from_localized(and_term)(like).with_ordinals(locale)
+    }
+    fn with_ordinals(self, locale: &Locale) -> Self {
+        match self {
+            NumericValue::Tokens(verb, ts, is_numeric) => {
+                let ts = ts
+                    .into_iter()
+                    .map(|t| match t {
+                        Affixed(pre, num, suf)
+                            if pre.is_empty() && is_ordinal(num, &suf, locale) =>
+                        {
+                            Ordinal(num, suf)
+                        }
+                        t => t,
+                    })
+                    .collect();
+                NumericValue::Tokens(verb, ts, is_numeric)
+            }
+            s => s,
+        }
+    }
+}
+
+/// Whether `suffix` is an ordinal suffix for `num` in this locale, in any gender. Other affixes,
+/// like the `a` in `12a`, are not.
+fn is_ordinal(num: u32, suffix: &str, locale: &Locale) -> bool {
+    let term = OrdinalTerm::from_number_for_selector(num, false);
+    !suffix.is_empty()
+        && [Gender::Neuter, Gender::Masculine, Gender::Feminine]
+            .iter()
+            .filter_map(|&g| locale.get_ordinal_term(OrdinalTermSelector(term, g)))
+            .any(|ord| ord == suffix)
 }
 
 use nom::{
@@ -529,6 +577,34 @@ fn test_integer_overflow() {
     test_parse!("071124114012001-???", @noparse);
 }
 
+#[test]
+fn test_ordinals() {
+    let locale = Locale::parse(
+        r#"<locale xmlns="http://purl.org/net/xbiblio/csl" version="1.0" xml:lang="en-US">
+            <terms>
+                <term name="ordinal">th</term>
+                <term name="ordinal-02">nd</term>
+            </terms>
+        </locale>"#,
+    )
+    .unwrap();
+    let like = NumberLike::Str("2nd-4th, 12a".into());
+    assert_eq!(
+        NumericValue::from_locale(&locale)(&like),
+        NumericValue::Tokens(
+            "2nd-4th, 12a".into(),
+            vec![
+                Ordinal(2, "nd".into()),
+                Hyphen,
+                Ordinal(4, "th".into()),
+                Comma,
+                afxd("", 12, "a")
+            ],
+            true
+        )
+    );
+}
+
 #[test]
 fn test_page_first() {
     assert_eq!(
@@ -7,7 +7,7 @@
 use self::InlineElement::*;
 use super::links::Link;
 use super::micro_html::MicroNode;
-use super::{FormatCmd, LocalizedQuotes, OutputFormat};
+use super::{FormatCmd, LocalizedQuotes, OutputFormat, Provenance};
 use crate::utils::JoinMany;
 use crate::IngestOptions;
 use csl::{
@@ -25,6 +25,24 @@ use self::html::HtmlWriter;
 mod plain;
 use self::plain::PlainWriter;
 
+mod jats;
+use self::jats::JatsWriter;
+
+mod ooxml;
+use self::ooxml::OoxmlWriter;
+
+mod odf;
+use self::odf::OdfWriter;
+
+#[cfg(feature = "pandoc")]
+mod pandoc;
+#[cfg(feature = "pandoc")]
+use self::pandoc::PandocWriter;
+#[cfg(feature = "pandoc")]
+use pandoc_types::definition::Inline as PandocInline;
+
+mod xml;
+
 mod flip_flop;
 use self::flip_flop::FlipFlopState;
 mod move_punctuation;
@@ -39,10 +57,21 @@ pub enum Markup {
     Html(FormatOptions),
     Rtf(FormatOptions),
     Plain(FormatOptions),
+    /// JATS XML, suitable for the contents of a `<mixed-citation>` element.
+    Jats(FormatOptions),
+    /// WordprocessingML runs (`<w:r>`), to be placed inside a `<w:p>` paragraph.
+    Ooxml(FormatOptions),
+    /// OpenDocument text content, to be placed inside a `<text:p>` paragraph.
+    Odf(FormatOptions),
+    /// A JSON array of Pandoc `Inline`s; see also [`Markup::output_pandoc`]. Input is parsed as
+    /// Pandoc Markdown instead of HTML.
+    #[cfg(feature = "pandoc")]
+    Pandoc(FormatOptions),
 }
 
 /// Controls how the output is formatted.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct FormatOptions {
     /// See CSL 1.1, Appendix VI -- enable or disable making urls clickable. Default is enabled.
     pub link_anchors: bool,
@@ -79,6 +108,8 @@ pub enum InlineElement {
     Text(String),
     Linked(Link),
     Div(DisplayMode, Vec<InlineElement>),
+    /// Content rendered from a particular variable. Only emitted for formats that can use it.
+    Tagged(Provenance, Vec<InlineElement>),
 }
 
 impl InlineElement {}
@@ -96,6 +127,22 @@ impl Markup {
     pub fn plain() -> Self {
         Markup::Plain(FormatOptions::default())
     }
+    pub fn jats() -> Self {
+        Markup::Jats(FormatOptions::default())
+    }
+    pub fn test_jats() -> Self {
+        Markup::Jats(FormatOptions::test_suite())
+    }
+    pub fn ooxml() -> Self {
+        Markup::Ooxml(FormatOptions::default())
+    }
+    pub fn odf() -> Self {
+        Markup::Odf(FormatOptions::default())
+    }
+    #[cfg(feature = "pandoc")]
+    pub fn pandoc() -> Self {
+        Markup::Pandoc(FormatOptions::default())
+    }
 }
 
 impl Default for Markup {
@@ -110,6 +157,39 @@ pub struct MarkupBibMeta {
     markup_pre: String,
     #[serde(rename = "markupPost")]
     markup_post: String,
+    /// For word processor formats, the paragraph properties (OOXML) or automatic styles (ODF) to
+    /// apply to each bibliography entry.
+    #[serde(
+        rename = "paragraphProperties",
+        skip_serializing_if = "Option::is_none"
+    )]
+    paragraph_properties: Option<String>,
+}
+
+/// The bibliography layout options that word processor formats turn into paragraph properties.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BibParagraph {
+    pub hanging_indent: bool,
+    pub line_spacing: u32,
+    pub entry_spacing: u32,
+    pub second_field_align: Option<csl::SecondFieldAlign>,
+    /// The width, in characters, of the widest first field when using second-field-align. Zero if
+    /// unknown.
+    pub max_offset: u32,
+}
+
+impl Markup {
+    /// Like [`OutputFormat::meta`], but also fills in the paragraph properties for formats that
+    /// have them.
+    pub fn meta_with_paragraph(&self, para: BibParagraph) -> MarkupBibMeta {
+        let mut meta = self.meta();
+        meta.paragraph_properties = match self {
+            Markup::Ooxml(_) => Some(ooxml::paragraph_properties(&para)),
+            Markup::Odf(_) => Some(odf::paragraph_properties(&para)),
+            _ => None,
+        };
+        meta
+    }
 }
 
 impl OutputFormat for Markup {
@@ -123,16 +203,22 @@ impl OutputFormat for Markup {
             Markup::Html(_) => ("<div class=\"csl-bib-body\">", "</div>"),
             Markup::Rtf(_) => ("", ""),
             Markup::Plain(_) => ("", ""),
+            Markup::Jats(_) => ("", ""),
+            Markup::Ooxml(_) => ("", ""),
+            Markup::Odf(_) => ("", ""),
+            #[cfg(feature = "pandoc")]
+            Markup::Pandoc(_) => ("", ""),
         };
         MarkupBibMeta {
             markup_pre: pre.into(),
             markup_post: post.into(),
+            paragraph_properties: None,
         }
     }
 
     #[inline]
     fn ingest(&self, input: &str, options: &IngestOptions) -> Self::Build {
-        let mut nodes = MicroNode::parse(input, options);
+        let mut nodes = self.parse_micro(input, options);
         options.apply_text_case_micro(&mut nodes);
         if nodes.is_empty() {
             return Vec::new();
@@ -214,6 +300,14 @@ impl OutputFormat for Markup {
         vec![InlineElement::Linked(link)]
     }
 
+    #[inline]
+    fn with_provenance(&self, b: Self::Build, provenance: Provenance) -> Self::Build {
+        match self {
+            Markup::Jats(_) if !b.is_empty() => vec![InlineElement::Tagged(provenance, b)],
+            _ => b,
+        }
+    }
+
     #[inline]
     fn is_empty(&self, a: &Self::Build) -> bool {
         a.is_empty()
@@ -242,6 +336,11 @@ impl OutputFormat for Markup {
             Markup::Html(options) => HtmlWriter::new(dest, options).stack_preorder(stack),
             Markup::Rtf(options) => PlainWriter::new(dest, options).stack_preorder(stack),
             Markup::Plain(options) => PlainWriter::new(dest, options).stack_preorder(stack),
+            Markup::Jats(options) => JatsWriter::new(dest, options).stack_preorder(stack),
+            Markup::Ooxml(options) => PlainWriter::new(dest, options).stack_preorder(stack),
+            Markup::Odf(options) => PlainWriter::new(dest, options).stack_preorder(stack),
+            #[cfg(feature = "pandoc")]
+            Markup::Pandoc(options) => PlainWriter::new(dest, options).stack_preorder(stack),
         }
     }
 
@@ -251,6 +350,11 @@ impl OutputFormat for Markup {
             Markup::Html(options) => HtmlWriter::new(dest, options).stack_postorder(stack),
             Markup::Rtf(options) => PlainWriter::new(dest, options).stack_postorder(stack),
             Markup::Plain(options) => PlainWriter::new(dest, options).stack_postorder(stack),
+            Markup::Jats(options) => JatsWriter::new(dest, options).stack_postorder(stack),
+            Markup::Ooxml(options) => PlainWriter::new(dest, options).stack_postorder(stack),
+            Markup::Odf(options) => PlainWriter::new(dest, options).stack_postorder(stack),
+            #[cfg(feature = "pandoc")]
+            Markup::Pandoc(options) => PlainWriter::new(dest, options).stack_postorder(stack),
         }
     }
 
@@ -282,6 +386,16 @@ impl OutputFormat for Markup {
 }
 
 impl Markup {
+    fn parse_micro(&self, input: &str, options: &IngestOptions) -> Vec<MicroNode> {
+        #[cfg(feature = "pandoc")]
+        {
+            if let Markup::Pandoc(_) = self {
+                return MicroNode::parse_markdown(input, options);
+            }
+        }
+        MicroNode::parse(input, options)
+    }
+
     fn fmt_vec(
         &self,
         inlines: Vec<InlineElement>,
@@ -294,6 +408,24 @@ impl Markup {
         }
     }
 
+    /// Like [`OutputFormat::output`], but gives the Pandoc inlines themselves rather than the
+    /// JSON that `Markup::Pandoc` outputs. Any other variant is written with the default
+    /// options.
+    #[cfg(feature = "pandoc")]
+    pub fn output_pandoc(
+        &self,
+        intermediate: <Self as OutputFormat>::Build,
+        punctuation_in_quote: bool,
+    ) -> Vec<PandocInline> {
+        let mut flipped = FlipFlopState::default().flip_flop_inlines(&intermediate);
+        move_punctuation(&mut flipped, Some(punctuation_in_quote));
+        let options = match *self {
+            Markup::Pandoc(options) => options,
+            _ => FormatOptions::default(),
+        };
+        PandocWriter::new(options).write(&flipped)
+    }
+
     fn output_with_state(
         &self,
         intermediate: <Self as OutputFormat>::Build,
@@ -313,6 +445,17 @@ impl Markup {
             Markup::Plain(options) => {
                 PlainWriter::new(&mut dest, options).write_inlines(&flipped, false)
             }
+            Markup::Jats(options) => {
+                JatsWriter::new(&mut dest, options).write_inlines(&flipped, false)
+            }
+            Markup::Ooxml(options) => {
+                OoxmlWriter::new(&mut dest, options).write_inlines(&flipped, false)
+            }
+            Markup::Odf(options) => {
+                OdfWriter::new(&mut dest, options).write_inlines(&flipped, false)
+            }
+            #[cfg(feature = "pandoc")]
+            Markup::Pandoc(options) => dest = PandocWriter::new(options).write_json(&flipped),
         }
         dest
     }
@@ -391,6 +534,29 @@ pub trait MarkupWriter {
         self.stack_postorder(&stack);
     }
 
+    /// Writes a `MicroNode::Anchor`, which unlike [`Link`] has its own link text.
+    #[allow(clippy::too_many_arguments)]
+    fn write_anchor(
+        &mut self,
+        a_href: &str,
+        url: &Url,
+        href_close: &str,
+        children: &[MicroNode],
+        a_close: &str,
+        trim_start: bool,
+        options: FormatOptions,
+    ) {
+        if options.link_anchors && allow_url_scheme(url.scheme()) {
+            self.write_raw(a_href);
+            self.write_url(url, true, true);
+            self.write_raw(href_close);
+            self.write_micros(children, trim_start);
+            self.write_raw(a_close);
+        } else {
+            self.write_micros(children, trim_start);
+        }
+    }
+
     fn write_micro(&mut self, micro: &MicroNode, trim_start: bool);
     /// Returned boolean = true if it used the peeked element to move some punctuation inside, and
     /// hence should skip it.
@@ -503,7 +669,7 @@ fn write_url(
     })
 }
 
-fn allow_url_scheme(scheme: &str) -> bool {
+pub(crate) fn allow_url_scheme(scheme: &str) -> bool {
     // see https://security.stackexchange.com/questions/148428/which-url-schemes-are-dangerous-xss-exploitable
     // list from wordpress https://developer.wordpress.org/reference/functions/wp_allowed_protocols/
     [
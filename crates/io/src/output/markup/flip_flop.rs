@@ -125,6 +125,11 @@ fn flip_flop(
             Ok(InlineElement::Div(dm, nodes))
         }
 
+        InlineElement::Tagged(prov, ref inlines) => {
+            let nodes = state.flip_flop_inlines(inlines);
+            Ok(InlineElement::Tagged(prov, nodes))
+        }
+
         InlineElement::Text(ref string) if string.is_empty() => Err(vec![]),
 
         _ => Ok(inline.clone()),
@@ -222,11 +227,25 @@ fn flip_flop_node(node: &MicroNode, state: &FlipFlopState) -> Result<MicroNode,
                 }
             }
         }
-        MicroNode::Text(_) => Ok(node.clone()),
+        MicroNode::Text(_) | MicroNode::Math(_) => Ok(node.clone()),
         MicroNode::NoCase(ref nodes) => {
             let nodes = flip_flop_nodes(nodes, state);
             Ok(MicroNode::NoCase(nodes))
         }
+        MicroNode::Lang(ref lang, ref nodes) => {
+            let nodes = flip_flop_nodes(nodes, state);
+            Ok(MicroNode::Lang(lang.clone(), nodes))
+        }
+        MicroNode::Anchor {
+            ref url,
+            ref children,
+        } => {
+            let children = flip_flop_nodes(children, state);
+            Ok(MicroNode::Anchor {
+                url: url.clone(),
+                children,
+            })
+        }
         MicroNode::NoDecor(ref nodes) => {
             let mut flop = state.clone();
             flop.font_style = FontStyle::Normal;
@@ -80,6 +80,32 @@ impl<'a> MarkupWriter for HtmlWriter<'a> {
             NoDecor(inners) => {
                 self.write_micros(inners, trim_start);
             }
+            Math(tex) => {
+                // The same markup Pandoc uses for MathJax/KaTeX.
+                self.write_raw(r#"<span class="math inline">\("#);
+                self.write_escaped(tex);
+                self.write_raw(r#"\)</span>"#);
+            }
+            Anchor { url, children } => self.write_anchor(
+                r#"<a href=""#,
+                url,
+                r#"">"#,
+                children,
+                "</a>",
+                trim_start,
+                self.options,
+            ),
+            Lang(lang, children) => {
+                let lang = lang.to_string();
+                write!(
+                    self.dest,
+                    r#"<span lang="{}">"#,
+                    escape_html_attribute(&lang)
+                )
+                .unwrap();
+                self.write_micros(children, trim_start);
+                self.write_raw("</span>");
+            }
         }
     }
 
@@ -98,6 +124,9 @@ impl<'a> MarkupWriter for HtmlWriter<'a> {
             Formatted(inlines, formatting) => {
                 self.stack_formats(inlines, *formatting, None);
             }
+            Tagged(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
             Quoted {
                 is_inner,
                 localized,
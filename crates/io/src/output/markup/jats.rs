@@ -0,0 +1,421 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! JATS XML output, intended to be placed inside a `<mixed-citation>` element.
+//!
+//! Formatting maps onto the JATS emphasis elements (`<italic>`, `<bold>`, `<sc>`, `<sup>`,
+//! ...), links become `<ext-link>`, and content that the processor has tagged with a
+//! [`Provenance`] is wrapped in the matching element (`<article-title>`, `<source>`, `<year>`,
+//! `<fpage>`, `<person-group>`, etc). JATS does not allow those elements to appear inside
+//! emphasis or inside each other, so in those positions the tag is dropped and only the
+//! content is written.
+
+use super::xml::escape_xml;
+use super::{FormatOptions, InlineElement, MarkupWriter, MaybeTrimStart};
+use crate::output::links::Link;
+use crate::output::micro_html::MicroNode;
+use crate::output::{FormatCmd, Provenance};
+use crate::String;
+use core::fmt::Write;
+use csl::{DateVariable, NameVariable, NumberVariable, Variable};
+use url::Url;
+
+#[derive(Debug)]
+pub struct JatsWriter<'a> {
+    dest: &'a mut String,
+    options: FormatOptions,
+    /// Set while writing the contents of a semantic element; these cannot nest.
+    in_tag: bool,
+    /// Set while writing the contents of an element that only allows character data.
+    text_only: bool,
+    /// How many formatting commands that wrote an emphasis element are currently open.
+    format_depth: u32,
+}
+
+impl<'a> JatsWriter<'a> {
+    pub fn new(dest: &'a mut String, options: FormatOptions) -> Self {
+        JatsWriter {
+            dest,
+            options,
+            in_tag: false,
+            text_only: false,
+            format_depth: 0,
+        }
+    }
+
+    fn write_tagged(&mut self, tag: JatsTag, inlines: &[InlineElement], trim_start: bool) {
+        match tag {
+            JatsTag::Rich(name) => {
+                write!(self.dest, "<{}>", name).unwrap();
+                self.in_tag = true;
+                self.write_inlines(inlines, trim_start);
+                self.in_tag = false;
+                write!(self.dest, "</{}>", name).unwrap();
+            }
+            JatsTag::Text(name) => {
+                write!(self.dest, "<{}>", name).unwrap();
+                self.write_text_only(inlines, trim_start);
+                write!(self.dest, "</{}>", name).unwrap();
+            }
+            JatsTag::PersonGroup(var) => {
+                write!(
+                    self.dest,
+                    r#"<person-group person-group-type="{}">"#,
+                    var.as_ref()
+                )
+                .unwrap();
+                self.write_text_only(inlines, trim_start);
+                self.dest.push_str("</person-group>");
+            }
+            JatsTag::Pages => {
+                let mut text = String::new();
+                let mut sub = JatsWriter::new(&mut text, self.options);
+                sub.write_text_only(inlines, trim_start);
+                match split_page_range(&text) {
+                    PageRange::Single(first) => {
+                        write!(self.dest, "<fpage>{}</fpage>", first).unwrap();
+                    }
+                    PageRange::Range(first, delim, last) => {
+                        write!(
+                            self.dest,
+                            "<fpage>{}</fpage>{}<lpage>{}</lpage>",
+                            first, delim, last
+                        )
+                        .unwrap();
+                    }
+                    PageRange::Other => self.dest.push_str(&text),
+                }
+            }
+        }
+    }
+
+    fn write_text_only(&mut self, inlines: &[InlineElement], trim_start: bool) {
+        let (in_tag, text_only) = (self.in_tag, self.text_only);
+        self.in_tag = true;
+        self.text_only = true;
+        self.write_inlines(inlines, trim_start);
+        self.in_tag = in_tag;
+        self.text_only = text_only;
+    }
+}
+
+impl<'a> MarkupWriter for JatsWriter<'a> {
+    fn buf(&mut self) -> &mut String {
+        self.dest
+    }
+
+    fn write_escaped(&mut self, text: &str) {
+        write!(self.dest, "{}", escape_xml(text)).unwrap();
+    }
+
+    fn stack_preorder(&mut self, stack: &[FormatCmd]) {
+        if self.text_only {
+            return;
+        }
+        let mut pushed = false;
+        for tag in stack.iter().filter_map(|cmd| cmd.jats_tag()) {
+            self.dest.push('<');
+            self.dest.push_str(tag);
+            self.dest.push('>');
+            pushed = true;
+        }
+        if pushed {
+            self.format_depth += 1;
+        }
+    }
+
+    fn stack_postorder(&mut self, stack: &[FormatCmd]) {
+        if self.text_only {
+            return;
+        }
+        let mut popped = false;
+        for tag in stack.iter().rev().filter_map(|cmd| cmd.jats_tag()) {
+            self.dest.push_str("</");
+            self.dest.push_str(tag);
+            self.dest.push('>');
+            popped = true;
+        }
+        if popped {
+            self.format_depth -= 1;
+        }
+    }
+
+    fn write_micro(&mut self, micro: &MicroNode, trim_start: bool) {
+        use MicroNode::*;
+        match micro {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            Quoted {
+                is_inner,
+                localized,
+                children,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_micros(children, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Formatted(nodes, cmd) => {
+                self.stack_preorder(&[*cmd][..]);
+                self.write_micros(nodes, trim_start);
+                self.stack_postorder(&[*cmd][..]);
+            }
+            NoCase(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            NoDecor(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            Math(tex) if self.text_only => {
+                self.write_escaped(tex);
+            }
+            Math(tex) => {
+                self.write_raw("<inline-formula><tex-math>");
+                self.write_escaped(tex);
+                self.write_raw("</tex-math></inline-formula>");
+            }
+            Anchor { children, .. } if self.text_only => {
+                self.write_micros(children, trim_start);
+            }
+            Anchor { url, children } => self.write_anchor(
+                r#"<ext-link ext-link-type="uri" xlink:href=""#,
+                url,
+                r#"">"#,
+                children,
+                "</ext-link>",
+                trim_start,
+                self.options,
+            ),
+            Lang(_, children) => {
+                self.write_micros(children, trim_start);
+            }
+        }
+    }
+
+    fn write_inline(&mut self, inline: &InlineElement, trim_start: bool) {
+        use super::InlineElement::*;
+        match inline {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            // JATS has no equivalent of the CSL display modes.
+            Div(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
+            Micro(micros) => {
+                self.write_micros(micros, trim_start);
+            }
+            Formatted(inlines, formatting) => {
+                self.stack_formats(inlines, *formatting, None);
+            }
+            Tagged(provenance, inlines) => match JatsTag::for_provenance(*provenance) {
+                Some(tag) if !self.in_tag && self.format_depth == 0 => {
+                    self.write_tagged(tag, inlines, trim_start)
+                }
+                _ => self.write_inlines(inlines, trim_start),
+            },
+            Quoted {
+                is_inner,
+                localized,
+                inlines,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_inlines(inlines, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Linked(link) if self.text_only => {
+                let options = FormatOptions {
+                    link_anchors: false,
+                };
+                self.write_link("", link, "", "", options);
+            }
+            Linked(link) => {
+                let a_href = format!(
+                    r#"<ext-link ext-link-type="{}" xlink:href=""#,
+                    ext_link_type(link)
+                );
+                self.write_link(&a_href, link, r#"">"#, "</ext-link>", self.options);
+            }
+        }
+    }
+
+    fn write_url(&mut self, url: &Url, trailing_slash: bool, in_attr: bool) {
+        super::write_url(
+            self.dest,
+            url,
+            trailing_slash,
+            in_attr,
+            |b, s| write!(b, "{}", escape_xml(s)),
+            |b, s| write!(b, "{}", escape_xml(s)),
+        )
+        .unwrap()
+    }
+}
+
+fn ext_link_type(link: &Link) -> &'static str {
+    let url = match link {
+        Link::Url { url, .. } | Link::Id { url, .. } => url,
+    };
+    match url.host_str() {
+        Some("doi.org") | Some("dx.doi.org") => "doi",
+        _ => "uri",
+    }
+}
+
+/// The JATS element that content from a variable is wrapped in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum JatsTag {
+    /// An element that may contain emphasis.
+    Rich(&'static str),
+    /// An element that may only contain character data.
+    Text(&'static str),
+    PersonGroup(NameVariable),
+    /// `<fpage>` and, if the value is a simple range, `<lpage>`.
+    Pages,
+}
+
+impl JatsTag {
+    fn for_provenance(provenance: Provenance) -> Option<Self> {
+        use JatsTag::*;
+        Some(match provenance {
+            Provenance::Ordinary(var) => match var {
+                Variable::Title => Rich("article-title"),
+                Variable::ContainerTitle
+                | Variable::ContainerTitleShort
+                | Variable::JournalAbbreviation => Rich("source"),
+                Variable::CollectionTitle => Rich("series"),
+                Variable::Publisher => Text("publisher-name"),
+                Variable::PublisherPlace => Text("publisher-loc"),
+                Variable::Event | Variable::EventTitle => Text("conf-name"),
+                Variable::EventPlace => Text("conf-loc"),
+                Variable::ISBN => Text("isbn"),
+                Variable::ISSN => Text("issn"),
+                Variable::Version => Text("version"),
+                _ => return None,
+            },
+            Provenance::Number(var) => match var {
+                NumberVariable::Volume => Text("volume"),
+                NumberVariable::Issue => Text("issue"),
+                NumberVariable::Edition => Text("edition"),
+                NumberVariable::Page => Pages,
+                NumberVariable::PageFirst => Text("fpage"),
+                _ => return None,
+            },
+            Provenance::Names(var) => PersonGroup(var),
+            Provenance::Year(DateVariable::Issued) => Text("year"),
+            Provenance::Year(_) => return None,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum PageRange<'a> {
+    Single(&'a str),
+    Range(&'a str, &'a str, &'a str),
+    /// Lists of pages, ranges with prefixes, etc. These are left untagged.
+    Other,
+}
+
+/// Works on already-escaped text; none of the characters we care about are escaped.
+fn split_page_range(text: &str) -> PageRange {
+    let is_delim = |c: char| c == '-' || c == '\u{2013}';
+    let is_page = |s: &str| {
+        !s.is_empty()
+            && !s
+                .chars()
+                .any(|c| is_delim(c) || c == ',' || c == '&' || c.is_whitespace())
+    };
+    match text.find(is_delim) {
+        None if is_page(text) => PageRange::Single(text),
+        Some(ix) => {
+            let delim_len = text[ix..].chars().next().map_or(1, char::len_utf8);
+            let (first, rest) = text.split_at(ix);
+            let (delim, last) = rest.split_at(delim_len);
+            if is_page(first) && is_page(last) {
+                PageRange::Range(first, delim, last)
+            } else {
+                PageRange::Other
+            }
+        }
+        None => PageRange::Other,
+    }
+}
+
+impl FormatCmd {
+    fn jats_tag(self) -> Option<&'static str> {
+        use super::FormatCmd::*;
+        match self {
+            FontStyleItalic | FontStyleOblique => Some("italic"),
+            FontStyleNormal => Some("roman"),
+            FontWeightBold => Some("bold"),
+            FontVariantSmallCaps => Some("sc"),
+            TextDecorationUnderline => Some("underline"),
+            VerticalAlignmentSuperscript => Some("sup"),
+            VerticalAlignmentSubscript => Some("sub"),
+            // JATS has no way to turn these off, so emit nothing.
+            FontWeightNormal
+            | FontWeightLight
+            | FontVariantNormal
+            | TextDecorationNone
+            | VerticalAlignmentBaseline => None,
+            DisplayBlock | DisplayIndent | DisplayLeftMargin | DisplayRightInline => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_page_range() {
+        assert_eq!(split_page_range("123"), PageRange::Single("123"));
+        assert_eq!(
+            split_page_range("123\u{2013}45"),
+            PageRange::Range("123", "\u{2013}", "45")
+        );
+        assert_eq!(
+            split_page_range("e12-e19"),
+            PageRange::Range("e12", "-", "e19")
+        );
+        assert_eq!(split_page_range("1-3, 5"), PageRange::Other);
+        assert_eq!(split_page_range("-3"), PageRange::Other);
+        assert_eq!(split_page_range(""), PageRange::Other);
+    }
+
+    #[test]
+    fn test_ext_link() {
+        let url = Url::parse("https://doi.org/10.1000/182").unwrap();
+        let link = Link::Url {
+            url,
+            trailing_slash: false,
+        };
+        let mut dest = String::new();
+        JatsWriter::new(&mut dest, FormatOptions::default())
+            .write_inline(&InlineElement::Linked(link), false);
+        assert_eq!(
+            dest.as_str(),
+            r#"<ext-link ext-link-type="doi" xlink:href="https://doi.org/10.1000/182">https://doi.org/10.1000/182</ext-link>"#
+        );
+    }
+
+    #[test]
+    fn test_formatting_without_tag() {
+        use csl::{FontWeight, Formatting};
+        let title = InlineElement::Tagged(
+            Provenance::Ordinary(Variable::Title),
+            vec![InlineElement::Text("Title".into())],
+        );
+        let formatting = Formatting {
+            font_weight: Some(FontWeight::Normal),
+            ..Default::default()
+        };
+        let mut dest = String::new();
+        JatsWriter::new(&mut dest, FormatOptions::default())
+            .write_inline(&InlineElement::Formatted(vec![title], formatting), false);
+        assert_eq!(dest.as_str(), "<article-title>Title</article-title>");
+    }
+}
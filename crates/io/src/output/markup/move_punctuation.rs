@@ -189,13 +189,15 @@ pub fn normalise_text_elements(slice: &mut Vec<InlineElement>) {
                         smash_string_push(s, &s2);
                         pop_tail = true;
                     }
-                    (InlineElement::Formatted(children, _), InlineElement::Text(s2)) => {
+                    (InlineElement::Formatted(children, _), InlineElement::Text(s2))
+                    | (InlineElement::Tagged(_, children), InlineElement::Text(s2)) => {
                         match children.last_mut().and_then(find_string_right_f) {
                             Some(s1) => smash_just_punc(s1, s2),
                             None => {}
                         }
                     }
-                    (InlineElement::Formatted(children, _), InlineElement::Micro(ms2)) => {
+                    (InlineElement::Formatted(children, _), InlineElement::Micro(ms2))
+                    | (InlineElement::Tagged(_, children), InlineElement::Micro(ms2)) => {
                         trace!("formatted, micro");
                         match children.last_mut().and_then(find_string_right_f) {
                             Some(s1) => match ms2.first_mut().and_then(find_string_left_micro) {
@@ -229,6 +231,7 @@ pub fn normalise_text_elements(slice: &mut Vec<InlineElement>) {
         match inl {
             InlineElement::Quoted { inlines, .. }
             | InlineElement::Div(_, inlines)
+            | InlineElement::Tagged(_, inlines)
             | InlineElement::Formatted(inlines, _) => normalise_text_elements(inlines),
             InlineElement::Micro(micros) => normalise_text_elements_micro(micros),
             _ => {}
@@ -262,7 +265,9 @@ pub fn normalise_text_elements_micro(slice: &mut Vec<MicroNode>) {
             MicroNode::Quoted { children, .. }
             | MicroNode::NoDecor(children)
             | MicroNode::NoCase(children)
-            | MicroNode::Formatted(children, _) => normalise_text_elements_micro(children),
+            | MicroNode::Formatted(children, _)
+            | MicroNode::Anchor { children, .. }
+            | MicroNode::Lang(_, children) => normalise_text_elements_micro(children),
             _ => {}
         }
     }
@@ -310,6 +315,7 @@ pub fn move_punctuation(slice: &mut Vec<InlineElement>, punctuation_in_quote: Op
         match inl {
             InlineElement::Quoted { inlines, .. }
             | InlineElement::Div(_, inlines)
+            | InlineElement::Tagged(_, inlines)
             | InlineElement::Formatted(inlines, _) => {
                 move_punctuation(inlines, punctuation_in_quote)
             }
@@ -499,9 +505,10 @@ fn is_punc_space(c: char) -> bool {
 fn find_string_left_micro(m: &mut MicroNode) -> Option<&mut String> {
     match m {
         MicroNode::Text(string) => Some(string),
-        MicroNode::NoDecor(nodes) | MicroNode::NoCase(nodes) | MicroNode::Formatted(nodes, _) => {
-            nodes.first_mut().and_then(find_string_left_micro)
-        }
+        MicroNode::NoDecor(nodes)
+        | MicroNode::NoCase(nodes)
+        | MicroNode::Formatted(nodes, _)
+        | MicroNode::Lang(_, nodes) => nodes.first_mut().and_then(find_string_left_micro),
         _ => None,
     }
 }
@@ -522,7 +529,9 @@ fn find_string_right_f(next: &mut InlineElement) -> Option<&mut String> {
         InlineElement::Micro(ref mut micros) => {
             micros.last_mut().and_then(find_string_right_f_micro)
         }
-        InlineElement::Formatted(children, _) => children.last_mut().and_then(find_string_right_f),
+        InlineElement::Formatted(children, _) | InlineElement::Tagged(_, children) => {
+            children.last_mut().and_then(find_string_right_f)
+        }
         InlineElement::Quoted { .. } => None,
         _ => None,
     }
@@ -531,9 +540,10 @@ fn find_string_right_f(next: &mut InlineElement) -> Option<&mut String> {
 fn find_string_right_f_micro(m: &mut MicroNode) -> Option<&mut String> {
     match m {
         MicroNode::Text(string) => Some(string),
-        MicroNode::NoDecor(nodes) | MicroNode::NoCase(nodes) | MicroNode::Formatted(nodes, _) => {
-            nodes.last_mut().and_then(find_string_right_f_micro)
-        }
+        MicroNode::NoDecor(nodes)
+        | MicroNode::NoCase(nodes)
+        | MicroNode::Formatted(nodes, _)
+        | MicroNode::Lang(_, nodes) => nodes.last_mut().and_then(find_string_right_f_micro),
         _ => None,
     }
 }
@@ -636,7 +646,9 @@ fn find_right_quote_inside<'a>(
         InlineElement::Micro(micros) => micros
             .last_mut()
             .and_then(move |x| find_right_quote_inside_micro(x, next)),
-        InlineElement::Div(_, inlines) | InlineElement::Formatted(inlines, _) => inlines
+        InlineElement::Div(_, inlines)
+        | InlineElement::Tagged(_, inlines)
+        | InlineElement::Formatted(inlines, _) => inlines
             .last_mut()
             .and_then(move |x| find_right_quote_inside(x, next)),
         _ => None,
@@ -671,11 +683,12 @@ fn find_right_quote_inside_micro<'b>(
             Some(RightQuoteInsertionPoint::InsideMicro(children, next))
         }
         // Dive into formatted bits
-        MicroNode::NoDecor(nodes) | MicroNode::NoCase(nodes) | MicroNode::Formatted(nodes, _) => {
-            nodes
-                .last_mut()
-                .and_then(move |x| find_right_quote_inside_micro(x, next))
-        }
+        MicroNode::NoDecor(nodes)
+        | MicroNode::NoCase(nodes)
+        | MicroNode::Formatted(nodes, _)
+        | MicroNode::Lang(_, nodes) => nodes
+            .last_mut()
+            .and_then(move |x| find_right_quote_inside_micro(x, next)),
         _ => None,
     }
 }
@@ -752,6 +765,7 @@ fn last_string(is: &mut [InlineElement]) -> Option<&mut String> {
         InlineElement::Micro(micros) => last_string_micro(micros),
         InlineElement::Quoted { inlines, .. }
         | InlineElement::Div(_, inlines)
+        | InlineElement::Tagged(_, inlines)
         | InlineElement::Formatted(inlines, _) => last_string(inlines),
         InlineElement::Text(string) => Some(string),
         _ => None,
@@ -763,8 +777,11 @@ fn last_string_micro(ms: &mut [MicroNode]) -> Option<&mut String> {
         MicroNode::Quoted { children, .. }
         | MicroNode::NoDecor(children)
         | MicroNode::NoCase(children)
-        | MicroNode::Formatted(children, _) => last_string_micro(children),
+        | MicroNode::Formatted(children, _)
+        | MicroNode::Lang(_, children) => last_string_micro(children),
         MicroNode::Text(string) => Some(string),
+        // Punctuation stays outside links.
+        MicroNode::Math(_) | MicroNode::Anchor { .. } => None,
     })
 }
 
@@ -941,9 +958,9 @@ pub fn ends_with_full_stop(els: &[InlineElement], top: bool) -> bool {
             (txt.is_empty() || txt.trim_end().ends_with(".")) && !is_single_word
         }
         InlineElement::Text(txt) => txt.trim_end().ends_with("."),
-        InlineElement::Formatted(inlines, _) | InlineElement::Quoted { inlines, .. } => {
-            ends_with_full_stop(inlines, false)
-        }
+        InlineElement::Formatted(inlines, _)
+        | InlineElement::Tagged(_, inlines)
+        | InlineElement::Quoted { inlines, .. } => ends_with_full_stop(inlines, false),
         InlineElement::Div(..) | InlineElement::Linked(_) => true,
 
         InlineElement::Micro(micros) => {
@@ -967,7 +984,10 @@ pub fn ends_with_full_stop(els: &[InlineElement], top: bool) -> bool {
                     MicroNode::Formatted(children, _)
                     | MicroNode::NoDecor(children)
                     | MicroNode::NoCase(children)
-                    | MicroNode::Quoted { children, .. } => micro_ends_fs(children, false),
+                    | MicroNode::Quoted { children, .. }
+                    | MicroNode::Anchor { children, .. }
+                    | MicroNode::Lang(_, children) => micro_ends_fs(children, false),
+                    MicroNode::Math(_) => false,
                 }
             }
         }
@@ -0,0 +1,364 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! OpenDocument (ODF) text output, for insertion into a `<text:p>` paragraph.
+//!
+//! Formatting is written as nested `<text:span>` elements referring to named text styles. The
+//! definitions of those styles are part of the bibliography's paragraph properties (see
+//! [`paragraph_properties`]), and must be added to the document's automatic styles.
+
+use super::xml::escape_xml;
+use super::{BibParagraph, FormatOptions, InlineElement, MarkupWriter, MaybeTrimStart};
+use crate::output::micro_html::MicroNode;
+use crate::output::FormatCmd;
+use crate::String;
+use core::fmt::Write;
+use csl::{Formatting, SecondFieldAlign};
+use url::Url;
+
+#[derive(Debug)]
+pub struct OdfWriter<'a> {
+    dest: &'a mut String,
+    options: FormatOptions,
+}
+
+impl<'a> OdfWriter<'a> {
+    pub fn new(dest: &'a mut String, options: FormatOptions) -> Self {
+        OdfWriter { dest, options }
+    }
+}
+
+impl<'a> MarkupWriter for OdfWriter<'a> {
+    fn buf(&mut self) -> &mut String {
+        self.dest
+    }
+
+    /// ODF collapses whitespace, so runs of spaces, tabs and newlines need their own elements.
+    fn write_escaped(&mut self, text: &str) {
+        let mut spaces = 0u32;
+        let flush_spaces = |dest: &mut String, spaces: &mut u32| {
+            match *spaces {
+                0 => {}
+                1 => dest.push(' '),
+                n => write!(dest, r#" <text:s text:c="{}"/>"#, n - 1).unwrap(),
+            }
+            *spaces = 0;
+        };
+        let mut start = 0;
+        for (ix, c) in text.char_indices() {
+            if c == ' ' || c == '\t' || c == '\n' {
+                if start < ix {
+                    write!(self.dest, "{}", escape_xml(&text[start..ix])).unwrap();
+                }
+                start = ix + 1;
+            }
+            match c {
+                ' ' => spaces += 1,
+                '\t' => {
+                    flush_spaces(self.dest, &mut spaces);
+                    self.dest.push_str("<text:tab/>");
+                }
+                '\n' => {
+                    flush_spaces(self.dest, &mut spaces);
+                    self.dest.push_str("<text:line-break/>");
+                }
+                _ => flush_spaces(self.dest, &mut spaces),
+            }
+        }
+        flush_spaces(self.dest, &mut spaces);
+        if start < text.len() {
+            write!(self.dest, "{}", escape_xml(&text[start..])).unwrap();
+        }
+    }
+
+    fn write_url(&mut self, url: &Url, trailing_slash: bool, in_attr: bool) {
+        super::write_url(
+            self.dest,
+            url,
+            trailing_slash,
+            in_attr,
+            |b, s| write!(b, "{}", escape_xml(s)),
+            |b, s| write!(b, "{}", escape_xml(s)),
+        )
+        .unwrap()
+    }
+
+    fn stack_preorder(&mut self, stack: &[FormatCmd]) {
+        for cmd in stack.iter() {
+            match cmd {
+                FormatCmd::DisplayBlock => {
+                    if !self.dest.is_empty() {
+                        self.dest.push_str("<text:line-break/>");
+                    }
+                }
+                FormatCmd::DisplayIndent => {
+                    if !self.dest.is_empty() {
+                        self.dest.push_str("<text:line-break/>");
+                    }
+                    self.dest.push_str("<text:tab/>");
+                }
+                FormatCmd::DisplayLeftMargin | FormatCmd::DisplayRightInline => {}
+                _ => {
+                    if let Some((name, _)) = cmd.odf_style() {
+                        write!(self.dest, r#"<text:span text:style-name="{}">"#, name).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    fn stack_postorder(&mut self, stack: &[FormatCmd]) {
+        for cmd in stack.iter().rev() {
+            match cmd {
+                FormatCmd::DisplayLeftMargin => self.dest.push_str("<text:tab/>"),
+                _ => {
+                    if cmd.odf_style().is_some() {
+                        self.dest.push_str("</text:span>");
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_micro(&mut self, micro: &MicroNode, trim_start: bool) {
+        use MicroNode::*;
+        match micro {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            Quoted {
+                is_inner,
+                localized,
+                children,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_micros(children, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Formatted(nodes, cmd) => {
+                self.stack_preorder(&[*cmd][..]);
+                self.write_micros(nodes, trim_start);
+                self.stack_postorder(&[*cmd][..]);
+            }
+            NoCase(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            NoDecor(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            Math(tex) => {
+                // Inline math needs an embedded formula object, so write the TeX source as text.
+                self.write_escaped(tex);
+            }
+            Anchor { url, children } => self.write_anchor(
+                r#"<text:a xlink:type="simple" xlink:href=""#,
+                url,
+                r#"">"#,
+                children,
+                "</text:a>",
+                trim_start,
+                self.options,
+            ),
+            Lang(_, children) => {
+                self.write_micros(children, trim_start);
+            }
+        }
+    }
+
+    fn write_inline(&mut self, inline: &InlineElement, trim_start: bool) {
+        use super::InlineElement::*;
+        match inline {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            Div(display, inlines) => {
+                self.stack_formats(inlines, Formatting::default(), Some(*display));
+            }
+            Micro(micros) => {
+                self.write_micros(micros, trim_start);
+            }
+            Formatted(inlines, formatting) => {
+                self.stack_formats(inlines, *formatting, None);
+            }
+            Tagged(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
+            Quoted {
+                is_inner,
+                localized,
+                inlines,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_inlines(inlines, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Linked(link) => self.write_link(
+                r#"<text:a xlink:type="simple" xlink:href=""#,
+                link,
+                r#"">"#,
+                "</text:a>",
+                self.options,
+            ),
+        }
+    }
+}
+
+impl FormatCmd {
+    /// The name of the text style for this command, and its `<style:text-properties>`
+    /// attributes.
+    fn odf_style(self) -> Option<(&'static str, &'static str)> {
+        use super::FormatCmd::*;
+        Some(match self {
+            FontStyleItalic => ("csl-italic", r#"fo:font-style="italic""#),
+            FontStyleOblique => ("csl-oblique", r#"fo:font-style="oblique""#),
+            FontStyleNormal => ("csl-font-style-normal", r#"fo:font-style="normal""#),
+            FontWeightBold => ("csl-bold", r#"fo:font-weight="bold""#),
+            FontWeightNormal => ("csl-font-weight-normal", r#"fo:font-weight="normal""#),
+            FontWeightLight => ("csl-light", r#"fo:font-weight="300""#),
+            FontVariantSmallCaps => ("csl-small-caps", r#"fo:font-variant="small-caps""#),
+            FontVariantNormal => ("csl-font-variant-normal", r#"fo:font-variant="normal""#),
+            TextDecorationUnderline => (
+                "csl-underline",
+                r#"style:text-underline-style="solid" style:text-underline-width="auto" style:text-underline-color="font-color""#,
+            ),
+            TextDecorationNone => ("csl-no-decoration", r#"style:text-underline-style="none""#),
+            VerticalAlignmentSuperscript => {
+                ("csl-superscript", r#"style:text-position="super 58%""#)
+            }
+            VerticalAlignmentSubscript => ("csl-subscript", r#"style:text-position="sub 58%""#),
+            VerticalAlignmentBaseline => ("csl-baseline", r#"style:text-position="0% 100%""#),
+            DisplayBlock | DisplayIndent | DisplayLeftMargin | DisplayRightInline => return None,
+        })
+    }
+}
+
+/// Roughly the width of one character at 12pt.
+const CHAR_PT: u32 = 6;
+const INDENT_PT: u32 = 36;
+const LINE_PT: u32 = 12;
+
+/// Style definitions for `<office:automatic-styles>`: a paragraph style named `csl-entry` for each
+/// bibliography entry, followed by the text styles used by [`OdfWriter`].
+pub fn paragraph_properties(para: &BibParagraph) -> String {
+    use super::FormatCmd::*;
+    let mut ps = String::new();
+    let offset = if para.max_offset > 0 {
+        para.max_offset * CHAR_PT
+    } else {
+        INDENT_PT
+    };
+    let (margin_left, text_indent) = match para.second_field_align {
+        Some(SecondFieldAlign::Flush) => (offset, offset),
+        Some(SecondFieldAlign::Margin) => (0, offset),
+        None if para.hanging_indent => (INDENT_PT, INDENT_PT),
+        None => (0, 0),
+    };
+    write!(
+        ps,
+        concat!(
+            r#"<style:style style:name="csl-entry" style:family="paragraph">"#,
+            r#"<style:paragraph-properties fo:margin-left="{}pt" fo:text-indent="-{}pt" "#,
+            r#"fo:line-height="{}%" fo:margin-bottom="{}pt">"#,
+        ),
+        margin_left,
+        text_indent,
+        para.line_spacing.max(1) * 100,
+        para.entry_spacing * LINE_PT,
+    )
+    .unwrap();
+    if para.second_field_align.is_some() {
+        // Tab positions are relative to the left margin of the paragraph
+        write!(
+            ps,
+            r#"<style:tab-stops><style:tab-stop style:position="{}pt"/></style:tab-stops>"#,
+            offset - margin_left
+        )
+        .unwrap();
+    }
+    ps.push_str("</style:paragraph-properties></style:style>");
+    for cmd in &[
+        FontStyleItalic,
+        FontStyleOblique,
+        FontStyleNormal,
+        FontWeightBold,
+        FontWeightNormal,
+        FontWeightLight,
+        FontVariantSmallCaps,
+        FontVariantNormal,
+        TextDecorationUnderline,
+        TextDecorationNone,
+        VerticalAlignmentSuperscript,
+        VerticalAlignmentSubscript,
+        VerticalAlignmentBaseline,
+    ] {
+        if let Some((name, props)) = cmd.odf_style() {
+            write!(
+                ps,
+                r#"<style:style style:name="{}" style:family="text"><style:text-properties {}/></style:style>"#,
+                name, props
+            )
+            .unwrap();
+        }
+    }
+    ps
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(inlines: &[InlineElement]) -> String {
+        let mut dest = String::new();
+        OdfWriter::new(&mut dest, FormatOptions::default()).write_inlines(inlines, false);
+        dest
+    }
+
+    #[test]
+    fn test_spans() {
+        let bold_italic = Formatting {
+            font_style: Some(csl::FontStyle::Italic),
+            font_weight: Some(csl::FontWeight::Bold),
+            ..Default::default()
+        };
+        let inlines = vec![
+            InlineElement::Text("A <B>  ".into()),
+            InlineElement::Formatted(vec![InlineElement::Text("Title".into())], bold_italic),
+        ];
+        assert_eq!(
+            write(&inlines).as_str(),
+            concat!(
+                r#"A &lt;B&gt; <text:s text:c="1"/>"#,
+                r#"<text:span text:style-name="csl-bold"><text:span text:style-name="csl-italic">"#,
+                r#"Title</text:span></text:span>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_math() {
+        let micros = vec![MicroNode::Text("on ".into()), MicroNode::Math("x<2".into())];
+        assert_eq!(write(&[InlineElement::Micro(micros)]).as_str(), "on x&lt;2");
+    }
+
+    #[test]
+    fn test_paragraph_properties() {
+        let para = BibParagraph {
+            hanging_indent: true,
+            line_spacing: 2,
+            entry_spacing: 0,
+            second_field_align: None,
+            max_offset: 0,
+        };
+        let props = paragraph_properties(&para);
+        assert!(props.starts_with(concat!(
+            r#"<style:style style:name="csl-entry" style:family="paragraph">"#,
+            r#"<style:paragraph-properties fo:margin-left="36pt" fo:text-indent="-36pt" "#,
+            r#"fo:line-height="200%" fo:margin-bottom="0pt"></style:paragraph-properties>"#,
+            r#"</style:style>"#,
+        )));
+        assert!(props.contains(r#"<style:style style:name="csl-italic" style:family="text">"#));
+    }
+}
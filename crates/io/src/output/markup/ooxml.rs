@@ -0,0 +1,447 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! WordprocessingML (OOXML) output. Each piece of text becomes a `<w:r>` run carrying its own
+//! run properties, so the output can be inserted directly into a `<w:p>` paragraph.
+//!
+//! Hyperlinks are written as `HYPERLINK` fields, as a `<w:hyperlink>` would need a relationship
+//! in the containing document part.
+
+use super::xml::escape_xml;
+use super::{BibParagraph, FormatOptions, InlineElement, MarkupWriter, MaybeTrimStart};
+use crate::output::links::Link;
+use crate::output::micro_html::MicroNode;
+use crate::output::FormatCmd;
+use crate::String;
+use core::fmt::Write;
+use csl::{Formatting, SecondFieldAlign};
+use url::Url;
+
+#[derive(Debug)]
+pub struct OoxmlWriter<'a> {
+    dest: &'a mut String,
+    options: FormatOptions,
+    /// The formatting commands in effect, innermost last.
+    stack: Vec<FormatCmd>,
+}
+
+impl<'a> OoxmlWriter<'a> {
+    pub fn new(dest: &'a mut String, options: FormatOptions) -> Self {
+        OoxmlWriter {
+            dest,
+            options,
+            stack: Vec::new(),
+        }
+    }
+
+    fn write_run_properties(&mut self) {
+        let mut props = RunProperties::default();
+        for cmd in &self.stack {
+            props.apply(*cmd);
+        }
+        if props == RunProperties::default() {
+            return;
+        }
+        self.dest.push_str("<w:rPr>");
+        if props.bold {
+            self.dest.push_str("<w:b/>");
+        }
+        if props.italic {
+            self.dest.push_str("<w:i/>");
+        }
+        if props.small_caps {
+            self.dest.push_str("<w:smallCaps/>");
+        }
+        if props.underline {
+            self.dest.push_str(r#"<w:u w:val="single"/>"#);
+        }
+        if let Some(valign) = props.vert_align {
+            write!(self.dest, r#"<w:vertAlign w:val="{}"/>"#, valign).unwrap();
+        }
+        self.dest.push_str("</w:rPr>");
+    }
+
+    fn write_special_run(&mut self, content: &str) {
+        self.dest.push_str("<w:r>");
+        self.dest.push_str(content);
+        self.dest.push_str("</w:r>");
+    }
+}
+
+#[derive(Default, Debug, PartialEq, Eq)]
+struct RunProperties {
+    bold: bool,
+    italic: bool,
+    small_caps: bool,
+    underline: bool,
+    vert_align: Option<&'static str>,
+}
+
+impl RunProperties {
+    fn apply(&mut self, cmd: FormatCmd) {
+        use super::FormatCmd::*;
+        match cmd {
+            FontStyleItalic | FontStyleOblique => self.italic = true,
+            FontStyleNormal => self.italic = false,
+            FontWeightBold => self.bold = true,
+            FontWeightNormal | FontWeightLight => self.bold = false,
+            FontVariantSmallCaps => self.small_caps = true,
+            FontVariantNormal => self.small_caps = false,
+            TextDecorationUnderline => self.underline = true,
+            TextDecorationNone => self.underline = false,
+            VerticalAlignmentSuperscript => self.vert_align = Some("superscript"),
+            VerticalAlignmentSubscript => self.vert_align = Some("subscript"),
+            VerticalAlignmentBaseline => self.vert_align = None,
+            DisplayBlock | DisplayIndent | DisplayLeftMargin | DisplayRightInline => {}
+        }
+    }
+}
+
+impl<'a> MarkupWriter for OoxmlWriter<'a> {
+    fn buf(&mut self) -> &mut String {
+        self.dest
+    }
+
+    /// Writes a complete run, with tabs and newlines converted to `<w:tab/>` and `<w:br/>`.
+    fn write_escaped(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.dest.push_str("<w:r>");
+        self.write_run_properties();
+        for (ix, line) in text.split('\n').enumerate() {
+            if ix > 0 {
+                self.dest.push_str("<w:br/>");
+            }
+            for (jx, chunk) in line.split('\t').enumerate() {
+                if jx > 0 {
+                    self.dest.push_str("<w:tab/>");
+                }
+                if !chunk.is_empty() {
+                    write!(
+                        self.dest,
+                        r#"<w:t xml:space="preserve">{}</w:t>"#,
+                        escape_xml(chunk)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        self.dest.push_str("</w:r>");
+    }
+
+    fn write_url(&mut self, url: &Url, trailing_slash: bool, in_attr: bool) {
+        let mut s = String::new();
+        super::write_url(
+            &mut s,
+            url,
+            trailing_slash,
+            in_attr,
+            |b, s| Ok(b.push_str(s)),
+            |b, s| Ok(b.push_str(s)),
+        )
+        .unwrap();
+        if in_attr {
+            write!(self.dest, "{}", escape_xml(&s)).unwrap();
+        } else {
+            self.write_escaped(&s);
+        }
+    }
+
+    /// Overridden so that the visible part of a link is always written as a run.
+    fn write_link(
+        &mut self,
+        a_href: &str,
+        link: &Link,
+        href_close: &str,
+        a_close: &str,
+        options: FormatOptions,
+    ) {
+        let (url, trailing_slash) = match link {
+            Link::Url {
+                url,
+                trailing_slash,
+            } => (url, *trailing_slash),
+            Link::Id { url, .. } => (url, false),
+        };
+        let anchor = options.link_anchors && super::allow_url_scheme(url.scheme());
+        if anchor {
+            self.write_raw(a_href);
+            self.write_url(url, trailing_slash, true);
+            self.write_raw(href_close);
+        }
+        match link {
+            Link::Id { id, .. } if !anchor => self.write_escaped(id),
+            _ => self.write_url(url, trailing_slash, false),
+        }
+        if anchor {
+            self.write_raw(a_close);
+        }
+    }
+
+    fn stack_preorder(&mut self, stack: &[FormatCmd]) {
+        for cmd in stack.iter() {
+            match cmd {
+                FormatCmd::DisplayBlock => {
+                    if !self.dest.is_empty() {
+                        self.write_special_run("<w:br/>");
+                    }
+                }
+                FormatCmd::DisplayIndent => {
+                    if !self.dest.is_empty() {
+                        self.write_special_run("<w:br/>");
+                    }
+                    self.write_special_run("<w:tab/>");
+                }
+                FormatCmd::DisplayLeftMargin | FormatCmd::DisplayRightInline => {}
+                _ => self.stack.push(*cmd),
+            }
+        }
+    }
+
+    fn stack_postorder(&mut self, stack: &[FormatCmd]) {
+        for cmd in stack.iter().rev() {
+            match cmd {
+                FormatCmd::DisplayLeftMargin => self.write_special_run("<w:tab/>"),
+                FormatCmd::DisplayBlock
+                | FormatCmd::DisplayIndent
+                | FormatCmd::DisplayRightInline => {}
+                _ => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+
+    fn write_micro(&mut self, micro: &MicroNode, trim_start: bool) {
+        use MicroNode::*;
+        match micro {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            Quoted {
+                is_inner,
+                localized,
+                children,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_micros(children, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Formatted(nodes, cmd) => {
+                self.stack_preorder(&[*cmd][..]);
+                self.write_micros(nodes, trim_start);
+                self.stack_postorder(&[*cmd][..]);
+            }
+            NoCase(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            NoDecor(inners) => {
+                self.write_micros(inners, trim_start);
+            }
+            Math(tex) => {
+                // No conversion from TeX to Office Math, so write the TeX source as text.
+                self.write_escaped(tex);
+            }
+            Anchor { url, children } => self.write_anchor(
+                concat!(
+                    r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+                    r#"<w:r><w:instrText xml:space="preserve"> HYPERLINK ""#,
+                ),
+                url,
+                concat!(
+                    r#"" </w:instrText></w:r>"#,
+                    r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r>"#,
+                ),
+                children,
+                r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+                trim_start,
+                self.options,
+            ),
+            Lang(_, children) => {
+                self.write_micros(children, trim_start);
+            }
+        }
+    }
+
+    fn write_inline(&mut self, inline: &InlineElement, trim_start: bool) {
+        use super::InlineElement::*;
+        match inline {
+            Text(text) => {
+                self.write_escaped(text.trim_start_if(trim_start));
+            }
+            Div(display, inlines) => {
+                self.stack_formats(inlines, Formatting::default(), Some(*display));
+            }
+            Micro(micros) => {
+                self.write_micros(micros, trim_start);
+            }
+            Formatted(inlines, formatting) => {
+                self.stack_formats(inlines, *formatting, None);
+            }
+            Tagged(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
+            Quoted {
+                is_inner,
+                localized,
+                inlines,
+            } => {
+                self.write_escaped(localized.opening(*is_inner).trim_start_if(trim_start));
+                self.write_inlines(inlines, false);
+                self.write_escaped(localized.closing(*is_inner));
+            }
+            Linked(link) => self.write_link(
+                concat!(
+                    r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+                    r#"<w:r><w:instrText xml:space="preserve"> HYPERLINK ""#,
+                ),
+                link,
+                concat!(
+                    r#"" </w:instrText></w:r>"#,
+                    r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r>"#,
+                ),
+                r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+                self.options,
+            ),
+        }
+    }
+}
+
+/// Roughly the width of one character at 12pt, in twentieths of a point.
+const CHAR_TWIPS: u32 = 120;
+/// Half an inch, the usual hanging indent.
+const INDENT_TWIPS: u32 = 720;
+/// One line at 12pt.
+const LINE_TWIPS: u32 = 240;
+
+/// A `<w:pPr>` element for each bibliography entry's paragraph.
+pub fn paragraph_properties(para: &BibParagraph) -> String {
+    let mut pp = String::new();
+    pp.push_str("<w:pPr>");
+    let offset = if para.max_offset > 0 {
+        para.max_offset * CHAR_TWIPS
+    } else {
+        INDENT_TWIPS
+    };
+    if para.second_field_align.is_some() {
+        write!(
+            pp,
+            r#"<w:tabs><w:tab w:val="left" w:pos="{}"/></w:tabs>"#,
+            offset
+        )
+        .unwrap();
+    }
+    // Schema order: w:tabs, w:spacing, w:ind
+    write!(
+        pp,
+        r#"<w:spacing w:after="{}" w:line="{}" w:lineRule="auto"/>"#,
+        para.entry_spacing * LINE_TWIPS,
+        para.line_spacing.max(1) * LINE_TWIPS,
+    )
+    .unwrap();
+    match para.second_field_align {
+        Some(SecondFieldAlign::Flush) => {
+            write!(pp, r#"<w:ind w:left="{0}" w:hanging="{0}"/>"#, offset).unwrap();
+        }
+        Some(SecondFieldAlign::Margin) => {
+            write!(pp, r#"<w:ind w:left="0" w:hanging="{}"/>"#, offset).unwrap();
+        }
+        None if para.hanging_indent => {
+            write!(pp, r#"<w:ind w:left="{0}" w:hanging="{0}"/>"#, INDENT_TWIPS).unwrap();
+        }
+        None => {}
+    }
+    pp.push_str("</w:pPr>");
+    pp
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write(inlines: &[InlineElement]) -> String {
+        let mut dest = String::new();
+        OoxmlWriter::new(&mut dest, FormatOptions::default()).write_inlines(inlines, false);
+        dest
+    }
+
+    #[test]
+    fn test_runs() {
+        let italic = Formatting {
+            font_style: Some(csl::FontStyle::Italic),
+            ..Default::default()
+        };
+        let sup = Formatting {
+            vertical_alignment: Some(csl::VerticalAlignment::Superscript),
+            ..Default::default()
+        };
+        let inlines = vec![
+            InlineElement::Text("A & B ".into()),
+            InlineElement::Formatted(
+                vec![
+                    InlineElement::Text("Title".into()),
+                    InlineElement::Formatted(vec![InlineElement::Text("2".into())], sup),
+                ],
+                italic,
+            ),
+        ];
+        assert_eq!(
+            write(&inlines).as_str(),
+            concat!(
+                r#"<w:r><w:t xml:space="preserve">A &amp; B </w:t></w:r>"#,
+                r#"<w:r><w:rPr><w:i/></w:rPr><w:t xml:space="preserve">Title</w:t></w:r>"#,
+                r#"<w:r><w:rPr><w:i/><w:vertAlign w:val="superscript"/></w:rPr>"#,
+                r#"<w:t xml:space="preserve">2</w:t></w:r>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_math() {
+        let micros = vec![MicroNode::Text("on ".into()), MicroNode::Math("x<2".into())];
+        assert_eq!(
+            write(&[InlineElement::Micro(micros)]).as_str(),
+            concat!(
+                r#"<w:r><w:t xml:space="preserve">on </w:t></w:r>"#,
+                r#"<w:r><w:t xml:space="preserve">x&lt;2</w:t></w:r>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        let link = Link::Url {
+            url: Url::parse("https://example.com/a?b&c").unwrap(),
+            trailing_slash: false,
+        };
+        assert_eq!(
+            write(&[InlineElement::Linked(link)]).as_str(),
+            concat!(
+                r#"<w:r><w:fldChar w:fldCharType="begin"/></w:r>"#,
+                r#"<w:r><w:instrText xml:space="preserve"> HYPERLINK "https://example.com/a?b&amp;c" </w:instrText></w:r>"#,
+                r#"<w:r><w:fldChar w:fldCharType="separate"/></w:r>"#,
+                r#"<w:r><w:t xml:space="preserve">https://example.com/a?b&amp;c</w:t></w:r>"#,
+                r#"<w:r><w:fldChar w:fldCharType="end"/></w:r>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_paragraph_properties() {
+        let para = BibParagraph {
+            hanging_indent: true,
+            line_spacing: 2,
+            entry_spacing: 1,
+            second_field_align: None,
+            max_offset: 0,
+        };
+        assert_eq!(
+            paragraph_properties(&para).as_str(),
+            r#"<w:pPr><w:spacing w:after="240" w:line="480" w:lineRule="auto"/><w:ind w:left="720" w:hanging="720"/></w:pPr>"#
+        );
+    }
+}
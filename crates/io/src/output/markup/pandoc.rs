@@ -0,0 +1,362 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+use super::{allow_url_scheme, tag_stack, FormatOptions, InlineElement};
+use crate::output::links::Link;
+use crate::output::micro_html::MicroNode;
+use crate::output::FormatCmd;
+use csl::Formatting;
+use pandoc_types::definition::{Attr, Inline, MathType, QuoteType, Target};
+
+/// Unlike the other writers, this one builds a tree of Pandoc [`Inline`]s instead of writing
+/// into a string. [`super::Markup`] serializes the result as Pandoc JSON, except in
+/// [`super::Markup::output_pandoc`].
+#[derive(Debug)]
+pub struct PandocWriter {
+    options: FormatOptions,
+}
+
+impl PandocWriter {
+    pub fn new(options: FormatOptions) -> Self {
+        PandocWriter { options }
+    }
+
+    pub fn write(&self, inlines: &[InlineElement]) -> Vec<Inline> {
+        let mut dest = Vec::new();
+        self.write_inlines(&mut dest, inlines);
+        dest
+    }
+
+    pub fn write_json(&self, inlines: &[InlineElement]) -> crate::String {
+        let dest = self.write(inlines);
+        if dest.is_empty() {
+            return crate::String::new();
+        }
+        serde_json::to_string(&dest)
+            .expect("pandoc inlines are always serializable")
+            .into()
+    }
+
+    fn write_inlines(&self, dest: &mut Vec<Inline>, inlines: &[InlineElement]) {
+        for inline in inlines {
+            self.write_inline(dest, inline);
+        }
+    }
+
+    fn write_inline(&self, dest: &mut Vec<Inline>, inline: &InlineElement) {
+        match inline {
+            InlineElement::Text(text) => push_text(dest, text),
+            InlineElement::Micro(micros) => self.write_micros(dest, micros),
+            InlineElement::Formatted(inlines, formatting) => {
+                let mut children = Vec::new();
+                self.write_inlines(&mut children, inlines);
+                push_all(dest, wrap_stack(&tag_stack(*formatting, None), children));
+            }
+            InlineElement::Div(display, inlines) => {
+                let mut children = Vec::new();
+                self.write_inlines(&mut children, inlines);
+                trim_spaces(&mut children);
+                let stack = tag_stack(Formatting::default(), Some(*display));
+                push_all(dest, wrap_stack(&stack, children));
+            }
+            InlineElement::Tagged(_, inlines) => self.write_inlines(dest, inlines),
+            InlineElement::Quoted {
+                is_inner, inlines, ..
+            } => {
+                let mut children = Vec::new();
+                self.write_inlines(&mut children, inlines);
+                dest.push(Inline::Quoted(quote_type(*is_inner), children));
+            }
+            InlineElement::Linked(link) => self.write_link(dest, link),
+        }
+    }
+
+    fn write_micros(&self, dest: &mut Vec<Inline>, micros: &[MicroNode]) {
+        for micro in micros {
+            self.write_micro(dest, micro);
+        }
+    }
+
+    fn write_micro(&self, dest: &mut Vec<Inline>, micro: &MicroNode) {
+        match micro {
+            MicroNode::Text(text) => push_text(dest, text),
+            MicroNode::Formatted(nodes, cmd) => {
+                let mut children = Vec::new();
+                self.write_micros(&mut children, nodes);
+                push_all(dest, wrap_stack(&[*cmd], children));
+            }
+            MicroNode::Quoted {
+                is_inner, children, ..
+            } => {
+                let mut inner = Vec::new();
+                self.write_micros(&mut inner, children);
+                dest.push(Inline::Quoted(quote_type(*is_inner), inner));
+            }
+            MicroNode::NoCase(nodes) | MicroNode::NoDecor(nodes) => self.write_micros(dest, nodes),
+            MicroNode::Math(tex) => {
+                dest.push(Inline::Math(MathType::InlineMath, tex.to_string()));
+            }
+            MicroNode::Anchor { url, children } => {
+                let mut inner = Vec::new();
+                self.write_micros(&mut inner, children);
+                if self.options.link_anchors && allow_url_scheme(url.scheme()) {
+                    dest.push(Inline::Link(
+                        Attr::null(),
+                        inner,
+                        Target(url.to_string(), String::new()),
+                    ));
+                } else {
+                    push_all(dest, inner);
+                }
+            }
+            MicroNode::Lang(lang, children) => {
+                let mut inner = Vec::new();
+                self.write_micros(&mut inner, children);
+                if !inner.is_empty() {
+                    let attr = Attr(
+                        String::new(),
+                        Vec::new(),
+                        vec![("lang".into(), lang.to_string())],
+                    );
+                    dest.push(Inline::Span(attr, inner));
+                }
+            }
+        }
+    }
+
+    fn write_link(&self, dest: &mut Vec<Inline>, link: &Link) {
+        let (url, text) = match link {
+            Link::Url {
+                url,
+                trailing_slash,
+            } => (url, url_text(url, *trailing_slash)),
+            Link::Id { id, url } => (url, id.to_string()),
+        };
+        if self.options.link_anchors && allow_url_scheme(url.scheme()) {
+            let mut children = Vec::new();
+            push_text(&mut children, &text);
+            dest.push(Inline::Link(
+                Attr::null(),
+                children,
+                Target(url.to_string(), String::new()),
+            ));
+        } else {
+            if !allow_url_scheme(url.scheme()) {
+                warn!(
+                    "refusing to render url anchor for scheme {} on url {}",
+                    url.scheme(),
+                    url
+                );
+            }
+            push_text(dest, &text);
+        }
+    }
+}
+
+/// Same as the other writers: be faithful to the user's trailing slash, or absence thereof.
+fn url_text(url: &url::Url, trailing_slash: bool) -> String {
+    let mut text = url.to_string();
+    if url.has_host() && matches!(url.scheme(), "https" | "http") {
+        if !trailing_slash && text.ends_with('/') {
+            text.pop();
+        }
+    }
+    text
+}
+
+fn quote_type(is_inner: bool) -> QuoteType {
+    // is_inner is true for the outermost quotes; see flip_flop.
+    if is_inner {
+        QuoteType::DoubleQuote
+    } else {
+        QuoteType::SingleQuote
+    }
+}
+
+fn span(class: &str, children: Vec<Inline>) -> Inline {
+    Inline::Span(
+        Attr(String::new(), vec![class.to_owned()], Vec::new()),
+        children,
+    )
+}
+
+/// Class names follow Pandoc's own citeproc, so existing filters and templates apply.
+fn wrap_stack(stack: &[FormatCmd], mut children: Vec<Inline>) -> Vec<Inline> {
+    if children.is_empty() {
+        return children;
+    }
+    for cmd in stack.iter().rev() {
+        let wrapped = match cmd {
+            FormatCmd::FontStyleItalic | FormatCmd::FontStyleOblique => Inline::Emph(children),
+            FormatCmd::FontStyleNormal => span("csl-no-emph", children),
+            FormatCmd::FontWeightBold => Inline::Strong(children),
+            FormatCmd::FontWeightNormal => span("csl-no-strong", children),
+            FormatCmd::FontWeightLight => span("csl-light", children),
+            FormatCmd::FontVariantSmallCaps => Inline::SmallCaps(children),
+            FormatCmd::FontVariantNormal => span("csl-no-smallcaps", children),
+            FormatCmd::TextDecorationUnderline => span("underline", children),
+            FormatCmd::VerticalAlignmentSuperscript => Inline::Superscript(children),
+            FormatCmd::VerticalAlignmentSubscript => Inline::Subscript(children),
+            FormatCmd::DisplayBlock => span("csl-block", children),
+            FormatCmd::DisplayIndent => span("csl-indent", children),
+            FormatCmd::DisplayLeftMargin => span("csl-left-margin", children),
+            FormatCmd::DisplayRightInline => span("csl-right-inline", children),
+            FormatCmd::TextDecorationNone | FormatCmd::VerticalAlignmentBaseline => continue,
+        };
+        children = vec![wrapped];
+    }
+    children
+}
+
+fn push_all(dest: &mut Vec<Inline>, inlines: Vec<Inline>) {
+    for inline in inlines {
+        match inline {
+            Inline::Str(s) => push_text(dest, &s),
+            Inline::Space => push_space(dest),
+            other => dest.push(other),
+        }
+    }
+}
+
+fn push_space(dest: &mut Vec<Inline>) {
+    if dest.last() != Some(&Inline::Space) {
+        dest.push(Inline::Space);
+    }
+}
+
+/// Pandoc represents text as words (`Str`) separated by `Space`. Adjacent text is merged.
+fn push_text(dest: &mut Vec<Inline>, text: &str) {
+    for (ix, word) in text.split(|c: char| c == ' ' || c == '\n').enumerate() {
+        if ix > 0 {
+            push_space(dest);
+        }
+        if word.is_empty() {
+            continue;
+        }
+        if let Some(Inline::Str(last)) = dest.last_mut() {
+            last.push_str(word);
+        } else {
+            dest.push(Inline::Str(word.to_owned()));
+        }
+    }
+}
+
+fn trim_spaces(inlines: &mut Vec<Inline>) {
+    if inlines.first() == Some(&Inline::Space) {
+        inlines.remove(0);
+    }
+    if inlines.last() == Some(&Inline::Space) {
+        inlines.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output::LocalizedQuotes;
+
+    fn s(x: &str) -> Inline {
+        Inline::Str(x.into())
+    }
+
+    fn write(inlines: &[InlineElement]) -> Vec<Inline> {
+        PandocWriter::new(FormatOptions::default()).write(inlines)
+    }
+
+    #[test]
+    fn words_and_spaces() {
+        assert_eq!(
+            write(&[
+                InlineElement::Text("Hello, ".into()),
+                InlineElement::Text("wor".into()),
+                InlineElement::Text("ld".into()),
+            ]),
+            vec![s("Hello,"), Inline::Space, s("world")]
+        );
+    }
+
+    #[test]
+    fn formatting_and_quotes() {
+        let italic = Formatting {
+            font_style: Some(csl::FontStyle::Italic),
+            ..Default::default()
+        };
+        assert_eq!(
+            write(&[
+                InlineElement::Formatted(vec![InlineElement::Text("Title".into())], italic),
+                InlineElement::Text(" ".into()),
+                InlineElement::Quoted {
+                    is_inner: true,
+                    localized: LocalizedQuotes::simple(),
+                    inlines: vec![InlineElement::Micro(vec![
+                        MicroNode::Text("on ".into()),
+                        MicroNode::Math("x^2".into()),
+                    ])],
+                },
+            ]),
+            vec![
+                Inline::Emph(vec![s("Title")]),
+                Inline::Space,
+                Inline::Quoted(
+                    QuoteType::DoubleQuote,
+                    vec![
+                        s("on"),
+                        Inline::Space,
+                        Inline::Math(MathType::InlineMath, "x^2".into())
+                    ]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn anchors_and_lang() {
+        let url = url::Url::parse("https://example.com/a").unwrap();
+        let micros = vec![
+            MicroNode::Anchor {
+                url: url.clone(),
+                children: vec![MicroNode::Text("the docs".into())],
+            },
+            MicroNode::Lang("fr".parse().unwrap(), vec![MicroNode::Text(" et".into())]),
+        ];
+        let lang = Attr(
+            String::new(),
+            Vec::new(),
+            vec![("lang".into(), "fr".into())],
+        );
+        assert_eq!(
+            write(&[InlineElement::Micro(micros.clone())]),
+            vec![
+                Inline::Link(
+                    Attr::null(),
+                    vec![s("the"), Inline::Space, s("docs")],
+                    Target(url.to_string(), String::new())
+                ),
+                Inline::Span(lang.clone(), vec![Inline::Space, s("et")]),
+            ]
+        );
+        assert_eq!(
+            PandocWriter::new(FormatOptions::test_suite()).write(&[InlineElement::Micro(micros)]),
+            vec![
+                s("the"),
+                Inline::Space,
+                s("docs"),
+                Inline::Span(lang, vec![Inline::Space, s("et")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let writer = PandocWriter::new(FormatOptions::default());
+        let json = writer.write_json(&[InlineElement::Text("a b".into())]);
+        assert_eq!(
+            &*json,
+            r#"[{"t":"Str","c":"a"},{"t":"Space"},{"t":"Str","c":"b"}]"#
+        );
+        assert_eq!(&*writer.write_json(&[]), "");
+    }
+}
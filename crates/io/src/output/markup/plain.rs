@@ -74,6 +74,13 @@ impl<'a> MarkupWriter for PlainWriter<'a> {
             NoDecor(inners) => {
                 self.write_micros(inners, trim_start);
             }
+            Math(tex) => {
+                // No inline math markup here, so write the TeX source as text.
+                self.write_escaped(tex);
+            }
+            Anchor { children, .. } | Lang(_, children) => {
+                self.write_micros(children, trim_start);
+            }
         }
     }
 
@@ -92,6 +99,9 @@ impl<'a> MarkupWriter for PlainWriter<'a> {
             Formatted(inlines, formatting) => {
                 self.stack_formats(inlines, *formatting, None);
             }
+            Tagged(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
             Quoted {
                 is_inner,
                 localized,
@@ -90,6 +90,23 @@ impl<'a> MarkupWriter for RtfWriter<'a> {
             NoDecor(inners) => {
                 self.write_micros(inners, trim_start);
             }
+            Math(tex) => {
+                // No inline math markup here, so write the TeX source as text.
+                self.write_escaped(tex);
+            }
+            Anchor { url, children } => self.write_anchor(
+                r#"{\field{\*\fldinst{HYPERLINK ""#,
+                url,
+                r#""}}{\fldrslt "#,
+                children,
+                "}}",
+                trim_start,
+                self.options,
+            ),
+            // RTF's \lang takes Windows language IDs, which we don't have.
+            Lang(_, children) => {
+                self.write_micros(children, trim_start);
+            }
         }
     }
 
@@ -109,6 +126,9 @@ impl<'a> MarkupWriter for RtfWriter<'a> {
             Formatted(inlines, formatting) => {
                 self.stack_formats(inlines, *formatting, None);
             }
+            Tagged(_, inlines) => {
+                self.write_inlines(inlines, trim_start);
+            }
             Quoted {
                 is_inner,
                 localized,
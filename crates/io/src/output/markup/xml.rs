@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Escaping shared by the XML-based writers.
+
+use core::fmt;
+
+use nom::{bytes::complete as nbc, IResult, Parser};
+
+enum Encodable<'a> {
+    Chunk(&'a str),
+    Esc(&'static str),
+}
+
+/// Try to gobble up as many non-escaping characters as possible. Escapes enough to be valid in
+/// both character data and attribute values.
+fn scan_encodable<'a>(remain: &'a str) -> IResult<&'a str, Encodable<'a>> {
+    nbc::take_till1(|x| matches!(x, '<' | '>' | '&' | '"'))
+        .map(Encodable::Chunk)
+        .or(nbc::tag("<").map(|_| Encodable::Esc("&lt;")))
+        .or(nbc::tag(">").map(|_| Encodable::Esc("&gt;")))
+        .or(nbc::tag("&").map(|_| Encodable::Esc("&amp;")))
+        .or(nbc::tag("\"").map(|_| Encodable::Esc("&quot;")))
+        .parse(remain)
+}
+
+pub(super) struct XmlEscaper<'a>(&'a str);
+
+impl fmt::Display for XmlEscaper<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut remain = self.0;
+        while let Ok((rest, chunk)) = scan_encodable(remain) {
+            remain = rest;
+            match chunk {
+                Encodable::Chunk(s) => f.write_str(s)?,
+                Encodable::Esc(s) => f.write_str(s)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn escape_xml(text: &str) -> XmlEscaper {
+    XmlEscaper(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::String;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_escape_xml() {
+        let mut dest = String::new();
+        write!(dest, "{}", escape_xml(r#"a < b & "c" > d"#)).unwrap();
+        assert_eq!(dest.as_str(), "a &lt; b &amp; &quot;c&quot; &gt; d");
+    }
+}
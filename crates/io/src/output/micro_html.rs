@@ -2,6 +2,8 @@ use super::FormatCmd;
 use crate::output::LocalizedQuotes;
 use crate::IngestOptions;
 use crate::String;
+use csl::Lang;
+use url::Url;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum MicroNode {
@@ -19,9 +21,41 @@ pub enum MicroNode {
     NoCase(Vec<MicroNode>),
 
     NoDecor(Vec<MicroNode>),
+
+    /// TeX source for inline math, from `$...$` or `<span class="math">`. Never case-transformed
+    /// or otherwise modified; formats that cannot typeset it write the source back out.
+    Math(String),
+
+    /// A link from `<a href="...">`. Urls with unsafe schemes (like `javascript:`) are dropped
+    /// when parsing, leaving only the children.
+    Anchor {
+        url: Url,
+        children: Vec<MicroNode>,
+    },
+
+    /// Text in a particular language, from `<span lang="...">`. Text-case rules and quotation
+    /// marks inside follow that language instead of the style's.
+    Lang(Lang, Vec<MicroNode>),
 }
 
 impl MicroNode {
+    /// An [`MicroNode::Anchor`], or just the children if `href` is not a url we will link to.
+    pub(crate) fn anchor(children: Vec<MicroNode>, href: &str) -> Vec<MicroNode> {
+        match Url::parse(href) {
+            Ok(url) if super::markup::allow_url_scheme(url.scheme()) => {
+                vec![MicroNode::Anchor { url, children }]
+            }
+            Ok(url) => {
+                warn!("dropping link with scheme {} on url {}", url.scheme(), url);
+                children
+            }
+            Err(e) => {
+                warn!("dropping link with invalid url due to {}: {}", e, href);
+                children
+            }
+        }
+    }
+
     pub(crate) fn take_text(&mut self) -> Option<String> {
         match self {
             MicroNode::Text(ref mut text) => Some(std::mem::replace(text, String::new())),
@@ -50,6 +84,9 @@ impl MicroNode {
 pub trait HtmlReader<T> {
     fn constructor(&self, tag: &Tag, children: Vec<T>) -> Vec<T>;
     fn plain(&self, s: &str) -> Option<Vec<T>>;
+    /// Called instead of `constructor` for tags marked with [`Tag::keep_raw_contents`], with
+    /// their text content unparsed.
+    fn raw(&self, tag: &Tag, text: &str) -> Vec<T>;
     fn filter(&self, tag: &mut Tag) {
         if tag.name == "html" || tag.name == "body" {
             // ignore <html> and <body> tags, but still parse their children
@@ -57,8 +94,15 @@ pub trait HtmlReader<T> {
         } else if tag.name == "i" || tag.name == "b" || tag.name == "sup" || tag.name == "sub" {
             // ok
         } else if tag.name == "span" {
-            tag.allow_attribute("style");
-            tag.allow_attribute("class");
+            tag.allow_attributes(&["style", "class", "lang"]);
+            let is_math = |&(name, value): &(&str, &str)| {
+                name == "class" && value.split_whitespace().any(|class| class == "math")
+            };
+            if tag.attrs.iter().any(is_math) {
+                tag.keep_raw_contents();
+            }
+        } else if tag.name == "a" {
+            tag.allow_attribute("href");
         } else {
             tag.ignore_self();
         }
@@ -95,13 +139,17 @@ impl HtmlReader<String> for PlainHtmlReader {
             "b" => children,
             "sup" => children,
             "sub" => children,
-            "span" => match tag.attrs {
+            "a" => children,
+            "span" => match span_attrs(tag) {
+                (Some(_), rest) if rest.is_empty() => children,
                 // very specific!
-                [("style", "font-variant:small-caps;")]
-                | [("style", "font-variant: small-caps;")]
-                | [("class", "nocase")]
-                | [("class", "nodecor")] => children,
-                _ => return vec![],
+                (_, rest) => match rest[..] {
+                    [("style", "font-variant:small-caps;")]
+                    | [("style", "font-variant: small-caps;")]
+                    | [("class", "nocase")]
+                    | [("class", "nodecor")] => children,
+                    _ => return vec![],
+                },
             },
             _ => return vec![],
         }
@@ -111,6 +159,25 @@ impl HtmlReader<String> for PlainHtmlReader {
         let plain = self.options.plain(s);
         Some(vec![plain.as_ref().into()])
     }
+
+    fn raw(&self, _tag: &Tag, text: &str) -> Vec<String> {
+        vec![text.into()]
+    }
+}
+
+/// Splits a span's `lang` attribute from the rest, so `<span lang="fr" class="nocase">` can be
+/// handled as a `nocase` span wrapped in a language span.
+fn span_attrs<'a>(tag: &Tag<'a>) -> (Option<Lang>, Vec<(&'a str, &'a str)>) {
+    let mut lang = None;
+    let mut rest = Vec::with_capacity(tag.attrs.len());
+    for &(name, value) in tag.attrs {
+        if name == "lang" {
+            lang = value.parse().ok();
+        } else {
+            rest.push((name, value));
+        }
+    }
+    (lang, rest)
 }
 
 struct MicroHtmlReader<'a> {
@@ -124,18 +191,31 @@ impl HtmlReader<MicroNode> for MicroHtmlReader<'_> {
             "b" => MicroNode::Formatted(children, FormatCmd::FontWeightBold),
             "sup" => MicroNode::Formatted(children, FormatCmd::VerticalAlignmentSuperscript),
             "sub" => MicroNode::Formatted(children, FormatCmd::VerticalAlignmentSubscript),
-            "span" => match tag.attrs {
-                // very specific!
-                [("style", "font-variant:small-caps;")]
-                | [("style", "font-variant: small-caps;")] => {
-                    MicroNode::Formatted(children, FormatCmd::FontVariantSmallCaps)
-                }
-                [("class", "nocase")] => MicroNode::NoCase(children),
-                [("class", "nodecor")] => MicroNode::NoDecor(children),
-                // TODO: do we really want <span class="unrecognised">Children</span> to be removed
-                // completely?
-                _ => return vec![],
+            "a" => match tag.attrs {
+                [("href", href)] => return MicroNode::anchor(children, href),
+                _ => return children,
             },
+            "span" => {
+                let (lang, rest) = span_attrs(tag);
+                let inner = match rest[..] {
+                    [] if lang.is_some() => children,
+                    // very specific!
+                    [("style", "font-variant:small-caps;")]
+                    | [("style", "font-variant: small-caps;")] => vec![MicroNode::Formatted(
+                        children,
+                        FormatCmd::FontVariantSmallCaps,
+                    )],
+                    [("class", "nocase")] => vec![MicroNode::NoCase(children)],
+                    [("class", "nodecor")] => vec![MicroNode::NoDecor(children)],
+                    // TODO: do we really want <span class="unrecognised">Children</span> to be
+                    // removed completely?
+                    _ => return vec![],
+                };
+                match lang {
+                    Some(lang) => MicroNode::Lang(lang, inner),
+                    None => return inner,
+                }
+            }
             // TODO: Same here
             _ => return vec![],
         };
@@ -143,8 +223,38 @@ impl HtmlReader<MicroNode> for MicroHtmlReader<'_> {
     }
 
     fn plain<'input>(&self, s: &'input str) -> Option<Vec<MicroNode>> {
-        let plain = self.options.plain(s);
-        Some(super::superscript::parse_sup_sub(&plain))
+        let mut nodes = Vec::new();
+        let push_text = |nodes: &mut Vec<MicroNode>, text: &str| {
+            if text.is_empty() {
+                return;
+            }
+            let plain = self.options.plain(text);
+            nodes.extend(super::superscript::parse_sup_sub(&plain));
+        };
+        // `$...$` inline math, by Pandoc's rules, so "$5 and $6" is still text.
+        let (mut start, mut search) = (0, 0);
+        while let Some(found) = s[search..].find('$') {
+            let ix = search + found;
+            match super::micro_markdown::math_len(&s[ix..]) {
+                Some(len) => {
+                    push_text(&mut nodes, &s[start..ix]);
+                    nodes.push(MicroNode::Math(s[ix + 1..ix + len - 1].into()));
+                    start = ix + len;
+                    search = start;
+                }
+                None => search = ix + 1,
+            }
+        }
+        push_text(&mut nodes, &s[start..]);
+        Some(nodes)
+    }
+
+    fn raw(&self, tag: &Tag, text: &str) -> Vec<MicroNode> {
+        match tag.name {
+            "span" if text.is_empty() => vec![],
+            "span" => vec![MicroNode::Math(text.into())],
+            _ => self.plain(text).unwrap_or_default(),
+        }
     }
 }
 
@@ -169,6 +279,54 @@ fn test_sanitize() {
     );
 }
 
+#[test]
+fn test_extensions() {
+    use FormatCmd::*;
+    use MicroNode::*;
+    let parse = |s: &str| MicroNode::parse(s, &Default::default());
+    assert_eq!(
+        parse(r#"<a href="https://example.com/a">the <i>docs</i></a>"#),
+        &[Anchor {
+            url: Url::parse("https://example.com/a").unwrap(),
+            children: vec![
+                Text("the ".into()),
+                Formatted(vec![Text("docs".into())], FontStyleItalic)
+            ],
+        }]
+    );
+    assert_eq!(
+        parse(r#"<a href="javascript:alert(1)">click</a>"#),
+        &[Text("click".into())]
+    );
+    assert_eq!(
+        parse(r#"<span lang="fr" class="nocase">la vie</span>"#),
+        &[Lang(
+            "fr".parse().unwrap(),
+            vec![NoCase(vec![Text("la vie".into())])]
+        )]
+    );
+    assert_eq!(
+        parse(r#"<span class="math">x^2 &lt; <b>y</b></span>"#),
+        &[Math("x^2 < y".into())]
+    );
+    assert_eq!(
+        parse("costs $5, or $x_1$"),
+        &[Text("costs $5, or ".into()), Math("x_1".into())]
+    );
+    let de: Lang = "de".parse().unwrap();
+    assert_eq!(
+        parse(r#"<span lang="de">"Hallo"</span>"#),
+        &[Lang(
+            de.clone(),
+            vec![Quoted {
+                is_inner: false,
+                localized: LocalizedQuotes::builtin(&de).unwrap(),
+                children: vec![Text("Hallo".into())],
+            }]
+        )]
+    );
+}
+
 // The following is based on the MIT-licensed html_sanitizer crate,
 // and adjusted to work on *inline* HTML, not entire documents.
 //
@@ -242,7 +400,12 @@ impl<'input> TagParser {
                 .cloned()
                 .collect();
 
-            if !tag.ignore_self && !tag.ignore_contents {
+            if tag.raw_contents && !tag.ignore_self {
+                let proposed = Tag::from_name_and_attrs(tag.name, &attrs);
+                let mut text = std::string::String::new();
+                TagParser::collect_text(handle, &mut text);
+                output.extend(callbacks.raw(&proposed, &text));
+            } else if !tag.ignore_self && !tag.ignore_contents {
                 let proposed = Tag::from_name_and_attrs(tag.name, &attrs);
                 let mut children = Vec::new();
                 for child in handle.children.borrow().iter() {
@@ -282,6 +445,15 @@ impl<'input> TagParser {
         output
     }
 
+    fn collect_text(handle: &Handle, dest: &mut std::string::String) {
+        if let NodeData::Text { contents } = &handle.data {
+            dest.push_str(&contents.borrow());
+        }
+        for child in handle.children.borrow().iter() {
+            TagParser::collect_text(child, dest);
+        }
+    }
+
     /// Recursively walk through all the HTML nodes, calling `callback` for each tag.
     fn walk<T, R>(&mut self, callbacks: &R) -> Vec<T>
     where
@@ -304,6 +476,7 @@ pub struct Tag<'a> {
     allowed_attributes: Vec<&'static str>,
     ignore_self: bool,
     ignore_contents: bool,
+    raw_contents: bool,
 }
 
 impl<'a> Tag<'a> {
@@ -315,6 +488,7 @@ impl<'a> Tag<'a> {
             allowed_attributes: Vec::new(),
             ignore_self: false,
             ignore_contents: false,
+            raw_contents: false,
         }
     }
 
@@ -345,4 +519,10 @@ impl<'a> Tag<'a> {
     pub fn ignore_self(&mut self) {
         self.ignore_self = true;
     }
+
+    /// Don't parse this tag's contents. Its text, with any child tags stripped, is passed to
+    /// [`HtmlReader::raw`] instead.
+    pub fn keep_raw_contents(&mut self) {
+        self.raw_contents = true;
+    }
 }
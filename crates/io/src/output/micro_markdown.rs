@@ -0,0 +1,418 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! The Pandoc equivalent of [`micro_html`](super::micro_html): a very small subset of Pandoc
+//! Markdown, enough for the rich text people put in reference fields.
+//!
+//! | Markdown                | MicroNode                         |
+//! | ----------------------- | --------------------------------- |
+//! | `*x*`, `_x_`            | italic                            |
+//! | `**x**`, `__x__`        | bold                              |
+//! | `^x^`, `~x~`            | superscript, subscript            |
+//! | `$\alpha$`              | [`MicroNode::Math`]               |
+//! | `[x]{.nocase}`          | [`MicroNode::NoCase`]             |
+//! | `[x]{.nodecor}`         | [`MicroNode::NoDecor`]            |
+//! | `[x]{.smallcaps}`       | small caps                        |
+//! | `[x]{.underline}`       | underline                         |
+//! | `[x]{lang=fr}`          | [`MicroNode::Lang`]               |
+//! | `[x](https://a.b)`      | [`MicroNode::Anchor`]             |
+//! | `\*`                    | a literal `*`                     |
+//!
+//! Anything else, including an unmatched delimiter, is plain text.
+
+use super::micro_html::MicroNode;
+use super::FormatCmd;
+use crate::IngestOptions;
+
+impl MicroNode {
+    /// Parses `fragment` as Pandoc Markdown, rather than CSL's micro-HTML.
+    pub fn parse_markdown(fragment: &str, options: &IngestOptions) -> Vec<MicroNode> {
+        if options.is_attribute {
+            let plain = options.plain(fragment);
+            return super::superscript::parse_sup_sub(&plain);
+        }
+        let result = parse_inlines(fragment, options);
+        if options.no_parse_quotes {
+            result
+        } else {
+            super::parse_quotes::parse_quotes(result, options)
+        }
+    }
+}
+
+fn parse_inlines(input: &str, options: &IngestOptions) -> Vec<MicroNode> {
+    let mut nodes = Vec::new();
+    let mut text = std::string::String::new();
+    let flush = |text: &mut std::string::String, nodes: &mut Vec<MicroNode>| {
+        if !text.is_empty() {
+            nodes.extend(super::superscript::parse_sup_sub(&options.plain(text)));
+            text.clear();
+        }
+    };
+    let mut ix = 0;
+    while let Some(c) = input[ix..].chars().next() {
+        let rest = &input[ix..];
+        let prev = input[..ix].chars().next_back();
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest[1..]
+                    .chars()
+                    .next()
+                    .filter(|e| e.is_ascii_punctuation())
+                {
+                    text.push(escaped);
+                    ix += 1 + escaped.len_utf8();
+                    continue;
+                }
+            }
+            '$' => {
+                if let Some(len) = math_len(rest) {
+                    flush(&mut text, &mut nodes);
+                    nodes.push(MicroNode::Math(rest[1..len - 1].into()));
+                    ix += len;
+                    continue;
+                }
+            }
+            '*' | '_' => {
+                let strong = rest[1..].starts_with(c);
+                let delim = &rest[..if strong { 2 } else { 1 }];
+                if let Some(inner_len) = emphasis_len(rest, delim, prev) {
+                    flush(&mut text, &mut nodes);
+                    let inner = &rest[delim.len()..delim.len() + inner_len];
+                    let cmd = if strong {
+                        FormatCmd::FontWeightBold
+                    } else {
+                        FormatCmd::FontStyleItalic
+                    };
+                    nodes.push(MicroNode::Formatted(parse_inlines(inner, options), cmd));
+                    ix += inner_len + 2 * delim.len();
+                    continue;
+                }
+            }
+            '^' | '~' if !rest[1..].starts_with(c) => {
+                if let Some(inner_len) = script_len(rest, c) {
+                    flush(&mut text, &mut nodes);
+                    let inner = &rest[1..1 + inner_len];
+                    let cmd = if c == '^' {
+                        FormatCmd::VerticalAlignmentSuperscript
+                    } else {
+                        FormatCmd::VerticalAlignmentSubscript
+                    };
+                    nodes.push(MicroNode::Formatted(parse_inlines(inner, options), cmd));
+                    ix += inner_len + 2;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((inner_len, classes, len)) = bracketed_span(rest) {
+                    flush(&mut text, &mut nodes);
+                    let children = parse_inlines(&rest[1..1 + inner_len], options);
+                    nodes.extend(span_for_classes(children, classes));
+                    ix += len;
+                    continue;
+                }
+                if let Some((inner_len, dest, len)) = inline_link(rest) {
+                    flush(&mut text, &mut nodes);
+                    let children = parse_inlines(&rest[1..1 + inner_len], options);
+                    nodes.extend(MicroNode::anchor(children, dest));
+                    ix += len;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        text.push(c);
+        ix += c.len_utf8();
+    }
+    flush(&mut text, &mut nodes);
+    nodes
+}
+
+/// Byte index of the first unescaped position in `s` where `pred` holds, skipping over math.
+fn find_unescaped(s: &str, mut pred: impl FnMut(&str, usize) -> bool) -> Option<usize> {
+    let mut ix = 0;
+    while let Some(c) = s[ix..].chars().next() {
+        if c == '\\' {
+            ix += 1 + s[ix + 1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if pred(s, ix) {
+            return Some(ix);
+        }
+        if c == '$' {
+            if let Some(len) = math_len(&s[ix..]) {
+                ix += len;
+                continue;
+            }
+        }
+        ix += c.len_utf8();
+    }
+    None
+}
+
+/// Pandoc's rule for `$...$`: no whitespace just inside the dollar signs, and the closing one
+/// cannot be followed by a digit (so `$5 and $6` stays text). Returns the length including both
+/// dollar signs.
+pub(super) fn math_len(s: &str) -> Option<usize> {
+    let body = &s[1..];
+    if body.is_empty() || body.starts_with(char::is_whitespace) || body.starts_with('$') {
+        return None;
+    }
+    let mut ix = 0;
+    while let Some(c) = body[ix..].chars().next() {
+        if c == '\\' {
+            ix += 1 + body[ix + 1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if c == '$' {
+            let before_ok = !body[..ix].ends_with(char::is_whitespace);
+            let after_ok = !body[ix + 1..].starts_with(|c: char| c.is_ascii_digit());
+            return if before_ok && after_ok {
+                Some(ix + 2)
+            } else {
+                None
+            };
+        }
+        ix += c.len_utf8();
+    }
+    None
+}
+
+/// Returns the length of the content between `delim` and its matching closing `delim`.
+fn emphasis_len(s: &str, delim: &str, prev: Option<char>) -> Option<usize> {
+    let body = &s[delim.len()..];
+    // A delimiter followed by whitespace is not an opener.
+    if body.is_empty() || body.starts_with(char::is_whitespace) {
+        return None;
+    }
+    // Pandoc's intraword_underscores: snake_case_words are not emphasis.
+    let underscore = delim.starts_with('_');
+    if underscore && prev.map_or(false, char::is_alphanumeric) {
+        return None;
+    }
+    let single = delim.len() == 1;
+    find_unescaped(body, |body, ix| {
+        let here = &body[ix..];
+        if ix == 0 || !here.starts_with(delim) || body[..ix].ends_with(char::is_whitespace) {
+            return false;
+        }
+        let after = &here[delim.len()..];
+        // `*a **b** c*`: a single delimiter must not close on half of a double one.
+        if single && (after.starts_with(delim) || body[..ix].ends_with(delim)) {
+            return false;
+        }
+        !(underscore && after.starts_with(char::is_alphanumeric))
+    })
+}
+
+/// Pandoc's `^sup^` and `~sub~`, which may not contain unescaped spaces.
+fn script_len(s: &str, delim: char) -> Option<usize> {
+    let body = &s[1..];
+    let end = find_unescaped(body, |body, ix| {
+        body[ix..].starts_with(|c: char| c == delim || c.is_whitespace())
+    })?;
+    if end == 0 || !body[end..].starts_with(delim) {
+        return None;
+    }
+    Some(end)
+}
+
+/// Matches `[inner]{.class .class}`, returning the length of `inner`, the attributes, and the
+/// length of the whole span.
+fn bracketed_span(s: &str) -> Option<(usize, &str, usize)> {
+    let body = &s[1..];
+    let close = closing_bracket(body)?;
+    let attrs = body[close + 1..].strip_prefix('{')?;
+    let attrs_end = attrs.find('}')?;
+    let len = 1 + close + 2 + attrs_end + 1;
+    Some((close, &attrs[..attrs_end], len))
+}
+
+/// Matches `[inner](destination "title")`, returning the length of `inner`, the destination, and
+/// the length of the whole link. The title is ignored.
+fn inline_link(s: &str) -> Option<(usize, &str, usize)> {
+    let body = &s[1..];
+    let close = closing_bracket(body)?;
+    let paren = body[close + 1..].strip_prefix('(')?;
+    let paren_end = paren.find(')')?;
+    let dest = paren[..paren_end].split_whitespace().next()?;
+    let len = 1 + close + 2 + paren_end + 1;
+    Some((close, dest, len))
+}
+
+/// The index of the `]` matching an already-consumed `[`.
+fn closing_bracket(body: &str) -> Option<usize> {
+    let mut depth = 0u32;
+    find_unescaped(body, |body, ix| match body[ix..].chars().next() {
+        Some('[') => {
+            depth += 1;
+            false
+        }
+        Some(']') if depth > 0 => {
+            depth -= 1;
+            false
+        }
+        Some(']') => true,
+        _ => false,
+    })
+}
+
+fn span_for_classes(children: Vec<MicroNode>, attrs: &str) -> Vec<MicroNode> {
+    let mut wrapped = children;
+    let lang = attrs
+        .split_whitespace()
+        .filter_map(|attr| attr.strip_prefix("lang="))
+        .filter_map(|lang| lang.trim_matches('"').parse().ok())
+        .last();
+    for class in attrs
+        .split_whitespace()
+        .filter_map(|attr| attr.strip_prefix('.'))
+    {
+        let single = match class {
+            "nocase" => MicroNode::NoCase(wrapped),
+            "nodecor" => MicroNode::NoDecor(wrapped),
+            "smallcaps" => MicroNode::Formatted(wrapped, FormatCmd::FontVariantSmallCaps),
+            "underline" => MicroNode::Formatted(wrapped, FormatCmd::TextDecorationUnderline),
+            // Other classes mean nothing to us; keep the text.
+            _ => continue,
+        };
+        wrapped = vec![single];
+    }
+    match lang {
+        Some(lang) => vec![MicroNode::Lang(lang, wrapped)],
+        None => wrapped,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use url::Url;
+    use FormatCmd::*;
+    use MicroNode::*;
+
+    fn parse(s: &str) -> Vec<MicroNode> {
+        MicroNode::parse_markdown(
+            s,
+            &IngestOptions {
+                no_parse_quotes: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn text(s: &str) -> MicroNode {
+        Text(s.into())
+    }
+
+    #[test]
+    fn emphasis() {
+        assert_eq!(
+            parse("A *very* **bold** _claim_"),
+            vec![
+                text("A "),
+                Formatted(vec![text("very")], FontStyleItalic),
+                text(" "),
+                Formatted(vec![text("bold")], FontWeightBold),
+                text(" "),
+                Formatted(vec![text("claim")], FontStyleItalic),
+            ]
+        );
+        assert_eq!(
+            parse("*a **b** c*"),
+            vec![Formatted(
+                vec![
+                    text("a "),
+                    Formatted(vec![text("b")], FontWeightBold),
+                    text(" c"),
+                ],
+                FontStyleItalic
+            )]
+        );
+    }
+
+    #[test]
+    fn not_emphasis() {
+        assert_eq!(parse("snake_case_word"), vec![text("snake_case_word")]);
+        assert_eq!(parse("2 * 3 * 4"), vec![text("2 * 3 * 4")]);
+        assert_eq!(parse("a \\*literal\\*"), vec![text("a *literal*")]);
+        assert_eq!(parse("unclosed *star"), vec![text("unclosed *star")]);
+    }
+
+    #[test]
+    fn math() {
+        assert_eq!(
+            parse("The $\\alpha$-helix"),
+            vec![text("The "), Math("\\alpha".into()), text("-helix")]
+        );
+        assert_eq!(parse("from $5 to $6"), vec![text("from $5 to $6")]);
+        // emphasis delimiters inside math are not emphasis
+        assert_eq!(
+            parse("*see $a*b$*"),
+            vec![Formatted(
+                vec![text("see "), Math("a*b".into())],
+                FontStyleItalic
+            )]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        assert_eq!(
+            parse("[DNA]{.nocase} and [Caps]{.smallcaps}"),
+            vec![
+                NoCase(vec![text("DNA")]),
+                text(" and "),
+                Formatted(vec![text("Caps")], FontVariantSmallCaps),
+            ]
+        );
+        assert_eq!(
+            parse("[*x*]{.unknown}"),
+            vec![Formatted(vec![text("x")], FontStyleItalic)]
+        );
+        assert_eq!(parse("[not a span]"), vec![text("[not a span]")]);
+        assert_eq!(
+            parse("[la *vie*]{lang=fr .nocase}"),
+            vec![MicroNode::Lang(
+                "fr".parse().unwrap(),
+                vec![NoCase(vec![
+                    text("la "),
+                    Formatted(vec![text("vie")], FontStyleItalic)
+                ])]
+            )]
+        );
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(
+            parse("see [the *docs*](https://example.com/a \"Title\")."),
+            vec![
+                text("see "),
+                Anchor {
+                    url: Url::parse("https://example.com/a").unwrap(),
+                    children: vec![text("the "), Formatted(vec![text("docs")], FontStyleItalic)],
+                },
+                text("."),
+            ]
+        );
+        assert_eq!(parse("[click](javascript:void)"), vec![text("click")]);
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(
+            parse("H~2~O and 2^10^"),
+            vec![
+                text("H"),
+                Formatted(vec![text("2")], VerticalAlignmentSubscript),
+                text("O and 2"),
+                Formatted(vec![text("10")], VerticalAlignmentSuperscript),
+            ]
+        );
+        assert_eq!(parse("a ^b c^"), vec![text("a ^b c^")]);
+    }
+}
@@ -8,21 +8,19 @@ use crate::String;
 use std::marker::{Send, Sync};
 
 use crate::IngestOptions;
-use csl::{Atom, Locale, QuoteTerm, SimpleTermSelector};
+use csl::{Atom, IsoLang, Lang, Locale, QuoteTerm, SimpleTermSelector};
 
 #[cfg(feature = "markup")]
 pub mod markup;
 
-// #[cfg(feature = "pandoc")]
-// pub mod pandoc;
-
 pub mod links;
 pub mod micro_html;
+mod micro_markdown;
 mod parse_quotes;
 mod puncttable;
 mod superscript;
 
-use csl::{Affixes, DisplayMode, Formatting};
+use csl::{Affixes, DateVariable, DisplayMode, Formatting, NameVariable, NumberVariable, Variable};
 use serde::{de::DeserializeOwned, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -57,6 +55,31 @@ impl LocalizedQuotes {
         }
     }
 
+    /// Quotation marks for text tagged with a language, where there is no locale to ask. These
+    /// match the quote terms in the CSL locales. `punctuation_in_quote` is always false.
+    pub fn builtin(lang: &Lang) -> Option<Self> {
+        let (outer, inner) = match lang {
+            Lang::Iso(IsoLang::English, _) => (("\u{201C}", "\u{201D}"), ("\u{2018}", "\u{2019}")),
+            Lang::Iso(IsoLang::Deutsch, _) => (("\u{201E}", "\u{201C}"), ("\u{201A}", "\u{2018}")),
+            Lang::Iso(IsoLang::French, _) => {
+                (("\u{AB}\u{A0}", "\u{A0}\u{BB}"), ("\u{201C}", "\u{201D}"))
+            }
+            Lang::Iso(IsoLang::Spanish, _) => (("\u{AB}", "\u{BB}"), ("\u{201C}", "\u{201D}")),
+            Lang::Iso(IsoLang::Japanese, _) => (("\u{300C}", "\u{300D}"), ("\u{300E}", "\u{300F}")),
+            Lang::Iso(IsoLang::Other(code), _) => match code.as_str() {
+                "it" | "ita" => (("\u{AB}", "\u{BB}"), ("\u{201C}", "\u{201D}")),
+                "ru" | "rus" => (("\u{AB}", "\u{BB}"), ("\u{201E}", "\u{201C}")),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(LocalizedQuotes {
+            outer: (Atom::from(outer.0), Atom::from(outer.1)),
+            inner: (Atom::from(inner.0), Atom::from(inner.1)),
+            punctuation_in_quote: false,
+        })
+    }
+
     pub fn from_locale(locale: &Locale) -> Self {
         let getter = |qt: QuoteTerm| {
             locale
@@ -104,6 +127,34 @@ pub enum FormatCmd {
     DisplayRightInline,
 }
 
+/// Records which variable a piece of output was rendered from, so that formats with semantic
+/// markup (like JATS) can tag it. Most formats ignore this entirely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Provenance {
+    Ordinary(Variable),
+    Number(NumberVariable),
+    Names(NameVariable),
+    /// The year part of a date variable.
+    Year(DateVariable),
+}
+
+impl Provenance {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Provenance::Ordinary(v) => v.as_ref(),
+            Provenance::Number(v) => v.as_ref(),
+            Provenance::Names(v) => v.as_ref(),
+            Provenance::Year(v) => v.as_ref(),
+        }
+    }
+}
+
+impl Serialize for Provenance {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
 use std::hash::Hash;
 
 use self::links::Link;
@@ -225,6 +276,13 @@ pub trait OutputFormat: Send + Sync + Clone + Default + PartialEq + std::fmt::De
 
     fn link(&self, link: Link) -> Self::Build;
 
+    /// Mark a built node as having been rendered from a particular variable. The default
+    /// implementation discards the information.
+    #[inline]
+    fn with_provenance(&self, b: Self::Build, _provenance: Provenance) -> Self::Build {
+        b
+    }
+
     fn stack_preorder(&self, s: &mut String, stack: &[FormatCmd]);
     fn stack_postorder(&self, s: &mut String, stack: &[FormatCmd]);
     fn tag_stack(&self, formatting: Formatting, display: Option<DisplayMode>) -> Vec<FormatCmd>;
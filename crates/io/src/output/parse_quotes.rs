@@ -277,11 +277,17 @@ fn stamp<'a>(
                     MicroNode::Quoted { children, .. }
                     | MicroNode::NoDecor(children)
                     | MicroNode::NoCase(children)
-                    | MicroNode::Formatted(children, _) => {
+                    | MicroNode::Formatted(children, _)
+                    | MicroNode::Anchor { children, .. } => {
                         let to_parse_owned = mem::replace(children, Vec::new());
                         let parsed = parse_quotes(to_parse_owned, options);
                         *children = parsed;
                     }
+                    MicroNode::Lang(lang, children) => {
+                        let to_parse_owned = mem::replace(children, Vec::new());
+                        let parsed = parse_quotes(to_parse_owned, &options.for_lang(lang));
+                        *children = parsed;
+                    }
                     _ => {}
                 };
                 if let Some(ref mut range) = range_wip {
@@ -354,13 +360,16 @@ fn leaning_text(node: &MicroNode, rightmost: bool) -> Option<&str> {
         MicroNode::Quoted { ref children, .. }
         | MicroNode::NoDecor(ref children)
         | MicroNode::NoCase(ref children)
-        | MicroNode::Formatted(ref children, _) => if rightmost {
+        | MicroNode::Formatted(ref children, _)
+        | MicroNode::Anchor { ref children, .. }
+        | MicroNode::Lang(_, ref children) => if rightmost {
             children.last()
         } else {
             children.first()
         }
         .and_then(|n| leaning_text(n, rightmost)),
         MicroNode::Text(text) => Some(text.as_str()),
+        MicroNode::Math(_) => None,
     }
 }
 
@@ -416,7 +425,10 @@ impl<'a> QuoteMatcher<'a> {
                 MicroNode::Quoted { .. }
                 | MicroNode::NoDecor(_)
                 | MicroNode::NoCase(_)
-                | MicroNode::Formatted(..) => EachSplitter::Index(Some(ix)),
+                | MicroNode::Formatted(..)
+                | MicroNode::Math(_)
+                | MicroNode::Anchor { .. }
+                | MicroNode::Lang(..) => EachSplitter::Index(Some(ix)),
                 MicroNode::Text(ref string) => {
                     let prev = self
                         .original
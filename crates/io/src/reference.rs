@@ -8,11 +8,12 @@
 // std::collections::HashMap does
 extern crate fnv;
 
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 
 use super::date::DateOrRange;
 use super::names::Name;
 use crate::NumberLike;
+use crate::SmartString;
 use csl::{Atom, CslType, DateVariable, Lang, NameVariable, NumberVariable, Variable};
 
 // We're saving copies and allocations by not using String here.
@@ -30,6 +31,13 @@ pub struct Reference {
     pub number: FnvHashMap<NumberVariable, NumberLike>,
     pub name: FnvHashMap<NameVariable, Vec<Name>>,
     pub date: FnvHashMap<DateVariable, DateOrRange>,
+    /// Dates given as `raw` strings that could not be parsed without knowing more month names.
+    /// They are `DateOrRange::Literal`s in `date` until the processor tries its locales' terms.
+    pub raw_dates: FnvHashSet<DateVariable>,
+    /// CSL-JSON keys that are not CSL variables, which were otherwise ignored.
+    pub unknown_variables: Vec<SmartString>,
+    /// Dates that could not be read in full, and why. Whatever could be read is in `date`.
+    pub invalid_dates: FnvHashMap<DateVariable, String>,
 }
 
 impl Reference {
@@ -42,6 +50,9 @@ impl Reference {
             number: FnvHashMap::default(),
             name: FnvHashMap::default(),
             date: FnvHashMap::default(),
+            raw_dates: FnvHashSet::default(),
+            unknown_variables: Vec::new(),
+            invalid_dates: FnvHashMap::default(),
         }
     }
 }
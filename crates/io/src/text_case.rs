@@ -2,9 +2,284 @@ use crate::output::markup::InlineElement;
 use crate::output::micro_html::MicroNode;
 use crate::output::LocalizedQuotes;
 use crate::{lazy, IngestOptions, SmartCow, String};
-use csl::{FontVariant, TextCase, VerticalAlignment};
+use csl::{FontVariant, IsoLang, Lang, TextCase, VerticalAlignment};
+use fnv::FnvHashMap;
+use itertools::Either;
+use std::iter::{once, Once};
+use std::sync::Arc;
 use unic_segment::{GraphemeIndices, WordBoundIndices, Words};
 
+/// The languages that have their own text-case rules. Everything else is [`CaseLanguage::Other`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaseLanguage {
+    English,
+    /// Nouns are capitalised, so no word is ever lowercased.
+    German,
+    /// No capital after a colon in title case. Sentence case also capitalises the noun after a
+    /// leading definite article, as in _Les Misérables_ or _L'Étranger_.
+    French,
+    /// No capital after a colon in title case. In sentence case, `¿` and `¡` open a new sentence,
+    /// so an uppercase `¿QUÉ ES? UNA RESPUESTA` becomes `¿Qué es? Una respuesta`.
+    Spanish,
+    /// Also Azerbaijani. Dotted and dotless i are separate letters.
+    Turkish,
+    Other,
+}
+
+impl Default for CaseLanguage {
+    fn default() -> Self {
+        CaseLanguage::Other
+    }
+}
+
+impl CaseLanguage {
+    pub fn from_lang(lang: &Lang) -> Self {
+        match lang {
+            Lang::Iso(IsoLang::English, _) => CaseLanguage::English,
+            Lang::Iso(IsoLang::Deutsch, _) => CaseLanguage::German,
+            Lang::Iso(IsoLang::French, _) => CaseLanguage::French,
+            Lang::Iso(IsoLang::Spanish, _) => CaseLanguage::Spanish,
+            Lang::Iso(IsoLang::Other(code), _) => match code.as_str() {
+                "tr" | "tur" | "az" | "aze" => CaseLanguage::Turkish,
+                _ => CaseLanguage::Other,
+            },
+            _ => CaseLanguage::Other,
+        }
+    }
+
+    fn capitalizes_after_colon(self) -> bool {
+        match self {
+            CaseLanguage::French | CaseLanguage::Spanish => false,
+            _ => true,
+        }
+    }
+
+    fn lowercase(self, s: &str) -> SmartCow {
+        match self {
+            CaseLanguage::Turkish => lazy::lazy_char_transform(s, turkish_lowercase),
+            _ => lazy::lazy_lowercase(s),
+        }
+    }
+
+    fn lowercase_owned(self, s: String) -> String {
+        match self {
+            CaseLanguage::Turkish => lazy::lazy_char_transform_owned(s, turkish_lowercase),
+            _ => lazy::lazy_lowercase_owned(s),
+        }
+    }
+
+    fn uppercase_owned(self, s: String) -> String {
+        match self {
+            CaseLanguage::Turkish => lazy::lazy_char_transform_owned(s, |c| self.uppercase_char(c)),
+            _ => lazy::lazy_uppercase_owned(s),
+        }
+    }
+
+    fn uppercase_char(self, c: char) -> Either<Once<char>, std::char::ToUppercase> {
+        match (self, c) {
+            (CaseLanguage::Turkish, 'i') => Either::Left(once('\u{130}')),
+            _ => Either::Right(c.to_uppercase()),
+        }
+    }
+}
+
+fn turkish_lowercase(c: char) -> Either<Once<char>, std::char::ToLowercase> {
+    match c {
+        'I' => Either::Left(once('\u{131}')),
+        '\u{130}' => Either::Left(once('i')),
+        _ => Either::Right(c.to_lowercase()),
+    }
+}
+
+/// A list of words that title case leaves in lowercase, unless they begin or end the title, or
+/// follow a colon.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stopwords {
+    /// Lowercase, longest first so that multi-word stopwords are preferred.
+    words: Vec<String>,
+}
+
+impl Stopwords {
+    /// Words may contain spaces (`according to`). A word ending in an apostrophe (`l'`) matches
+    /// the start of a word, for elision.
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        let mut words: Vec<String> = words
+            .into_iter()
+            .map(|w| lazy::lazy_lowercase(w.as_ref().trim()).as_ref().into())
+            .filter(|w: &String| !w.is_empty())
+            .collect();
+        words.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+        words.dedup();
+        Stopwords { words }
+    }
+
+    /// Lowercase, longest first.
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(|w| w.as_str())
+    }
+
+    /// Built-in lists of articles, conjunctions and short prepositions. English is not included,
+    /// as it uses the citeproc-js stopword list unless configured otherwise.
+    pub fn builtin(language: CaseLanguage) -> Option<Self> {
+        let words: &[&str] = match language {
+            CaseLanguage::German => &[
+                "der", "die", "das", "den", "dem", "des", "ein", "eine", "einer", "eines", "einem",
+                "einen", "und", "oder", "aber", "von", "vom", "zu", "zum", "zur", "im", "in", "am",
+                "an", "auf", "aus", "bei", "mit", "nach", "über", "unter", "für", "gegen", "ohne",
+                "um", "durch", "als", "wie",
+            ],
+            CaseLanguage::French => &[
+                "le",
+                "la",
+                "les",
+                "l'",
+                "l\u{2019}",
+                "un",
+                "une",
+                "des",
+                "du",
+                "de",
+                "d'",
+                "d\u{2019}",
+                "et",
+                "ou",
+                "à",
+                "au",
+                "aux",
+                "en",
+                "par",
+                "pour",
+                "sur",
+                "dans",
+                "avec",
+                "sans",
+                "sous",
+                "chez",
+                "entre",
+            ],
+            CaseLanguage::Spanish => &[
+                "el", "la", "los", "las", "lo", "un", "una", "unos", "unas", "y", "e", "o", "u",
+                "de", "del", "a", "al", "en", "con", "por", "para", "sin", "sobre", "entre", "que",
+            ],
+            _ => return None,
+        };
+        Some(Stopwords::new(words))
+    }
+
+    /// Returns the length of the matched stopword at the start of `word_and_rest`.
+    fn find(&self, word_and_rest: &str) -> Option<usize> {
+        self.words.iter().find_map(|word| {
+            let mut len = 0;
+            let mut input = word_and_rest.chars();
+            for expect in word.chars() {
+                let c = input.next()?;
+                if !c.to_lowercase().eq(once(expect))
+                    && !(is_apostrophe(c) && is_apostrophe(expect))
+                {
+                    return None;
+                }
+                len += c.len_utf8();
+            }
+            let elided = word.chars().last().map_or(false, is_apostrophe);
+            match input.next() {
+                _ if elided => Some(len),
+                None => Some(len),
+                Some(c) if c.is_whitespace() => Some(len),
+                Some(_) => None,
+            }
+        })
+    }
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+fn is_french_article(word: &str) -> bool {
+    ["le", "la", "les"]
+        .iter()
+        .any(|article| word.eq_ignore_ascii_case(article))
+}
+
+/// The length of an elided article at the start of a French word, like the `l'` in `l'Étranger`.
+fn french_elided_article(word: &str) -> Option<usize> {
+    let mut chars = word.char_indices();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some((_, l)), Some((_, c)), Some((noun_ix, _)))
+            if (l == 'l' || l == 'L') && is_apostrophe(c) =>
+        {
+            Some(noun_ix)
+        }
+        _ => None,
+    }
+}
+
+/// The language-specific rules used by text-case transforms.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CaseRules {
+    pub language: CaseLanguage,
+    /// Replaces the built-in English stopwords. Title case is only applied to non-English text if
+    /// this is set.
+    pub stopwords: Option<Arc<Stopwords>>,
+}
+
+impl CaseRules {
+    pub fn english() -> Self {
+        CaseRules {
+            language: CaseLanguage::English,
+            stopwords: None,
+        }
+    }
+
+    fn applies_title_case(&self) -> bool {
+        self.language == CaseLanguage::English || self.stopwords.is_some()
+    }
+
+    fn find_stopword(&self, word_and_rest: &str) -> Option<usize> {
+        match &self.stopwords {
+            Some(stopwords) => stopwords.find(word_and_rest),
+            None if self.language == CaseLanguage::English => is_stopword(word_and_rest),
+            None => None,
+        }
+    }
+}
+
+/// Title-case stopwords configured for particular languages.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StopwordTable {
+    langs: FnvHashMap<Lang, Arc<Stopwords>>,
+}
+
+impl StopwordTable {
+    /// Stopwords for a language without a country (`de`) also apply to its dialects (`de-AT`).
+    pub fn insert(&mut self, lang: Lang, stopwords: Stopwords) {
+        self.langs.insert(lang, Arc::new(stopwords));
+    }
+
+    pub fn remove(&mut self, lang: &Lang) -> bool {
+        self.langs.remove(lang).is_some()
+    }
+
+    /// The configured languages, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Lang, &Stopwords)> {
+        self.langs
+            .iter()
+            .map(|(lang, stopwords)| (lang, &**stopwords))
+    }
+
+    /// The rules for text in `lang`.
+    pub fn case_rules(&self, lang: &Lang) -> CaseRules {
+        let stopwords = self.langs.get(lang).or_else(|| match lang {
+            Lang::Iso(iso, Some(_)) => self.langs.get(&Lang::Iso(iso.clone(), None)),
+            _ => None,
+        });
+        CaseRules {
+            language: CaseLanguage::from_lang(lang),
+            stopwords: stopwords.cloned(),
+        }
+    }
+}
+
 // from the unic_segment example code
 fn has_alphanumeric(s: &&str) -> bool {
     is_word(*s)
@@ -37,8 +312,8 @@ where
     }
 }
 
-fn transform_uppercase_first(word: &str) -> SmartCow {
-    transform_first_char_of_word(word, |c| c.to_uppercase())
+fn transform_uppercase_first(word: &str, lang: CaseLanguage) -> SmartCow {
+    transform_first_char_of_word(word, |c| lang.uppercase_char(c))
 }
 
 // use phf::phf_set;
@@ -220,14 +495,14 @@ fn is_stopword(word_and_rest: &str) -> Option<usize> {
     stopword_regex().find(word_and_rest).map(|mat| mat.end())
 }
 
-fn upper_word_to_title(word: &str) -> Option<String> {
+fn upper_word_to_title(word: &str, lang: CaseLanguage) -> Option<String> {
     let mut upper_gs = GraphemeIndices::new(word);
     if let Some((_, first_g)) = upper_gs.next() {
         let mut ret = String::new();
         ret.push_str(first_g);
         if let Some((rest_ix, _)) = upper_gs.next() {
             let rest = &word[rest_ix..];
-            let rest_lower = lazy::lazy_lowercase(rest);
+            let rest_lower = lang.lowercase(rest);
             ret.push_str(rest_lower.as_ref());
         }
         return Some(ret);
@@ -235,23 +510,62 @@ fn upper_word_to_title(word: &str) -> Option<String> {
     None
 }
 
-fn transform_sentence_case(s: String, seen_one: bool, is_last: bool, is_uppercase: bool) -> String {
-    if is_uppercase {
-        transform_each_word(
+fn sentence_first_word(word: &str, is_uppercase: bool, lang: CaseLanguage) -> SmartCow {
+    let first = if is_uppercase {
+        upper_word_to_title(word, lang).map_or(SmartCow::Borrowed(word), SmartCow::Owned)
+    } else {
+        transform_uppercase_first(word, lang)
+    };
+    let first_str: &str = first.as_ref();
+    match french_elided_article(first_str) {
+        Some(noun_ix) if lang == CaseLanguage::French => {
+            let mut ret = String::new();
+            ret.push_str(&first_str[..noun_ix]);
+            ret.push_str(&transform_uppercase_first(&first_str[noun_ix..], lang));
+            SmartCow::Owned(ret)
+        }
+        _ => first,
+    }
+}
+
+fn transform_sentence_case(
+    s: String,
+    seen_one: bool,
+    is_last: bool,
+    is_uppercase: bool,
+    lang: CaseLanguage,
+) -> String {
+    match lang {
+        // German nouns keep their capitals, and we can't tell which words are nouns.
+        CaseLanguage::German => {
+            transform_first_word(s, |word| transform_uppercase_first(word, lang))
+        }
+        _ if is_uppercase => transform_each_word(
             &s,
             seen_one,
             is_last,
+            lang,
             |word, _word_and_rest, is_first, _no_stop| {
                 if is_first {
-                    if let Some(upper) = upper_word_to_title(word) {
-                        return (SmartCow::Owned(upper), None);
-                    }
+                    return (sentence_first_word(word, true, lang), None);
                 }
-                (lazy::lazy_lowercase(word), None)
+                (lang.lowercase(word), None)
             },
-        )
-    } else {
-        transform_first_word(s, transform_uppercase_first)
+        ),
+        // These can capitalise more than one word, but otherwise leave the rest alone.
+        CaseLanguage::French | CaseLanguage::Spanish => transform_each_word(
+            &s,
+            seen_one,
+            is_last,
+            lang,
+            |word, _word_and_rest, is_first, _no_stop| {
+                if is_first {
+                    return (sentence_first_word(word, false, lang), None);
+                }
+                (SmartCow::Borrowed(word), None)
+            },
+        ),
+        _ => transform_first_word(s, |word| transform_uppercase_first(word, lang)),
     }
 }
 
@@ -260,9 +574,11 @@ fn title_case_word<'a>(
     word_and_rest: &'a str,
     entire_is_uppercase: bool,
     no_stopword: bool,
+    rules: &CaseRules,
 ) -> (SmartCow<'a>, Option<usize>) {
+    let lang = rules.language;
     if !no_stopword {
-        if let Some(mut match_len) = is_stopword(word_and_rest) {
+        if let Some(mut match_len) = rules.find_stopword(word_and_rest) {
             // drop the trailing whitespace
             let matched = &word_and_rest[..match_len];
             let last_char = matched.chars().rev().nth(0).map_or(0, |c| {
@@ -274,39 +590,50 @@ fn title_case_word<'a>(
             });
             match_len = match_len - last_char;
             let word_rest_upto_match = &word_and_rest[..match_len];
-            let lowered = lazy::lazy_lowercase(word_rest_upto_match);
+            if lang == CaseLanguage::German {
+                return (SmartCow::Borrowed(word_rest_upto_match), Some(match_len));
+            }
+            let lowered = lang.lowercase(word_rest_upto_match);
             return (lowered, Some(match_len));
         }
     }
-    if !word.chars().any(|c| c.is_ascii_alphabetic() || c == '.') {
+    let is_letter = |c: char| match lang {
+        CaseLanguage::English => c.is_ascii_alphabetic(),
+        _ => c.is_alphabetic(),
+    };
+    if !word.chars().any(|c| is_letter(c) || c == '.') {
         // Entirely non-English
         // e.g. "β" in "β-Carotine"
         // Full stop is so A.D. doesn't become a.D.
         return (SmartCow::Borrowed(word), None);
     }
-    if entire_is_uppercase {
-        if let Some(ret) = upper_word_to_title(word) {
+    if entire_is_uppercase && lang != CaseLanguage::German {
+        if let Some(ret) = upper_word_to_title(word, lang) {
             return (SmartCow::Owned(ret), None);
         }
     }
-    (
-        transform_first_char_of_word(word, |c| c.to_uppercase()),
-        None,
-    )
+    (transform_uppercase_first(word, lang), None)
 }
 
-fn transform_title_case(s: &str, seen_one: bool, is_last: bool) -> String {
+fn transform_title_case(s: &str, seen_one: bool, is_last: bool, rules: &CaseRules) -> String {
     transform_each_word(
         &s,
         seen_one,
         is_last,
+        rules.language,
         |word, word_and_rest, _is_first, no_stop| {
-            title_case_word(word, word_and_rest, false, no_stop)
+            title_case_word(word, word_and_rest, false, no_stop, rules)
         },
     )
 }
 
-fn transform_each_word<'a, F>(mut s: &'a str, seen_one: bool, is_last: bool, transform: F) -> String
+fn transform_each_word<'a, F>(
+    mut s: &'a str,
+    seen_one: bool,
+    is_last: bool,
+    lang: CaseLanguage,
+    transform: F,
+) -> String
 where
     F: Fn(&'a str, &'a str, bool, bool) -> (SmartCow<'a>, Option<usize>),
 {
@@ -328,7 +655,7 @@ where
                });
         if wordy {
             let before = &s[..ix].chars().rev().filter(|c| !c.is_whitespace()).nth(0);
-            let follows_colon = *before == Some(':')
+            let follows_colon = (*before == Some(':') && lang.capitalizes_after_colon())
                 || *before == Some('?')
                 || *before == Some('!')
                 || *before == Some('.');
@@ -346,7 +673,15 @@ where
         } else {
             acc.push_str(substr);
         }
-        is_first = false;
+        is_first = match lang {
+            // A Spanish question or exclamation is a sentence of its own, opened by ¿ or ¡ and
+            // closed by ? or !.
+            CaseLanguage::Spanish if !wordy => is_first || substr.contains(&['?', '!'][..]),
+            // Les Misérables
+            CaseLanguage::French if wordy => is_first && is_french_article(substr),
+            CaseLanguage::French => is_first,
+            _ => false,
+        };
     }
     acc
 }
@@ -377,6 +712,26 @@ fn string_contains_word(s: &str) -> bool {
 }
 
 impl IngestOptions {
+    /// The options for text inside `<span lang="...">`. Unless it is in the language these
+    /// options are already for, it gets that language's text-case rules, without any configured
+    /// stopwords (so no title case), and its quotation marks if we know them.
+    pub(crate) fn for_lang(&self, lang: &Lang) -> IngestOptions {
+        let language = CaseLanguage::from_lang(lang);
+        if language != CaseLanguage::Other && language == self.case_rules.language {
+            return self.clone();
+        }
+        let mut quotes = LocalizedQuotes::builtin(lang).unwrap_or_else(|| self.quotes.clone());
+        quotes.punctuation_in_quote = self.quotes.punctuation_in_quote;
+        IngestOptions {
+            case_rules: CaseRules {
+                language,
+                stopwords: None,
+            },
+            quotes,
+            ..self.clone()
+        }
+    }
+
     pub fn plain<'s>(&self, s: &'s str) -> SmartCow<'s> {
         if self.replace_hyphens && self.strip_periods {
             let cow = lazy::lazy_replace_char(s, '-', "\u{2013}");
@@ -418,7 +773,8 @@ impl IngestOptions {
                 InlineElement::Quoted {
                     inlines: content, ..
                 }
-                | InlineElement::Div(_, content) => {
+                | InlineElement::Div(_, content)
+                | InlineElement::Tagged(_, content) => {
                     seen_one = self.apply_text_case_inner(content.as_mut(), seen_one, is_uppercase)
                         || seen_one;
                 }
@@ -473,11 +829,25 @@ impl IngestOptions {
                 | MicroNode::NoCase(children) => {
                     seen_one = seen_one || self.contains_word_micro(children.as_ref());
                 }
-                MicroNode::Formatted(children, _) | MicroNode::Quoted { children, .. } => {
+                MicroNode::Formatted(children, _)
+                | MicroNode::Quoted { children, .. }
+                | MicroNode::Anchor { children, .. } => {
                     seen_one =
                         self.apply_text_case_micro_inner(children.as_mut(), seen_one, is_uppercase)
                             || seen_one;
                 }
+                MicroNode::Lang(lang, children) => {
+                    let options = self.for_lang(lang);
+                    seen_one = options.apply_text_case_micro_inner(
+                        children.as_mut(),
+                        seen_one,
+                        is_uppercase,
+                    ) || seen_one;
+                }
+                // Left alone, but counts as a word for capitalize-first and sentence case.
+                MicroNode::Math(_) => {
+                    seen_one = true;
+                }
             }
         }
         seen_one
@@ -501,18 +871,23 @@ impl IngestOptions {
         is_last: bool,
         entire_is_uppercase: bool,
     ) -> String {
+        let lang = self.case_rules.language;
         match self.text_case {
-            TextCase::Lowercase => lazy::lazy_lowercase_owned(s),
-            TextCase::Uppercase => lazy::lazy_uppercase_owned(s),
-            TextCase::CapitalizeFirst => transform_first_word(s, transform_uppercase_first),
+            TextCase::Lowercase => lang.lowercase_owned(s),
+            TextCase::Uppercase => lang.uppercase_owned(s),
+            TextCase::CapitalizeFirst => {
+                transform_first_word(s, |word| transform_uppercase_first(word, lang))
+            }
             TextCase::Sentence if !seen_one => {
-                transform_sentence_case(s, seen_one, is_last, entire_is_uppercase)
+                transform_sentence_case(s, seen_one, is_last, entire_is_uppercase, lang)
             }
             // Fallback is nothing
-            TextCase::Title if self.is_english => transform_title_case(&s, seen_one, is_last),
+            TextCase::Title if self.case_rules.applies_title_case() => {
+                transform_title_case(&s, seen_one, is_last, &self.case_rules)
+            }
             TextCase::CapitalizeAll => {
-                transform_each_word(&s, seen_one, is_last, |word, _, _, _| {
-                    (transform_uppercase_first(word), None)
+                transform_each_word(&s, seen_one, is_last, lang, |word, _, _, _| {
+                    (transform_uppercase_first(word, lang), None)
                 })
             }
             TextCase::None | _ => s,
@@ -536,6 +911,7 @@ fn any_inlines<F: Fn(&str) -> bool + Copy>(f: F, invert: bool, inlines: &[Inline
         InlineElement::Micro(micros) => any_micros(f, invert, micros.as_ref()),
         InlineElement::Quoted { inlines, .. }
         | InlineElement::Div(_, inlines)
+        | InlineElement::Tagged(_, inlines)
         | InlineElement::Formatted(inlines, _) => any_inlines(f, invert, inlines.as_ref()) ^ invert,
         InlineElement::Linked(_) => false,
     }) ^ invert
@@ -547,7 +923,11 @@ fn any_micros<F: Fn(&str) -> bool + Copy>(f: F, invert: bool, micros: &[MicroNod
         MicroNode::Formatted(children, _)
         | MicroNode::Quoted { children, .. }
         | MicroNode::NoDecor(children)
-        | MicroNode::NoCase(children) => any_micros(f, invert, children) ^ invert,
+        | MicroNode::NoCase(children)
+        | MicroNode::Anchor { children, .. }
+        | MicroNode::Lang(_, children) => any_micros(f, invert, children) ^ invert,
+        // Math is a word, but has no case.
+        MicroNode::Math(_) => !invert,
     }) ^ invert
 }
 
@@ -563,3 +943,83 @@ fn test_any_micros() {
     assert_eq!(upper("HELLOSUPERSCRIPT"), true);
     assert_eq!(upper("HELLO, <sup>SUPERSCRIPT</sup>"), true);
 }
+
+#[test]
+fn test_stopwords_find() {
+    let stopwords = Stopwords::builtin(CaseLanguage::French).unwrap();
+    assert_eq!(stopwords.find("de la France"), Some(2));
+    assert_eq!(stopwords.find("l’Égypte ancienne"), Some("l’".len()));
+    assert_eq!(stopwords.find("Les misérables"), Some(3));
+    assert_eq!(stopwords.find("dessin"), None);
+}
+
+#[test]
+fn test_language_case_rules() {
+    fn transform(text_case: TextCase, language: CaseLanguage, s: &str) -> std::string::String {
+        let options = IngestOptions {
+            text_case,
+            case_rules: CaseRules {
+                language,
+                stopwords: Stopwords::builtin(language).map(Arc::new),
+            },
+            ..Default::default()
+        };
+        options
+            .transform_case(s.into(), false, true, !any_lowercase(s))
+            .to_string()
+    }
+    use CaseLanguage::*;
+    assert_eq!(
+        transform(TextCase::Uppercase, Turkish, "istanbul"),
+        "İSTANBUL"
+    );
+    assert_eq!(transform(TextCase::Lowercase, Turkish, "IŞIK"), "ışık");
+    assert_eq!(
+        transform(TextCase::Title, Other, "the history"),
+        "the history"
+    );
+    assert_eq!(
+        transform(TextCase::Title, German, "Die Geschichte Der Stadt"),
+        "Die Geschichte Der Stadt"
+    );
+    assert_eq!(
+        transform(TextCase::Title, French, "histoire de la france: une étude"),
+        "Histoire de la France: une Étude"
+    );
+    assert_eq!(
+        transform(TextCase::Title, Spanish, "la casa de los espíritus"),
+        "La Casa de los Espíritus"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, French, "LE ROUGE ET LE NOIR"),
+        "Le Rouge et le noir"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, French, "les misérables"),
+        "Les Misérables"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, French, "l’étranger"),
+        "L’Étranger"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, French, "histoire de la France"),
+        "Histoire de la France"
+    );
+    assert_eq!(
+        transform(
+            TextCase::Sentence,
+            Spanish,
+            "¿QUÉ ES LA VIDA? UNA RESPUESTA"
+        ),
+        "¿Qué es la vida? Una respuesta"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, Spanish, "¡viva la vida!"),
+        "¡Viva la vida!"
+    );
+    assert_eq!(
+        transform(TextCase::Sentence, Other, "LES MISÉRABLES"),
+        "Les misérables"
+    );
+}
@@ -26,6 +26,101 @@ pub fn is_latin_cyrillic(s: &str) -> bool {
     s.chars().all(|c| char_is_latin_cyrillic(c))
 }
 
+/// The writing systems that get their own name formatting rules, as determined by
+/// [`name_script`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum NameScript {
+    /// Anything [`is_latin_cyrillic`] accepts.
+    LatinCyrillic,
+    /// Han characters only.
+    Chinese,
+    /// Kana, possibly mixed with Han characters.
+    Japanese,
+    /// Hangul, possibly mixed with Han characters (hanja).
+    Korean,
+    /// Any other script.
+    Other,
+}
+
+fn char_is_kana(c: char) -> bool {
+    match c {
+        // Hiragana, Katakana, Katakana Phonetic Extensions, Halfwidth Katakana
+        '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}' => true,
+        // Kana Supplement, Kana Extended-A, Small Kana Extension
+        '\u{1B000}'..='\u{1B16F}' => true,
+        _ => false,
+    }
+}
+
+fn char_is_hangul(c: char) -> bool {
+    match c {
+        // Jamo, Compatibility Jamo, Jamo Extended-A, Syllables, Jamo Extended-B
+        '\u{1100}'..='\u{11FF}'
+        | '\u{3130}'..='\u{318F}'
+        | '\u{A960}'..='\u{A97F}'
+        | '\u{AC00}'..='\u{D7FF}' => true,
+        // Halfwidth Hangul
+        '\u{FFA0}'..='\u{FFDC}' => true,
+        _ => false,
+    }
+}
+
+fn char_is_han(c: char) -> bool {
+    match c {
+        // Radicals, Kangxi Radicals, iteration mark 々 and 〇
+        '\u{2E80}'..='\u{2FDF}' | '\u{3005}' | '\u{3007}' => true,
+        // Extension A, Unified Ideographs, Compatibility Ideographs
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '\u{F900}'..='\u{FAFF}' => true,
+        // Extensions B onwards, Compatibility Ideographs Supplement
+        '\u{20000}'..='\u{3FFFF}' => true,
+        _ => false,
+    }
+}
+
+/// Works out which script a name (or part of a name) is written in. Kana marks a name as Japanese
+/// and Hangul marks it as Korean, even alongside Han characters, which all three languages use.
+pub fn name_script(s: &str) -> NameScript {
+    if is_latin_cyrillic(s) {
+        return NameScript::LatinCyrillic;
+    }
+    let (mut han, mut kana, mut hangul) = (false, false, false);
+    for c in s.chars().filter(|&c| !COMMON.contains_char(c)) {
+        if char_is_kana(c) {
+            kana = true;
+        } else if char_is_hangul(c) {
+            hangul = true;
+        } else if char_is_han(c) {
+            han = true;
+        } else if !char_is_latin_cyrillic(c) {
+            return NameScript::Other;
+        }
+    }
+    if kana {
+        NameScript::Japanese
+    } else if hangul {
+        NameScript::Korean
+    } else if han {
+        NameScript::Chinese
+    } else {
+        NameScript::Other
+    }
+}
+
+#[test]
+fn test_name_script() {
+    assert_eq!(name_script("Mao Zedong"), NameScript::LatinCyrillic);
+    assert_eq!(name_script("毛泽东"), NameScript::Chinese);
+    assert_eq!(name_script("⺙⺛⻳"), NameScript::Chinese);
+    assert_eq!(name_script("村上"), NameScript::Chinese);
+    assert_eq!(name_script("はるき"), NameScript::Japanese);
+    assert_eq!(name_script("山田 タロウ"), NameScript::Japanese);
+    assert_eq!(name_script("ｶﾀｶﾅ"), NameScript::Japanese);
+    assert_eq!(name_script("남궁"), NameScript::Korean);
+    assert_eq!(name_script("洪길동"), NameScript::Korean);
+    assert_eq!(name_script("สมชาย"), NameScript::Other);
+    assert_eq!(name_script("अमित"), NameScript::Other);
+}
+
 #[test]
 fn test_is_latin_cyrillic() {
     assert!(is_latin_cyrillic(" @")); // Common only
@@ -410,8 +410,8 @@ fn test_date_combos() {
     }
 
     // none represents not being parsed as a date at all, flat out ignored
-    // we won't test the interaction of year/literal/raw/date-parts/edtf, because that's just
-    // last-write-wins.
+    // we won't test the interaction of year/literal/raw/date-parts, because that's just
+    // last-write-wins. edtf wins over all of them.
     let mut tests: HashMap<BTreeSet<&'static str>, Option<DateOrRange>> = HashMap::new();
     tests.insert(
         mk_set(&["literal", "raw"]),
@@ -485,10 +485,142 @@ fn test_date_combos() {
 }
 
 #[test]
-#[ignore = "EDTF dates not implemented yet"]
 fn test_edtf_date() {
     setup();
     let _edtf_schema = get_schema().named_schema(CslDataSchema::EDTF_DATATYPE);
     let doc = json!({ "id": 1, "issued": "199X" });
-    let _refr: Reference = serde_json::from_value(doc).unwrap();
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    let issued = refr.date.get(&Issued).unwrap();
+    assert_eq!(issued.single_or_first().map(|d| d.year), Some(1990));
+    assert_eq!(issued.to_edtf().as_deref(), Some("199X"));
+}
+
+test_parse!(
+    edtf_key,
+    r#" { "id": 1, "issued": { "edtf": "2004-06~/.." } } "#,
+    |r: Reference| {
+        let issued = r.date.get(&Issued).unwrap();
+        assert!(issued.is_uncertain_date());
+        match issued {
+            DateOrRange::Range(start, end) => {
+                assert_eq!((start.year, start.month), (2004, 6));
+                assert!(start.qualifiers.approximate.month);
+                assert_eq!(*end, Date::new(0, 0, 0));
+            }
+            _ => panic!("not a range: {:?}", issued),
+        }
+    }
+);
+test_parse!(
+    edtf_before_date_parts,
+    r#" { "id": 1, "issued": { "edtf": "2004-06~", "date-parts": [[1995, 8, 1]] } } "#,
+    |r: Reference| {
+        assert_key!(r.date, Issued, DateOrRange::from_edtf("2004-06~"));
+    }
+);
+test_parse!(
+    edtf_after_date_parts,
+    r#" { "id": 1, "issued": { "date-parts": [[1995, 8, 1]], "edtf": "2004-06~" } } "#,
+    |r: Reference| {
+        assert_key!(r.date, Issued, DateOrRange::from_edtf("2004-06~"));
+    }
+);
+test_parse!(
+    edtf_key_invalid,
+    r#" { "id": 1, "issued": { "edtf": "sometime" } } "#,
+    |r: Reference| {
+        assert_key!(
+            r.date,
+            Issued,
+            Some(DateOrRange::Literal {
+                literal: "sometime".into(),
+                circa: false,
+            })
+        );
+    }
+);
+
+#[test]
+fn test_edtf_round_trip() {
+    let doc = json!({ "id": 1, "issued": { "edtf": "?2004-06-~11" } });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    let value = serde_json::to_value(&refr).unwrap();
+    assert_eq!(value["issued"]["edtf"], json!("?2004-06-~11"));
+    assert_eq!(value["issued"]["date-parts"], json!([[2004, 6, 11]]));
+    let again: Reference = serde_json::from_value(value).unwrap();
+    assert_eq!(again.date, refr.date);
+}
+
+test_parse!(
+    raw_words,
+    r#" { "id": 1, "issued": { "raw": "March 3–5, 1998" } } "#,
+    |r: Reference| {
+        assert_key!(
+            r.date,
+            Issued,
+            Some(DateOrRange::Range(
+                Date::new(1998, 3, 3),
+                Date::new(1998, 3, 5)
+            ))
+        );
+        assert!(r.raw_dates.is_empty());
+    }
+);
+
+#[test]
+fn test_unparsed_raw_date() {
+    setup();
+    let doc = json!({ "id": 1, "issued": { "raw": "3 mars 1998" }, "accessed": "in press" });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    assert_eq!(
+        refr.date.get(&Issued),
+        Some(&DateOrRange::Literal {
+            literal: "3 mars 1998".into(),
+            circa: false,
+        })
+    );
+    assert!(refr.raw_dates.contains(&Issued));
+    assert!(refr.raw_dates.contains(&DateVariable::Accessed));
+    // still raw when written back out, so it gets another go next time
+    let value = serde_json::to_value(&refr).unwrap();
+    assert_eq!(value["issued"], json!({ "raw": "3 mars 1998" }));
+    let again: Reference = serde_json::from_value(value).unwrap();
+    assert_eq!(again, refr);
+
+    // a literal is never parsed
+    let doc = json!({ "id": 1, "issued": { "literal": "3 mars 1998" } });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    assert!(refr.raw_dates.is_empty());
+}
+
+#[test]
+fn test_ingestion_problems() {
+    setup();
+    let doc = json!({
+        "id": 1,
+        "tittle": "A typo",
+        "issued": { "date-parts": "1998" },
+        "accessed": { "edtf": "not a date" }
+    });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    assert_eq!(refr.unknown_variables, vec![SmartString::from("tittle")]);
+    assert!(refr.invalid_dates.contains_key(&Issued));
+    assert_eq!(refr.date.get(&Issued), None);
+    // the literal is still rendered
+    assert!(refr.invalid_dates.contains_key(&DateVariable::Accessed));
+    assert!(refr.date.contains_key(&DateVariable::Accessed));
+
+    // an invalid season is only reported; the rest of the date is kept, as it always was
+    let doc = json!({ "id": 1, "issued": { "date-parts": [[1998]], "season": 7 } });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    assert_eq!(
+        refr.date.get(&Issued),
+        Some(&DateOrRange::Single(Date::new(1998, 0, 0)))
+    );
+    assert!(refr.invalid_dates.contains_key(&Issued));
+
+    let doc = json!({ "id": 1, "issued": { "date-parts": [[1998]] } });
+    let refr: Reference = serde_json::from_value(doc).unwrap();
+    assert!(refr.unknown_variables.is_empty());
+    assert!(refr.invalid_dates.is_empty());
 }
@@ -1,7 +1,144 @@
+//! Generating `citation-label`s for references that don't supply one, for alphabetic styles.
+
+use crate::prelude::*;
 use citeproc_io::{Name, PersonName, Reference};
-use csl::{DateVariable, NameVariable};
+use csl::{DateVariable, NameVariable, Variable};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{self, Write};
+use unic_segment::Graphemes;
+
+/// How `citation-label` is generated when a reference doesn't have one. Pass it to the processor
+/// through `InitOptions::citation_label`.
+///
+/// By default, labels follow `Aaaa00:AaAa00:AaAA00:AAAA00`, using the authors, or the editors if
+/// there are none, or the first three letters of the title if there are neither.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CitationLabelConfig {
+    /// Which names contribute how many letters, and how many digits of the year to use.
+    pub template: Trigraph,
+    /// Where to look for names, in order. The first of these the reference has is used.
+    #[serde(with = "name_variables")]
+    pub names: Vec<NameVariable>,
+    /// How many letters of the (short) title to use when there are no names at all.
+    pub title_letters: u32,
+    /// Write the first letter of each word in a non-dropping particle before the family name,
+    /// like biblatex's `useprefix`, so `van Gogh` becomes `vGog` instead of `Gogh`.
+    pub particles: bool,
+    /// Give references with identical labels the suffixes `a`, `b`, etc. across the whole
+    /// document, in the order year suffixes would be assigned. Without this, a colliding label
+    /// only gets a suffix through `disambiguate-add-year-suffix`, and only when the rest of the
+    /// cite is identical too. With it, the labels are already distinct, so a year suffix is not
+    /// added on top.
+    ///
+    /// Sorting by `citation-label` uses the label without its suffix.
+    pub collision_suffixes: bool,
+}
+
+impl Default for CitationLabelConfig {
+    fn default() -> Self {
+        CitationLabelConfig {
+            template: Trigraph::default(),
+            names: vec![NameVariable::Author, NameVariable::Editor],
+            title_letters: 3,
+            particles: false,
+            collision_suffixes: false,
+        }
+    }
+}
+
+impl CitationLabelConfig {
+    /// The label for a reference, without any collision suffix. A `citation-label` field on the
+    /// reference is used as-is.
+    pub fn make_label(&self, refr: &Reference) -> SmartString {
+        if let Some(explicit) = refr.ordinary.get(&Variable::CitationLabel) {
+            return explicit.as_str().into();
+        }
+        let mut label = SmartString::new();
+        let names = self
+            .names
+            .iter()
+            .filter_map(|var| refr.name.get(var))
+            .find(|names| !names.is_empty());
+        let row = self.template.row(names.map_or(1, |names| names.len()));
+        match names {
+            Some(names) => {
+                let letters = row.iter().filter_map(|cell| match cell {
+                    ConfigCell::Author { first_n_letters } => Some(*first_n_letters),
+                    _ => None,
+                });
+                for (name, n_letters) in names.iter().zip(letters) {
+                    self.write_name(&mut label, name, n_letters);
+                }
+            }
+            None => {
+                let title = refr
+                    .ordinary
+                    .get(&Variable::TitleShort)
+                    .or_else(|| refr.ordinary.get(&Variable::Title));
+                if let Some(title) = title {
+                    // Skip spaces and punctuation, but keep combining marks with their letters.
+                    let letters = Graphemes::new(title)
+                        .filter(|g| g.chars().next().map_or(false, char::is_alphanumeric));
+                    for g in letters.take(self.title_letters as usize) {
+                        label.push_str(g);
+                    }
+                }
+            }
+        }
+        let year = refr
+            .date
+            .get(&DateVariable::Issued)
+            .and_then(|issued| issued.single_or_first())
+            .map(|date| date.year);
+        if let Some(year) = year {
+            for cell in row {
+                if let ConfigCell::Year { last_n_digits } = *cell {
+                    // More digits than that won't fit in an i32 anyway.
+                    let digits = last_n_digits.min(9);
+                    // rem_euclid, so BC years don't produce a minus sign
+                    let value = year.rem_euclid(10i32.pow(digits));
+                    write!(label, "{:0width$}", value, width = digits as usize).unwrap();
+                }
+            }
+        }
+        label
+    }
 
-#[derive(Debug, PartialEq, Eq)]
+    fn write_name(&self, label: &mut SmartString, name: &Name, n_letters: u32) {
+        let (particle, to_write) = match name {
+            Name::Literal { literal, .. } => (None, literal),
+            Name::Person(PersonName {
+                family: Some(family),
+                non_dropping_particle,
+                ..
+            }) => (non_dropping_particle.as_ref(), family),
+            Name::Person(PersonName {
+                family: None,
+                given: Some(given),
+                ..
+            }) => (None, given),
+            _ => return,
+        };
+        if self.particles {
+            // "van der" => "vd", "d'" => "d"
+            for word in particle.into_iter().flat_map(|p| p.split_whitespace()) {
+                if let Some(first) = Graphemes::new(word).next() {
+                    label.push_str(first);
+                }
+            }
+        }
+        for g in Graphemes::new(to_write).take(n_letters as usize) {
+            label.push_str(g);
+        }
+    }
+}
+
+/// A label template: a colon-separated list of rows, where row `n` is used for references with
+/// `n` names (and the last row for any more than that). In each row, `A` followed by `k` `a`s
+/// takes the first `k + 1` letters of the next name, and a run of `0`s takes that many trailing
+/// digits of the year. For example, `Aaa00:AA00` gives `Smi01` or `SJ01`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Trigraph(Vec<Vec<ConfigCell>>);
 
 impl Trigraph {
@@ -16,60 +153,20 @@ impl Trigraph {
                 }
             })
     }
-    pub fn make_label(&self, refr: &Reference) -> String {
-        let mut string = String::with_capacity(6);
-        let authors = refr.name.get(&NameVariable::Author);
-        let issued = refr.date.get(&DateVariable::Issued);
-        if self.0.len() == 0 {
-            return string;
+
+    fn row(&self, name_count: usize) -> &[ConfigCell] {
+        match self.0.len() {
+            0 => &[],
+            len => &self.0[name_count.max(1).min(len) - 1],
         }
-        use std::fmt::Write;
-        use unic_segment::Graphemes;
-        if let Some(authors) = authors {
-            let count = authors.len();
-            let ix = std::cmp::min(count, self.0.len()) - 1;
-            if count > 0 {
-                let mut prog = 0usize;
-                for author_printer in self.0[ix].iter().filter_map(|cell| match cell {
-                    ConfigCell::Author { first_n_letters } => Some(*first_n_letters),
-                    _ => None,
-                }) {
-                    let name_to_write = match &authors[prog] {
-                        Name::Literal { literal, .. } => literal,
-                        Name::Person(PersonName {
-                            family: Some(family),
-                            ..
-                        }) => family,
-                        Name::Person(PersonName {
-                            family: None,
-                            given: Some(given),
-                            ..
-                        }) => given,
-                        _ => {
-                            prog += 1;
-                            continue;
-                        }
-                    };
-                    let len = Graphemes::new(name_to_write)
-                        .take(author_printer as usize)
-                        .fold(0, |acc, x| acc + x.len());
-                    write!(string, "{}", &name_to_write[..len]).unwrap();
-                    prog += 1;
-                }
-            }
-            if let Some(issued) = issued {
-                if let Some(single) = issued.single_or_first() {
-                    for year_digits in self.0[ix].iter().filter_map(|cell| match cell {
-                        ConfigCell::Year { last_n_digits } => Some(*last_n_digits),
-                        _ => None,
-                    }) {
-                        // Probably behaves weirdly for BC dates
-                        write!(string, "{:02}", single.year % (10i32.pow(year_digits))).unwrap();
-                    }
-                }
-            }
+    }
+
+    pub fn make_label(&self, refr: &Reference) -> SmartString {
+        CitationLabelConfig {
+            template: self.clone(),
+            ..Default::default()
         }
-        string
+        .make_label(refr)
     }
 }
 
@@ -80,38 +177,160 @@ impl Default for Trigraph {
     }
 }
 
+impl fmt::Display for Trigraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_char(':')?;
+            }
+            for cell in row {
+                match *cell {
+                    ConfigCell::Author { first_n_letters } => {
+                        f.write_char('A')?;
+                        for _ in 1..first_n_letters {
+                            f.write_char('a')?;
+                        }
+                    }
+                    ConfigCell::Year { last_n_digits } => {
+                        for _ in 0..last_n_digits {
+                            f.write_char('0')?;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Trigraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Trigraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Trigraph::parse(&s)
+            .map_err(|()| de::Error::custom(format!("invalid citation label template `{}`", s)))
+    }
+}
+
+/// NameVariable has no serde impls of its own, so use its CSL names.
+mod name_variables {
+    use csl::NameVariable;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(
+        vars: &[NameVariable],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(vars.iter().map(|v| v.as_ref()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<NameVariable>, D::Error> {
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        strings
+            .iter()
+            .map(|s| {
+                NameVariable::from_str(s)
+                    .map_err(|_| de::Error::custom(format!("unknown name variable `{}`", s)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+fn person(family: &str) -> Name {
+    Name::Person(PersonName {
+        family: Some(family.into()),
+        ..Default::default()
+    })
+}
+
 #[test]
 fn test_write_label() {
     use citeproc_io::{Date, DateOrRange};
     let trigraph = Trigraph::default();
     use csl::CslType;
     let mut refr = Reference::empty("ref_id".into(), CslType::Book);
+    refr.name.insert(NameVariable::Author, vec![person("Jobs")]);
+    refr.date.insert(
+        DateVariable::Issued,
+        DateOrRange::Single(Date::new(1995, 0, 0)),
+    );
+    assert_eq!(trigraph.make_label(&refr), "Jobs95");
     refr.name.insert(
         NameVariable::Author,
-        vec![Name::Person(PersonName {
-            family: Some("Jobs".into()),
-            ..Default::default()
-        })],
+        vec![person("Boris"), person("Johnson")],
     );
+    assert_eq!(trigraph.make_label(&refr), "BoJo95");
+}
+
+#[test]
+fn test_label_fallbacks() {
+    use citeproc_io::{Date, DateOrRange};
+    use csl::CslType;
+    let config = CitationLabelConfig::default();
+    let mut refr = Reference::empty("ref_id".into(), CslType::Book);
     refr.date.insert(
         DateVariable::Issued,
-        DateOrRange::Single(Date::new(1995, 0, 0)),
+        DateOrRange::Single(Date::new(2005, 0, 0)),
+    );
+    refr.ordinary
+        .insert(Variable::Title, "The Éminence grise".into());
+    assert_eq!(config.make_label(&refr), "The05");
+    refr.name
+        .insert(NameVariable::Editor, vec![person("Ødegaard")]);
+    assert_eq!(config.make_label(&refr), "Øde05");
+    refr.ordinary
+        .insert(Variable::CitationLabel, "Custom".into());
+    assert_eq!(config.make_label(&refr), "Custom");
+}
+
+#[test]
+fn test_label_particles_and_digits() {
+    use citeproc_io::{Date, DateOrRange};
+    use csl::CslType;
+    let mut config = CitationLabelConfig {
+        template: Trigraph::parse("Aaaa000").unwrap(),
+        ..Default::default()
+    };
+    let mut refr = Reference::empty("ref_id".into(), CslType::Book);
+    refr.date.insert(
+        DateVariable::Issued,
+        DateOrRange::Single(Date::new(2005, 0, 0)),
     );
-    assert_eq!(trigraph.make_label(&refr), "Jobs95".to_owned());
     refr.name.insert(
         NameVariable::Author,
-        vec![
-            Name::Person(PersonName {
-                family: Some("Boris".into()),
-                ..Default::default()
-            }),
-            Name::Person(PersonName {
-                family: Some("Johnson".into()),
-                ..Default::default()
-            }),
-        ],
+        vec![Name::Person(PersonName {
+            family: Some("Gogh".into()),
+            non_dropping_particle: Some("van der".into()),
+            ..Default::default()
+        })],
     );
-    assert_eq!(trigraph.make_label(&refr), "BoJo95".to_owned());
+    assert_eq!(config.make_label(&refr), "Gogh005");
+    config.particles = true;
+    assert_eq!(config.make_label(&refr), "vdGogh005");
+    // template rows with more names than the reference has don't panic
+    config.template = Trigraph::parse("AaAa00").unwrap();
+    assert_eq!(config.make_label(&refr), "vdGo05");
+}
+
+#[test]
+fn test_trigraph_roundtrip() {
+    let source = "Aaaa00:AaAa00:AaAA00:AAAA00";
+    assert_eq!(Trigraph::parse(source).unwrap().to_string(), source);
+    let json = r#"{"template":"Aa0","names":["editor"],"collisionSuffixes":true}"#;
+    let config: CitationLabelConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(config.template.to_string(), "Aa0");
+    assert_eq!(config.names, vec![NameVariable::Editor]);
+    assert!(config.collision_suffixes);
+    assert_eq!(config.title_letters, 3);
 }
 
 #[test]
@@ -145,7 +364,7 @@ fn test_parse_trigraph() {
     )
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum ConfigCell {
     Author { first_n_letters: u32 },
     Year { last_n_digits: u32 },
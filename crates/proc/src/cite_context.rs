@@ -10,7 +10,7 @@ use super::DisambPass;
 use crate::choose::CondChecker;
 use crate::SmartString;
 use citeproc_io::output::markup::Markup;
-use citeproc_io::{Cite, DateOrRange, Locator, Name, NumericValue, Reference};
+use citeproc_io::{Cite, DateOrRange, Locator, Name, NumericValue, Reference, StopwordTable};
 use csl::Features;
 use csl::Locale;
 use csl::*;
@@ -31,6 +31,10 @@ pub struct CiteContext<
     pub locale: &'c Locale,
     pub name_citation: Arc<NameEl>,
     pub names_delimiter: Option<SmartString>,
+    /// Configured title-case stopwords, keyed by language.
+    pub stopwords: Arc<StopwordTable>,
+    /// Generated ahead of time, as collision suffixes depend on every other reference's label.
+    pub citation_label: Option<SmartString>,
 
     pub position: (Position, Option<u32>),
 
@@ -87,6 +91,8 @@ impl<'c, O: OutputFormat, I: OutputFormat> CiteContext<'c, O, I> {
             locale: self.locale,
             name_citation: self.name_citation.clone(),
             names_delimiter: self.names_delimiter.clone(),
+            stopwords: self.stopwords.clone(),
+            citation_label: self.citation_label.clone(),
             position: self.position,
             disamb_pass: self.disamb_pass,
             bib_number: self.bib_number,
@@ -101,6 +107,9 @@ impl<'a, O: OutputFormat, I: OutputFormat> RenderContext for CiteContext<'a, O,
     fn style(&self) -> &Style {
         self.style
     }
+    fn citation_label(&self) -> Option<&str> {
+        self.citation_label.as_opt_str()
+    }
     fn reference(&self) -> &Reference {
         self.reference
     }
@@ -110,12 +119,11 @@ impl<'a, O: OutputFormat, I: OutputFormat> RenderContext for CiteContext<'a, O,
 
     fn get_number(&self, var: NumberVariable) -> Option<NumericValue<'_>> {
         // TODO: always use the default locale
-        let and_term = self.locale.and_term(None).unwrap_or("and");
         let get = |v: NumberVariable| {
             self.reference()
                 .number
                 .get(&v)
-                .map(NumericValue::from_localized(and_term))
+                .map(NumericValue::from_locale(self.locale))
         };
         match var {
             NumberVariable::Locator => self
@@ -126,7 +134,7 @@ impl<'a, O: OutputFormat, I: OutputFormat> RenderContext for CiteContext<'a, O,
                 // For now we'll just ignore any more than the one.
                 .and_then(|ls| ls.single())
                 .map(Locator::value)
-                .map(NumericValue::from_localized(and_term)),
+                .map(NumericValue::from_locale(self.locale)),
             NumberVariable::FirstReferenceNoteNumber => self.position.1.map(NumericValue::num),
             NumberVariable::CitationNumber => self.bib_number.map(NumericValue::num),
             NumberVariable::PageFirst => get(NumberVariable::Page).and_then(|pp| pp.page_first()),
@@ -140,6 +148,7 @@ pub trait RenderContext {
     fn reference(&self) -> &Reference;
     fn locale(&self) -> &Locale;
     fn get_number(&self, var: NumberVariable) -> Option<NumericValue<'_>>;
+    fn citation_label(&self) -> Option<&str>;
 
     fn cite_lang(&self) -> Option<&Lang> {
         let refr = self.reference();
@@ -160,10 +169,8 @@ pub trait RenderContext {
             (Variable::ContainerTitle, VariableForm::Short) => get(Variable::ContainerTitleShort)
                 .or_else(|| get(Variable::JournalAbbreviation))
                 .or_else(|| get(Variable::ContainerTitle)),
-            (Variable::CitationLabel, _) if refr.ordinary.get(&var).is_none() => {
-                let tri = crate::citation_label::Trigraph::default();
-                Some(Cow::Owned(tri.make_label(self.reference())))
-            }
+            // Includes an explicit citation-label from the reference
+            (Variable::CitationLabel, _) => self.citation_label().map(Cow::Borrowed),
             _ => get(var),
         }
     }
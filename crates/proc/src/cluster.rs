@@ -8,8 +8,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use citeproc_db::ClusterId;
-use citeproc_io::{Cite, ClusterMode};
-use csl::Collapse;
+use citeproc_io::{Cite, ClusterMode, DateOrRange, Reference};
+use csl::{Collapse, CslType, DateVariable, Variable};
 
 use crate::helpers::slice_group_by::{group_by, group_by_mut};
 
@@ -68,6 +68,16 @@ pub fn built_cluster_before_output(
         collapse_cites(&fmt, collapse, &mut irs);
     }
 
+    if style.features.parallel_citations {
+        for cite in irs.iter_mut() {
+            cite.parallel = db
+                .reference(cite.cite.ref_id.clone())
+                .and_then(|refr| ParallelKey::new(&refr))
+                .into();
+        }
+        collapse_parallel(&mut irs);
+    }
+
     // Cite capitalization
     // TODO: allow clients to pass a flag to prevent this (on ix==0) when a cluster is in the
     // middle of an existing footnote, and isn't preceded by a period (or however else a client
@@ -237,6 +247,8 @@ pub(crate) struct CiteInCluster<O: OutputFormat = Markup> {
     pub year: Partial<SmartString>,
     /// A key to group_by cites in order to collapse runs of the same **year-suffix**.
     pub year_suffix: Partial<u32>,
+    /// A key to group_by cites in order to find runs of **parallel citations**.
+    pub parallel: Partial<ParallelKey>,
     pub has_locator: bool,
     pub has_locator_or_affixes: bool,
     pub own_delimiter: Option<DelimKind>,
@@ -255,6 +267,9 @@ impl<O: OutputFormat> CiteInCluster<O> {
     pub(crate) fn by_year_suffix(&self) -> Partial<u32> {
         self.year_suffix
     }
+    pub(crate) fn by_parallel(&self) -> Partial<&ParallelKey> {
+        self.parallel.as_ref()
+    }
 }
 
 use std::fmt::{Debug, Formatter};
@@ -281,6 +296,7 @@ impl<O: OutputFormat<Output = SmartString>> Debug for CiteInCluster<O> {
             .field("own_delimiter", &self.own_delimiter)
             .field("unique_name_number", &self.unique_name_number)
             .field("year_suffix", &self.year_suffix)
+            .field("parallel", &self.parallel)
             .field("gen4_full", &self.gen4)
             .finish()
     }
@@ -320,6 +336,7 @@ impl CiteInCluster<Markup> {
             unique_name_number: Partial::Incomparable,
             year: Partial::Incomparable,
             year_suffix: Partial::Incomparable,
+            parallel: Partial::Incomparable,
         }
     }
 
@@ -579,6 +596,66 @@ pub(crate) fn collapse_cites<O: OutputFormat<Output = SmartString>>(
     }
 }
 
+/// The same case reported in different reporters, e.g. "347 U.S. 483" and "74 S. Ct. 686", has
+/// the same title, authority and year. Only legal types get a key, and only with an authority or
+/// a year, so two undated works that happen to share a title are not parallel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParallelKey {
+    title: String,
+    authority: Option<String>,
+    year: Option<i32>,
+}
+
+impl ParallelKey {
+    fn new(refr: &Reference) -> Option<Self> {
+        match refr.csl_type {
+            CslType::LegalCase | CslType::Legislation | CslType::Bill | CslType::Regulation => {}
+            _ => return None,
+        }
+        let title = refr.ordinary.get(&Variable::Title)?.clone();
+        let authority = refr.ordinary.get(&Variable::Authority).cloned();
+        let year = refr
+            .date
+            .get(&DateVariable::Issued)
+            .and_then(|dor| match dor {
+                DateOrRange::Single(d) | DateOrRange::Range(d, _) => Some(d.year),
+                DateOrRange::Literal { .. } => None,
+            });
+        if authority.is_none() && year.is_none() {
+            return None;
+        }
+        Some(ParallelKey {
+            title,
+            authority,
+            year,
+        })
+    }
+}
+
+/// Adjacent cites of different references with the same [ParallelKey] form a parallel
+/// citation. A prefix or suffix in between breaks the run.
+fn collapse_parallel<O: OutputFormat>(cites: &mut Vec<CiteInCluster<O>>) {
+    let parallel_runs = group_by_mut(cites.as_mut(), |a, b| {
+        a.by_parallel() == b.by_parallel()
+            && a.cite.ref_id != b.cite.ref_id
+            && !a.cite.has_suffix()
+            && !b.cite.has_prefix()
+    });
+    for run in parallel_runs {
+        if run.len() < 2 {
+            continue;
+        }
+        let last_ix = run.len() - 1;
+        for (ix, cite) in run.iter_mut().enumerate() {
+            let gen4 = Arc::make_mut(&mut cite.gen4);
+            gen4.tree_mut().suppress_parallel(ix == 0, ix == last_ix);
+            if ix != last_ix {
+                cite.own_delimiter = Some(DelimKind::Parallel);
+            }
+        }
+    }
+}
+
 fn collapse_year_suffix_run<O: OutputFormat>(
     ysuf_run: &mut [CiteInCluster<O>],
     is_first_ysuf_run: bool,
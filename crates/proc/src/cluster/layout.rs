@@ -206,6 +206,7 @@ pub(crate) struct LayoutDelimiters<'a> {
     pub year_suffix: &'a str,
     pub after_collapse: &'a str,
     pub layout_delim: &'a str,
+    pub parallel: &'a str,
     pub affixes: Option<&'a Affixes>,
     pub formatting: Option<Formatting>,
     pub and_last_delimiter: Option<SmartString>,
@@ -219,6 +220,8 @@ pub(crate) enum DelimKind {
     YearSuffix,
     Range,
     And,
+    /// Between the cites of a parallel citation
+    Parallel,
 }
 
 impl<'a> LayoutDelimiters<'a> {
@@ -229,6 +232,7 @@ impl<'a> LayoutDelimiters<'a> {
             DelimKind::YearSuffix => self.year_suffix,
             DelimKind::Layout => self.layout_delim,
             DelimKind::Range => "\u{2013}",
+            DelimKind::Parallel => self.parallel,
             // should not have to observe None here, simply don't write any Ands until you are sure
             // you have and_last_delimiter
             DelimKind::And => return self.and_last_delimiter.as_opt_str(),
@@ -249,6 +253,10 @@ impl<'a> LayoutDelimiters<'a> {
             .or(layout_opt)
             .unwrap_or("");
         let layout_delim = layout_opt.unwrap_or("");
+        let parallel = citation
+            .parallel_delimiter_override
+            .as_opt_str()
+            .unwrap_or(layout_delim);
         let affixes = citation.layout.affixes.as_ref();
         let formatting = citation.layout.formatting.clone();
         Self {
@@ -256,6 +264,7 @@ impl<'a> LayoutDelimiters<'a> {
             year_suffix,
             after_collapse,
             layout_delim,
+            parallel,
             affixes,
             formatting,
             and_last_delimiter: None,
@@ -297,6 +306,7 @@ impl<'a> LayoutDelimiters<'a> {
                 year_suffix: citation.year_suffix,
                 after_collapse,
                 layout_delim,
+                parallel: citation.parallel,
                 affixes,
                 formatting,
                 and_last_delimiter,
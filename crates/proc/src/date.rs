@@ -7,6 +7,7 @@
 use crate::prelude::*;
 
 use crate::number::render_ordinal;
+use citeproc_io::output::Provenance;
 use citeproc_io::{lazy, Date, DateOrRange};
 use csl::terms::*;
 use csl::LocaleDate;
@@ -736,7 +737,7 @@ fn dp_render_either<'c, O: OutputFormat, I: OutputFormat>(
                     let b = fmt.affixed_text(s, part.formatting, affixes.as_ref());
                     Either::Build(Some(b))
                 } else {
-                    let b = fmt.plain(&s);
+                    let b = fmt.with_provenance(fmt.plain(&s), Provenance::Year(var));
                     let seq = arena.new_node((
                         IR::Seq(IrSeq {
                             affixes,
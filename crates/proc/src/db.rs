@@ -10,15 +10,20 @@
 use fnv::FnvHashMap;
 use std::sync::Arc;
 
+use crate::citation_label::CitationLabelConfig;
 use crate::cluster;
 use crate::disamb::names::{replace_single_child, NameDisambPass};
-use crate::disamb::{Dfa, DisambName, DisambNameData, EdgeData, FreeCondSets};
+use crate::disamb::{
+    Dfa, DisambExplanation, DisambName, DisambNameData, DisambStep, DisambStepReport, EdgeData,
+    FreeCondSets,
+};
 use crate::prelude::*;
-use crate::sort::BibNumber;
+use crate::sort::{BibNumber, Collation};
 use crate::{CiteContext, DisambPass, IrState, Proc, IR};
 use citeproc_db::{CiteData, ClusterData, ClusterId, ClusterNumber, IntraNote};
-use citeproc_io::output::{markup::Markup, OutputFormat};
-use citeproc_io::{Cite, Name, Reference};
+use citeproc_io::output::markup::{InlineElement, Markup};
+use citeproc_io::output::OutputFormat;
+use citeproc_io::{Cite, Name, Reference, StopwordTable};
 use csl::GivenNameDisambiguationRule as GNDR;
 use csl::{Atom, Bibliography, Position, SortKey};
 
@@ -56,6 +61,9 @@ pub trait IrDatabase:
     fn year_suffixes(&self) -> Arc<FnvHashMap<Atom, u32>>;
     fn year_suffix_for(&self, ref_id: Atom) -> Option<u32>;
     fn ir_fully_disambiguated(&self, key: CiteId) -> Arc<IrGen>;
+    /// Runs the disambiguation of a cite again, recording what each step did. Not used for
+    /// rendering.
+    fn disamb_explanation(&self, key: CiteId) -> Arc<DisambExplanation>;
     fn built_cluster(&self, key: ClusterId) -> Arc<MarkupOutput>;
 
     /// render the `<intext>` element on demand
@@ -64,6 +72,9 @@ pub trait IrDatabase:
     fn bib_item_gen0(&self, ref_id: Atom) -> Option<Arc<IrGen>>;
     fn bib_item(&self, ref_id: Atom) -> Arc<MarkupOutput>;
     fn get_bibliography_map(&self) -> Arc<FnvHashMap<Atom, Arc<MarkupOutput>>>;
+    /// The width, in characters, of the widest first field that `second-field-align` put in the
+    /// bibliography's left margin. Zero if there are none.
+    fn bib_max_offset(&self) -> u32;
 
     fn branch_runs(&self) -> Arc<FreeCondSets>;
 
@@ -111,6 +122,23 @@ pub trait IrDatabase:
     fn sorted_refs(&self) -> Arc<(Vec<Atom>, FnvHashMap<Atom, BibNumber>)>;
     #[salsa::input]
     fn bibliography_no_sort(&self) -> bool;
+    /// Title-case stopwords for languages other than English, or to replace the English list.
+    #[salsa::input]
+    fn title_case_stopwords(&self) -> Arc<StopwordTable>;
+    /// How `citation-label` is generated for references that don't have one.
+    #[salsa::input]
+    fn citation_label_config(&self) -> Arc<CitationLabelConfig>;
+    /// A reference's `citation-label`, without a collision suffix. Sorting uses this, because
+    /// collision suffixes are allocated in bibliography order.
+    fn citation_label_base(&self, ref_id: Atom) -> Option<SmartString>;
+    fn citation_label_suffixes(&self) -> Arc<FnvHashMap<Atom, u32>>;
+    /// A reference's `citation-label` as rendered, including any collision suffix.
+    fn citation_label(&self, ref_id: Atom) -> Option<SmartString>;
+    /// Replaces the collation picked for the style's default locale.
+    #[salsa::input]
+    fn collation_override(&self) -> Option<Collation>;
+    /// How sort keys are compared.
+    fn collation(&self) -> Collation;
 
     #[salsa::invoke(crate::sort::bib_number)]
     fn bib_number(&self, id: CiteId) -> Option<BibNumber>;
@@ -118,6 +146,14 @@ pub trait IrDatabase:
 
 pub fn safe_default(db: &mut dyn IrDatabase) {
     db.set_bibliography_no_sort_with_durability(false, salsa::Durability::HIGH);
+    db.set_title_case_stopwords_with_durability(Default::default(), salsa::Durability::HIGH);
+    db.set_citation_label_config_with_durability(Default::default(), salsa::Durability::HIGH);
+    db.set_collation_override_with_durability(None, salsa::Durability::HIGH);
+}
+
+fn collation(db: &dyn IrDatabase) -> Collation {
+    db.collation_override()
+        .unwrap_or_else(|| Collation::for_lang(&db.default_lang()))
 }
 
 fn all_person_names(db: &dyn IrDatabase) -> Arc<Vec<DisambNameData>> {
@@ -299,6 +335,48 @@ fn year_suffixes(db: &dyn IrDatabase) -> Arc<FnvHashMap<Atom, u32>> {
     Arc::new(suffixes)
 }
 
+fn citation_label_base(db: &dyn IrDatabase, ref_id: Atom) -> Option<SmartString> {
+    let refr = db.reference(ref_id)?;
+    let label = db.citation_label_config().make_label(&refr);
+    if label.is_empty() {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+/// Labels are only compared with each other, not with the rest of the cite, so unlike
+/// [year_suffixes] this doesn't need to know which cites are ambiguous. Suffixes are given out in
+/// bibliography order, like year suffixes.
+fn citation_label_suffixes(db: &dyn IrDatabase) -> Arc<FnvHashMap<Atom, u32>> {
+    if !db.citation_label_config().collision_suffixes {
+        return Arc::new(FnvHashMap::default());
+    }
+    let sorted_refs = db.sorted_refs();
+    let (refs, _) = &*sorted_refs;
+    let mut by_label: FnvHashMap<SmartString, Vec<Atom>> = FnvHashMap::default();
+    for ref_id in refs {
+        if let Some(label) = db.citation_label_base(ref_id.clone()) {
+            by_label.entry(label).or_default().push(ref_id.clone());
+        }
+    }
+    let mut suffixes = FnvHashMap::default();
+    for group in by_label.values().filter(|group| group.len() > 1) {
+        for (i, ref_id) in group.iter().enumerate() {
+            suffixes.insert(ref_id.clone(), i as u32 + 1); // "a" = 1
+        }
+    }
+    Arc::new(suffixes)
+}
+
+fn citation_label(db: &dyn IrDatabase, ref_id: Atom) -> Option<SmartString> {
+    let mut label = db.citation_label_base(ref_id.clone())?;
+    if let Some(&suffix) = db.citation_label_suffixes().get(&ref_id) {
+        label.push_str(&citeproc_io::utils::to_bijective_base_26(suffix));
+    }
+    Some(label)
+}
+
 // Not cached
 fn ref_bib_number(bib_numbers: &FnvHashMap<Atom, BibNumber>, ref_id: &Atom) -> u32 {
     let ret = bib_numbers.get(ref_id).cloned();
@@ -405,6 +483,8 @@ macro_rules! preamble {
             in_bibliography: false,
             names_delimiter,
             name_citation: name_el,
+            stopwords: $db.title_case_stopwords(),
+            citation_label: $db.citation_label($cite.ref_id.clone()),
             sort_key: None,
             year_suffix: None,
         };
@@ -936,22 +1016,24 @@ fn disambiguate_add_year_suffix(tree: &mut IrTree, ctx: &CiteContext<'_, Markup>
     tree.recompute_group_vars();
 }
 
+/// Returns whether any `<choose>` was rendered again.
 #[inline(never)]
 fn disambiguate_true(
     db: &dyn IrDatabase,
     tree: &mut IrTree,
     state: &mut IrState,
     ctx: &CiteContext<'_, Markup>,
-) {
+) -> bool {
     debug!(
         "attempting to disambiguate {:?} ({}) with {:?}",
         ctx.cite_id, &ctx.reference.id, ctx.disamb_pass
     );
     let un = is_unambiguous(db, tree.tree_ref(), &ctx.reference.id);
     if un {
-        return;
+        return false;
     }
     let cond_refs = list_all_cond_disambs(tree.tree_ref());
+    let rerendered = !cond_refs.is_empty();
     for cid in cond_refs.into_iter() {
         if is_unambiguous(db, tree.tree_ref(), &ctx.reference.id) {
             debug!("successfully disambiguated with Cond");
@@ -970,6 +1052,7 @@ fn disambiguate_true(
         }
         tree.recompute_group_vars();
     }
+    rerendered
 }
 
 fn ir_gen0(db: &dyn IrDatabase, id: CiteId) -> Arc<IrGen> {
@@ -1029,37 +1112,55 @@ impl IrGenCow {
     fn new(arc: Arc<IrGen>) -> Self {
         Self { arc }
     }
-    /// Returned true indicates the cite is now unambiguous.
-    fn disambiguate_add_names(&mut self, db: &dyn IrDatabase, ctx: &mut CiteContext<Markup>) {
+    // Each of these returns the step it applied, if any, for a StepRecorder.
+
+    fn disambiguate_add_names(
+        &mut self,
+        db: &dyn IrDatabase,
+        ctx: &mut CiteContext<Markup>,
+    ) -> Option<(DisambStep, DisambPass)> {
         if self.disambiguation_finished {
-            return;
+            return None;
         }
         if ctx.style.citation.disambiguate_add_names {
             // Clone ir0; disambiguate by adding names
             let cloned = self.to_mut();
             cloned.disambiguation_finished =
                 disambiguate_add_names(db, cloned.tree_mut(), ctx, false);
+            return Some((DisambStep::AddNames, DisambPass::AddNames));
         }
+        None
     }
 
-    fn disambiguate_add_given_name(&mut self, db: &dyn IrDatabase, ctx: &mut CiteContext<Markup>) {
+    fn disambiguate_add_given_name(
+        &mut self,
+        db: &dyn IrDatabase,
+        ctx: &mut CiteContext<Markup>,
+    ) -> Option<(DisambStep, DisambPass)> {
         if self.disambiguation_finished {
-            return;
+            return None;
         }
         if ctx.style.citation.disambiguate_add_givenname {
             let cloned = self.to_mut();
             let also_add_names = ctx.style.citation.disambiguate_add_names;
             disambiguate_add_givennames(db, cloned.tree_mut(), ctx, also_add_names);
+            let gndr = ctx.style.citation.givenname_disambiguation_rule;
+            return Some((DisambStep::AddGivenName, DisambPass::AddGivenName(gndr)));
         }
+        None
     }
-    fn disambiguate_add_year_suffix(&mut self, db: &dyn IrDatabase, ctx: &mut CiteContext<Markup>) {
+    fn disambiguate_add_year_suffix(
+        &mut self,
+        db: &dyn IrDatabase,
+        ctx: &mut CiteContext<Markup>,
+    ) -> Option<(DisambStep, DisambPass)> {
         // the other disambiguate_ routines would exit here if disambiguation_finished was true,
         // but whether we apply year suffixes is actually unconditional at this point.
         // Year suffixes have been produced already through db.year_suffix_for(refId).
         if ctx.style.citation.disambiguate_add_year_suffix {
             let year_suffix = match db.year_suffix_for(ctx.cite.ref_id.clone()) {
                 Some(y) => y,
-                _ => return,
+                _ => return None,
             };
             let cloned = self.to_mut();
             ctx.disamb_pass = Some(DisambPass::AddYearSuffix(year_suffix));
@@ -1069,7 +1170,12 @@ impl IrGenCow {
             if !self.disambiguation_finished {
                 self.update_is_ambiguous(db, ctx);
             }
+            return Some((
+                DisambStep::AddYearSuffix { year_suffix },
+                DisambPass::AddYearSuffix(year_suffix),
+            ));
         }
+        None
     }
 
     fn update_is_ambiguous(&mut self, db: &dyn IrDatabase, ctx: &CiteContext<Markup>) {
@@ -1080,14 +1186,60 @@ impl IrGenCow {
         }
     }
 
-    fn disambiguate_conditionals(&mut self, db: &dyn IrDatabase, ctx: &mut CiteContext<Markup>) {
+    fn disambiguate_conditionals(
+        &mut self,
+        db: &dyn IrDatabase,
+        ctx: &mut CiteContext<Markup>,
+    ) -> Option<(DisambStep, DisambPass)> {
         if self.disambiguation_finished {
-            return;
+            return None;
         }
         let cloned = self.to_mut();
         ctx.disamb_pass = Some(DisambPass::Conditionals);
         cloned.used_disambiguate_true = true;
-        disambiguate_true(db, &mut cloned.tree, &mut cloned.state, &ctx);
+        if disambiguate_true(db, &mut cloned.tree, &mut cloned.state, &ctx) {
+            Some((DisambStep::Conditionals, DisambPass::Conditionals))
+        } else {
+            None
+        }
+    }
+}
+
+/// Called with the IR after each disambiguation step that was applied to it.
+type StepRecorder<'a> = dyn FnMut(&IrGen, DisambStep, Option<DisambPass>) + 'a;
+
+/// The steps of ir_gen2_add_given_name.
+fn disambiguate_names(
+    db: &dyn IrDatabase,
+    id: CiteId,
+    ctx: &mut CiteContext<Markup>,
+    record: &mut StepRecorder,
+) -> IrGenCow {
+    let mut irgen = IrGenCow::new(db.ir_gen0(id));
+    irgen.update_is_ambiguous(db, ctx);
+    record(&*irgen, DisambStep::Initial, None);
+    if let Some((step, pass)) = irgen.disambiguate_add_names(db, ctx) {
+        record(&*irgen, step, Some(pass));
+    }
+    if let Some((step, pass)) = irgen.disambiguate_add_given_name(db, ctx) {
+        record(&*irgen, step, Some(pass));
+    }
+    irgen
+}
+
+/// The steps of ir_fully_disambiguated, after ir_gen2_add_given_name.
+fn disambiguate_rest(
+    db: &dyn IrDatabase,
+    irgen: &mut IrGenCow,
+    ctx: &mut CiteContext<Markup>,
+    record: &mut StepRecorder,
+) {
+    if let Some((step, pass)) = irgen.disambiguate_add_year_suffix(db, ctx) {
+        record(&**irgen, step, Some(pass));
+    }
+    log::debug!("ir_add_year_suffix: {}", irgen.deref().tree);
+    if let Some((step, pass)) = irgen.disambiguate_conditionals(db, ctx) {
+        record(&**irgen, step, Some(pass));
     }
 }
 
@@ -1100,10 +1252,7 @@ fn ir_gen2_add_given_name(db: &dyn IrDatabase, id: CiteId) -> Arc<IrGen> {
     let mut ctx;
     preamble!(style, locale, cite, refr, ctx, db, id, None);
 
-    let mut irgen = IrGenCow::new(db.ir_gen0(id));
-    irgen.update_is_ambiguous(db, &ctx);
-    irgen.disambiguate_add_names(db, &mut ctx);
-    irgen.disambiguate_add_given_name(db, &mut ctx);
+    let irgen = disambiguate_names(db, id, &mut ctx, &mut |_, _, _| {});
     log::debug!("ir_gen2_add_given_name: {}", irgen.deref().tree);
     irgen.into_arc()
 }
@@ -1118,14 +1267,65 @@ fn ir_fully_disambiguated(db: &dyn IrDatabase, id: CiteId) -> Arc<IrGen> {
 
     // Start with the given names done.
     let mut irgen = IrGenCow::new(db.ir_gen2_add_given_name(id));
-    irgen.disambiguate_add_year_suffix(db, &mut ctx);
-    log::debug!("ir_add_year_suffix: {}", irgen.deref().tree);
-    irgen.disambiguate_conditionals(db, &mut ctx);
+    disambiguate_rest(db, &mut irgen, &mut ctx, &mut |_, _, _| {});
     log::debug!("ir_fully_disambiguated: {}", irgen.deref().tree);
     irgen.into_arc()
 }
 
-fn get_piq(db: &dyn IrDatabase) -> bool {
+fn disamb_explanation(db: &dyn IrDatabase, id: CiteId) -> Arc<DisambExplanation> {
+    let ref_id = id.lookup(db).ref_id.clone();
+    let reference_found = db.reference(ref_id.clone()).is_some();
+    let mut steps = Vec::new();
+    if reference_found {
+        replay_disambiguation(db, id, &mut steps);
+    }
+    let ambiguous = steps
+        .last()
+        .map_or(false, |last| !last.collisions.is_empty());
+    Arc::new(DisambExplanation {
+        ref_id,
+        reference_found,
+        steps,
+        ambiguous,
+    })
+}
+
+/// Runs the same steps as ir_gen2_add_given_name followed by ir_fully_disambiguated, but reports
+/// after each one.
+fn replay_disambiguation(
+    db: &dyn IrDatabase,
+    id: CiteId,
+    steps: &mut Vec<DisambStepReport>,
+) -> Arc<IrGen> {
+    let style;
+    let locale;
+    let cite;
+    let refr;
+    let mut ctx;
+    preamble!(style, locale, cite, refr, ctx, db, id, None);
+    let fmt = db.get_formatter();
+    let mut record = |irgen: &IrGen, step: DisambStep, pass: Option<DisambPass>| {
+        let output = irgen
+            .tree_ref()
+            .flatten(&fmt, None)
+            .map(|flat| fmt.output(flat, get_piq(db)))
+            .unwrap_or_default();
+        let collisions = refs_accepting_cite(db, irgen.tree_ref(), Some(id), &refr.id, pass)
+            .into_iter()
+            .filter(|k| *k != refr.id)
+            .collect();
+        steps.push(DisambStepReport {
+            step,
+            output,
+            collisions,
+        });
+    };
+    let mut irgen = disambiguate_names(db, id, &mut ctx, &mut record);
+    disambiguate_rest(db, &mut irgen, &mut ctx, &mut record);
+    irgen.into_arc()
+}
+
+pub fn get_piq(db: &dyn IrDatabase) -> bool {
     // We pant PIQ to be global in a document, not change within a cluster because one cite
     // decided to use a different language. Use the default locale to get it.
     let default_locale = db.default_locale();
@@ -1248,6 +1448,8 @@ pub fn with_cite_context<T>(
         in_bibliography: false,
         names_delimiter,
         name_citation: name_el,
+        stopwords: db.title_case_stopwords(),
+        citation_label: db.citation_label_base(cite.ref_id.clone()),
         sort_key,
         year_suffix,
     };
@@ -1291,6 +1493,9 @@ pub fn with_bib_context<T>(
         in_bibliography: true,
         names_delimiter,
         name_citation: name_el,
+        stopwords: db.title_case_stopwords(),
+        // Without a collision suffix, for sorting. bib_item_gen0 adds it back.
+        citation_label: db.citation_label_base(ref_id.clone()),
         sort_key,
         year_suffix,
     };
@@ -1327,6 +1532,8 @@ fn format_single_bib_item(ir_gen: Option<&IrGen>, fmt: &Markup, piq: bool) -> Sm
         .unwrap_or_else(|| CSL_STYLE_ERROR.into())
 }
 
+/// A single bibliography entry without the rest of the bibliography, like `bib_item`, before it
+/// is written with [`OutputFormat::output`].
 fn bib_item(db: &dyn IrDatabase, ref_id: Atom) -> Arc<MarkupOutput> {
     let fmt = db.get_formatter();
     let gen0_arc = db.bib_item_gen0(ref_id);
@@ -1364,6 +1571,7 @@ fn bib_item_gen0_acontextual(
         None,
         None,
         |bib, mut ctx| {
+            ctx.citation_label = db.citation_label(ref_id.clone());
             let mut state = IrState::new();
             let mut arena = IrArena::new();
             let root = bib.intermediate(db, &mut state, &ctx, &mut arena);
@@ -1467,54 +1675,109 @@ fn first_cite_used_disambiguate_true(db: &dyn IrDatabase, ref_id: Atom) -> bool
 
 fn get_bibliography_map(db: &dyn IrDatabase) -> Arc<FnvHashMap<Atom, Arc<MarkupOutput>>> {
     let fmt = db.get_formatter();
-    let style = db.style();
+    let builds = bibliography_before_output(db, &fmt);
+    let mut m = FnvHashMap::with_capacity_and_hasher(builds.len(), fnv::FnvBuildHasher::default());
+    for (key, flat) in builds {
+        let string = fmt.output(flat, get_piq(db));
+        if !string.is_empty() {
+            m.insert(key, Arc::new(string));
+        }
+    }
+    Arc::new(m)
+}
+
+fn bib_max_offset(db: &dyn IrDatabase) -> u32 {
+    fn left_margin(inlines: &[InlineElement]) -> Option<&[InlineElement]> {
+        inlines.iter().find_map(|inline| match inline {
+            InlineElement::Div(DisplayMode::LeftMargin, inner) => Some(inner.as_slice()),
+            InlineElement::Formatted(inner, _) | InlineElement::Tagged(_, inner) => {
+                left_margin(inner)
+            }
+            _ => None,
+        })
+    }
+    let fmt = db.get_formatter();
+    let plain = Markup::plain();
+    bibliography_before_output(db, &fmt)
+        .iter()
+        .filter_map(|(_, build)| left_margin(build))
+        .map(|first| plain.output(first.to_vec(), false).chars().count() as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The bibliography entries in order, with subsequent-author-substitute applied, before they are
+/// written with [`OutputFormat::output`]. Entries can be empty.
+pub fn bibliography_before_output(db: &dyn IrDatabase, fmt: &Markup) -> Vec<(Atom, MarkupBuild)> {
     let sorted_refs = db.sorted_refs();
-    let mut m =
-        FnvHashMap::with_capacity_and_hasher(sorted_refs.0.len(), fnv::FnvBuildHasher::default());
-    let mut prev: Option<(NodeId, Arc<IrGen>)> = None;
-    for key in sorted_refs.0.iter() {
+    let mut substitution = BibSubstitution::default();
+    sorted_refs
+        .0
+        .iter()
+        .filter_map(|key| {
+            substitution
+                .next_entry(db, key.clone(), fmt)
+                .map(|flat| (key.clone(), flat))
+        })
+        .collect()
+}
+
+/// Renders bibliography entries one at a time, in bibliography order, applying
+/// subsequent-author-substitute against the entry before. This lets a caller catch a panic in
+/// one entry and carry on with the rest.
+#[derive(Default)]
+pub struct BibSubstitution {
+    prev: Option<(NodeId, Arc<IrGen>)>,
+}
+
+impl BibSubstitution {
+    /// The next entry, flattened. None if the reference has no bibliography entry. If this
+    /// panics, the next entry is not substituted.
+    pub fn next_entry(
+        &mut self,
+        db: &dyn IrDatabase,
+        ref_id: Atom,
+        fmt: &Markup,
+    ) -> Option<MarkupBuild> {
+        let prev = self.prev.take();
         // TODO: put Nones in there so they can be updated
-        if let Some(mut gen0) = db.bib_item_gen0(key.clone()) {
-            // in a bibliography, we do the affixes etc inside Layout, so they're not here
-            let current = gen0.tree_ref().first_names_block();
-            let sas = style.bibliography.as_ref().and_then(|bib| {
-                bib.subsequent_author_substitute
-                    .as_ref()
-                    .map(|x| (x.as_ref(), bib.subsequent_author_substitute_rule))
-            });
-            if let (Some(prev_name_block), Some(current_name_block), Some((sas, sas_rule))) = (
-                prev.as_ref()
-                    .and_then(|(first_block, gen)| gen.tree.arena.get(*first_block)),
-                current,
+        let mut gen0 = db.bib_item_gen0(ref_id)?;
+        let style = db.style();
+        // in a bibliography, we do the affixes etc inside Layout, so they're not here
+        let current = gen0.tree_ref().first_names_block();
+        let sas = style.bibliography.as_ref().and_then(|bib| {
+            bib.subsequent_author_substitute
+                .as_ref()
+                .map(|x| (x.as_ref(), bib.subsequent_author_substitute_rule))
+        });
+        if let (Some(prev_name_block), Some(current_name_block), Some((sas, sas_rule))) = (
+            prev.as_ref()
+                .and_then(|(first_block, gen)| gen.tree.arena.get(*first_block)),
+            current,
+            sas,
+        ) {
+            let mutated = Arc::make_mut(&mut gen0);
+            let did = transforms::subsequent_author_substitute(
+                fmt,
+                // In order to unwrap this here, you must only replace the NameIR node's
+                // children, not the IR.
+                prev_name_block.get().0.unwrap_name_ir(),
+                current_name_block,
+                &mut mutated.tree.arena,
                 sas,
-            ) {
-                let mutated = Arc::make_mut(&mut gen0);
-                let did = transforms::subsequent_author_substitute(
-                    &fmt,
-                    // In order to unwrap this here, you must only replace the NameIR node's
-                    // children, not the IR.
-                    prev_name_block.get().0.unwrap_name_ir(),
-                    current_name_block,
-                    &mut mutated.tree.arena,
-                    sas,
-                    sas_rule,
-                );
-                if did {
-                    mutated.tree_mut().recompute_group_vars();
-                }
-            }
-            let flat = gen0
-                .tree_ref()
-                .flatten(&fmt, None)
-                .unwrap_or_else(|| fmt.plain(""));
-            let string = fmt.output(flat, get_piq(db));
-            if !string.is_empty() {
-                m.insert(key.clone(), Arc::new(string));
+                sas_rule,
+            );
+            if did {
+                mutated.tree_mut().recompute_group_vars();
             }
-            prev = current.map(|cur| (cur, gen0));
         }
+        let flat = gen0
+            .tree_ref()
+            .flatten(fmt, None)
+            .unwrap_or_else(|| fmt.plain(""));
+        self.prev = current.map(|cur| (cur, gen0));
+        Some(flat)
     }
-    Arc::new(m)
 }
 
 // See https://github.com/jgm/pandoc-citeproc/blob/e36c73ac45c54dec381920e92b199787601713d1/src/Text/CSL/Reference.hs#L910
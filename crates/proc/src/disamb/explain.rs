@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! A record of what disambiguation did to a single cite, for answering "why does this cite look
+//! like that?". See `IrDatabase::disamb_explanation`.
+
+use crate::prelude::*;
+use csl::Atom;
+use serde_derive::Serialize;
+
+/// One of the disambiguation methods from the CSL spec, in the order they are tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "step")]
+pub enum DisambStep {
+    /// The cite as first rendered, before any disambiguation.
+    Initial,
+    /// `disambiguate-add-names`
+    AddNames,
+    /// `disambiguate-add-givenname`, following the `givenname-disambiguation-rule`.
+    AddGivenName,
+    /// `disambiguate-add-year-suffix`. Year suffixes are assigned across the whole document, so
+    /// this can apply to a cite that was already unambiguous.
+    #[serde(rename_all = "camelCase")]
+    AddYearSuffix { year_suffix: u32 },
+    /// Rendering again with `<if disambiguate="true">` branches taken. Only reported if the cite
+    /// was still ambiguous and the style has such a branch.
+    Conditionals,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisambStepReport {
+    #[serde(flatten)]
+    pub step: DisambStep,
+    /// The cite as rendered after this step.
+    pub output: SmartString,
+    /// Other references that could have produced this output.
+    pub collisions: Vec<Atom>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisambExplanation {
+    pub ref_id: Atom,
+    /// False if the cite refers to a reference that does not exist, in which case there are no
+    /// steps.
+    pub reference_found: bool,
+    /// The initial rendering, followed by each step that was applied.
+    pub steps: Vec<DisambStepReport>,
+    /// Whether the cite still matches other references after all the steps.
+    pub ambiguous: bool,
+}
+
+impl DisambExplanation {
+    /// The references this cite still collides with after disambiguation.
+    pub fn remaining_collisions(&self) -> &[Atom] {
+        match self.steps.last() {
+            Some(last) if self.ambiguous => &last.collisions,
+            _ => &[],
+        }
+    }
+}
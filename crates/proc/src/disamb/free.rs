@@ -8,7 +8,7 @@ use crate::prelude::fnv_set_with_cap;
 use csl::LocatorType;
 use csl::{AnyVariable, NumberVariable, Variable};
 use csl::{Cond, Position};
-use csl::{CondSet, Match};
+use csl::{CondSet, Conditions, Match};
 use fnv::FnvHashSet;
 
 bitflags::bitflags! {
@@ -335,8 +335,7 @@ fn cond_to_frees(c: &Cond) -> Option<(FreeCond, FreeCond)> {
             LocatorType::SubVerbo => (FreeCond::LT_SUBVERBO, FreeCond::LT_SUBVERBO_FALSE),
             LocatorType::Verse => (FreeCond::LT_VERSE, FreeCond::LT_VERSE_FALSE),
             LocatorType::Volume => (FreeCond::LT_VOLUME, FreeCond::LT_VOLUME_FALSE),
-            _ => unimplemented!("CSL-M locator types")
-
+            _ => unreachable!("CSL-M locator types are rejected by the style parser"),
             // TODO(CSL-M) enable
             // Article => (FreeCond::LT_LINE, FreeCond::LT_LINE_FALSE),
             // Subparagraph => (FreeCond::LT_LINE, FreeCond::LT_LINE_FALSE),
@@ -452,13 +451,13 @@ impl FreeCondSets {
         self.scalar_multiply_cond(k.0, k.1);
     }
     pub fn all_branches<'a>(
-        cond_results: impl Iterator<Item = (&'a CondSet, Self)>,
+        cond_results: impl Iterator<Item = (&'a Conditions, Self)>,
         else_result: Option<Self>,
     ) -> Self {
         let mut all = FreeCondSets::empty();
         let mut accumulator = FreeCondSets::default();
-        for (cond_set, inner) in cond_results {
-            let (mut outer, negation) = condset_to_frees(cond_set, inner);
+        for (conditions, inner) in cond_results {
+            let (mut outer, negation) = conditions_to_frees(conditions, inner);
             // outer.0.extend(accumulator.0.clone().drain());
             for x in &accumulator.0 {
                 outer = outer.scalar_multiply(*x);
@@ -484,6 +483,43 @@ impl FreeCondSets {
     }
 }
 
+/// Like condset_to_frees, for the CSL-M `<conditions>` element, which combines several condition
+/// sets. A plain `<if>` is a single set with match="all".
+fn conditions_to_frees(c: &Conditions, inner: FreeCondSets) -> (FreeCondSets, FreeCondSets) {
+    let Conditions(match_type, sets) = c;
+    if let (Match::All, [single]) = (match_type, sets.as_slice()) {
+        return condset_to_frees(single, inner);
+    }
+    let (taken, not_taken): (Vec<_>, Vec<_>) = sets
+        .iter()
+        .map(|set| condset_to_frees(set, FreeCondSets::mult_identity()))
+        .unzip();
+    // Every one of them
+    let product = |all: Vec<FreeCondSets>| {
+        all.into_iter()
+            .fold(FreeCondSets::mult_identity(), |mut acc, x| {
+                acc.cross_product(x);
+                acc
+            })
+    };
+    // At least one of them
+    let union = |all: Vec<FreeCondSets>| {
+        let mut union = FreeCondSets::empty();
+        for x in all {
+            union.0.extend(x.0);
+        }
+        union.at_least_1()
+    };
+    let (mut taken, not_taken) = match match_type {
+        Match::All => (product(taken), union(not_taken)),
+        Match::Any => (union(taken), product(not_taken)),
+        Match::None => (product(not_taken), union(taken)),
+        Match::Nand => unreachable!("rejected by the style parser"),
+    };
+    taken.cross_product(inner);
+    (taken, not_taken)
+}
+
 /// The second one is the negation
 fn condset_to_frees(c: &CondSet, inner: FreeCondSets) -> (FreeCondSets, FreeCondSets) {
     let conds = &c.conds;
@@ -534,7 +570,7 @@ fn condset_to_frees(c: &CondSet, inner: FreeCondSets) -> (FreeCondSets, FreeCond
             none.insert_validated(all_false);
             (any, none.at_least_1())
         }
-        Match::Nand => unimplemented!(),
+        Match::Nand => unreachable!("rejected by the style parser"),
         // // Completely Untested
         // Match::Nand => {
         //     // _exactly one_ of them is true
@@ -717,6 +753,7 @@ fn free_all_branches_match_all() {
         // should not end up in the output
         conds: if_branch_conds,
     };
+    let if_branch = Conditions(Match::All, vec![if_branch]);
     let cs = vec![(&if_branch, if_inner)];
     let all = FreeCondSets::all_branches(cs.into_iter(), None);
     let mut result = FnvHashSet::default();
@@ -740,6 +777,7 @@ fn free_all_branches_match_none() {
         // should not end up in the output
         conds: if_branch_conds,
     };
+    let if_branch = Conditions(Match::All, vec![if_branch]);
     let cs = vec![(&if_branch, if_inner)];
     let all = FreeCondSets::all_branches(cs.into_iter(), None);
     let mut result = FnvHashSet::default();
@@ -768,6 +806,7 @@ fn free_all_branches_match_any() {
         // should not end up in the output
         conds: if_branch_conds,
     };
+    let if_branch = Conditions(Match::All, vec![if_branch]);
     let cs = vec![(&if_branch, if_inner)];
     let all = FreeCondSets::all_branches(cs.into_iter(), None);
     let mut result = FnvHashSet::default();
@@ -779,3 +818,36 @@ fn free_all_branches_match_any() {
     result.insert(FreeCond::LOCATOR_FALSE | FreeCond::LT_PAGE_FALSE);
     assert_eq!(all.0, result);
 }
+
+#[test]
+fn free_all_branches_conditions_any() {
+    // <conditions match="any">
+    //   <condition position="first"/>
+    //   <condition variable="locator"/>
+    // </conditions>
+    use csl::Position;
+    let set = |cond| {
+        let mut conds = FnvHashSet::default();
+        conds.insert(cond);
+        CondSet {
+            match_type: Match::All,
+            conds,
+        }
+    };
+    let if_branch = Conditions(
+        Match::Any,
+        vec![
+            set(Cond::Position(Position::First)),
+            set(Cond::Variable(AnyVariable::Number(NumberVariable::Locator))),
+        ],
+    );
+    let cs = vec![(&if_branch, FreeCondSets::mult_identity())];
+    let all = FreeCondSets::all_branches(cs.into_iter(), None);
+    let mut result = FnvHashSet::default();
+    // taken if either one is true
+    result.insert(FreeCond::FIRST);
+    result.insert(FreeCond::LOCATOR);
+    // and not taken only when both are false
+    result.insert(FreeCond::FIRST_FALSE | FreeCond::LOCATOR_FALSE);
+    assert_eq!(all.0, result);
+}
@@ -67,6 +67,7 @@ use petgraph::visit::EdgeRef;
 #[macro_use]
 pub(crate) mod test;
 
+mod explain;
 mod finite_automata;
 mod free;
 pub(crate) mod implementation;
@@ -74,6 +75,7 @@ pub(crate) mod implementation;
 pub(crate) mod names;
 mod ref_context;
 
+pub use explain::{DisambExplanation, DisambStep, DisambStepReport};
 pub use free::{FreeCond, FreeCondSets};
 pub use names::{DisambName, DisambNameData};
 pub use ref_context::RefContext;
@@ -81,8 +83,8 @@ pub use ref_context::RefContext;
 pub use finite_automata::{Dfa, EdgeData, Nfa, NfaEdge};
 
 use csl::{
-    variables::*, BodyDate, Choose, Cond, Conditions, IfThen, IsIndependent, LabelElement, Match,
-    Names, NumberElement, Position, TextElement, VariableForm,
+    variables::*, BodyDate, Choose, Cond, IfThen, IsIndependent, LabelElement, Names,
+    NumberElement, Position, TextElement, VariableForm,
 };
 
 pub fn get_free_conds(db: &dyn IrDatabase) -> FreeCondSets {
@@ -205,19 +207,11 @@ impl<'a> StyleWalker for FreeCondWalker<'a> {
         use std::iter;
         let Choose(ifthen, elseifs, else_) = choose;
         let IfThen(if_conditions, if_els) = ifthen;
-        let Conditions(ifc_match, ifc_cond_set) = if_conditions;
-        // Other kinds (CSL-M) not yet supported
-        assert!(*ifc_match == Match::All);
-        assert!(ifc_cond_set.len() == 1);
-        let ifthen = (&ifc_cond_set[0], self.fold(if_els, WalkerFoldType::IfThen));
+        let ifthen = (if_conditions, self.fold(if_els, WalkerFoldType::IfThen));
         let first: Vec<_> = iter::once(ifthen)
             .chain(elseifs.iter().map(|fi: &IfThen| {
                 let IfThen(if_conditions, if_els) = fi;
-                let Conditions(ifc_match, ifc_cond_set) = if_conditions;
-                // Other kinds (CSL-M) not yet supported
-                assert!(*ifc_match == Match::All);
-                assert!(ifc_cond_set.len() == 1);
-                (&ifc_cond_set[0], self.fold(if_els, WalkerFoldType::IfThen))
+                (if_conditions, self.fold(if_els, WalkerFoldType::IfThen))
             }))
             .collect();
         FreeCondSets::all_branches(
@@ -277,14 +271,24 @@ pub fn create_ref_ir<O: OutputFormat>(
     let ysh_edge = EdgeData::YearSuffix;
     let fcs = db.branch_runs();
     let fmt = db.get_formatter();
+    let stopwords = db.title_case_stopwords();
+    let citation_label = db.citation_label(refr.id.clone());
     let mut vec: Vec<(FreeCond, RefIR)> = fcs
         .0
         .iter()
         .cloned()
         .flat_map(|fc| {
             // Now we construct one ctx for every different count of disambiguate="X" checks
-            let ctx =
-                RefContext::from_free_cond(fc, &fmt, &style, &locale, refr, CiteOrBib::Citation);
+            let ctx = RefContext::from_free_cond(
+                fc,
+                &fmt,
+                &style,
+                &locale,
+                refr,
+                stopwords.clone(),
+                citation_label.clone(),
+                CiteOrBib::Citation,
+            );
             let count = ctx.disamb_count;
             // 0 = none of them enabled
             // 1 = first disambiguate="X" tests as true
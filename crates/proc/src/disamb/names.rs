@@ -754,6 +754,10 @@ where
         }
     }
 
+    pub fn variable(&self) -> NameVariable {
+        self.variable
+    }
+
     pub fn achieved_count(&mut self, count: u16) {
         let (prev_best, _at) = self.achieved_at;
         if count < prev_best {
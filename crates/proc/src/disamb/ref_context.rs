@@ -2,7 +2,7 @@ use crate::choose::CondChecker;
 use crate::cite_context::RenderContext;
 use crate::prelude::*;
 use citeproc_io::output::markup::Markup;
-use citeproc_io::{DateOrRange, NumericValue, Reference};
+use citeproc_io::{DateOrRange, NumericValue, Reference, StopwordTable};
 use csl::{style::*, terms::*, variables::*, Features, Locale, Name as NameEl};
 use std::sync::Arc;
 
@@ -19,6 +19,8 @@ pub struct RefContext<'a, O: OutputFormat = Markup> {
     pub year_suffix: bool,
     pub names_delimiter: Option<SmartString>,
     pub name_el: Arc<NameEl>,
+    pub stopwords: Arc<StopwordTable>,
+    pub citation_label: Option<SmartString>,
     pub disamb_count: u32,
 }
 
@@ -64,6 +66,8 @@ where
             year_suffix: false,
             names_delimiter: ctx.names_delimiter.clone(),
             name_el: ctx.name_citation.clone(),
+            stopwords: ctx.stopwords.clone(),
+            citation_label: ctx.citation_label.clone(),
             disamb_count: 0,
         };
         ctx.count_disambiguate_branches(CiteOrBib::Citation);
@@ -76,6 +80,8 @@ where
         style: &'c Style,
         locale: &'c Locale,
         reference: &'c Reference,
+        stopwords: Arc<StopwordTable>,
+        citation_label: Option<SmartString>,
         location: CiteOrBib,
     ) -> Self {
         let name_info = match location {
@@ -92,6 +98,8 @@ where
             year_suffix: fc.contains(FreeCond::YEAR_SUFFIX),
             names_delimiter: name_info.0,
             name_el: name_info.1,
+            stopwords,
+            citation_label,
             disamb_count: 0,
         };
         ctx.count_disambiguate_branches(location);
@@ -114,6 +122,9 @@ impl<'c, O: OutputFormat> RenderContext for RefContext<'c, O> {
     fn style(&self) -> &Style {
         self.style
     }
+    fn citation_label(&self) -> Option<&str> {
+        self.citation_label.as_opt_str()
+    }
     fn reference(&self) -> &Reference {
         self.reference
     }
@@ -121,12 +132,11 @@ impl<'c, O: OutputFormat> RenderContext for RefContext<'c, O> {
         self.locale
     }
     fn get_number(&self, var: NumberVariable) -> Option<NumericValue<'_>> {
-        let and_term = self.locale.and_term(None).unwrap_or("and");
         let get = |v: NumberVariable| {
             self.reference()
                 .number
                 .get(&v)
-                .map(NumericValue::from_localized(and_term))
+                .map(NumericValue::from_locale(self.locale))
         };
         match var {
             NumberVariable::PageFirst => get(NumberVariable::Page).and_then(|pp| pp.page_first()),
@@ -162,7 +172,7 @@ where
                 // Generated on demand
                 Variable::CitationLabel => true,
                 // TODO: make Hereinafter a FreeCond
-                Variable::Hereinafter => unimplemented!("Hereinafter as a FreeCond"),
+                Variable::Hereinafter => unreachable!("rejected by the style parser"),
                 Variable::YearSuffix => self.year_suffix,
                 _ => self.get_ordinary(v, VariableForm::Long).is_some(),
             },
@@ -279,6 +289,8 @@ mod test {
                     &style,
                     &locale,
                     &reference,
+                    Default::default(),
+                    None,
                     CiteOrBib::Citation,
                 );
                 let mut counter = DisambCounter::new(&ctx);
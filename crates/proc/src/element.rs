@@ -245,6 +245,7 @@ where
                     formatting: g.formatting,
                     affixes: g.affixes.clone(),
                     display: g.display,
+                    parallel_only: ParallelOnly::for_group(g, ctx),
                     ..Default::default()
                 }),
             ),
@@ -335,6 +336,7 @@ impl<'a, O: OutputFormat, I: OutputFormat> StyleWalker for ProcWalker<'a, O, I>
                     formatting: group.formatting,
                     affixes: group.affixes.clone(),
                     display: group.display,
+                    parallel_only: ParallelOnly::for_group(group, self.ctx),
                     ..Default::default()
                 }),
             ),
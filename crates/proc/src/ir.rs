@@ -4,12 +4,13 @@
 //
 // Copyright © 2018 Corporation for Digital Scholarship
 
+use crate::choose::CondChecker;
 use crate::prelude::*;
 use citeproc_io::output::markup::Markup;
-use citeproc_io::output::LocalizedQuotes;
+use citeproc_io::output::{LocalizedQuotes, Provenance};
 use core::fmt;
 use csl::{Affixes, Choose, DateVariable, Formatting, GivenNameDisambiguationRule, TextElement};
-use csl::{NumberVariable, StandardVariable, Variable};
+use csl::{AnyVariable, NumberVariable, StandardVariable, Variable};
 
 use std::sync::Arc;
 
@@ -113,6 +114,29 @@ pub struct IrSeq {
     /// Useful for identifying each top-of-cite `<layout>` element, especially when two or more
     /// cites have already been combined into one tree.
     pub is_layout: bool,
+    /// For `<group parallel-first="...">` and `parallel-last`. Such groups are removed from the
+    /// other cites of a run of parallel citations.
+    pub parallel_only: Option<ParallelOnly>,
+}
+
+/// Which cite of a run of parallel citations a group is rendered in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParallelOnly {
+    First,
+    Last,
+}
+
+impl ParallelOnly {
+    pub(crate) fn for_group<C: CondChecker>(group: &csl::Group, checker: &C) -> Option<Self> {
+        let has_any = |vars: &[AnyVariable]| vars.iter().any(|&v| checker.has_variable(v));
+        if has_any(&group.parallel_first) {
+            Some(ParallelOnly::First)
+        } else if has_any(&group.parallel_last) {
+            Some(ParallelOnly::Last)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Debug for IrSeq {
@@ -121,6 +145,7 @@ impl fmt::Debug for IrSeq {
         let Self {
             formatting, affixes, delimiter, display, quotes,
             text_case, dropped_gv, should_inherit_delim, is_layout,
+            parallel_only,
         } = self;
         let mut f = f.debug_struct("IrSeq");
         if formatting.is_some() { f.field("formatting", &formatting); }
@@ -132,6 +157,7 @@ impl fmt::Debug for IrSeq {
         if dropped_gv.is_some() { f.field("dropped_gv", &dropped_gv); }
         if *should_inherit_delim { f.field("should_inherit_delim", &should_inherit_delim); }
         if *is_layout { f.field("is_layout", &is_layout); }
+        if parallel_only.is_some() { f.field("parallel_only", &parallel_only); }
         f.finish()
     }
 }
@@ -463,7 +489,10 @@ impl<'a, O: OutputFormat<Output = SmartString>> IrTreeRef<'a, O> {
             IR::Rendered(None) => None,
             IR::Rendered(Some(ref x)) => Some(x.inner()),
             IR::ConditionalDisamb(_) => self.flatten_children(fmt, override_delim),
-            IR::YearSuffix(_) | IR::NameCounter(_) | IR::Name(_) | IR::Substitute => {
+            IR::Name(nir) => self
+                .flatten_children(fmt, None)
+                .map(|b| fmt.with_provenance(b, Provenance::Names(nir.variable()))),
+            IR::YearSuffix(_) | IR::NameCounter(_) | IR::Substitute => {
                 self.flatten_children(fmt, None)
             }
             IR::Seq(seq) if gv.should_render_tree(seq.is_implicit_conditional()) => {
@@ -524,6 +553,7 @@ impl IrSeq {
             dropped_gv: _,
             should_inherit_delim,
             is_layout: _,
+            parallel_only: _,
         } = *self;
         let xs: Vec<_> = tree
             .children()
@@ -576,6 +606,7 @@ impl IrSeq {
             dropped_gv: _,
             should_inherit_delim,
             is_layout: _,
+            parallel_only: _,
         } = *self;
         let delimiter = override_delim
             .filter(|_| should_inherit_delim)
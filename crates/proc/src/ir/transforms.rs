@@ -261,6 +261,60 @@ impl<O: OutputFormat> IrTree<O> {
     }
 }
 
+impl<'a, O: OutputFormat> IrTreeRef<'a, O> {
+    /// Finds the outermost `<group parallel-first/parallel-last>` seqs that should not be
+    /// rendered in this cite of a parallel citation.
+    fn parallel_only_elsewhere(&self, is_first: bool, is_last: bool, vec: &mut Vec<NodeId>) {
+        let me = match self.get_node() {
+            Some(x) => x.get(),
+            None => return,
+        };
+        match &me.0 {
+            IR::Seq(IrSeq {
+                parallel_only: Some(only),
+                ..
+            }) if (*only == ParallelOnly::First && !is_first)
+                || (*only == ParallelOnly::Last && !is_last) =>
+            {
+                vec.push(self.node)
+            }
+            IR::ConditionalDisamb(_) | IR::Seq(_) | IR::Substitute => self
+                .children()
+                .for_each(|child| child.parallel_only_elsewhere(is_first, is_last, vec)),
+            _ => {}
+        }
+    }
+}
+
+impl<O: OutputFormat> IrTree<O> {
+    /// Removes the groups that belong to the first or last cite of a parallel citation from
+    /// the others.
+    ///
+    /// A removed group counts as a variable that rendered nothing, so that a surrounding group
+    /// left with only plain text is removed too.
+    pub fn suppress_parallel(&mut self, is_first: bool, is_last: bool) {
+        let mut suppress = Vec::new();
+        self.tree_ref()
+            .parallel_only_elsewhere(is_first, is_last, &mut suppress);
+        if suppress.is_empty() {
+            return;
+        }
+        for node in suppress {
+            let children: Vec<_> = node.children(&self.arena).collect();
+            for child in children {
+                child.remove_subtree(&mut self.arena);
+            }
+            let value = self.arena.get_mut(node).unwrap().get_mut();
+            let gv = match value.1 {
+                GroupVars::Plain => GroupVars::Plain,
+                _ => GroupVars::Missing,
+            };
+            *value = (IR::Rendered(None), gv);
+        }
+        self.recompute_group_vars();
+    }
+}
+
 impl<'a, O: OutputFormat> IrTreeMut<'a, O> {
     /// Rest of the name: "if it has a year suffix"
     fn suppress_first_year(&mut self, has_explicit: bool) -> Option<NodeId> {
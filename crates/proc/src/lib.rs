@@ -44,10 +44,12 @@ mod sort;
 mod tree;
 mod walker;
 
+pub use crate::citation_label::{CitationLabelConfig, Trigraph};
 pub use crate::cluster::built_cluster_before_output;
-pub use crate::db::bib_item_preview;
 pub use crate::db::safe_default;
-pub use crate::sort::BibNumber;
+pub use crate::db::{bib_item_preview, bibliography_before_output, get_piq, BibSubstitution};
+pub use crate::sort::{BibNumber, Collation, Collator};
+pub use crate::walker::style_text_terms;
 
 pub(crate) mod prelude {
     pub(crate) trait AsRefOptStr {
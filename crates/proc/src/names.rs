@@ -208,16 +208,17 @@ pub(crate) fn sort_strings_for_names(
         initialize_with_hyphen: style.initialize_with_hyphen,
         fmt: &fmt,
     };
+    let collation = db.collation();
     let mut out = Vec::new();
     if let Some(values) = refr.name.get(&var) {
         for value in values {
             match value {
                 Name::Person(pn) => {
-                    runner.person_name_sort_keys(pn, &mut out);
+                    runner.person_name_sort_keys(pn, &collation, &mut out);
                 }
                 Name::Literal { literal, .. } => {
                     if !literal.is_empty() {
-                        out.push(Natural::new(literal.clone()));
+                        out.push(Natural::new(literal.clone(), collation.clone()));
                     }
                 }
             }
@@ -606,6 +607,7 @@ pub fn pn_filtered_parts(pn: &PersonName, order: DisplayOrdering) -> Vec<NamePar
             use self::ord::NamePartToken::*;
             match (acc.last(), token) {
                 (None, Space)
+                | (None, ScriptSpace)
                 | (None, SortSeparator)
                 | (Some(Space), Space)
                 | (Some(SortSeparator), SortSeparator)
@@ -644,6 +646,7 @@ fn pn_filter_part(pn: &PersonName, token: NamePartToken) -> bool {
             .map_or(false, |s| !s.is_empty()),
         NamePartToken::Suffix => pn.suffix.as_ref().map_or(false, |s| !s.is_empty()),
         NamePartToken::Space => true,
+        NamePartToken::ScriptSpace => true,
         NamePartToken::SortSeparator => true,
     }
 }
@@ -790,9 +793,12 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
     }
 
     // TODO: strip html/markup for sort keys.
+    /// Sort keys for one name. Where the reference supplies a `family-reading` or
+    /// `given-reading`, that is used in place of the name as written.
     pub(crate) fn person_name_sort_keys(
         &self,
         pn: &PersonName,
+        collation: &crate::sort::Collation,
         out: &mut Vec<Natural<SmartString>>,
     ) {
         let order = get_sort_order(
@@ -811,7 +817,9 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
                     NamePartToken::Given
                     | NamePartToken::GivenAndDropping
                     | NamePartToken::GivenAndBoth => {
-                        if let Some(ref given) = pn.given {
+                        if let Some(reading) = pn.given_reading.as_ref() {
+                            s.push_str(reading);
+                        } else if let Some(ref given) = pn.given {
                             // TODO: parametrize for disambiguation
                             let string = initialize(
                                 &given,
@@ -863,7 +871,7 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
                                     s.push_str(" ");
                                 }
                             }
-                            s.push_str(fam);
+                            s.push_str(pn.family_reading.as_ref().unwrap_or(fam));
                         }
                     }
                     NamePartToken::NonDroppingParticle => {
@@ -876,6 +884,7 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
                         s.push_str(&pn.suffix.as_ref().unwrap());
                     }
                     NamePartToken::Space => {}
+                    NamePartToken::ScriptSpace => {}
                     NamePartToken::SortSeparator => {}
                 }
             }
@@ -892,7 +901,7 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
                 if stripped.starts_with(is_punc) {
                     stripped = SmartString::from(stripped.trim_start_matches(is_punc));
                 }
-                out.push(crate::sort::Natural::new(stripped));
+                out.push(crate::sort::Natural::new(stripped, collation.clone()));
             }
         }
     }
@@ -1057,6 +1066,11 @@ impl<'a, O: OutputFormat> OneNameVar<'a, O> {
                 NamePartToken::Space => {
                     build.push(fmt.plain(" "));
                 }
+                NamePartToken::ScriptSpace => {
+                    if family_given_space(pn) {
+                        build.push(fmt.plain(" "));
+                    }
+                }
                 NamePartToken::SortSeparator => {
                     build.push(if let Some(sep) = &self.name_el.sort_separator {
                         fmt.plain(&sep)
@@ -1203,6 +1217,9 @@ mod ord {
         Suffix,
         SortSeparator,
         Space,
+        /// A space between family and given names in a non-Latin name, if the scripts involved
+        /// call for one. See `family_given_space`.
+        ScriptSpace,
     }
 
     impl NamePartToken {
@@ -1210,6 +1227,7 @@ mod ord {
             match self {
                 SortSeparator => false,
                 Space => false,
+                ScriptSpace => false,
                 _ => true,
             }
         }
@@ -1291,11 +1309,8 @@ mod ord {
         &[Suffix],
     ];
 
-    /// 毛泽东 [Mao Zedong]
-    static NON_LATIN_LONG: DisplayOrdering = &[
-        Family, // TODO: how do we determine if spaces are required?
-        Given,
-    ];
+    /// 毛泽东 [Mao Zedong], 남궁 억 [Namgung Eok]
+    static NON_LATIN_LONG: DisplayOrdering = &[Family, ScriptSpace, Given];
     /// 毛 [Mao]
     static NON_LATIN_SHORT: DisplayOrdering = &[Family];
     /// 毛泽东 [Mao Zedong]
@@ -1304,6 +1319,34 @@ mod ord {
     static NON_LATIN_SORT_SHORT: SortOrdering = &[&[Family]];
 }
 
+/// Whether a non-Latin name, written family-first, needs a space between the family and given
+/// names.
+///
+/// - Chinese and Japanese names are written without one: "毛泽东", "村上春樹".
+/// - Korean names are too, except that two-syllable family names are spaced off so the reader
+///   can tell where the family name ends: "홍길동", but "남궁 억".
+/// - Mixed-script names, e.g. a family name in Han characters and a romanised given name, do get
+///   a space: "王 Wei".
+fn family_given_space(pn: &PersonName) -> bool {
+    use citeproc_io::unicode::{name_script, NameScript};
+    let (family, given) = match (&pn.family, &pn.given) {
+        (Some(f), Some(g)) => (f, g),
+        _ => return false,
+    };
+    let is_cjk = |script| {
+        matches!(
+            script,
+            NameScript::Chinese | NameScript::Japanese | NameScript::Korean
+        )
+    };
+    match (name_script(family), name_script(given)) {
+        (NameScript::Korean, g) if is_cjk(g) => family.trim().chars().count() >= 2,
+        (f, g) if is_cjk(f) && is_cjk(g) => false,
+        (NameScript::Other, NameScript::Other) => false,
+        _ => true,
+    }
+}
+
 /// we usually want to append a space to a non-dropping particle
 ///
 /// "von" + "Crumb" = "von Crumb"
@@ -18,7 +18,7 @@ pub fn render_ordinal(
     let mut s = SmartString::new();
     for token in ts {
         match *token {
-            Num(n) | Roman(n, _) => {
+            Num(n) | Roman(n, _) | Ordinal(n, _) => {
                 if !long || n == 0 || n > 10 {
                     write!(s, "{}", n).unwrap();
                 }
@@ -249,6 +249,12 @@ fn tokens_to_string(
                 write!(s, "{}{}{}", prefix, cropped, suf).unwrap();
                 newstate
             }
+            Ordinal(num, ref suf) => {
+                let (_, cropped, hyphen, newstate) = state.crop(prf, num, false, "", suf);
+                hyphen.write(&mut s, locale, variable);
+                write!(s, "{}{}", cropped, suf).unwrap();
+                newstate
+            }
             Roman(i, upper) => {
                 let (_, _, hyphen, newstate) = state.crop(prf, i, true, "", "");
                 hyphen.write(&mut s, locale, variable);
@@ -323,7 +329,7 @@ pub fn roman_lower(
     let mut s = SmartString::new();
     for t in ts {
         match t {
-            Roman(i, _) | Num(i) => {
+            Roman(i, _) | Num(i) | Ordinal(i, _) => {
                 if let Some(x) = roman::to(*i) {
                     s.push_str(&x);
                 }
@@ -371,3 +377,70 @@ fn test_roman_lower() {
         "iii\u{2013}xi, 2E, iii, iii"
     );
 }
+
+#[cfg(test)]
+fn ordinal_locale() -> Locale {
+    Locale::parse(
+        r#"<locale xmlns="http://purl.org/net/xbiblio/csl" version="1.0" xml:lang="en-US">
+            <terms>
+                <term name="ordinal">th</term>
+                <term name="ordinal-01">st</term>
+                <term name="ordinal-02">nd</term>
+                <term name="ordinal-03">rd</term>
+                <term name="ordinal-11">th</term>
+                <term name="ordinal-12">th</term>
+                <term name="ordinal-13">th</term>
+                <term name="long-ordinal-02">second</term>
+                <term name="long-ordinal-04">fourth</term>
+            </terms>
+        </locale>"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_ordinal_ranges() {
+    let locale = &ordinal_locale();
+    let ts = &[
+        NumericToken::Ordinal(2, "nd".into()),
+        NumericToken::Hyphen,
+        NumericToken::Ordinal(4, "th".into()),
+        NumericToken::Comma,
+        NumericToken::Affixed("".into(), 12, "a".into()),
+    ];
+    let ordinal = |long| {
+        render_ordinal(
+            &ts[..],
+            locale,
+            NumberVariable::Edition,
+            None,
+            Gender::Neuter,
+            long,
+        )
+    };
+    assert_eq!(&ordinal(false), "2nd\u{2013}4th, 12a");
+    assert_eq!(&ordinal(true), "second\u{2013}fourth, 12a");
+    assert_eq!(
+        &roman_lower(&ts[..], locale, NumberVariable::Edition, None),
+        "ii\u{2013}iv, 12a"
+    );
+}
+
+#[test]
+fn test_long_ordinal_fallback() {
+    let locale = &ordinal_locale();
+    let ts = &[
+        NumericToken::Num(2),
+        NumericToken::Comma,
+        NumericToken::Num(11),
+    ];
+    let ordinal = render_ordinal(
+        &ts[..],
+        locale,
+        NumberVariable::Edition,
+        None,
+        Gender::Feminine,
+        true,
+    );
+    assert_eq!(&ordinal, "second, 11th");
+}
@@ -1,8 +1,8 @@
 use crate::cite_context::RenderContext;
 use crate::number::{arabic_number, render_ordinal, roman_lower, roman_representable};
 use crate::prelude::*;
-use citeproc_io::output::LocalizedQuotes;
-use citeproc_io::{Name, NumericToken, NumericValue, Reference};
+use citeproc_io::output::{LocalizedQuotes, Provenance};
+use citeproc_io::{CaseRules, Name, NumericToken, NumericValue, Reference};
 use csl::{
     Features, GenderedTermSelector, LabelElement, Lang, Locale, LocatorType, NameLabel,
     NameVariable, NumberElement, NumberVariable, NumericForm, PageRangeFormat, Plural,
@@ -71,14 +71,26 @@ impl<'a, O: OutputFormat, I: OutputFormat> GenericContext<'a, O, I> {
         }
     }
 
+    /// Text-case rules for the reference's `language`, falling back to the rendering locale's
+    /// language, which is the processor's locale override if it has one, and then the style's
+    /// default locale. Title case is only applied to English unless stopwords have been configured
+    /// for the language.
+    ///
     /// https://docs.citationstyles.org/en/stable/specification.html#non-english-items
-    pub fn is_english(&self) -> bool {
-        let sty = self.style();
-        let cite = self.cite_lang();
+    pub fn case_rules(&self) -> CaseRules {
+        let stopwords = match self {
+            GenericContext::Cit(ctx) => &ctx.stopwords,
+            GenericContext::Ref(ctx) => &ctx.stopwords,
+        };
         // Bit messy but matches the spec wording
         // If a style doesn't have a default, it's en-US, which is English.
-        let default_is_english = sty.default_locale.as_ref().map_or(true, |x| x.is_english());
-        cite.map_or(default_is_english, |l| l.is_english())
+        let en_us = Lang::en_us();
+        let lang = self
+            .cite_lang()
+            .or_else(|| self.locale().lang.as_ref())
+            .or_else(|| self.style().default_locale.as_ref())
+            .unwrap_or(&en_us);
+        stopwords.case_rules(lang)
     }
 
     /// For setting display="X" on elements, where this should only take effect in the
@@ -217,11 +229,12 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
         let options = IngestOptions {
             text_case: number.text_case,
             quotes: self.quotes(),
-            is_english: self.ctx.is_english(),
+            case_rules: self.ctx.case_rules(),
             ..Default::default()
         };
         let b = fmt.ingest(&string, &options);
         let b = fmt.with_format(b, number.formatting);
+        let b = fmt.with_provenance(b, Provenance::Number(number.variable));
         let b = fmt.affixed(b, number.affixes.as_ref());
         fmt.with_display(b, number.display, self.ctx.in_bibliography())
     }
@@ -245,7 +258,10 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
     ) -> O::Build {
         let style = self.ctx.style();
         let _mod_page = style.page_range_format.is_some();
-        if variable == NumberVariable::Locator || variable == NumberVariable::Page {
+        let as_number = variable == NumberVariable::Locator
+            || variable == NumberVariable::Page
+            || (self.ctx.features().more_numerics && val.is_numeric());
+        if as_number {
             let number = csl::NumberElement {
                 variable,
                 form: csl::NumericForm::default(),
@@ -274,14 +290,18 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
             text_case: text.text_case,
             quotes: self.quotes(),
             strip_periods: text.strip_periods,
-            is_english: self.ctx.is_english(),
+            case_rules: self.ctx.case_rules(),
             ..Default::default()
         };
         let hyper = match var {
             StandardVariable::Ordinary(v) => Some(v),
             StandardVariable::Number(_) => None,
         };
-        self.render_text_el(value, text, &options, hyper)
+        let provenance = match var {
+            StandardVariable::Ordinary(v) => Provenance::Ordinary(v),
+            StandardVariable::Number(v) => Provenance::Number(v),
+        };
+        self.render_text_el(value, text, &options, hyper, Some(provenance))
     }
 
     pub fn text_value(&self, text: &TextElement, value: &str) -> Option<O::Build> {
@@ -292,10 +312,10 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
             text_case: text.text_case,
             quotes: self.quotes(),
             strip_periods: text.strip_periods,
-            is_english: self.ctx.is_english(),
+            case_rules: self.ctx.case_rules(),
             ..Default::default()
         };
-        Some(self.render_text_el(value, text, &options, None))
+        Some(self.render_text_el(value, text, &options, None, None))
     }
 
     pub fn text_term(
@@ -313,10 +333,10 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
                     text_case: text.text_case,
                     quotes: self.quotes(),
                     strip_periods: text.strip_periods,
-                    is_english: self.ctx.is_english(),
+                    case_rules: self.ctx.case_rules(),
                     ..Default::default()
                 };
-                self.render_text_el(val, text, &options, None)
+                self.render_text_el(val, text, &options, None, None)
             })
     }
 
@@ -326,12 +346,16 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
         text: &TextElement,
         options: &IngestOptions,
         hyper: Option<Variable>,
+        provenance: Option<Provenance>,
     ) -> O::Build {
         let fmt = self.fmt();
         let mut affixes = text.affixes.as_ref();
         let (mut b, fixed_af) = self.try_link(string, options, hyper, affixes);
         affixes = fixed_af.as_ref().or(affixes);
         b = fmt.with_format(b, text.formatting);
+        if let Some(provenance) = provenance {
+            b = fmt.with_provenance(b, provenance);
+        }
         b = fmt.affixed_quoted(b, affixes, self.quotes_if(text.quotes));
         fmt.with_display(b, text.display, self.ctx.in_bibliography())
     }
@@ -391,7 +415,7 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
                         text_case,
                         strip_periods,
                         quotes: self.quotes(),
-                        is_english: self.ctx.is_english(),
+                        case_rules: self.ctx.case_rules(),
                         ..Default::default()
                     };
                     let b = fmt.ingest(term_text, &options);
@@ -421,7 +445,7 @@ impl<'c, O: OutputFormat, I: OutputFormat> Renderer<'c, O, I> {
             let options = IngestOptions {
                 text_case: label.text_case,
                 quotes: self.quotes(),
-                is_english: self.ctx.is_english(),
+                case_rules: self.ctx.case_rules(),
                 ..Default::default()
             };
             self.ctx
@@ -6,8 +6,10 @@ use csl::{style::*, terms::*, variables::*, Atom};
 use fnv::FnvHashMap;
 use std::sync::Arc;
 
+mod collation;
 mod lexical;
 pub mod natural_sort;
+pub use collation::{Collation, Collator};
 pub(crate) use lexical::Natural;
 mod output_format;
 pub(crate) use output_format::SortStringFormat;
@@ -357,6 +359,7 @@ fn ctx_sort_items(
         }
     };
 
+    let collation = db.collation();
     use std::cell::Cell;
     let fake_cnum = Cell::new(None);
     let mut items = Vec::with_capacity(sort.keys.len());
@@ -392,7 +395,7 @@ fn ctx_sort_items(
                     };
                     push_item(cnum_item);
                 }
-                let a_nat = NaturalCmp::new(a_string);
+                let a_nat = NaturalCmp::new(a_string, collation.clone());
                 SortValue::Macro(a_nat)
             }
             // For variables, we're not going to use the CiteContext wrappers, because if a
@@ -407,7 +410,7 @@ fn ctx_sort_items(
                     let got = a_ctx
                         .get_ordinary(v, VariableForm::default())
                         .map(strip_markup)
-                        .map(Natural::new);
+                        .map(|s| Natural::new(s, collation.clone()));
                     SortValue::OrdinaryVariable(got)
                 }
                 AnyVariable::Number(NumberVariable::CitationNumber) => {
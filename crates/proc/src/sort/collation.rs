@@ -0,0 +1,368 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Locale-aware string comparison for sort keys.
+//!
+//! The built-in collations are the Unicode Collation Algorithm, as implemented by
+//! `icu_collator`, with the CLDR root collation and CLDR's tailoring for each language compiled
+//! in. Strings are compared by letters first, then accents, then case, and runs of digits compare
+//! as numbers, so `Article 3` sorts before `Article 20`.
+
+use csl::Lang;
+use icu_collator::{Collator as IcuCollator, CollatorOptions, Numeric};
+use icu_locid::Locale;
+use std::cmp::Ordering;
+use std::fmt;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+/// Compares sort keys. Implement this to replace the built-in collations.
+///
+/// The strings passed in are the text of a single sort key, with formatting removed. Numbers,
+/// dates and citation numbers are compared separately, and never reach the collator.
+pub trait Collator: Send + Sync + RefUnwindSafe + fmt::Debug {
+    fn compare(&self, a: &str, b: &str) -> Ordering;
+}
+
+/// A shareable [`Collator`]. Cheap to clone.
+///
+/// The built-in collations ([`Collation::root`], [`Collation::for_lang`] and
+/// [`Collation::for_tag`]) implement the Unicode Collation Algorithm with CLDR's tailorings.
+#[derive(Clone)]
+pub struct Collation {
+    inner: Arc<dyn Collator>,
+    /// Set for the built-in collations, so they can be saved and restored by name.
+    tag: Option<Arc<str>>,
+}
+
+impl fmt::Debug for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.tag {
+            Some(tag) => write!(f, "Collation({})", tag),
+            None => write!(f, "Collation({:?})", self.inner),
+        }
+    }
+}
+
+impl PartialEq for Collation {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.tag, &other.tag) {
+            (Some(a), Some(b)) => a == b,
+            _ => Arc::as_ptr(&self.inner) as *const u8 == Arc::as_ptr(&other.inner) as *const u8,
+        }
+    }
+}
+
+impl Eq for Collation {}
+
+impl Default for Collation {
+    fn default() -> Self {
+        Collation::root()
+    }
+}
+
+impl Collation {
+    /// Wraps a collator of your own.
+    pub fn new(collator: impl Collator + 'static) -> Self {
+        Collation {
+            inner: Arc::new(collator),
+            tag: None,
+        }
+    }
+
+    /// The CLDR root collation, used for languages without a tailoring (including English).
+    pub fn root() -> Self {
+        ROOT.clone()
+    }
+
+    /// The CLDR collation for a language. Falls back to [`Collation::root`].
+    pub fn for_lang(lang: &Lang) -> Self {
+        let tag = match lang {
+            Lang::Iso(..) => lang.to_string(),
+            Lang::Iana(s) | Lang::Unofficial(s) => s.to_string(),
+        };
+        Collation::for_tag(&tag).unwrap_or_else(Collation::root)
+    }
+
+    /// The CLDR collation for a BCP 47 language tag, like `sv-SE`, or `de-u-co-phonebk` for
+    /// German phonebook order (`ä` as `ae`). Languages without a tailoring, and collation
+    /// keywords (`-u-co-...`) CLDR has no data for, fall back to the root collation; `root` and
+    /// `und` ask for it directly. Returns None for an empty or malformed tag.
+    pub fn for_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().replace('_', "-");
+        let locale = if tag.eq_ignore_ascii_case("root") {
+            Locale::UND
+        } else {
+            tag.parse::<Locale>().ok()?
+        };
+        let mut options = CollatorOptions::new();
+        options.numeric = Some(Numeric::On);
+        let collator = IcuCollator::try_new(&(&locale).into(), options).ok()?;
+        let tag: Arc<str> = if locale == Locale::UND {
+            Arc::from("root")
+        } else {
+            Arc::from(locale.to_string())
+        };
+        Some(Collation {
+            inner: Arc::new(Uca {
+                collator,
+                tag: tag.clone(),
+            }),
+            tag: Some(tag),
+        })
+    }
+
+    /// The canonical form of the tag a built-in collation was made from, which
+    /// [`Collation::for_tag`] accepts. None for collators of your own.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.inner.compare(a, b)
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref ROOT: Collation = Collation::for_tag("root").expect("compiled root collation data");
+}
+
+struct Uca {
+    collator: IcuCollator,
+    tag: Arc<str>,
+}
+
+impl fmt::Debug for Uca {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Uca({})", self.tag)
+    }
+}
+
+impl Collator for Uca {
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.collator.compare(a, b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn sorted(tag: &str, words: &[&'static str]) -> Vec<&'static str> {
+        let collation = Collation::for_tag(tag).unwrap();
+        let mut words = words.to_vec();
+        words.sort_by(|a, b| collation.compare(a, b));
+        words
+    }
+
+    #[test]
+    fn root() {
+        let root = Collation::root();
+        for (a, b) in &[
+            ("Aaa", "ABC"),
+            ("Article 3", "Article 20"),
+            ("d'Wander", "de'Wander"),
+        ] {
+            assert_eq!(root.compare(a, b), Ordering::Less, "{} < {}", a, b);
+        }
+        assert_eq!(root.tag(), Some("root"));
+        assert_eq!(Collation::for_tag("und"), Some(root));
+        assert_eq!(Collation::for_tag(""), None);
+        assert_eq!(Collation::for_tag("not a tag"), None);
+    }
+
+    #[test]
+    fn accents_then_case() {
+        let words = &["rôle", "Role", "role", "roles"];
+        assert_eq!(sorted("root", words), &["role", "Role", "rôle", "roles"]);
+    }
+
+    #[test]
+    fn tags() {
+        assert_eq!(Collation::for_tag("sv_se").unwrap().tag(), Some("sv-SE"));
+        assert_eq!(
+            Collation::for_tag("de-u-co-phonebk").unwrap().tag(),
+            Some("de-u-co-phonebk")
+        );
+    }
+
+    #[test]
+    fn for_lang() {
+        let lang = Lang::from_str("sv-SE").unwrap();
+        assert_eq!(Collation::for_lang(&lang).tag(), Some("sv-SE"));
+        let lang = Lang::from_str("en-GB").unwrap();
+        assert_eq!(Collation::for_lang(&lang).tag(), Some("en-GB"));
+    }
+
+    #[test]
+    fn swedish() {
+        let words = &[
+            "Öberg",
+            "Zetterberg",
+            "Åkesson",
+            "Andersson",
+            "Ärlig",
+            "Olsson",
+        ];
+        assert_eq!(
+            sorted("sv-SE", words),
+            &[
+                "Andersson",
+                "Olsson",
+                "Zetterberg",
+                "Åkesson",
+                "Ärlig",
+                "Öberg"
+            ]
+        );
+        assert_eq!(
+            sorted("en-US", words),
+            &[
+                "Åkesson",
+                "Andersson",
+                "Ärlig",
+                "Öberg",
+                "Olsson",
+                "Zetterberg"
+            ]
+        );
+    }
+
+    #[test]
+    fn decomposed_input() {
+        // "Åkesson" with a combining ring above
+        let words = &["A\u{30A}kesson", "Zetterberg"];
+        assert_eq!(sorted("fi", words), &["Zetterberg", "A\u{30A}kesson"]);
+    }
+
+    #[test]
+    fn danish() {
+        let words = &["Aarhus", "Ærø", "Zealand", "Østerbro", "Aabenraa", "Odense"];
+        assert_eq!(
+            sorted("da-DK", words),
+            &["Odense", "Zealand", "Ærø", "Østerbro", "Aabenraa", "Aarhus"]
+        );
+    }
+
+    #[test]
+    fn spanish() {
+        let words = &["ñandú", "nube", "oso"];
+        assert_eq!(sorted("es-ES", words), &["nube", "ñandú", "oso"]);
+    }
+
+    #[test]
+    fn czech_contraction() {
+        let words = &["chata", "hrad", "cibule", "čaj", "ihned"];
+        assert_eq!(
+            sorted("cs-CZ", words),
+            &["cibule", "čaj", "hrad", "chata", "ihned"]
+        );
+    }
+
+    #[test]
+    fn hungarian_trigraph() {
+        let words = &["dzsungel", "dzéta", "dobos", "eper"];
+        assert_eq!(
+            sorted("hu-HU", words),
+            &["dobos", "dzéta", "dzsungel", "eper"]
+        );
+    }
+
+    #[test]
+    fn polish() {
+        let words = &["łódź", "lato", "mama", "żaba", "zebra", "źle"];
+        assert_eq!(
+            sorted("pl-PL", words),
+            &["lato", "łódź", "mama", "zebra", "źle", "żaba"]
+        );
+    }
+
+    #[test]
+    fn turkish_dotless_i() {
+        let words = &["ilk", "ışık", "hoca"];
+        assert_eq!(sorted("tr-TR", words), &["hoca", "ışık", "ilk"]);
+    }
+
+    #[test]
+    fn vietnamese_tones() {
+        let words = &["ặn", "an", "ân", "ba"];
+        assert_eq!(sorted("vi-VN", words), &["an", "ặn", "ân", "ba"]);
+    }
+
+    #[test]
+    fn german_phonebook() {
+        let words = &["Müller", "Mueller", "Muff", "Mull"];
+        assert_eq!(
+            sorted("de-DE", words),
+            &["Mueller", "Muff", "Mull", "Müller"]
+        );
+        assert_eq!(
+            sorted("de-u-co-phonebk", words),
+            &["Mueller", "Müller", "Muff", "Mull"]
+        );
+    }
+
+    #[test]
+    fn cyrillic() {
+        // Transliterated, Жуков ("Zhukov") would come after Зайцев ("Zaytsev").
+        let words = &["Ёлкин", "Зайцев", "Жуков", "Егоров", "Фёдоров"];
+        assert_eq!(
+            sorted("ru-RU", words),
+            &["Егоров", "Ёлкин", "Жуков", "Зайцев", "Фёдоров"]
+        );
+        let words = &["їжак", "йод", "іній", "иней"];
+        assert_eq!(sorted("uk-UA", words), &["иней", "іній", "їжак", "йод"]);
+    }
+
+    #[test]
+    fn greek_accents() {
+        let words = &["Όμηρος", "Αριστοτέλης", "Πλάτων", "Ευκλείδης"];
+        assert_eq!(
+            sorted("el-GR", words),
+            &["Αριστοτέλης", "Ευκλείδης", "Όμηρος", "Πλάτων"]
+        );
+    }
+
+    #[test]
+    fn thai_prevowels() {
+        // เกม is "ก + เ + ม", and ไก่ ("ก + ไ", with a tone mark) comes after it.
+        let words = &["ไก่", "เกม", "กา", "ขาว"];
+        assert_eq!(sorted("th-TH", words), &["กา", "เกม", "ไก่", "ขาว"]);
+    }
+
+    #[test]
+    fn kana_together() {
+        let words = &["カメラ", "いぬ", "アイス", "かさ", "漢字"];
+        assert_eq!(
+            sorted("ja-JP", words),
+            &["アイス", "いぬ", "かさ", "カメラ", "漢字"]
+        );
+    }
+
+    #[test]
+    fn numbers_still_natural() {
+        let words = &["Åsa 10", "Åsa 9", "Zorn"];
+        assert_eq!(sorted("sv", words), &["Zorn", "Åsa 9", "Åsa 10"]);
+    }
+
+    #[derive(Debug)]
+    struct Reverse;
+    impl Collator for Reverse {
+        fn compare(&self, a: &str, b: &str) -> Ordering {
+            b.cmp(a)
+        }
+    }
+
+    #[test]
+    fn custom_collator() {
+        let custom = Collation::new(Reverse);
+        assert_eq!(custom.tag(), None);
+        assert_eq!(custom.compare("a", "b"), Ordering::Greater);
+        assert_eq!(custom, custom.clone());
+        assert_ne!(custom, Collation::new(Reverse));
+    }
+}
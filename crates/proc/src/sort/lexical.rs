@@ -1,4 +1,5 @@
-use lexical_sort::{lexical_cmp, natural_lexical_cmp};
+use super::Collation;
+use lexical_sort::lexical_cmp;
 use std::cmp::Ordering;
 
 #[derive(Debug)]
@@ -37,12 +38,13 @@ fn test_lexical_d_wander() {
     );
 }
 
+/// A sort key compared with a [`Collation`].
 #[derive(Debug)]
-pub(crate) struct Natural<S: AsRef<str>>(S);
+pub(crate) struct Natural<S: AsRef<str>>(S, Collation);
 
 impl<S: AsRef<str>> Natural<S> {
-    pub(crate) fn new(inner: S) -> Self {
-        Natural(inner)
+    pub(crate) fn new(inner: S, collation: Collation) -> Self {
+        Natural(inner, collation)
     }
 }
 impl<S: AsRef<str>> Eq for Natural<S> {}
@@ -60,14 +62,15 @@ impl<S: AsRef<str>> PartialOrd for Natural<S> {
 
 impl<S: AsRef<str>> Ord for Natural<S> {
     fn cmp(&self, other: &Self) -> Ordering {
-        natural_lexical_cmp(self.0.as_ref(), other.0.as_ref())
+        self.1.compare(self.0.as_ref(), other.0.as_ref())
     }
 }
 
 #[test]
 fn test_natural_numbers() {
+    let root = Collation::root();
     assert_eq!(
-        Natural("Article 3").cmp(&Natural("Article 20")),
+        Natural::new("Article 3", root.clone()).cmp(&Natural::new("Article 20", root)),
         Ordering::Less
     );
 }
@@ -234,11 +234,10 @@ enum Token<'a> {
     Date(CmpRange<'a>),
 }
 
-impl<'a> PartialOrd for Token<'a> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        use super::Natural;
+impl<'a> Token<'a> {
+    fn partial_cmp(&self, other: &Self, collation: &Collation) -> Option<Ordering> {
         match (self, other) {
-            (Token::Str(a), Token::Str(b)) => Natural::new(a).partial_cmp(&Natural::new(b)),
+            (Token::Str(a), Token::Str(b)) => Some(collation.compare(a, b)),
             (Token::Date(a), Token::Date(b)) => a.partial_cmp(b),
             (Token::Num(a), Token::Num(b)) => a.partial_cmp(b),
             // Don't compare cnums here. If we've extracted it and it goes first, then it's already
@@ -264,16 +263,17 @@ impl<'a> Iterator for TokenIterator<'a> {
     }
 }
 
+use super::Collation;
 use citeproc_io::SmartString;
 
 #[derive(Debug, PartialEq, Eq)]
-pub struct NaturalCmp(SmartString);
+pub struct NaturalCmp(SmartString, Collation);
 impl NaturalCmp {
-    pub fn new(s: SmartString) -> Option<Self> {
+    pub fn new(s: SmartString, collation: Collation) -> Option<Self> {
         if s.is_empty() {
             None
         } else {
-            Some(NaturalCmp(s))
+            Some(NaturalCmp(s, collation))
         }
     }
 }
@@ -284,11 +284,11 @@ impl PartialOrd for NaturalCmp {
 }
 impl Ord for NaturalCmp {
     fn cmp(&self, other: &Self) -> Ordering {
-        natural_cmp(&self.0, &other.0)
+        natural_cmp(&self.0, &other.0, &self.1)
     }
 }
 
-fn natural_cmp(a: &str, b: &str) -> Ordering {
+fn natural_cmp(a: &str, b: &str, collation: &Collation) -> Ordering {
     let a_i = TokenIterator { remain: a };
     let b_i = TokenIterator { remain: b };
     let mut iter = a_i.zip(b_i);
@@ -297,7 +297,7 @@ fn natural_cmp(a: &str, b: &str) -> Ordering {
         if o != Ordering::Equal {
             return o;
         }
-        if let Some(c) = a_t.partial_cmp(&b_t) {
+        if let Some(c) = a_t.partial_cmp(&b_t, collation) {
             o = c;
         }
     }
@@ -306,6 +306,7 @@ fn natural_cmp(a: &str, b: &str) -> Ordering {
 
 #[test]
 fn natural_cmp_strings() {
+    let natural_cmp = |a: &str, b: &str| natural_cmp(a, b, &Collation::root());
     assert_eq!(natural_cmp("a", "z"), Ordering::Less, "a - z");
     assert_eq!(natural_cmp("z", "a"), Ordering::Greater, "z - a");
     assert_eq!(
@@ -132,3 +132,61 @@ pub trait StyleWalker {
         }
     }
 }
+
+/// Every `<text term="..."/>` in the citation and bibliography layouts and the macros they call,
+/// in every branch of every `<choose>`, once each.
+pub fn style_text_terms(style: &Style) -> Vec<(TextTermSelector, bool)> {
+    let mut walker = TextTermWalker {
+        style,
+        macros: Vec::new(),
+        terms: Vec::new(),
+    };
+    walker.walk_citation(style);
+    walker.walk_bibliography(style);
+    walker.terms
+}
+
+struct TextTermWalker<'a> {
+    style: &'a Style,
+    /// Macros already walked, which also stops recursive ones
+    macros: Vec<SmartString>,
+    terms: Vec<(TextTermSelector, bool)>,
+}
+
+impl<'a> StyleWalker for TextTermWalker<'a> {
+    type Output = ();
+    type Checker = crate::choose::UselessCondChecker;
+
+    fn default(&mut self) {}
+    fn should_take_branch(&mut self, _conditions: &Conditions) -> bool {
+        true
+    }
+    /// Walks every branch, not just the first one taken
+    fn choose(&mut self, choose: &Choose) {
+        let Choose(head, rest, last) = choose;
+        for branch in std::iter::once(head).chain(rest.iter()) {
+            self.ifthen(branch);
+        }
+        self.fold(&last.0, WalkerFoldType::Else)
+    }
+    fn text_term(&mut self, _source: &TextElement, sel: TextTermSelector, plural: bool) {
+        if !self.terms.contains(&(sel, plural)) {
+            self.terms.push((sel, plural));
+        }
+    }
+    fn text_macro(&mut self, text: &TextElement, name: &SmartString) {
+        if self.macros.contains(name) {
+            return;
+        }
+        self.macros.push(name.clone());
+        let style = self.style;
+        if let Some(elements) = style.macros.get(name) {
+            self.fold(elements, WalkerFoldType::Macro(text));
+        }
+    }
+    fn names(&mut self, names: &Names) {
+        if let Some(subst) = &names.substitute {
+            self.fold(&subst.0, WalkerFoldType::Substitute);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+use super::humans::{CiteprocJsInstruction, CompatCitationItem};
+use super::{Mode, TestCase};
+use anyhow::{anyhow, Error};
+use citeproc::{FormatOptions, SupportedFormat};
+use citeproc_io::Reference;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// A YAML or TOML fixture file, as written. See the crate docs for the format.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Fixture {
+    pub mode: Mode,
+    #[serde(default, flatten)]
+    pub options: TestInitOptions,
+    #[serde(alias = "style")]
+    pub csl: String,
+    #[serde(alias = "references")]
+    pub input: Vec<Reference>,
+    /// The input for a `pandoc` result, which reads reference fields as Pandoc Markdown instead
+    /// of micro-HTML. Defaults to `input`.
+    pub pandoc_input: Option<Vec<Reference>>,
+    pub result: Expected,
+    pub clusters: Option<Vec<CompatCitationItem>>,
+    pub process_citation_clusters: Option<Vec<CiteprocJsInstruction>>,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum Expected {
+    /// The output in the fixture's `format`.
+    Single(String),
+    /// The output in each of several formats, keyed by format name.
+    PerFormat(BTreeMap<String, String>),
+}
+
+/// One of the test cases in a fixture. There is one per expected output.
+pub struct FixtureCase {
+    /// Which key of a per-format `result` this case checks, or None for a single `result`.
+    pub format: Option<SupportedFormat>,
+    pub case: TestCase,
+}
+
+fn bool_true() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(remote = "FormatOptions", rename_all = "kebab-case")]
+struct KebabFormatOpts {
+    #[serde(default = "bool_true")]
+    link_anchors: bool,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestInitOptions {
+    #[serde(default)]
+    pub csl_features: Option<csl::Features>,
+    // Optional
+    #[serde(default)]
+    pub format: SupportedFormat,
+    #[serde(default, with = "KebabFormatOpts")]
+    pub format_options: FormatOptions,
+    /// You might get this from a dependent style via `StyleMeta::parse(dependent_xml_string)`
+    #[serde(default)]
+    pub locale_override: Option<csl::Lang>,
+    /// Disables sorting on the bibliography
+    #[serde(default)]
+    pub bibliography_no_sort: bool,
+
+    // not in InitOptions, only for tests
+    #[serde(default = "bool_true")]
+    pub normalise: bool,
+}
+
+impl Fixture {
+    /// Makes a test case for each expected output.
+    pub fn into_cases(self) -> Result<Vec<FixtureCase>, Error> {
+        if self.mode == Mode::Preview && self.process_citation_clusters.is_some() {
+            return Err(anyhow!(
+                "process-citation-clusters cannot be used in preview mode"
+            ));
+        }
+        let expected = match self.result {
+            Expected::Single(result) => vec![(None, result)],
+            Expected::PerFormat(map) => {
+                let mut expected = Vec::new();
+                for (key, result) in map {
+                    match SupportedFormat::from_str(&key) {
+                        Ok(format) => expected.push((Some(format), result)),
+                        // Only known with citeproc's `pandoc` feature; skip it without.
+                        Err(()) if key == "pandoc" => {}
+                        Err(()) => return Err(anyhow!("unknown format {} in result", key)),
+                    }
+                }
+                expected
+            }
+        };
+        let clusters: Option<Vec<_>> = self.clusters.map(|cls| {
+            cls.into_iter()
+                .enumerate()
+                .map(|(n, c_item)| c_item.to_note_cluster(n as u32 + 1u32))
+                .collect()
+        });
+        Ok(expected
+            .into_iter()
+            .map(|(format, result)| {
+                let mut options = self.options.clone();
+                if let Some(format) = format {
+                    options.format = format;
+                }
+                let input = match &self.pandoc_input {
+                    Some(input) if options.format.as_str() == "pandoc" => input,
+                    _ => &self.input,
+                };
+                let case = TestCase::new(
+                    self.mode,
+                    options,
+                    self.csl.clone(),
+                    input.clone(),
+                    result,
+                    clusters.clone(),
+                    self.process_citation_clusters.clone(),
+                );
+                FixtureCase { format, case }
+            })
+            .collect())
+    }
+}
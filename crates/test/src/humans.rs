@@ -4,7 +4,7 @@
 //
 // Copyright © 2019 Corporation for Digital Scholarship
 
-use crate::test_format::yaml::TestInitOptions;
+use crate::TestInitOptions;
 
 use super::{Mode, TestCase};
 
@@ -351,7 +351,19 @@ enum Chunk {
 //     out
 // }
 
-pub fn parse_human_test(contents: &str, csl_features: Option<csl::Features>) -> TestCase {
+/// Just the CSL section of a test case, for tests that only need the style. Unlike
+/// [`parse_human_test`], this works for test cases with modes or sections the processor does not
+/// support.
+pub fn parse_human_csl(contents: &str) -> Option<String> {
+    parse_chunks(contents)
+        .into_iter()
+        .find_map(|chunk| match chunk {
+            Chunk::Csl(s) => Some(s),
+            _ => None,
+        })
+}
+
+fn parse_chunks(contents: &str) -> Vec<Chunk> {
     use regex::Regex;
     lazy_static! {
         static ref BEGIN: Regex = Regex::new(r">>=+ ([A-Z\-]+) =+>>").unwrap();
@@ -402,6 +414,11 @@ pub fn parse_human_test(contents: &str, csl_features: Option<csl::Features>) ->
             // otherwise, it's a comment
         }
     }
+    chunks
+}
+
+pub fn parse_human_test(contents: &str, csl_features: Option<csl::Features>) -> TestCase {
+    let chunks = parse_chunks(contents);
 
     let mut mode = None;
     let mut csl = None;
@@ -0,0 +1,441 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Test fixtures for CSL styles, and a runner for them.
+//!
+//! A fixture is a YAML or TOML file with a `csl` style, some `input` references, optionally some
+//! `clusters` of cites, and the `result` that the style should produce. This is the format of
+//! citeproc-rs' own tests in `crates/citeproc/tests/data/humans`, and what `citeproc-rs test
+//! <dir>` runs.
+//!
+//! ```yaml
+//! mode: citation
+//! format: html
+//! csl: |
+//!   <style class="in-text" version="1.0.1"> ... </style>
+//! input:
+//!   - { id: smith, type: book, author: [{ family: Smith }], issued: { raw: "1999" } }
+//! clusters:
+//!   - [{ id: smith, locator: "5", label: page }]
+//! result: |
+//!   (Smith 1999, p. 5)
+//! ```
+//!
+//! The `mode` is one of:
+//!
+//! - `citation`: each cluster's output, one per line. With no clusters, all the references are
+//!   cited together in a single cluster.
+//! - `bibliography`: the bibliography, in a `csl-bib-body` div.
+//! - `preview`: the last cluster, previewed as if it were being inserted after the others.
+//!
+//! The `result` can also be a map from output format to the expected output, which runs the
+//! fixture once for each format:
+//!
+//! ```yaml
+//! result:
+//!   html: |
+//!     <i>Title</i>
+//!   rtf: |
+//!     {\i Title}
+//! ```
+//!
+//! The Pandoc format reads reference fields as Pandoc Markdown rather than micro-HTML, so a
+//! `pandoc` result can have its own `pandoc-input`. It is skipped unless citeproc is built with
+//! the `pandoc` feature.
+//!
+//! The `style` and `references` keys are accepted as aliases for `csl` and `input`.
+//!
+//! [`humans`] also parses the citeproc-js test suite's human-readable `.txt` format.
+
+pub use citeproc;
+pub use citeproc_proc;
+
+use citeproc::prelude::string_id::Cluster as ClusterStr;
+use citeproc::prelude::*;
+use csl::Lang;
+
+use directories::ProjectDirs;
+
+use serde::{de::Error as DeError, Deserialize, Deserializer};
+
+use std::fmt;
+use std::path::PathBuf;
+
+use std::sync::Arc;
+
+mod fixture;
+pub mod humans;
+pub mod runner;
+pub mod toml;
+pub mod yaml;
+
+pub use self::fixture::{Expected, Fixture, FixtureCase, TestInitOptions};
+
+use self::humans::{CiteprocJsInstruction, JsExecutor, Results};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Mode {
+    Citation,
+    Bibliography,
+    Preview,
+}
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Citation
+    }
+}
+
+impl<'de> Deserialize<'de> for Mode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(match s.as_str() {
+            "citation" => Mode::Citation,
+            "bibliography" => Mode::Bibliography,
+            "preview" => Mode::Preview,
+            x => return Err(DeError::custom(format!("unrecognized test mode {}", x))),
+        })
+    }
+}
+
+/// See https://github.com/colin-kiegel/rust-pretty-assertions/issues/24
+///
+/// Wrapper around string slice that makes debug output `{:?}` to print string same way as `{}`.
+/// Used in different `assert*!` macros in combination with `pretty_assertions` crate to make
+/// test failures to show nice diffs.
+#[derive(PartialEq, Eq)]
+pub struct PrettyString<'a>(pub &'a str);
+
+/// Make diff to display string as multi-line string
+impl<'a> fmt::Debug for PrettyString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// The same line-by-line diff that `pretty_assertions::assert_eq!` prints, with the expected
+/// output on the left and the actual output on the right.
+pub fn diff(expected: &str, actual: &str) -> String {
+    pretty_assertions::Comparison::new(&PrettyString(expected), &PrettyString(actual)).to_string()
+}
+
+#[derive(Clone)]
+pub struct TestCase {
+    pub mode: Mode,
+    pub init: TestInitOptions,
+    pub csl: String,
+    pub input: Vec<Reference>,
+    pub result: String,
+    pub clusters: Option<Vec<ClusterStr>>,
+    pub process_citation_clusters: Option<Vec<CiteprocJsInstruction>>,
+    /// `None` fetches locales from the cache directory `citeproc-rs` downloads them to.
+    fetcher: Option<Arc<dyn LocaleFetcher>>,
+}
+
+impl TestCase {
+    pub fn new(
+        mode: Mode,
+        init: TestInitOptions,
+        csl: String,
+        input: Vec<Reference>,
+        result: String,
+        clusters: Option<Vec<ClusterStr>>,
+        process_citation_clusters: Option<Vec<CiteprocJsInstruction>>,
+    ) -> Self {
+        TestCase {
+            mode,
+            init,
+            csl,
+            input,
+            result,
+            clusters,
+            process_citation_clusters,
+            fetcher: None,
+        }
+    }
+
+    /// Fetches locales with `fetcher` instead of from the cache directory `citeproc-rs` downloads
+    /// them to.
+    pub fn with_locale_fetcher(mut self, fetcher: Arc<dyn LocaleFetcher>) -> Self {
+        self.fetcher = Some(fetcher);
+        self
+    }
+
+    fn build_processor(&self) -> Result<Processor, String> {
+        let fetcher = match &self.fetcher {
+            Some(fetcher) => fetcher.clone(),
+            None => Arc::new(Filesystem::project_dirs()?),
+        };
+        let mut processor = Processor::new(InitOptions {
+            style: &self.csl,
+            fetcher: Some(fetcher),
+            test_mode: true,
+            format: self.init.format,
+            format_options: self.init.format_options,
+            bibliography_no_sort: self.init.bibliography_no_sort,
+            csl_features: self.init.csl_features.clone(),
+            locale_override: self.init.locale_override.clone(),
+            ..Default::default()
+        })
+        .map_err(|e| format!("could not construct processor: {}", e))?;
+        processor.reset_references(self.input.clone());
+        Warmup::maximum().execute(&mut processor);
+        Ok(processor)
+    }
+
+    /// Like [`TestCase::try_execute`], but panics if the processor cannot be constructed.
+    pub fn execute(&mut self) -> Option<String> {
+        self.try_execute().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Runs the test, returning the actual output to compare with `self.result`, or `None` if
+    /// there is nothing to compare. Fails if the style is invalid or the locale cache directory
+    /// cannot be found.
+    pub fn try_execute(&mut self) -> Result<Option<String>, String> {
+        let mut processor = self.build_processor()?;
+        Ok(self.run(&mut processor))
+    }
+
+    fn run(&mut self, processor: &mut Processor) -> Option<String> {
+        let mut res = String::new();
+        self.result = normalise_html(&self.result, &self.init);
+        if let Some(ref instructions) = &self.process_citation_clusters {
+            if self.mode == Mode::Citation && self.init.normalise {
+                self.result.push_str("\n");
+            }
+            let mut executor = JsExecutor::new(processor);
+            executor.execute(instructions);
+            let actual = executor.get_results();
+            use std::str::FromStr;
+            match self.mode {
+                Mode::Citation => {
+                    let desired = Results::from_str(&self.result).unwrap();
+                    self.result = desired.output_independent(&self.init);
+                    Some(actual.output_independent(&self.init))
+                }
+                Mode::Bibliography => Some(get_bib_string(processor, &self.init)),
+                Mode::Preview => unreachable!("rejected when parsing the fixture"),
+            }
+        // turns out it's easier to just produce the string the same way
+        } else {
+            let clusters: Vec<Cluster> = if let Some(ref clusters) = &self.clusters {
+                clusters
+                    .iter()
+                    .map(|str_cluster| Cluster {
+                        id: processor.cluster_id(&str_cluster.id),
+                        cites: str_cluster.cites.clone(),
+                        mode: str_cluster.mode.clone(),
+                    })
+                    .collect()
+            } else {
+                let mut cites = Vec::new();
+                // TODO: assemble cites/clusters the other few available ways
+                for refr in self.input.iter() {
+                    cites.push(Cite::basic(&*refr.id));
+                }
+                vec![Cluster {
+                    id: processor.random_cluster_id(),
+                    cites,
+                    mode: None,
+                }]
+            };
+
+            if self.mode == Mode::Preview {
+                return Some(self.preview(processor, clusters));
+            }
+
+            processor.init_clusters(clusters.clone());
+            let positions: Vec<_> = clusters
+                .iter()
+                .enumerate()
+                .map(|(ix, cluster)| ClusterPosition {
+                    id: Some(cluster.id),
+                    note: Some(ix as u32 + 1),
+                })
+                .collect();
+
+            processor.set_cluster_order(&positions).unwrap();
+            let mut pushed = false;
+            for cluster in clusters.iter() {
+                if let Some(html) = processor.get_cluster(cluster.id) {
+                    if pushed {
+                        res.push_str("\n");
+                    }
+                    res.push_str(&*html);
+                    pushed = true;
+                }
+            }
+            match self.mode {
+                Mode::Citation => {
+                    // Because citeproc-rs is a bit keen to escape things
+                    // Slashes are fine if they're not next to angle braces
+                    // let's hope they're not
+                    Some(normalise_html(&res, &self.init))
+                }
+                Mode::Bibliography => Some(get_bib_string(processor, &self.init)),
+                Mode::Preview => unreachable!("previewed above"),
+            }
+        }
+    }
+
+    /// Previews the last cluster as if it were being inserted after all the others.
+    fn preview(&self, processor: &mut Processor, mut clusters: Vec<Cluster>) -> String {
+        let last = match clusters.pop() {
+            Some(last) => last,
+            None => return String::new(),
+        };
+        processor.init_clusters(clusters.clone());
+        let mut positions: Vec<_> = clusters
+            .iter()
+            .enumerate()
+            .map(|(ix, cluster)| ClusterPosition {
+                id: Some(cluster.id),
+                note: Some(ix as u32 + 1),
+            })
+            .collect();
+        positions.push(ClusterPosition {
+            id: None,
+            note: Some(clusters.len() as u32 + 1),
+        });
+        let preview = processor
+            .preview_citation_cluster(
+                PreviewCluster::new(last.cites, last.mode),
+                PreviewPosition::MarkWithZero(&positions),
+                None,
+            )
+            .expect("there is exactly one preview position");
+        normalise_html(&preview, &self.init)
+    }
+}
+
+fn get_bib_string(proc: &Processor, options: &TestInitOptions) -> String {
+    let bib = proc.get_bibliography();
+    let fmt = &proc.formatter;
+    let mut string = String::new();
+    if let Markup::Jats(_) = fmt {
+        string.push_str("<ref-list>");
+        for entry in bib {
+            string.push_str("\n  <ref><mixed-citation>");
+            string.push_str(&entry.value);
+            string.push_str("</mixed-citation></ref>");
+        }
+        string.push_str("\n</ref-list>\n");
+        return normalise_html(&string, options);
+    }
+    string.push_str("<div class=\"csl-bib-body\">");
+    for entry in bib {
+        string.push('\n');
+        match fmt {
+            Markup::Html(_) => {
+                string.push_str("  <div class=\"csl-entry\">");
+                string.push_str(&entry.value);
+                string.push_str("</div>");
+            }
+            _ => {
+                string.push_str(&entry.value);
+            }
+        }
+    }
+    string.push_str("\n</div>\n");
+    normalise_html(&string, options)
+}
+
+struct Filesystem {
+    root: PathBuf,
+}
+
+impl Filesystem {
+    fn new(repo_dir: impl Into<PathBuf>) -> Self {
+        Filesystem {
+            root: repo_dir.into(),
+        }
+    }
+    fn project_dirs() -> Result<Self, String> {
+        let pd = ProjectDirs::from("net", "cormacrelf", "citeproc-rs")
+            .ok_or_else(|| "No home directory found.".to_string())?;
+        let mut locales_dir = pd.cache_dir().to_owned();
+        locales_dir.push("locales");
+        Ok(Self::new(locales_dir))
+    }
+}
+
+use std::{fs, io};
+
+impl LocaleFetcher for Filesystem {
+    fn fetch_string(&self, lang: &Lang) -> Result<Option<String>, LocaleFetchError> {
+        let mut path = self.root.clone();
+        path.push(&format!("locales-{}.xml", lang));
+        let read = fs::read_to_string(path);
+        match read {
+            Ok(string) => Ok(Some(string)),
+            Err(e) => match e.kind() {
+                io::ErrorKind::NotFound => Ok(None),
+                _ => Err(LocaleFetchError::Io(e)),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Warmup {
+    default_locale: bool,
+    _other_locales: Vec<Lang>,
+    ref_dfa: bool,
+}
+
+impl Warmup {
+    pub fn maximum() -> Self {
+        Warmup {
+            default_locale: true,
+            _other_locales: vec![],
+            ref_dfa: true,
+        }
+    }
+    pub fn execute(&self, proc: &mut Processor) {
+        if self.default_locale {
+            proc.default_locale();
+        }
+        if self.ref_dfa {
+            // Precompute dfas
+            // We don't know what 'cited_keys()' is yet, so just do all of them
+            for k in proc.all_keys().iter() {
+                let _dfa = proc
+                    .ref_dfa(k.clone())
+                    .expect("cited_keys should all exist");
+            }
+        }
+    }
+}
+
+macro_rules! regex {
+    ($re:literal $(,)?) => {{
+        static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
+        RE.get_or_init(|| regex::Regex::new($re).unwrap())
+    }};
+}
+
+pub fn normalise_html(strg: &str, options: &TestInitOptions) -> String {
+    if !options.normalise {
+        return strg.to_string();
+    }
+    let rep = strg
+        .replace("&#x2f;", "/")
+        .replace("&#x27;", "'")
+        .replace("&#60;", "&lt;")
+        .replace("&#62;", "&gt;")
+        .replace("&quot;", "\"")
+        // citeproc-js uses the #38 version
+        .replace("&#38;", "&amp;")
+        // citeproc-js puts successive unicode superscript transforms in their own tags,
+        // citeproc-rs joins them.
+        .replace("</sup><sup>", "");
+    let newlines = regex!(r"(?m)>\n*\s*<(/?)div");
+    let mut rep = newlines.replace_all(&rep, ">\n<${1}div").into_owned();
+    rep.truncate(rep.trim_end().trim_end_matches('\n').len());
+    rep
+}
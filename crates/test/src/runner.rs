@@ -0,0 +1,220 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! Runs every fixture in a directory, for `citeproc-rs test`.
+
+use super::toml::{parse_toml_test, update_toml_result};
+use super::yaml::{parse_yaml_test, update_yaml_result};
+use super::FixtureCase;
+use anyhow::{anyhow, Error};
+use citeproc::{LocaleFetcher, SupportedFormat};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FixtureKind {
+    Yaml,
+    Toml,
+}
+
+impl FixtureKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yml" | "yaml" => Some(FixtureKind::Yaml),
+            "toml" => Some(FixtureKind::Toml),
+            _ => None,
+        }
+    }
+
+    pub fn parse(self, source: &str) -> Result<Vec<FixtureCase>, Error> {
+        match self {
+            FixtureKind::Yaml => parse_yaml_test(source),
+            FixtureKind::Toml => parse_toml_test(source),
+        }
+    }
+
+    pub fn update_result(
+        self,
+        source: &str,
+        format: Option<SupportedFormat>,
+        result: &str,
+    ) -> Result<String, Error> {
+        match self {
+            FixtureKind::Yaml => update_yaml_result(source, format, result),
+            FixtureKind::Toml => update_toml_result(source, format, result),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RunOptions {
+    /// Rewrite the expected output of failing fixtures with the actual output.
+    pub update: bool,
+    /// Where to find locales. Defaults to the cache directory `citeproc-rs` downloads them to.
+    pub fetcher: Option<Arc<dyn LocaleFetcher>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail {
+        expected: String,
+        actual: String,
+    },
+    /// Failed, and the fixture now expects the actual output.
+    Updated,
+    /// The fixture could not be read, parsed, or run.
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub path: PathBuf,
+    /// The key of a per-format `result` this report is for.
+    pub format: Option<SupportedFormat>,
+    pub outcome: Outcome,
+}
+
+impl Report {
+    pub fn is_failure(&self) -> bool {
+        match self.outcome {
+            Outcome::Pass | Outcome::Updated => false,
+            Outcome::Fail { .. } | Outcome::Error(_) => true,
+        }
+    }
+}
+
+/// Runs the fixture at `path`, or every fixture in the directory at `path` and its
+/// subdirectories, in file name order.
+pub fn run_path(path: &Path, options: &RunOptions) -> Result<Vec<Report>, Error> {
+    if path.is_file() {
+        return Ok(run_file(path, options));
+    }
+    let mut files = Vec::new();
+    collect_fixtures(path, &mut files)?;
+    if files.is_empty() {
+        return Err(anyhow!("no .yml or .toml fixtures in {}", path.display()));
+    }
+    files.sort();
+    Ok(files
+        .iter()
+        .flat_map(|file| run_file(file, options))
+        .collect())
+}
+
+fn collect_fixtures(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_fixtures(&path, files)?;
+        } else if FixtureKind::from_path(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub fn run_file(path: &Path, options: &RunOptions) -> Vec<Report> {
+    let error = |e: Error| {
+        vec![Report {
+            path: path.to_owned(),
+            format: None,
+            outcome: Outcome::Error(e.to_string()),
+        }]
+    };
+    let kind = match FixtureKind::from_path(path) {
+        Some(kind) => kind,
+        None => return error(anyhow!("not a .yml or .toml fixture")),
+    };
+    let mut source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => return error(e.into()),
+    };
+    // Parsing human tests panics on malformed input, so the rest of the fixtures still get to run
+    let cases = panic::catch_unwind(|| kind.parse(&source));
+    let cases = match cases {
+        Ok(Ok(cases)) => cases,
+        Ok(Err(e)) => return error(e),
+        Err(panic) => return error(anyhow!(panic_message(panic))),
+    };
+    let mut reports = Vec::with_capacity(cases.len());
+    let mut updated = false;
+    for FixtureCase { format, case } in cases {
+        let mut case = match &options.fetcher {
+            Some(fetcher) => case.with_locale_fetcher(fetcher.clone()),
+            None => case,
+        };
+        let executed = panic::catch_unwind(AssertUnwindSafe(|| case.try_execute()));
+        let outcome = match executed {
+            Err(panic) => Outcome::Error(panic_message(panic)),
+            Ok(Err(e)) => Outcome::Error(e),
+            Ok(Ok(None)) => Outcome::Pass,
+            Ok(Ok(Some(actual))) if actual == case.result => Outcome::Pass,
+            Ok(Ok(Some(actual))) if options.update => {
+                if case.process_citation_clusters.is_some() {
+                    Outcome::Error("cannot update a process-citation-clusters fixture".into())
+                } else {
+                    // Normalised output has its trailing newline trimmed
+                    let written = if case.init.normalise {
+                        format!("{}\n", actual)
+                    } else {
+                        actual
+                    };
+                    match kind.update_result(&source, format, &written) {
+                        Ok(new_source) => {
+                            source = new_source;
+                            updated = true;
+                            Outcome::Updated
+                        }
+                        Err(e) => Outcome::Error(e.to_string()),
+                    }
+                }
+            }
+            Ok(Ok(Some(actual))) => Outcome::Fail {
+                expected: case.result.clone(),
+                actual,
+            },
+        };
+        reports.push(Report {
+            path: path.to_owned(),
+            format,
+            outcome,
+        });
+    }
+    if updated {
+        if let Err(e) = fs::write(path, &source) {
+            return error(e.into());
+        }
+    }
+    reports
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panicked".into()
+    }
+}
+
+#[test]
+fn invalid_style_is_an_error() {
+    use citeproc::prelude::PredefinedLocales;
+    let source = "mode: citation\ncsl: <style>\ninput: []\nresult: ''\n";
+    let mut cases = FixtureKind::Yaml.parse(source).unwrap();
+    let FixtureCase { case, .. } = cases.remove(0);
+    let mut case = case.with_locale_fetcher(Arc::new(PredefinedLocales(Default::default())));
+    let error = case.try_execute().unwrap_err();
+    assert!(
+        error.starts_with("could not construct processor"),
+        "{}",
+        error
+    );
+}
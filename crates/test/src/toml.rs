@@ -0,0 +1,92 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+//! The same fixture format as YAML, written in TOML. The top-level keys have to come before the
+//! `[[input]]` tables, and a per-format result is a `[result]` table.
+
+use super::{Fixture, FixtureCase};
+use anyhow::{anyhow, Error};
+use citeproc::SupportedFormat;
+
+pub fn parse_toml_test(s: &str) -> Result<Vec<FixtureCase>, Error> {
+    let fixture: Fixture = ::toml::from_str(s)?;
+    fixture.into_cases()
+}
+
+/// Rewrites the top-level `result` (or the `format` key of the `[result]` table) of a TOML
+/// fixture as a multi-line string containing `result`, leaving the rest of the file alone.
+pub fn update_toml_result(
+    source: &str,
+    format: Option<SupportedFormat>,
+    result: &str,
+) -> Result<String, Error> {
+    let mut offset = 0;
+    let mut table = None;
+    let mut found = None;
+    let (want_table, key) = match format {
+        None => (None, "result"),
+        Some(format) => (Some("result"), format.as_str()),
+    };
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            table = Some(trimmed.trim_matches(|c| c == '[' || c == ']').trim());
+        } else if table == want_table {
+            if let Some(rest) = trimmed.strip_prefix(key) {
+                if rest.trim_start().starts_with('=') {
+                    let eq = line.find('=').unwrap();
+                    found = Some(offset + eq + 1);
+                    break;
+                }
+            }
+        }
+        offset += line.len();
+    }
+    let value_start = found.ok_or_else(|| match format {
+        None => anyhow!("no top-level result"),
+        Some(_) => anyhow!("no {} key in the [result] table", key),
+    })?;
+    let value = source[value_start..].trim_start_matches(|c| c == ' ' || c == '\t');
+    let value_start = source.len() - value.len();
+    let value_len = if value.starts_with("'''") || value.starts_with("\"\"\"") {
+        let close = value[3..]
+            .find(&value[..3])
+            .ok_or_else(|| anyhow!("unterminated multi-line string"))?;
+        close + 6
+    } else {
+        value.find('\n').unwrap_or_else(|| value.len())
+    };
+    let mut out = String::with_capacity(source.len());
+    out.push_str(&source[..value_start]);
+    out.push_str(&multi_line_string(result));
+    out.push_str(&source[value_start + value_len..]);
+    Ok(out)
+}
+
+fn multi_line_string(text: &str) -> String {
+    // The newline straight after the opening quotes is not part of the string
+    if !text.contains("'''") && !text.ends_with('\'') {
+        format!("'''\n{}'''", text)
+    } else {
+        let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+        format!("\"\"\"\n{}\"\"\"", escaped)
+    }
+}
+
+#[test]
+fn test_update_toml_result() {
+    let source = "mode = \"citation\"\nresult = '''\nold\n'''\ncsl = ''\n";
+    assert_eq!(
+        update_toml_result(source, None, "new\n").unwrap(),
+        "mode = \"citation\"\nresult = '''\nnew\n'''\ncsl = ''\n"
+    );
+    let source = "mode = \"citation\"\n[result]\nhtml = \"<i>old</i>\"\nrtf = '{\\i old}'\n";
+    assert_eq!(
+        update_toml_result(source, Some(SupportedFormat::Html), "'quoted'").unwrap(),
+        "mode = \"citation\"\n[result]\nhtml = \"\"\"\n'quoted'\"\"\"\nrtf = '{\\i old}'\n"
+    );
+    assert!(update_toml_result(source, None, "").is_err());
+}
@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2019 Corporation for Digital Scholarship
+
+use super::{Fixture, FixtureCase};
+use anyhow::{anyhow, Error};
+use citeproc::SupportedFormat;
+
+pub fn parse_yaml_test(s: &str) -> Result<Vec<FixtureCase>, Error> {
+    let fixture: Fixture = serde_yaml::from_str(s)?;
+    fixture.into_cases()
+}
+
+/// Rewrites the top-level `result` (or its `format` key) of a YAML fixture as a block scalar
+/// containing `result`, leaving the rest of the file alone.
+pub fn update_yaml_result(
+    source: &str,
+    format: Option<SupportedFormat>,
+    result: &str,
+) -> Result<String, Error> {
+    let lines: Vec<&str> = source.lines().collect();
+    let result_ix = lines
+        .iter()
+        .position(|l| l.starts_with("result:"))
+        .ok_or_else(|| anyhow!("no top-level result"))?;
+    let (key_ix, indent, key) = match format {
+        None => (result_ix, 0, "result"),
+        Some(format) => {
+            let key = format.as_str();
+            let end = block_end(&lines, result_ix, 0);
+            lines[result_ix + 1..end]
+                .iter()
+                .enumerate()
+                .find_map(|(ix, line)| {
+                    let trimmed = line.trim_start();
+                    let rest = trimmed.strip_prefix(key)?;
+                    if rest.starts_with(':') {
+                        Some((result_ix + 1 + ix, line.len() - trimmed.len(), key))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or_else(|| anyhow!("no {} key under result", key))?
+        }
+    };
+    let end = block_end(&lines, key_ix, indent);
+    let mut out = String::with_capacity(source.len());
+    for line in &lines[..key_ix] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(&lines[key_ix][..indent]);
+    out.push_str(key);
+    out.push_str(": ");
+    out.push_str(&block_scalar(result, indent + 2));
+    for line in &lines[end..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if !source.ends_with('\n') {
+        out.pop();
+    }
+    Ok(out)
+}
+
+/// The index just past the lines nested under the key at `ix`, not counting trailing blank lines.
+fn block_end(lines: &[&str], ix: usize, indent: usize) -> usize {
+    let mut end = ix + 1;
+    while end < lines.len() {
+        let line = lines[end];
+        let line_indent = line.len() - line.trim_start().len();
+        if !line.trim().is_empty() && line_indent <= indent {
+            break;
+        }
+        end += 1;
+    }
+    while end > ix + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    end
+}
+
+fn block_scalar(text: &str, indent: usize) -> String {
+    // Leading spaces on the first line would otherwise be read as indentation
+    let indentation = match text.lines().find(|l| !l.is_empty()) {
+        Some(first) if first.starts_with(' ') => "2",
+        _ => "",
+    };
+    let chomping = if text.ends_with("\n\n") {
+        "+"
+    } else if text.ends_with('\n') {
+        ""
+    } else {
+        "-"
+    };
+    let mut out = format!("|{}{}\n", indentation, chomping);
+    for line in text.lines() {
+        if !line.is_empty() {
+            out.extend(std::iter::repeat(' ').take(indent));
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[test]
+fn test_update_yaml_result() {
+    let source = "mode: citation\nresult: |\n  old\n  lines\n\ninput: []\n";
+    assert_eq!(
+        update_yaml_result(source, None, "new\n").unwrap(),
+        "mode: citation\nresult: |\n  new\n\ninput: []\n"
+    );
+    assert_eq!(
+        update_yaml_result(source, None, "  indented").unwrap(),
+        "mode: citation\nresult: |2-\n    indented\n\ninput: []\n"
+    );
+    let source = "result:\n  html: |\n    <i>old</i>\n  rtf: '{\\i old}'\ninput: []";
+    assert_eq!(
+        update_yaml_result(source, Some(SupportedFormat::Rtf), "{\\i new}\n").unwrap(),
+        "result:\n  html: |\n    <i>old</i>\n  rtf: |\n    {\\i new}\ninput: []"
+    );
+    assert!(update_yaml_result(source, Some(SupportedFormat::Plain), "").is_err());
+}
@@ -0,0 +1,271 @@
+//! Generates the document fixtures in `crates/citeproc/benches/data`.
+//!
+//! The output depends only on the seeds, so regenerating gives byte-identical files. Change the
+//! generator and the benchmarks' baselines are no longer comparable.
+
+use anyhow::Error;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+use super::workspace_root;
+
+pub const REFERENCES: usize = 5000;
+pub const CLUSTERS: usize = 2000;
+
+/// A 64-bit LCG (Knuth's MMIX constants), taking the high bits.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn pick<T: Copy>(&mut self, xs: &[T]) -> T {
+        xs[self.below(xs.len())]
+    }
+}
+
+#[rustfmt::skip]
+const FAMILY: &[&str] = &[
+    "Smith", "Jones", "Williams", "Brown", "Taylor", "Davies", "Evans", "Wilson", "Thomas",
+    "Roberts", "Johnson", "Walker", "Wright", "Robinson", "Thompson", "White", "Hughes", "Edwards",
+    "Green", "Hall", "Wood", "Harris", "Lewis", "Martin", "Jackson", "Clarke", "Clark", "Turner",
+    "Hill", "Scott", "Cooper", "Morris", "Ward", "Moore", "King", "Watson", "Baker", "Harrison",
+    "Morgan", "Patel",
+];
+#[rustfmt::skip]
+const GIVEN: &[&str] = &[
+    "James", "Mary", "John", "Patricia", "Robert", "Jennifer", "Michael", "Linda", "William",
+    "Elizabeth", "David", "Barbara", "Richard", "Susan", "Joseph", "Jessica", "Thomas", "Sarah",
+    "Charles", "Karen", "Anna", "Peter", "Ruth", "Simon", "Alice", "Daniel", "Helen", "Mark",
+    "Grace", "Paul",
+];
+#[rustfmt::skip]
+const WORDS: &[&str] = &[
+    "analysis", "of", "the", "effects", "on", "regional", "development", "and", "policy", "in",
+    "modern", "law", "a", "study", "theory", "practice", "history", "evidence", "from", "markets",
+    "social", "networks", "courts", "reform", "language", "learning", "models", "for", "public",
+    "health", "climate", "change", "urban", "growth", "trade", "labour", "rights", "memory",
+    "identity", "water",
+];
+const JOURNALS: &[&str] = &[
+    "Journal of Applied Studies",
+    "Law Quarterly Review",
+    "Modern Economics",
+    "Public Health Reports",
+    "Journal of Social History",
+    "Urban Studies",
+    "Language Learning",
+    "Climate Policy",
+    "International Trade Journal",
+    "Review of Legal Theory",
+];
+const PUBLISHERS: &[(&str, &str)] = &[
+    ("Oxford University Press", "Oxford"),
+    ("Cambridge University Press", "Cambridge"),
+    ("Routledge", "London"),
+    ("Springer", "Berlin"),
+    ("MIT Press", "Cambridge, MA"),
+    ("Federation Press", "Sydney"),
+    ("Hart Publishing", "Oxford"),
+    ("Penguin", "London"),
+];
+const TYPES: &[&str] = &[
+    "article-journal",
+    "article-journal",
+    "book",
+    "chapter",
+    "report",
+];
+
+// Fields are serialized in declaration order, which the checked-in files depend on.
+
+#[derive(Serialize)]
+struct Name {
+    family: &'static str,
+    given: &'static str,
+}
+
+#[derive(Serialize)]
+struct Issued {
+    #[serde(rename = "date-parts")]
+    date_parts: [[usize; 1]; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Reference {
+    id: String,
+    #[serde(rename = "type")]
+    csl_type: &'static str,
+    author: Vec<Name>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    editor: Vec<Name>,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    container_title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    volume: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issue: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher_place: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    number: Option<String>,
+    issued: Issued,
+}
+
+#[derive(Serialize)]
+struct Cite {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct Cluster {
+    id: String,
+    note: usize,
+    cites: Vec<Cite>,
+}
+
+fn name(r: &mut Rng) -> Name {
+    Name {
+        family: r.pick(FAMILY),
+        given: r.pick(GIVEN),
+    }
+}
+
+fn names(r: &mut Rng, max: usize) -> Vec<Name> {
+    let count = 1 + r.below(max);
+    (0..count).map(|_| name(r)).collect()
+}
+
+fn title(r: &mut Rng) -> String {
+    let count = 3 + r.below(6);
+    let words: Vec<&str> = (0..count).map(|_| r.pick(WORDS)).collect();
+    let title = words.join(" ");
+    let mut chars = title.chars();
+    let first = chars.next().unwrap().to_uppercase();
+    first.chain(chars).collect()
+}
+
+fn page(r: &mut Rng) -> String {
+    let first = 1 + r.below(900);
+    format!("{}-{}", first, first + 1 + r.below(40))
+}
+
+fn reference(r: &mut Rng, n: usize) -> Reference {
+    let csl_type = r.pick(TYPES);
+    let author = names(r, 4);
+    let editor = if csl_type == "chapter" {
+        names(r, 2)
+    } else {
+        Vec::new()
+    };
+    let mut refr = Reference {
+        id: format!("ref-{:04}", n),
+        csl_type,
+        author,
+        editor,
+        title: title(r),
+        container_title: None,
+        volume: None,
+        issue: None,
+        page: None,
+        publisher: None,
+        publisher_place: None,
+        number: None,
+        issued: Issued { date_parts: [[0]] },
+    };
+    if csl_type == "article-journal" {
+        refr.container_title = Some(r.pick(JOURNALS).to_owned());
+        refr.volume = Some((1 + r.below(60)).to_string());
+        refr.issue = Some((1 + r.below(12)).to_string());
+        refr.page = Some(page(r));
+    } else if csl_type == "chapter" {
+        refr.container_title = Some(title(r));
+        refr.page = Some(page(r));
+    }
+    if csl_type != "article-journal" {
+        let (publisher, place) = r.pick(PUBLISHERS);
+        refr.publisher = Some(publisher);
+        refr.publisher_place = Some(place);
+    }
+    if csl_type == "report" {
+        refr.number = Some((1 + r.below(500)).to_string());
+    }
+    refr.issued.date_parts = [[1950 + r.below(70)]];
+    refr
+}
+
+/// Each cluster has one to three cites, some with locators. About a third of the cites repeat a
+/// reference from the previous cluster, so there are plenty of ibids and subsequent cites.
+fn clusters(r: &mut Rng, count: usize, references: usize) -> Vec<Cluster> {
+    let mut out = Vec::with_capacity(count);
+    let mut prev: Vec<usize> = Vec::new();
+    for n in 1..=count {
+        let mut cites = Vec::new();
+        let mut ixs = Vec::new();
+        for _ in 0..1 + r.below(3) {
+            let ix = if !prev.is_empty() && r.below(10) < 3 {
+                r.pick(&prev)
+            } else {
+                1 + r.below(references)
+            };
+            let mut cite = Cite {
+                id: format!("ref-{:04}", ix),
+                locator: None,
+                label: None,
+            };
+            if r.below(3) == 0 {
+                cite.locator = Some((1 + r.below(300)).to_string());
+                cite.label = Some("page");
+            }
+            cites.push(cite);
+            ixs.push(ix);
+        }
+        prev = ixs;
+        out.push(Cluster {
+            id: format!("cluster-{:04}", n),
+            note: n,
+            cites,
+        });
+    }
+    out
+}
+
+/// One item per line, so diffs stay readable.
+fn write_items<T: Serialize>(path: &Path, items: &[T]) -> Result<(), Error> {
+    let lines = items
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    let json = format!("[\n{}\n]\n", lines.join(",\n"));
+    fs::write(path, json)?;
+    Ok(())
+}
+
+pub fn gen_bench_data() -> Result<(), Error> {
+    let mut dir = workspace_root();
+    dir.push("crates/citeproc/benches/data");
+    let mut r = Rng(1);
+    let references: Vec<_> = (1..=REFERENCES).map(|n| reference(&mut r, n)).collect();
+    write_items(&dir.join("references.json"), &references)?;
+    let clusters = clusters(&mut Rng(2), CLUSTERS, REFERENCES);
+    write_items(&dir.join("clusters.json"), &clusters)?;
+    Ok(())
+}
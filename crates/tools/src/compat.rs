@@ -0,0 +1,409 @@
+//! Differential testing against citeproc-js, via `citeproc-js-runner render`.
+//!
+//! Both processors get the same style, references and clusters. citeproc-js runs in a
+//! subprocess, with locales read from the same directory as citeproc-rs, so nothing is fetched
+//! over the network.
+
+use anyhow::{anyhow, Error};
+use citeproc::io::cite_compat_vec;
+use citeproc::prelude::*;
+use citeproc::{Cluster, ClusterPosition};
+use citeproc_test::{normalise_html, TestInitOptions};
+use directories::ProjectDirs;
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(about = "compare citeproc-rs' output with citeproc-js' for the same input")]
+pub struct Compat {
+    /// A CSL style, or with --corpus, a directory of them (default: the styles from pull-styles)
+    style: Option<PathBuf>,
+    /// A CSL-JSON file of references
+    #[structopt(long)]
+    library: PathBuf,
+    /// A JSON array of clusters, each `{ "id": "...", "note": 1, "cites": [...] }`, omitting
+    /// `note` for in-text clusters (default: each reference cited in its own note)
+    #[structopt(long)]
+    clusters: Option<PathBuf>,
+    /// Compare every .csl file in the directory, and summarise
+    #[structopt(long)]
+    corpus: bool,
+    /// The citeproc-js-runner executable, installed with `yarn link` in citeproc-js-runner
+    #[structopt(long, default_value = "citeproc-js-runner")]
+    runner: PathBuf,
+    /// Only print the styles that diverge
+    #[structopt(long, short)]
+    quiet: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ClusterInput {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<u32>,
+    cites: Vec<Value>,
+}
+
+/// What gets written to the runner's stdin.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Job<'a> {
+    style: &'a str,
+    references: &'a [Value],
+    clusters: &'a [ClusterInput],
+    locales_dir: &'a Path,
+}
+
+#[derive(Deserialize)]
+struct Rendered {
+    clusters: Vec<RenderedItem>,
+    /// None if the style has no bibliography
+    bibliography: Option<Vec<RenderedItem>>,
+}
+
+#[derive(Deserialize)]
+struct RenderedItem {
+    id: String,
+    html: String,
+}
+
+struct StyleReport {
+    clusters: usize,
+    bib_entries: usize,
+    divergences: Vec<Divergence>,
+}
+
+struct Divergence {
+    /// `cluster <id>` or `bibliography <id>`
+    what: String,
+    rs: Option<String>,
+    js: Option<String>,
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let pd =
+        ProjectDirs::from("net", "cormacrelf", "citeproc-rs").expect("No home directory found.");
+    let mut dir = pd.cache_dir().to_owned();
+    dir.push(name);
+    dir
+}
+
+pub fn compat(opts: Compat) -> Result<(), Error> {
+    let library = fs::read_to_string(&opts.library)?;
+    let references: Vec<Value> = serde_json::from_str(&library)?;
+    let clusters: Vec<ClusterInput> = match &opts.clusters {
+        Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        None => references
+            .iter()
+            .enumerate()
+            .map(|(ix, refr)| ClusterInput {
+                id: format!("cluster-{}", ix + 1),
+                note: Some(ix as u32 + 1),
+                cites: vec![serde_json::json!({ "id": refr["id"] })],
+            })
+            .collect(),
+    };
+    let locales_dir = cache_dir("locales");
+    let style = match opts.style.clone() {
+        Some(style) => style,
+        None if opts.corpus => cache_dir("styles"),
+        None => return Err(anyhow!("no style given")),
+    };
+
+    let styles = if opts.corpus {
+        let mut styles: Vec<_> = fs::read_dir(&style)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "csl"))
+            .collect();
+        styles.sort();
+        styles
+    } else {
+        vec![style]
+    };
+
+    let mut diverged = 0;
+    let mut failed = 0;
+    for path in &styles {
+        let name = path.display();
+        let report = fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|csl| {
+                compare_style(&csl, &references, &clusters, &locales_dir, &opts.runner)
+            });
+        match report {
+            Ok(report) if report.divergences.is_empty() => {
+                if !opts.quiet {
+                    println!(
+                        "ok       {} ({} clusters, {} bibliography entries)",
+                        name, report.clusters, report.bib_entries
+                    );
+                }
+            }
+            Ok(report) => {
+                diverged += 1;
+                println!(
+                    "DIVERGED {} ({} of {} clusters and bibliography entries)",
+                    name,
+                    report.divergences.len(),
+                    report.clusters + report.bib_entries
+                );
+                for div in &report.divergences {
+                    println!("    {}", div.what);
+                    println!("        rs: {}", div.rs.as_deref().unwrap_or("(none)"));
+                    println!("        js: {}", div.js.as_deref().unwrap_or("(none)"));
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                println!("ERROR    {}", name);
+                println!("    {}", e);
+            }
+        }
+    }
+    if opts.corpus {
+        println!();
+        println!(
+            "{} styles: {} identical, {} diverged, {} failed to run",
+            styles.len(),
+            styles.len() - diverged - failed,
+            diverged,
+            failed
+        );
+    }
+    if diverged + failed > 0 {
+        return Err(anyhow!(
+            "{} of {} styles diverged or failed",
+            diverged + failed,
+            styles.len()
+        ));
+    }
+    Ok(())
+}
+
+fn compare_style(
+    csl: &str,
+    references: &[Value],
+    clusters: &[ClusterInput],
+    locales_dir: &Path,
+    runner: &Path,
+) -> Result<StyleReport, Error> {
+    // A panic in one style shouldn't end a corpus run
+    let rs = panic::catch_unwind(AssertUnwindSafe(|| {
+        render_rs(csl, references, clusters, locales_dir)
+    }))
+    .map_err(|panic| anyhow!("citeproc-rs panicked: {}", panic_message(panic)))??;
+    let js = render_js(csl, references, clusters, locales_dir, runner)?;
+    let mut report = StyleReport {
+        clusters: clusters.len(),
+        bib_entries: rs.bibliography.as_ref().map_or(0, |b| b.len()),
+        divergences: Vec::new(),
+    };
+    diff_items(
+        "cluster",
+        &rs.clusters,
+        &js.clusters,
+        &mut report.divergences,
+    );
+    let empty = Vec::new();
+    diff_items(
+        "bibliography",
+        rs.bibliography.as_ref().unwrap_or(&empty),
+        js.bibliography.as_ref().unwrap_or(&empty),
+        &mut report.divergences,
+    );
+    Ok(report)
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = panic.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = panic.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panicked".into()
+    }
+}
+
+/// Compares by id, in citeproc-rs' order, then anything only citeproc-js produced.
+fn diff_items(
+    kind: &str,
+    rs: &[RenderedItem],
+    js: &[RenderedItem],
+    divergences: &mut Vec<Divergence>,
+) {
+    let find = |items: &[RenderedItem], id: &str| {
+        items
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| normalise(&item.html))
+    };
+    for item in rs {
+        let rs_html = normalise(&item.html);
+        let js_html = find(js, &item.id);
+        if js_html.as_ref() != Some(&rs_html) {
+            divergences.push(Divergence {
+                what: format!("{} {}", kind, item.id),
+                rs: Some(rs_html),
+                js: js_html,
+            });
+        }
+    }
+    for item in js {
+        if !rs.iter().any(|r| r.id == item.id) {
+            divergences.push(Divergence {
+                what: format!("{} {}", kind, item.id),
+                rs: None,
+                js: Some(normalise(&item.html)),
+            });
+        }
+    }
+}
+
+/// The same normalisation as the test suite, plus unwrapping citeproc-js' bibliography entries.
+fn normalise(html: &str) -> String {
+    let options = TestInitOptions {
+        normalise: true,
+        ..Default::default()
+    };
+    let html = html.trim();
+    let html = html
+        .strip_prefix(r#"<div class="csl-entry">"#)
+        .and_then(|h| h.strip_suffix("</div>"))
+        .unwrap_or(html);
+    normalise_html(html.trim(), &options)
+}
+
+fn render_rs(
+    csl: &str,
+    references: &[Value],
+    clusters: &[ClusterInput],
+    locales_dir: &Path,
+) -> Result<Rendered, Error> {
+    let mut db = Processor::new(InitOptions {
+        style: csl,
+        fetcher: Some(Arc::new(LocaleDirectory::new(locales_dir.to_owned()))),
+        format: SupportedFormat::Html,
+        ..Default::default()
+    })
+    .map_err(|e| anyhow!("citeproc-rs could not parse the style: {:?}", e))?;
+    let refs: Vec<Reference> = serde_json::from_value(Value::Array(references.to_vec()))?;
+    db.reset_references(refs);
+    let mut positions = Vec::with_capacity(clusters.len());
+    for cluster in clusters {
+        let id = db.cluster_id(&cluster.id);
+        let cites = cite_compat_vec::deserialize(&Value::Array(cluster.cites.clone()))?;
+        db.insert_cluster(Cluster::new(id, cites, None));
+        positions.push(match cluster.note {
+            Some(note) => ClusterPosition::note(id, note),
+            None => ClusterPosition::in_text(id),
+        });
+    }
+    db.set_cluster_order(&positions)
+        .map_err(|e| anyhow!("invalid cluster order: {:?}", e))?;
+    let rendered_clusters = clusters
+        .iter()
+        .map(|cluster| RenderedItem {
+            id: cluster.id.clone(),
+            html: db
+                .get_cluster(db.cluster_id(&cluster.id))
+                .map_or_else(String::new, |html| html.to_string()),
+        })
+        .collect();
+    let bibliography = if db.get_style().bibliography.is_some() {
+        let entries = db.get_bibliography();
+        Some(
+            entries
+                .into_iter()
+                .map(|entry| RenderedItem {
+                    id: entry.id.to_string(),
+                    html: entry.value.to_string(),
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+    Ok(Rendered {
+        clusters: rendered_clusters,
+        bibliography,
+    })
+}
+
+fn render_js(
+    csl: &str,
+    references: &[Value],
+    clusters: &[ClusterInput],
+    locales_dir: &Path,
+    runner: &Path,
+) -> Result<Rendered, Error> {
+    let job = Job {
+        style: csl,
+        references,
+        clusters,
+        locales_dir,
+    };
+    let mut child = Command::new(runner)
+        .arg("render")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| anyhow!("could not run {}: {}", runner.display(), e))?;
+    {
+        let stdin = child.stdin.as_mut().expect("stdin is piped");
+        serde_json::to_writer(&mut *stdin, &job)?;
+        stdin.flush()?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("citeproc-js failed ({})", output.status));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+#[test]
+fn test_normalise() {
+    assert_eq!(
+        normalise("  <div class=\"csl-entry\">Smith, <i>Title</i>&#38; more</div>\n"),
+        "Smith, <i>Title</i>&amp; more"
+    );
+    assert_eq!(normalise("(Smith 1999)"), "(Smith 1999)");
+}
+
+#[test]
+fn test_diff_items() {
+    let item = |id: &str, html: &str| RenderedItem {
+        id: id.into(),
+        html: html.into(),
+    };
+    let rs = vec![
+        item("a", "Same"),
+        item("b", "Differs"),
+        item("c", "Only rs"),
+    ];
+    let js = vec![
+        item("d", "Only js"),
+        item("b", "Different"),
+        item("a", "<div class=\"csl-entry\">Same</div>"),
+    ];
+    let mut divergences = Vec::new();
+    diff_items("cluster", &rs, &js, &mut divergences);
+    let summary: Vec<_> = divergences
+        .iter()
+        .map(|d| (d.what.as_str(), d.rs.as_deref(), d.js.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("cluster b", Some("Differs"), Some("Different")),
+            ("cluster c", Some("Only rs"), None),
+            ("cluster d", None, Some("Only js")),
+        ]
+    );
+}
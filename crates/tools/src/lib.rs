@@ -1,6 +1,8 @@
 #[macro_use]
 extern crate serde_derive;
 
+pub mod bench_data;
+pub mod compat;
 pub mod ucd;
 
 use anyhow::{anyhow, Error};
@@ -66,8 +66,12 @@ struct TestSuite {
 enum Tools {
     PullTestSuite,
     PullLocales,
+    PullStyles,
     BuildUcd,
+    /// Regenerates the document fixtures in crates/citeproc/benches/data
+    GenBenchData,
     TestSuite(TestSuite),
+    Compat(compat::Compat),
 }
 
 fn main() -> Result<(), Error> {
@@ -75,7 +79,10 @@ fn main() -> Result<(), Error> {
     match opt {
         Tools::PullTestSuite => pull_test_suite(),
         Tools::PullLocales => pull_locales(),
+        Tools::PullStyles => pull_styles(),
         Tools::BuildUcd => build_superscript_trie(),
+        Tools::GenBenchData => bench_data::gen_bench_data(),
+        Tools::Compat(opts) => compat::compat(opts),
         Tools::TestSuite(test_suite) => match test_suite.sub {
             None => run(Vec::new(), false),
             Some(TestSuiteSub::Run { release, rest }) => run(rest, release),
@@ -65,6 +65,90 @@ describe("Driver", () => {
                 .toBe("Italicised https://google.com");
         })
     });
+
+    test("can lintStyle", () => {
+        withDriver({}, driver => {
+            let style = mkNoteStyle(`<choose><if variable="DOI"><text value="x" /></if></choose>`);
+            let lints = driver.lintStyle(style);
+            expect(lints).toHaveLength(1);
+            expect(lints[0].severity).toBe("Warning");
+            expect(lints[0].message).toBe("variable `DOI` is tested, but never rendered");
+        })
+    });
+
+    test("can exportSession and importSession", () => {
+        withDriver({ style: italicStyle, format: "html" }, driver => {
+            oneOneOne(driver, { title: "Italicised" });
+            let session = JSON.parse(JSON.stringify(driver.exportSession()));
+            let restored = Driver.importSession(session);
+            try {
+                expect(restored.builtCluster("one")).toBe(driver.builtCluster("one"));
+                expect(restored.exportSession()).toEqual(session);
+            } finally {
+                restored.free();
+            }
+        })
+    });
+
+    test("can explainDisambiguation and ambiguityReport", () => {
+        let style = mkInTextStyle(`<names variable="author"><name form="short" /></names>`);
+        withDriver({ style }, driver => {
+            driver.insertReferences([
+                { id: "a", type: "book", author: [{ family: "Smith", given: "John" }] },
+                { id: "b", type: "book", author: [{ family: "Smith", given: "Jane" }] },
+            ]);
+            driver.initClusters([
+                { id: "one", cites: [{ id: "a" }] },
+                { id: "two", cites: [{ id: "b" }] },
+            ]);
+            driver.setClusterOrder([{ id: "one" }, { id: "two" }]);
+            let [explanation] = driver.explainDisambiguation("one");
+            expect(explanation.refId).toBe("a");
+            expect(explanation.ambiguous).toBe(true);
+            expect(explanation.steps[0]).toEqual({ step: "Initial", output: "Smith", collisions: ["b"] });
+            expect(driver.ambiguityReport()).toEqual([
+                { refId: "a", collidesWith: ["b"], clusters: ["one"] },
+                { refId: "b", collidesWith: ["a"], clusters: ["two"] },
+            ]);
+            expect(() => driver.explainDisambiguation("nonexistent")).toThrow();
+        })
+    });
+
+    test("can collectGarbage and report memoryStats", () => {
+        withDriver({ sweepPolicy: { every: 0 } }, driver => {
+            oneOneOne(driver, { title: "Collected" });
+            expect(driver.builtCluster("one")).toBe("Collected");
+            let before = driver.memoryStats();
+            expect(before.ir.memoized).toBeGreaterThan(0);
+            expect(before.ir.queries.map(q => q.name)).toContain("BuiltClusterQuery");
+            driver.collectGarbage("allValues");
+            expect(driver.memoryStats().ir.memoized).toBeLessThan(before.ir.memoized);
+            expect(driver.builtCluster("one")).toBe("Collected");
+            driver.collectGarbage();
+            driver.setSweepPolicy({ every: 10, level: "outdated" });
+            expect(() => driver.collectGarbage("everything" as any)).toThrow();
+        })
+    });
+
+    test("sorts with a collation", () => {
+        let sortedStyle = mkNoteStyle(
+            '<text variable="title" />',
+            `<bibliography>
+                <sort><key variable="title" /></sort>
+                <layout><text variable="title" /></layout>
+            </bibliography>`
+        );
+        withDriver({ style: sortedStyle, collation: "sv-SE" }, driver => {
+            driver.insertReference({ id: "a", type: "book", title: "Ångström" });
+            driver.insertReference({ id: "z", type: "book", title: "Zeta" });
+            driver.initClusters([{ id: "one", cites: [{ id: "a" }, { id: "z" }] }]);
+            driver.setClusterOrder([{ id: "one" }]);
+            expect(driver.fullRender().bibEntries.map(e => e.id)).toEqual(["z", "a"]);
+            driver.setCollation(null);
+            expect(driver.fullRender().bibEntries.map(e => e.id)).toEqual(["a", "z"]);
+            expect(() => driver.setCollation("not a tag")).toThrow();
+        })
+    });
 });
 
 describe("batchedUpdates", () => {
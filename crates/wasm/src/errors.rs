@@ -46,6 +46,22 @@ pub enum Error {
         #[serde(skip_serializing)]
         citeproc::ReorderingError,
     ),
+    #[error("Unsupported session version {0}")]
+    UnsupportedSessionVersion(u32),
+    #[error("Unknown collation {0:?}")]
+    UnknownCollation(String),
+}
+
+impl From<citeproc::SessionError> for Error {
+    fn from(e: citeproc::SessionError) -> Self {
+        match e {
+            citeproc::SessionError::UnsupportedVersion(v) => Error::UnsupportedSessionVersion(v),
+            citeproc::SessionError::Style(se) => Error::StyleError(se),
+            citeproc::SessionError::ClusterOrder(re) => Error::ReorderingError(re),
+            citeproc::SessionError::UnknownCollation(tag) => Error::UnknownCollation(tag),
+            citeproc::SessionError::UnknownFeature(name) => Error::UnknownCSLFeature(name),
+        }
+    }
 }
 
 fn style_error_to_js_err(se: &StyleError) -> JsValue {
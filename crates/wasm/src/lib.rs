@@ -24,7 +24,6 @@ extern crate log;
 use js_sys::Promise;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
@@ -51,7 +50,7 @@ impl Driver {
     ///
     /// * `style` is a CSL style as a string. Independent styles only.
     /// * `fetcher` must implement the `Fetcher` interface
-    /// * `format` is one of { "html", "rtf", "plain" }
+    /// * `format` is one of { "html", "rtf", "plain", "jats", "ooxml", "odf" }
     ///
     /// Throws an error if it cannot parse the style you gave it.
     #[wasm_bindgen(constructor)]
@@ -59,19 +58,28 @@ impl Driver {
         utils::set_panic_hook();
         utils::init_log();
 
-        // The Processor gets a "only has en-US, otherwise empty" fetcher.
-        let us_fetcher = Arc::new(utils::USFetcher);
+        // The Processor gets a "only has en-US, otherwise empty" fetcher, or all the bundled
+        // locales if they were compiled in.
+        let us_fetcher = utils::builtin_fetcher();
         let fetcher = Fetcher::from_options_object(&options)?;
         let options = options.ts_deserialize()?;
         let csl_features =
             csl::version::read_features(options.csl_features.iter().map(|x| x.as_str()))
                 .map_err(|x| Error::UnknownCSLFeature(x.to_owned()))?;
+        let collation = options.collation.map(collation_for_tag).transpose()?;
+        let panic_mode = options
+            .render_error_placeholder
+            .map_or_else(PanicMode::default, PanicMode::placeholder);
         let init = InitOptions {
             style: options.style.as_ref(),
             fetcher: Some(us_fetcher),
             format: options.format,
             format_options: options.format_options,
             bibliography_no_sort: options.bibliography_no_sort,
+            citation_label: options.citation_label,
+            sweep_policy: options.sweep_policy,
+            panic_mode,
+            collation,
             locale_override: options.locale_override,
             test_mode: false,
             csl_features: Some(csl_features),
@@ -79,7 +87,10 @@ impl Driver {
         };
         let engine = Processor::new(init)?;
 
-        if engine.default_lang() != Lang::en_us() && fetcher.is_none() {
+        if engine.default_lang() != Lang::en_us()
+            && fetcher.is_none()
+            && !cfg!(feature = "bundled-locales")
+        {
             log::warn!("citeproc-rs was initialized with a locale other than en-US, but without a locale fetcher, using built-in en-US instead.");
         }
 
@@ -89,6 +100,32 @@ impl Driver {
         Ok(Driver { engine, fetcher })
     }
 
+    /// Recreates a Driver from a `Session` returned by `exportSession`, producing identical output.
+    ///
+    /// * `fetcher` is used for `fetchLocales`, as with the `fetcher` in `InitOptions`. The locales
+    ///   that were fetched before the session was exported are already included.
+    #[wasm_bindgen(js_name = "importSession")]
+    pub fn import_session(
+        session: typescript::Session,
+        fetcher: Option<Fetcher>,
+    ) -> Result<Driver, Error> {
+        utils::set_panic_hook();
+        utils::init_log();
+        let session = session.ts_deserialize()?;
+        let engine = Processor::import_session(session, Some(utils::builtin_fetcher()))?;
+        let engine = Rc::new(RefCell::new(engine));
+        Ok(Driver { engine, fetcher })
+    }
+
+    /// Saves everything this Driver has been given (style, output format, locales, references,
+    /// clusters, cluster order, uncited references) as a JSON-compatible object. Restore it with
+    /// `Driver.importSession`.
+    #[wasm_bindgen(js_name = "exportSession")]
+    pub fn export_session(&self) -> Result<typescript::Session, Error> {
+        let session = self.engine.borrow().export_session();
+        session.serialize_jsvalue()
+    }
+
     /// Sets the style (which will also cause everything to be recomputed, use sparingly)
     #[wasm_bindgen(js_name = "setStyle")]
     pub fn set_style(&self, style_text: &str) -> Result<(), Error> {
@@ -96,9 +133,17 @@ impl Driver {
         Ok(())
     }
 
+    /// Lints a style, returning its parse errors and any warnings about it. Terms are checked
+    /// against the current default locale. Does not change the style in use.
+    #[wasm_bindgen(js_name = "lintStyle")]
+    pub fn lint_style(&self, style_text: &str) -> Result<typescript::InvalidCslArray, Error> {
+        let lints = self.engine.borrow().lint_style(style_text)?;
+        lints.serialize_jsvalue()
+    }
+
     /// Sets the output format (which will also cause everything to be recomputed, use sparingly)
     ///
-    /// @param {"html" | "rtf" | "plain"} format The new output format as a string, same as `new Driver`
+    /// @param {"html" | "rtf" | "plain" | "jats" | "ooxml" | "odf"} format The new output format as a string, same as `new Driver`
     ///
     /// @param {FormatOptions | null} options If absent, this is set to the default FormatOptions.
     ///
@@ -363,6 +408,22 @@ impl Driver {
         all.serialize_jsvalue()
     }
 
+    /// Returns the render errors recorded since the last `batchedUpdates` or `takeRenderErrors`,
+    /// e.g. after `fullRender`. See `renderErrorPlaceholder` in `InitOptions`.
+    #[wasm_bindgen(js_name = "takeRenderErrors")]
+    pub fn take_render_errors(&self) -> Result<typescript::RenderErrors, Error> {
+        let eng = self.engine.borrow();
+        eng.take_render_errors_str().serialize_jsvalue()
+    }
+
+    /// Lists the problems the processor worked around: missing references, CSL-JSON keys that
+    /// are not variables, dates it could not parse, and missing locales and terms.
+    #[wasm_bindgen(js_name = "diagnostics")]
+    pub fn diagnostics(&self) -> Result<typescript::Diagnostics, Error> {
+        let eng = self.engine.borrow();
+        eng.diagnostics_str().serialize_jsvalue()
+    }
+
     /// Drains the `batchedUpdates` queue manually.
     #[wasm_bindgen(js_name = "drain")]
     pub fn drain(&self) {
@@ -401,6 +462,68 @@ impl Driver {
         })
     }
 
+    /// Explains how each cite in a cluster was disambiguated: its rendering after each step, and
+    /// which other references it could still be mistaken for.
+    #[wasm_bindgen(js_name = "explainDisambiguation")]
+    pub fn explain_disambiguation(
+        &self,
+        cluster_id: &str,
+    ) -> Result<typescript::DisambExplanations, Error> {
+        let eng = self.engine.borrow();
+        let explanations = eng
+            .explain_disambiguation_str(cluster_id)
+            .ok_or_else(|| Error::NonExistentCluster(cluster_id.into()))?;
+        explanations.serialize_jsvalue()
+    }
+
+    /// Lists every reference whose cites are still ambiguous after disambiguation, and the
+    /// clusters they appear in.
+    #[wasm_bindgen(js_name = "ambiguityReport")]
+    pub fn ambiguity_report(&self) -> Result<typescript::AmbiguousReferences, Error> {
+        let eng = self.engine.borrow();
+        eng.ambiguity_report_str().serialize_jsvalue()
+    }
+
+    /// Discards memoized results that are no longer needed, or with `"allValues"`, all of them.
+    /// Call it after removing many references or clusters, or when the document is idle.
+    ///
+    /// @param {GcLevel | null} level Defaults to `"outdated"`.
+    ///
+    #[wasm_bindgen(js_name = "collectGarbage")]
+    pub fn collect_garbage(&self, level: Option<typescript::GcLevel>) -> Result<(), Error> {
+        let level = level
+            .map(|l| l.ts_deserialize())
+            .transpose()?
+            .unwrap_or_default();
+        self.engine.borrow().collect_garbage(level);
+        Ok(())
+    }
+
+    /// Changes when `batchedUpdates` collects garbage by itself.
+    #[wasm_bindgen(js_name = "setSweepPolicy")]
+    pub fn set_sweep_policy(&self, policy: typescript::SweepPolicy) -> Result<(), Error> {
+        let policy = policy.ts_deserialize()?;
+        self.engine.borrow_mut().set_sweep_policy(policy);
+        Ok(())
+    }
+
+    /// Sorts with a built-in collation, given as a language tag like `"sv-SE"` or
+    /// `"de-u-co-phonebk"`. Pass null to go back to the collation for the default locale. The
+    /// built-in collations are CLDR's, and throw only for a malformed tag.
+    #[wasm_bindgen(js_name = "setCollation")]
+    pub fn set_collation(&self, tag: Option<String>) -> Result<(), Error> {
+        let collation = tag.map(collation_for_tag).transpose()?;
+        self.engine.borrow_mut().set_collation(collation);
+        Ok(())
+    }
+
+    /// Counts the memoized results held by the processor, with a rough size for each query.
+    #[wasm_bindgen(js_name = "memoryStats")]
+    pub fn memory_stats(&self) -> Result<typescript::MemoryStats, Error> {
+        let eng = self.engine.borrow();
+        eng.memory_stats().serialize_jsvalue()
+    }
+
     #[cfg(feature = "dot")]
     /// Spits out a GraphViz DOT-formatted representation of the internal representation of a
     /// Reference constructed for disambiguation purposes.
@@ -415,6 +538,10 @@ impl Driver {
     }
 }
 
+fn collation_for_tag(tag: String) -> Result<Collation, Error> {
+    Collation::for_tag(&tag).ok_or(Error::UnknownCollation(tag))
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[derive(Clone)]
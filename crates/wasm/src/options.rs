@@ -47,6 +47,17 @@ pub struct WasmInitOptions {
     /// Disables sorting on the bibliography
     #[serde(default)]
     pub bibliography_no_sort: bool,
+    #[serde(default)]
+    pub citation_label: CitationLabelConfig,
+    /// When to collect garbage after `batchedUpdates`
+    #[serde(default)]
+    pub sweep_policy: SweepPolicy,
+    /// A built-in collation to sort with, instead of the one for the default locale
+    #[serde(default)]
+    pub collation: Option<String>,
+    /// Rendered instead of a cluster or bibliography entry that panics
+    #[serde(default)]
+    pub render_error_placeholder: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -103,7 +103,7 @@ interface InitOptions {
     fetcher?: Fetcher;
 
     /** The output format for this driver instance (default: html) */
-    format?: "html" | "rtf" | "plain";
+    format?: "html" | "rtf" | "plain" | "jats" | "ooxml" | "odf";
     /** Configuration for the formatter */
     formatOptions?: FormatOptions;
 
@@ -119,6 +119,37 @@ interface InitOptions {
 
     /** Disables sorting in the bibliography; items appear in cited order. */
     bibliographyNoSort?: boolean;
+
+    /** How to generate citation-label for references that don't have one. */
+    citationLabel?: CitationLabelConfig;
+
+    /** When to collect garbage after batchedUpdates. Default: outdated results, every time. */
+    sweepPolicy?: SweepPolicy;
+
+    /** A built-in collation to sort with, as a language tag like "sv-SE" or "de-u-co-phonebk".
+      * Default: the collation for the style's default locale (or localeOverride). */
+    collation?: string;
+
+    /** Text to render in place of a cluster or bibliography entry that panics, which is then
+      * reported in UpdateSummary.renderErrors. It is escaped for the output format like any text.
+      *
+      * This only works where panics unwind. In the usual wasm32-unknown-unknown build, every
+      * panic aborts, and leaves the Driver unusable, whether this is set or not. */
+    renderErrorPlaceholder?: string;
+}
+
+interface CitationLabelConfig {
+    /** Letters per name and digits of the year, one row per number of names.
+      * Default "Aaaa00:AaAa00:AaAA00:AAAA00". */
+    template?: string;
+    /** Where to look for names, in order. Default ["author", "editor"]. */
+    names?: string[];
+    /** Letters of the title to use when there are no names. Default 3. */
+    titleLetters?: number;
+    /** Prefix names with the initials of their non-dropping particle ("vGog"). */
+    particles?: boolean;
+    /** Suffix identical labels with a, b, c across the document. */
+    collisionSuffixes?: boolean;
 }
 
 /** This interface lets citeproc retrieve locales or modules asynchronously,
@@ -142,9 +173,41 @@ interface BibliographyUpdate {
 type UpdateSummary<Output = string> = {
     clusters: [string, Output][];
     bibliography?: BibliographyUpdate;
+    /** Anything that was rendered as the renderErrorPlaceholder. */
+    renderErrors?: RenderError[];
 };
 "#
 );
+typescript_serialize!(
+    Vec<citeproc::string_id::RenderError>,
+    RenderErrors,
+    "RenderError[]",
+    r#"
+interface RenderError {
+    /** Null for a bibliography entry or a preview. */
+    clusterId: string | null;
+    /** The reference the failure was traced to, if any. */
+    refId: string | null;
+    message: string;
+}
+"#
+);
+typescript_serialize!(
+    Vec<citeproc::string_id::Diagnostic>,
+    Diagnostics,
+    "Diagnostic[]",
+    r#"
+type DiagnosticCode = "missingReference" | "unknownVariable" | "invalidDate" | "missingLocale" | "missingTerm";
+interface Diagnostic {
+    code: DiagnosticCode;
+    /** Set for a missingReference, to the cluster that cites it. */
+    clusterId: string | null;
+    /** The reference with the problem, or the missing one. */
+    refId: string | null;
+    message: string;
+}
+"#
+);
 typescript_serialize!(
     Vec<citeproc::BibEntry>,
     BibEntries,
@@ -185,6 +248,7 @@ interface BibliographyMeta {
 "#
 );
 typescript_serialize!(Vec<String>, StringArray, "string[]");
+typescript_serialize!(Vec<csl::InvalidCsl>, InvalidCslArray, "InvalidCsl[]");
 
 typescript_serialize!(
     csl::StyleMeta,
@@ -310,6 +374,142 @@ typescript_serialize!(
 "#
 );
 
+typescript_serialize!(
+    citeproc::Session,
+    Session,
+    "Session",
+    r#"
+/** A saved Driver, from exportSession. Treat it as opaque, apart from storing it as JSON. */
+interface Session {
+    version: number;
+    style: string;
+    cslFeatures?: string[];
+    format: "html" | "rtf" | "plain" | "jats" | "ooxml" | "odf";
+    formatOptions: FormatOptions;
+    localeOverride?: string;
+    locales: [string, string][];
+    bibliographyNoSort: boolean;
+    citationLabel: CitationLabelConfig;
+    collation?: string;
+    titleCaseStopwords?: [string, string[]][];
+    references: Reference[];
+    referenceProblems?: ReferenceProblems[];
+    clusters: Cluster[];
+    clusterOrder: ClusterPosition[];
+    uncited: IncludeUncited;
+}
+
+interface ReferenceProblems {
+    id: string;
+    unknownVariables?: string[];
+    invalidDates?: [string, string][];
+}
+"#
+);
+
+impl TypescriptDeserialize for Session {
+    type RustType = citeproc::Session;
+}
+
+typescript_serialize!(
+    Vec<std::sync::Arc<citeproc::DisambExplanation>>,
+    DisambExplanations,
+    "DisambExplanation[]",
+    r#"
+type DisambStep =
+    | { step: "Initial" }
+    | { step: "AddNames" }
+    | { step: "AddGivenName" }
+    | { step: "AddYearSuffix"; yearSuffix: number }
+    | { step: "Conditionals" };
+
+type DisambStepReport = DisambStep & {
+    /** The cite as rendered after this step. */
+    output: string;
+    /** Other references that could have produced this output. */
+    collisions: string[];
+};
+
+interface DisambExplanation {
+    refId: string;
+    referenceFound: boolean;
+    steps: DisambStepReport[];
+    ambiguous: boolean;
+}
+"#
+);
+
+typescript_serialize!(
+    Vec<citeproc::string_id::AmbiguousReference>,
+    AmbiguousReferences,
+    "AmbiguousReference[]",
+    r#"
+interface AmbiguousReference {
+    refId: string;
+    collidesWith: string[];
+    /** Cluster ids, in document order. */
+    clusters: string[];
+}
+"#
+);
+
+typescript_deserialize!(
+    citeproc::GcLevel,
+    GcLevel,
+    "GcLevel",
+    r#"
+/** "outdated" discards results not used since the last change; "allValues" discards everything
+  * that can be recomputed. */
+type GcLevel = "outdated" | "allValues";
+"#
+);
+
+typescript_deserialize!(
+    citeproc::SweepPolicy,
+    SweepPolicy,
+    "SweepPolicy",
+    r#"
+interface SweepPolicy {
+    /** Collect garbage after this many calls to batchedUpdates. 0 means never. Default 1. */
+    every?: number;
+    level?: GcLevel;
+}
+"#
+);
+
+typescript_serialize!(
+    citeproc::MemoryStats,
+    MemoryStats,
+    "MemoryStats",
+    r#"
+interface QueryStats {
+    name: string;
+    /** Keys the query has seen, including those whose values were discarded. */
+    entries: number;
+    /** Entries currently holding a value. */
+    memoized: number;
+    /**
+     * The size of each key, plus the size of each memoized value, not counting anything they
+     * point to. Most values are pointers, so this tracks the number of entries, not memory use.
+     */
+    shallowBytes: number;
+}
+
+interface QueryGroupStats {
+    memoized: number;
+    shallowBytes: number;
+    queries: QueryStats[];
+}
+
+interface MemoryStats {
+    ir: QueryGroupStats;
+    cite: QueryGroupStats;
+    locale: QueryGroupStats;
+    style: QueryGroupStats;
+}
+"#
+);
+
 // Some misc date objects, mostly made redundant by CSL 1.1 EDTF
 #[wasm_bindgen(typescript_custom_section)]
 const TS_APPEND_CONTENT_2: &'static str = r#"
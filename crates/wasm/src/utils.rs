@@ -126,6 +126,18 @@ where
     js.iter().map(|x| x.into_serde()).collect()
 }
 
+/// The locales available without fetching: all of them with `bundled-locales`.
+#[cfg(feature = "bundled-locales")]
+pub fn builtin_fetcher() -> std::sync::Arc<dyn LocaleFetcher> {
+    std::sync::Arc::new(citeproc::prelude::BundledLocales)
+}
+
+/// The locales available without fetching: only `en-US`.
+#[cfg(not(feature = "bundled-locales"))]
+pub fn builtin_fetcher() -> std::sync::Arc<dyn LocaleFetcher> {
+    std::sync::Arc::new(USFetcher)
+}
+
 /// A `LocaleFetcher` that statically includes `en-US`, so it never has to be async-fetched, but
 /// otherwise returns `None`.
 pub struct USFetcher;
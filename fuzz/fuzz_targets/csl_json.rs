@@ -0,0 +1,19 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+#![no_main]
+use citeproc_io::output::markup::Markup;
+use citeproc_io::{cite_compat_vec, Cite, Reference};
+use libfuzzer_sys::fuzz_target;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Cites(#[serde(deserialize_with = "cite_compat_vec::deserialize")] Vec<Cite<Markup>>);
+
+fuzz_target!(|json: &[u8]| {
+    let _ = serde_json::from_slice::<Vec<Reference>>(json);
+    let _ = serde_json::from_slice::<Cites>(json);
+});
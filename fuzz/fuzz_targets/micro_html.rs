@@ -0,0 +1,20 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+#![no_main]
+use citeproc_io::output::micro_html::MicroNode;
+use citeproc_io::IngestOptions;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|fragment: &str| {
+    MicroNode::parse(fragment, &IngestOptions::default());
+    // Cluster affixes are parsed slightly differently
+    let external = IngestOptions {
+        is_external: true,
+        ..Default::default()
+    };
+    MicroNode::parse(fragment, &external);
+});
@@ -0,0 +1,90 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+//! Renders a document against a fuzzed style.
+//!
+//! The input is a style, then a NUL byte, then one byte per cite. For each of those bytes:
+//!
+//! - the low four bits pick one of the references in `data/references.json`
+//! - `0x10` gives the cite a locator
+//! - `0x20` suppresses the author
+//! - `0x40` starts a new cluster
+//! - `0x80` puts that new cluster in a new footnote
+//!
+//! Without a NUL byte, every reference is cited once, each in its own footnote.
+
+#![no_main]
+use citeproc::prelude::*;
+use citeproc::{Cluster, ClusterPosition};
+use libfuzzer_sys::fuzz_target;
+use std::sync::Arc;
+
+/// Enough for disambiguation and ibid to kick in, while keeping each run fast.
+const MAX_CITES: usize = 64;
+
+fuzz_target!(|data: &[u8]| {
+    let (style, cites) = match data.iter().rposition(|&b| b == 0) {
+        Some(nul) => (&data[..nul], &data[nul + 1..]),
+        None => (data, &[][..]),
+    };
+    let style = match std::str::from_utf8(style) {
+        Ok(style) => style,
+        Err(_) => return,
+    };
+    let mut db = match Processor::new(InitOptions {
+        style,
+        fetcher: Some(Arc::new(PredefinedLocales::bundled_en_us())),
+        ..Default::default()
+    }) {
+        Ok(db) => db,
+        Err(_) => return,
+    };
+    let references: Vec<Reference> =
+        serde_json::from_str(include_str!("../data/references.json")).unwrap();
+    let ids: Vec<Atom> = references.iter().map(|r| r.id.clone()).collect();
+    db.reset_references(references);
+
+    let mut clusters: Vec<Vec<Cite<Markup>>> = Vec::new();
+    let mut notes = Vec::new();
+    if cites.is_empty() {
+        for (ix, id) in ids.iter().enumerate() {
+            clusters.push(vec![Cite::basic(id.clone())]);
+            notes.push(ix as u32 + 1);
+        }
+    }
+    for &b in cites.iter().take(MAX_CITES) {
+        if clusters.is_empty() || b & 0x40 != 0 {
+            let note = notes.last().copied().unwrap_or(0);
+            notes.push(if b & 0x80 != 0 { note + 1 } else { note.max(1) });
+            clusters.push(Vec::new());
+        }
+        let mut cite = Cite::basic(ids[(b & 0x0f) as usize % ids.len()].clone());
+        if b & 0x10 != 0 {
+            cite.locators = Some(Locators::Single(Locator {
+                locator: NumberLike::Str((b as u32 * 7).to_string().into()),
+                loc_type: Default::default(),
+            }));
+        }
+        if b & 0x20 != 0 {
+            cite.mode = Some(CiteMode::SuppressAuthor);
+        }
+        clusters.last_mut().unwrap().push(cite);
+    }
+
+    let mut positions = Vec::with_capacity(clusters.len());
+    for (ix, (cites, note)) in clusters.into_iter().zip(notes).enumerate() {
+        let id = db.cluster_id(format!("cluster-{}", ix));
+        db.insert_cluster(Cluster::new(id, cites, None));
+        positions.push(ClusterPosition::note(id, note));
+    }
+    if db.set_cluster_order(&positions).is_err() {
+        return;
+    }
+    for pos in &positions {
+        let _ = db.get_cluster(pos.id.unwrap());
+    }
+    let _ = db.get_bibliography();
+});
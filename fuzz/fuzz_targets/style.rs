@@ -0,0 +1,13 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright © 2020 Corporation for Digital Scholarship
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// Styles can opt in to features and CSL-M themselves, so the default options reach those too.
+fuzz_target!(|xml: &str| {
+    let _ = csl::Style::parse(xml);
+});